@@ -0,0 +1,78 @@
+//! Benchmarks for the core geometric operations
+//!
+//! Run with `cargo bench`; fixtures are built once per benchmark, outside
+//! the timed closures.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rust_decimal::Decimal;
+
+use triadchain::core::{
+    genesis::{genesis_fractal_triangle, genesis_triangle},
+    subdivision::{subdivide_to_depth, subdivide_triangle},
+    validation::validate_fractal_structure,
+};
+use triadchain::visualization::renderer::render_fractal_svg;
+use triadchain::{Point, TriangleAddress};
+
+fn bench_triangle(c: &mut Criterion) {
+    let triangle = genesis_triangle().unwrap();
+    let inside = triangle.centroid();
+    let outside = Point::new(Decimal::from(10), Decimal::from(10));
+
+    c.bench_function("triangle_area", |b| b.iter(|| black_box(&triangle).area().unwrap()));
+    c.bench_function("triangle_area_f64", |b| b.iter(|| black_box(&triangle).area_f64()));
+    c.bench_function("triangle_contains_point", |b| {
+        b.iter(|| {
+            black_box(&triangle).contains_point(black_box(&inside))
+                && !black_box(&triangle).contains_point(black_box(&outside))
+        })
+    });
+}
+
+fn bench_subdivision(c: &mut Criterion) {
+    let genesis = genesis_fractal_triangle().unwrap();
+
+    c.bench_function("subdivide_triangle", |b| {
+        b.iter(|| subdivide_triangle(black_box(&genesis)).unwrap())
+    });
+
+    let mut group = c.benchmark_group("subdivide_to_depth");
+    for depth in [4u8, 6, 8] {
+        let triangles = subdivide_to_depth(genesis.clone(), depth).unwrap().total_triangles();
+        group.throughput(Throughput::Elements(triangles as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &depth, |b, &depth| {
+            b.iter_batched(
+                || genesis.clone(),
+                |genesis| subdivide_to_depth(genesis, depth).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_structure(c: &mut Criterion) {
+    let structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 6).unwrap();
+
+    c.bench_function("validate_fractal_structure_depth_6", |b| {
+        b.iter(|| validate_fractal_structure(black_box(&structure)))
+    });
+    c.bench_function("total_active_area_depth_6", |b| {
+        b.iter(|| black_box(&structure).total_active_area().unwrap())
+    });
+    c.bench_function("approx_total_active_area_f64_depth_6", |b| {
+        b.iter(|| black_box(&structure).approx_total_active_area_f64())
+    });
+    c.bench_function("render_fractal_svg_depth_6", |b| {
+        b.iter(|| render_fractal_svg(black_box(&structure), 800, 800, false).unwrap())
+    });
+}
+
+fn bench_address(c: &mut Criterion) {
+    c.bench_function("address_parse", |b| {
+        b.iter(|| TriangleAddress::from_string_representation(black_box("0.1.2.0.1.2.0.1")).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_triangle, bench_subdivision, bench_structure, bench_address);
+criterion_main!(benches);
@@ -1,9 +1,18 @@
 //! Hierarchical addressing system for triangles in the fractal
 
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
 use crate::core::errors::{SierpinskiError, SierpinskiResult};
+use crate::core::geometry::Point;
+use crate::core::triangle::Triangle;
+
+/// Prefix of the checksummed address format, e.g. `tri1-01201-k7xq`
+pub const CHECKED_ADDRESS_PREFIX: &str = "tri1-";
+
+/// Characters used for checksums, as in bech32: no 1, b, i or o
+const CHECKSUM_ALPHABET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
 
 /// Hierarchical address for a triangle in the Sierpinski fractal
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -120,8 +129,55 @@ impl TriangleAddress {
         }
     }
 
-    /// Parse from string representation
+    /// Convert to the checksummed form for human entry, e.g.
+    /// `tri1-01201-k7xq`; the genesis is `tri1-genesis-` plus its checksum
+    pub fn to_checked_string(&self) -> String {
+        let path = self.checked_path();
+        format!("{}{}-{}", CHECKED_ADDRESS_PREFIX, path, checksum(&path))
+    }
+
+    /// Parse the checksummed form, rejecting addresses whose checksum does
+    /// not match their path
+    pub fn from_checked_string(s: &str) -> SierpinskiResult<Self> {
+        let invalid = || SierpinskiError::InvalidAddress { address: s.to_string() };
+        let (path, suffix) = s
+            .strip_prefix(CHECKED_ADDRESS_PREFIX)
+            .and_then(|rest| rest.rsplit_once('-'))
+            .ok_or_else(invalid)?;
+
+        let address = if path == "genesis" {
+            TriangleAddress::genesis()
+        } else {
+            let components = path
+                .chars()
+                .map(|c| c.to_digit(10).map(|d| d as u8))
+                .collect::<Option<Vec<u8>>>()
+                .filter(|components| !components.is_empty())
+                .ok_or_else(invalid)?;
+            TriangleAddress::new(components)?
+        };
+
+        let expected = checksum(path);
+        if !suffix.eq_ignore_ascii_case(&expected) {
+            return Err(SierpinskiError::ChecksumMismatch { address: s.to_string(), expected });
+        }
+        Ok(address)
+    }
+
+    /// Path part of the checked form: the components run together
+    fn checked_path(&self) -> String {
+        if self.path.is_empty() {
+            "genesis".to_string()
+        } else {
+            self.path.iter().map(|c| char::from(b'0' + c)).collect()
+        }
+    }
+
+    /// Parse from string representation, dotted or checksummed
     pub fn from_string_representation(s: &str) -> SierpinskiResult<Self> {
+        if s.starts_with(CHECKED_ADDRESS_PREFIX) {
+            return Self::from_checked_string(s);
+        }
         if s == "genesis" {
             return Ok(TriangleAddress::genesis());
         }
@@ -162,6 +218,9 @@ impl TriangleAddress {
     }
 
     /// Generate all possible child addresses
+    ///
+    /// Returns all four, the void (component 3) last; use `children_active`
+    /// and `child_void` where the difference matters.
     pub fn children(&self) -> Vec<TriangleAddress> {
         let mut children = Vec::new();
         
@@ -174,6 +233,33 @@ impl TriangleAddress {
         children
     }
 
+    /// The three corner children that can themselves be subdivided
+    pub fn children_active(&self) -> Vec<TriangleAddress> {
+        (0..3).filter_map(|component| self.child(component).ok()).collect()
+    }
+
+    /// The central void child
+    pub fn child_void(&self) -> TriangleAddress {
+        let mut path = self.path.clone();
+        path.push(3);
+        TriangleAddress { path }
+    }
+
+    /// Descendants at absolute depth `depth`, never passing through a void
+    ///
+    /// Empty when `depth` is not below this address or this is a void.
+    pub fn descendants_at_depth(&self, depth: u8) -> Vec<TriangleAddress> {
+        if depth <= self.depth() || self.is_void() {
+            return Vec::new();
+        }
+
+        let mut level = vec![self.clone()];
+        for _ in self.depth()..depth {
+            level = level.iter().flat_map(TriangleAddress::children_active).collect();
+        }
+        level
+    }
+
     /// Calculate the theoretical position index at this depth
     pub fn position_index(&self) -> u64 {
         let mut index = 0u64;
@@ -202,6 +288,99 @@ impl TriangleAddress {
         
         TriangleAddress { path: common_path }
     }
+
+    /// Get the same-depth triangles that share an edge with this one under
+    /// `genesis`, including cousins in other subtrees
+    ///
+    /// Void triangles are part of the tiling, so they count as neighbors.
+    /// Edges on the genesis boundary have no neighbor, so corner and edge
+    /// triangles return fewer than three.
+    pub fn geometric_neighbors(&self, genesis: &Triangle) -> Vec<TriangleAddress> {
+        let vertices = self
+            .path
+            .iter()
+            .fold(*genesis.vertices(), |vertices, &component| {
+                child_vertices(&vertices, component)
+            });
+        let three = Decimal::from(3);
+
+        let mut neighbors = Vec::new();
+        for opposite in 0..3 {
+            let r = vertices[opposite];
+            let p = vertices[(opposite + 1) % 3];
+            let q = vertices[(opposite + 2) % 3];
+
+            // The neighbor across edge pq completes a parallelogram, so its far
+            // vertex is r reflected through the edge midpoint. Its centroid is
+            // strictly inside it and picks out one child at every level.
+            let far = Point::new(p.x + q.x - r.x, p.y + q.y - r.y);
+            let target = Point::new((p.x + q.x + far.x) / three, (p.y + q.y + far.y) / three);
+
+            if let Some(neighbor) = locate_point(genesis, &target, self.path.len()) {
+                neighbors.push(neighbor);
+            }
+        }
+
+        neighbors
+    }
+}
+
+/// Four checksum characters from the first 20 bits of the path's blake3 hash
+fn checksum(path: &str) -> String {
+    let hash = blake3::hash(format!("{}{}", CHECKED_ADDRESS_PREFIX, path).as_bytes());
+    let bytes = hash.as_bytes();
+    let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 4;
+
+    (0..4)
+        .rev()
+        .map(|i| char::from(CHECKSUM_ALPHABET[((bits >> (i * 5)) & 0x1f) as usize]))
+        .collect()
+}
+
+/// Vertices of child `component`, in the same order `subdivision_geometry` uses
+fn child_vertices(vertices: &[Point; 3], component: u8) -> [Point; 3] {
+    let [a, b, c] = *vertices;
+    let mid_ab = a.midpoint(&b);
+    let mid_bc = b.midpoint(&c);
+    let mid_ca = c.midpoint(&a);
+
+    match component {
+        0 => [a, mid_ab, mid_ca],
+        1 => [mid_ab, b, mid_bc],
+        2 => [mid_ca, mid_bc, c],
+        _ => [mid_ab, mid_bc, mid_ca],
+    }
+}
+
+/// Check whether `point` is inside or on the triangle, for either winding
+fn vertices_contain(vertices: &[Point; 3], point: &Point) -> bool {
+    let [a, b, c] = vertices;
+    let d1 = a.cross_product(b, point);
+    let d2 = b.cross_product(c, point);
+    let d3 = c.cross_product(a, point);
+
+    let has_negative = d1 < Decimal::ZERO || d2 < Decimal::ZERO || d3 < Decimal::ZERO;
+    let has_positive = d1 > Decimal::ZERO || d2 > Decimal::ZERO || d3 > Decimal::ZERO;
+    !(has_negative && has_positive)
+}
+
+/// Descend from the genesis to the depth-`depth` triangle containing `point`
+fn locate_point(genesis: &Triangle, point: &Point, depth: usize) -> Option<TriangleAddress> {
+    let mut vertices = *genesis.vertices();
+    if !vertices_contain(&vertices, point) {
+        return None;
+    }
+
+    let mut path = Vec::with_capacity(depth);
+    for _ in 0..depth {
+        let (component, child) = (0..=3)
+            .map(|component| (component, child_vertices(&vertices, component)))
+            .find(|(_, child)| vertices_contain(child, point))?;
+        path.push(component);
+        vertices = child;
+    }
+
+    Some(TriangleAddress { path })
 }
 
 impl fmt::Display for TriangleAddress {
@@ -318,6 +497,24 @@ mod tests {
         assert!(!siblings.contains(&address)); // Should not contain self
     }
 
+    #[test]
+    fn test_active_and_void_children() {
+        let address = TriangleAddress::new(vec![0, 1]).unwrap();
+        let active = address.children_active();
+        assert_eq!(active.len(), 3);
+        assert!(active.iter().all(|child| !child.is_void() && child.is_child_of(&address)));
+        assert_eq!(address.child_void().components(), &[0, 1, 3]);
+        assert_eq!(address.children().len(), 4);
+
+        let genesis = TriangleAddress::genesis();
+        assert_eq!(genesis.descendants_at_depth(1).len(), 3);
+        let depth_two = genesis.descendants_at_depth(2);
+        assert_eq!(depth_two.len(), 9);
+        assert!(depth_two.iter().all(|a| a.depth() == 2 && !a.components().contains(&3)));
+        assert!(genesis.descendants_at_depth(0).is_empty());
+        assert!(address.child_void().descendants_at_depth(4).is_empty());
+    }
+
     #[test]
     fn test_void_detection() {
         let void_address = TriangleAddress::new(vec![0, 1, 3]).unwrap();
@@ -341,4 +538,99 @@ mod tests {
         let result = TriangleAddress::new(vec![0, 1, 4]);
         assert!(matches!(result, Err(SierpinskiError::AddressComponentOutOfRange { component: 4 })));
     }
+
+    #[test]
+    fn test_checked_string_round_trip() {
+        let address = TriangleAddress::new(vec![0, 1, 2, 0, 1]).unwrap();
+        let checked = address.to_checked_string();
+        assert!(checked.starts_with("tri1-01201-"));
+        assert_eq!(checked.len(), "tri1-01201-".len() + 4);
+
+        assert_eq!(TriangleAddress::from_checked_string(&checked).unwrap(), address);
+        assert_eq!(TriangleAddress::from_string_representation(&checked).unwrap(), address);
+        assert_eq!(TriangleAddress::from_string_representation("0.1.2.0.1").unwrap(), address);
+    }
+
+    #[test]
+    fn test_checked_string_detects_typo() {
+        let checked = TriangleAddress::new(vec![0, 1, 2, 0, 1]).unwrap().to_checked_string();
+        let typo = checked.replacen("01201", "01211", 1);
+        let expected = TriangleAddress::new(vec![0, 1, 2, 1, 1]).unwrap().to_checked_string();
+
+        match TriangleAddress::from_string_representation(&typo) {
+            Err(SierpinskiError::ChecksumMismatch { address, expected: suffix }) => {
+                assert_eq!(address, typo);
+                assert!(expected.ends_with(&suffix));
+            }
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_checked_string_genesis() {
+        let checked = TriangleAddress::genesis().to_checked_string();
+        assert!(checked.starts_with("tri1-genesis-"));
+        assert!(TriangleAddress::from_checked_string(&checked).unwrap().is_genesis());
+
+        // An empty path is not a shorthand for the genesis
+        let empty = format!("tri1--{}", &checked[checked.len() - 4..]);
+        assert!(matches!(
+            TriangleAddress::from_checked_string(&empty),
+            Err(SierpinskiError::InvalidAddress { .. })
+        ));
+    }
+
+    #[test]
+    fn test_geometric_neighbors_at_depth_two() {
+        // Hand-drawn depth-2 tiling of a = (0,0), b = (4,0), c = (0,4),
+        // one row of cells per unit of height:
+        //
+        //   y 3..4:  2.2
+        //   y 2..3:  2.0 | 2.3 | 2.1
+        //   y 1..2:  0.2 | 3.2 | 3.3 | 3.1 | 1.2
+        //   y 0..1:  0.0 | 0.3 | 0.1 | 3.0 | 1.0 | 1.3 | 1.1
+        //
+        // Cells next to each other in a row share an edge, and each
+        // upward cell shares its bottom edge with the cell below it.
+        let point = |x: i64, y: i64| Point::new(Decimal::from(x), Decimal::from(y));
+        let genesis = Triangle::new(point(0, 0), point(4, 0), point(0, 4)).unwrap();
+        let neighbors = |s: &str| {
+            let mut found: Vec<String> = TriangleAddress::from_string_representation(s)
+                .unwrap()
+                .geometric_neighbors(&genesis)
+                .iter()
+                .map(|a| a.to_string_representation())
+                .collect();
+            found.sort();
+            found
+        };
+
+        assert_eq!(neighbors("0.0"), vec!["0.3"]);
+        assert_eq!(neighbors("0.1"), vec!["0.3", "3.0"]);
+        assert_eq!(neighbors("3.0"), vec!["0.1", "1.0", "3.3"]);
+        assert_eq!(neighbors("3.3"), vec!["3.0", "3.1", "3.2"]);
+        assert_eq!(neighbors("1.2"), vec!["1.3", "3.1"]);
+        assert_eq!(neighbors("2.0"), vec!["2.3", "3.2"]);
+        assert!(TriangleAddress::genesis().geometric_neighbors(&genesis).is_empty());
+    }
+
+    #[test]
+    fn test_geometric_neighbors_are_symmetric() {
+        let genesis = crate::core::genesis::genesis_triangle().unwrap();
+        let depth_two: Vec<TriangleAddress> = AddressBfsIterator::new(2)
+            .filter(|a| a.depth() == 2)
+            .collect();
+
+        let mut edge_ends = 0;
+        for address in &depth_two {
+            for neighbor in address.geometric_neighbors(&genesis) {
+                assert_eq!(neighbor.depth(), 2);
+                assert!(neighbor.geometric_neighbors(&genesis).contains(address));
+                edge_ends += 1;
+            }
+        }
+
+        // A side-4 triangular grid has 18 interior edges, each seen from both sides
+        assert_eq!(edge_ends, 36);
+    }
 }
@@ -3,14 +3,31 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use rust_decimal::Decimal;
+use ed25519_dalek::VerifyingKey;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::core::{
     triangle::Triangle,
     address::TriangleAddress,
+    fractal::FractalStructure,
     errors::{SierpinskiError, SierpinskiResult},
+    target::Target,
 };
 
+/// Largest transaction, serialized as on the wire, that the mempool or a
+/// block accepts
+pub const MAX_TRANSACTION_BYTES: usize = 16 * 1024;
+
+/// Largest block, serialized as on the wire, coinbase included
+pub const MAX_BLOCK_BYTES: usize = 1024 * 1024;
+
+/// How far ahead of local time a block may be timestamped (two hours)
+pub const MAX_FUTURE_BLOCK_SECS: u64 = 2 * 60 * 60;
+
+/// First header version whose `bits` hold a compact target; earlier headers
+/// hold a legacy difficulty in leading zero bits
+pub const COMPACT_TARGET_VERSION: u32 = 2;
+
 /// Transaction representing triangle operations
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TriangleTransaction {
@@ -22,6 +39,18 @@ pub struct TriangleTransaction {
     pub signature: Vec<u8>,
     pub timestamp: u64,
     pub gas_fee: Decimal,
+    /// Wallet that signed the transaction and pays its gas fee
+    #[serde(default)]
+    pub sender: Option<String>,
+    /// Wallet receiving a transferred triangle
+    #[serde(default)]
+    pub recipient: Option<String>,
+    /// Key the signature was made with; must hash to `sender` when both are set
+    #[serde(default, with = "crate::core::wallet::verifying_key_serde::option")]
+    pub sender_public_key: Option<VerifyingKey>,
+    /// Price a transfer was traded at, which becomes the triangle's market price
+    #[serde(default)]
+    pub price: Option<Decimal>,
 }
 
 /// Types of triangle operations
@@ -39,6 +68,8 @@ pub enum TriangleOperation {
     Stake { amount: Decimal },
     /// Claim mining rewards
     ClaimReward { amount: Decimal },
+    /// Block reward paid to the miner; the first transaction of every block
+    Coinbase { amount: Decimal },
 }
 
 /// Geometric proof for triangle operations
@@ -57,14 +88,41 @@ pub struct GeometricProof {
 pub struct BlockHeader {
     pub previous_hash: String,
     pub merkle_root: String,
+    /// `FractalStructure::state_root` of the state the block builds on
+    pub fractal_state_root: String,
     pub timestamp: u64,
     pub nonce: u64,
-    pub difficulty: u32,
+    /// Proof-of-work target in the encoding of `Target::from_compact`, or a
+    /// legacy difficulty before `COMPACT_TARGET_VERSION`; see `target`
+    #[serde(alias = "difficulty")]
+    pub bits: u32,
     pub version: u32,
     pub triangle_count: usize,
     pub total_area: Decimal,
 }
 
+impl BlockHeader {
+    /// Proof-of-work target the block hash must not exceed
+    pub fn target(&self) -> SierpinskiResult<Target> {
+        if self.version < COMPACT_TARGET_VERSION {
+            Ok(Target::from_legacy_difficulty(self.bits))
+        } else {
+            Target::from_compact(self.bits)
+        }
+    }
+
+    /// Whether a block hash meets the header's target; an undecodable
+    /// target is met by nothing
+    pub fn is_met_by(&self, hash: &[u8; 32]) -> bool {
+        self.target().is_ok_and(|target| target.is_met_by(hash))
+    }
+
+    /// Legacy difficulty the target rounds to, which sets the block reward
+    pub fn difficulty(&self) -> u32 {
+        self.target().map_or(0, |target| target.leading_zero_bits())
+    }
+}
+
 /// Complete block in the Sierpinski blockchain
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
@@ -97,9 +155,34 @@ impl TriangleTransaction {
                 .unwrap()
                 .as_secs(),
             gas_fee,
+            sender: None,
+            recipient: None,
+            sender_public_key: None,
+            price: None,
         }
     }
 
+    /// Create the coinbase transaction paying `amount` to `miner_address`
+    pub fn coinbase(miner_address: &str, amount: Decimal) -> Self {
+        let mut transaction = TriangleTransaction::new(
+            None,
+            TriangleAddress::genesis(),
+            TriangleOperation::Coinbase { amount },
+            None,
+            Decimal::ZERO,
+        );
+        transaction.recipient = Some(miner_address.to_string());
+        transaction
+    }
+
+    /// Balance key charged the gas fee: the sending wallet, or the source
+    /// triangle address for transactions without one
+    pub fn fee_payer(&self) -> Option<String> {
+        self.sender
+            .clone()
+            .or_else(|| self.from_address.as_ref().map(|address| address.to_string()))
+    }
+
     /// Calculate transaction hash
     pub fn hash(&self) -> String {
         let mut hasher = blake3::Hasher::new();
@@ -112,16 +195,63 @@ impl TriangleTransaction {
         }
         
         hasher.update(self.to_address.to_string().as_bytes());
+
+        if let Some(sender) = &self.sender {
+            hasher.update(sender.as_bytes());
+        }
+        if let Some(recipient) = &self.recipient {
+            hasher.update(recipient.as_bytes());
+        }
+        if let Some(public_key) = &self.sender_public_key {
+            hasher.update(public_key.as_bytes());
+        }
         
         if let Some(triangle) = &self.triangle_data {
-            hasher.update(triangle.hash().as_bytes());
+            hasher.update(triangle.canonical_hash().as_bytes());
+        }
+
+        if let Some(price) = &self.price {
+            hasher.update(b"price");
+            hasher.update(price.normalize().to_string().as_bytes());
+        }
+
+        let (operation, amount): (&[u8], _) = match &self.operation {
+            TriangleOperation::Create => (b"create", None),
+            TriangleOperation::Subdivide => (b"subdivide", None),
+            TriangleOperation::Transfer => (b"transfer", None),
+            TriangleOperation::Merge => (b"merge", None),
+            TriangleOperation::Stake { amount } => (b"stake", Some(amount)),
+            TriangleOperation::ClaimReward { amount } => (b"claim", Some(amount)),
+            TriangleOperation::Coinbase { amount } => (b"coinbase", Some(amount)),
+        };
+        hasher.update(operation);
+        if let Some(amount) = amount {
+            hasher.update(amount.normalize().to_string().as_bytes());
         }
+
+        hasher.update(b"gas");
+        hasher.update(self.gas_fee.normalize().to_string().as_bytes());
+        hasher.update(&self.signature);
         
         hasher.finalize().to_hex().to_string()
     }
 
+    /// Size in bytes of the transaction as gossiped between peers
+    pub fn serialized_size(&self) -> usize {
+        serialized_size(self)
+    }
+
     /// Validate transaction structure
     pub fn validate(&self) -> SierpinskiResult<bool> {
+        let size = self.serialized_size();
+        if size > MAX_TRANSACTION_BYTES {
+            return Err(SierpinskiError::SizeLimitExceeded {
+                item: format!("Transaction {}", self.id),
+                size,
+                limit: MAX_TRANSACTION_BYTES,
+            });
+        }
+
         // Check timestamp is reasonable
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
         if self.timestamp > now + 3600 { // Not more than 1 hour in future
@@ -133,22 +263,56 @@ impl TriangleTransaction {
             return Err(SierpinskiError::validation("Gas fee cannot be negative"));
         }
 
+        if let Some(price) = self.price {
+            if self.operation != TriangleOperation::Transfer {
+                return Err(SierpinskiError::validation("Only transfers carry a price"));
+            }
+            if price < Decimal::ZERO {
+                return Err(SierpinskiError::validation("Transfer price cannot be negative"));
+            }
+        }
+
         // Operation-specific validation
         match &self.operation {
-            TriangleOperation::Create => {
-                if self.triangle_data.is_none() {
-                    return Err(SierpinskiError::validation("Create operation requires triangle data"));
-                }
+            TriangleOperation::Create if self.triangle_data.is_none() => {
+                return Err(SierpinskiError::validation("Create operation requires triangle data"));
+            }
+            TriangleOperation::Transfer if self.from_address.is_none() => {
+                return Err(SierpinskiError::validation("Transfer requires from address"));
             }
-            TriangleOperation::Transfer => {
-                if self.from_address.is_none() {
-                    return Err(SierpinskiError::validation("Transfer requires from address"));
-                }
+            // Ownership is checked against the sender, so it must be signed for
+            TriangleOperation::Transfer if self.sender.is_none() => {
+                return Err(SierpinskiError::validation("Transfer requires a signed sender"));
             }
-            TriangleOperation::Stake { amount } => {
-                if *amount <= Decimal::ZERO {
-                    return Err(SierpinskiError::validation("Stake amount must be positive"));
-                }
+            TriangleOperation::Transfer if self.recipient.is_none() => {
+                return Err(SierpinskiError::validation("Transfer requires a recipient"));
+            }
+            TriangleOperation::Stake { amount } if *amount <= Decimal::ZERO => {
+                return Err(SierpinskiError::validation("Stake amount must be positive"));
+            }
+            // The stake is drawn from the sender, who must own the triangle
+            TriangleOperation::Stake { .. } if self.from_address.is_none() || self.sender.is_none() => {
+                return Err(SierpinskiError::validation("Stake requires a triangle and a signed sender"));
+            }
+            // The sender must own the triangle split, and pays for it
+            TriangleOperation::Subdivide if self.from_address.is_none() || self.sender.is_none() => {
+                return Err(SierpinskiError::validation("Subdivide requires a triangle and a signed sender"));
+            }
+            TriangleOperation::ClaimReward { amount } if *amount <= Decimal::ZERO => {
+                return Err(SierpinskiError::validation("Claim amount must be positive"));
+            }
+            TriangleOperation::ClaimReward { .. } if self.sender.is_none() => {
+                return Err(SierpinskiError::validation("Reward claim requires a sender"));
+            }
+            TriangleOperation::Coinbase { amount } if *amount < Decimal::ZERO => {
+                return Err(SierpinskiError::validation("Coinbase amount cannot be negative"));
+            }
+            TriangleOperation::Coinbase { .. }
+                if self.recipient.is_none() || self.fee_payer().is_some() || !self.gas_fee.is_zero() =>
+            {
+                return Err(SierpinskiError::validation(
+                    "Coinbase requires a recipient and no sender or gas fee",
+                ));
             }
             _ => {}
         }
@@ -158,30 +322,50 @@ impl TriangleTransaction {
 }
 
 impl Block {
-    /// Create a new block
+    /// Create a new block paying the scheduled reward through a coinbase
+    /// placed before `transactions`
     pub fn new(
         previous_hash: String,
         transactions: Vec<TriangleTransaction>,
+        fractal_state: &FractalStructure,
         miner_address: String,
-        difficulty: u32,
+        target: Target,
+    ) -> Self {
+        let block_reward = Self::calculate_block_reward(target.leading_zero_bits(), &transactions);
+        Self::new_with_reward(previous_hash, transactions, fractal_state, miner_address, target, block_reward)
+    }
+
+    /// Create a new block whose coinbase pays `block_reward`, for rewards
+    /// held below the schedule by the supply cap and for the genesis supply
+    pub fn new_with_reward(
+        previous_hash: String,
+        transactions: Vec<TriangleTransaction>,
+        fractal_state: &FractalStructure,
+        miner_address: String,
+        target: Target,
+        block_reward: Decimal,
     ) -> Self {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
-        let merkle_root = Self::calculate_merkle_root(&transactions);
         let triangle_count = transactions.len();
         let total_area = Self::calculate_total_area(&transactions);
-        let block_reward = Self::calculate_block_reward(difficulty, &transactions);
+        let transactions: Vec<TriangleTransaction> =
+            std::iter::once(TriangleTransaction::coinbase(&miner_address, block_reward))
+                .chain(transactions)
+                .collect();
+        let merkle_root = Self::calculate_merkle_root(&transactions);
 
         let header = BlockHeader {
             previous_hash,
             merkle_root: merkle_root.clone(),
+            fractal_state_root: fractal_state.state_root(),
             timestamp,
             nonce: 0,
-            difficulty,
-            version: 1,
+            bits: target.to_compact(),
+            version: COMPACT_TARGET_VERSION,
             triangle_count,
             total_area,
         };
@@ -192,7 +376,7 @@ impl Block {
             area_conservation: true,
             merkle_root,
             nonce: 0,
-            difficulty,
+            difficulty: target.leading_zero_bits(),
         };
 
         Block {
@@ -205,35 +389,22 @@ impl Block {
         }
     }
 
-    /// Calculate Merkle root of transactions
-    fn calculate_merkle_root(transactions: &[TriangleTransaction]) -> String {
-        if transactions.is_empty() {
-            return "0".repeat(64);
-        }
+    /// Get the coinbase transaction, which leads every well-formed block
+    pub fn coinbase(&self) -> Option<&TriangleTransaction> {
+        self.triangle_transactions
+            .first()
+            .filter(|tx| matches!(tx.operation, TriangleOperation::Coinbase { .. }))
+    }
 
-        let mut hashes: Vec<String> = transactions
-            .iter()
-            .map(|tx| tx.hash())
-            .collect();
-
-        while hashes.len() > 1 {
-            let mut next_level = Vec::new();
-            
-            for chunk in hashes.chunks(2) {
-                let mut hasher = blake3::Hasher::new();
-                hasher.update(chunk[0].as_bytes());
-                if chunk.len() > 1 {
-                    hasher.update(chunk[1].as_bytes());
-                } else {
-                    hasher.update(chunk[0].as_bytes()); // Duplicate if odd number
-                }
-                next_level.push(hasher.finalize().to_hex().to_string());
-            }
-            
-            hashes = next_level;
-        }
+    /// Reward the schedule allows for this block, before the supply cap
+    pub fn scheduled_reward(&self) -> Decimal {
+        let transactions = self.triangle_transactions.get(1..).unwrap_or_default();
+        Self::calculate_block_reward(self.header.difficulty(), transactions)
+    }
 
-        hashes[0].clone()
+    /// Calculate Merkle root of transactions
+    fn calculate_merkle_root(transactions: &[TriangleTransaction]) -> String {
+        merkle_root(transactions.iter().map(|tx| tx.hash()).collect())
     }
 
     /// Calculate total area involved in transactions
@@ -251,15 +422,16 @@ impl Block {
         
         for tx in transactions {
             if let Some(triangle) = &tx.triangle_data {
-                hasher.update(triangle.hash().as_bytes());
+                hasher.update(triangle.canonical_hash().as_bytes());
             }
         }
         
         hasher.finalize().to_hex().to_string()
     }
 
-    /// Calculate block reward based on difficulty and triangle operations
-    fn calculate_block_reward(difficulty: u32, transactions: &[TriangleTransaction]) -> Decimal {
+    /// Calculate block reward based on difficulty, in leading zero bits, and
+    /// triangle operations
+    pub(crate) fn calculate_block_reward(difficulty: u32, transactions: &[TriangleTransaction]) -> Decimal {
         let base_reward = Decimal::new(50, 0); // Base 50 tokens
         let difficulty_multiplier = Decimal::new(difficulty as i64, 0) / Decimal::new(100, 0);
         let transaction_bonus = Decimal::new(transactions.len() as i64, 1); // 0.1 per transaction
@@ -269,20 +441,46 @@ impl Block {
 
     /// Calculate block hash
     pub fn hash(&self) -> String {
+        hex::encode(self.hash_bytes())
+    }
+
+    /// Raw block hash, big-endian when read as a 256-bit integer
+    pub fn hash_bytes(&self) -> [u8; 32] {
+        Self::compute_hash(&self.header, &self.geometric_proof.triangle_hash)
+    }
+
+    /// Block hash from a header and the geometric proof hash, the only other
+    /// field the hash covers; lets headers be checked without the full block
+    pub fn compute_hash(header: &BlockHeader, proof_hash: &str) -> [u8; 32] {
         let mut hasher = blake3::Hasher::new();
         
-        hasher.update(self.header.previous_hash.as_bytes());
-        hasher.update(self.header.merkle_root.as_bytes());
-        hasher.update(&self.header.timestamp.to_le_bytes());
-        hasher.update(&self.header.nonce.to_le_bytes());
-        hasher.update(&self.header.difficulty.to_le_bytes());
-        hasher.update(self.geometric_proof.triangle_hash.as_bytes());
+        hasher.update(header.previous_hash.as_bytes());
+        hasher.update(header.merkle_root.as_bytes());
+        hasher.update(header.fractal_state_root.as_bytes());
+        hasher.update(&header.timestamp.to_le_bytes());
+        hasher.update(&header.nonce.to_le_bytes());
+        hasher.update(&header.bits.to_le_bytes());
+        hasher.update(proof_hash.as_bytes());
         
-        hasher.finalize().to_hex().to_string()
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Size in bytes of the block as gossiped between peers
+    pub fn serialized_size(&self) -> usize {
+        serialized_size(self)
     }
 
     /// Validate block structure and proofs
     pub fn validate(&self) -> SierpinskiResult<bool> {
+        let size = self.serialized_size();
+        if size > MAX_BLOCK_BYTES {
+            return Err(SierpinskiError::SizeLimitExceeded {
+                item: format!("Block {}", self.height),
+                size,
+                limit: MAX_BLOCK_BYTES,
+            });
+        }
+
         // Validate all transactions
         for tx in &self.triangle_transactions {
             tx.validate()?;
@@ -294,6 +492,27 @@ impl Block {
             return Err(SierpinskiError::validation("Invalid Merkle root"));
         }
 
+        // Exactly one coinbase, first, paying the miner the block reward
+        let coinbase = self.coinbase()
+            .ok_or_else(|| SierpinskiError::validation("Block must start with a coinbase"))?;
+        let coinbases = self.triangle_transactions
+            .iter()
+            .filter(|tx| matches!(tx.operation, TriangleOperation::Coinbase { .. }))
+            .count();
+        if coinbases != 1 {
+            return Err(SierpinskiError::validation("Block must contain exactly one coinbase"));
+        }
+        if coinbase.operation != (TriangleOperation::Coinbase { amount: self.block_reward })
+            || coinbase.recipient.as_ref() != Some(&self.miner_address)
+        {
+            return Err(SierpinskiError::validation("Coinbase does not pay the block reward to the miner"));
+        }
+        // A miner may take less than the schedule allows, never more; the
+        // genesis coinbase carries the initial supply instead
+        if self.height > 0 && self.block_reward > self.scheduled_reward() {
+            return Err(SierpinskiError::validation("Block reward exceeds the reward schedule"));
+        }
+
         // Validate geometric proof
         if !self.geometric_proof.subdivision_valid {
             return Err(SierpinskiError::validation("Invalid subdivision proof"));
@@ -301,18 +520,32 @@ impl Block {
 
         // Validate timestamp
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        if self.header.timestamp > now + 7200 { // Not more than 2 hours in future
+        if self.header.timestamp > now + MAX_FUTURE_BLOCK_SECS {
             return Err(SierpinskiError::validation("Block timestamp too far in future"));
         }
 
         Ok(true)
     }
 
-    /// Check if block meets difficulty target
+    /// Validate the block as it applies on top of `fractal_state`, which
+    /// its header must commit to
+    pub fn validate_with_state(&self, fractal_state: &FractalStructure) -> SierpinskiResult<bool> {
+        self.validate()?;
+
+        if fractal_state.state_root() != self.header.fractal_state_root {
+            return Err(SierpinskiError::validation(format!(
+                "Block {} commits to a different fractal state",
+                self.height
+            )));
+        }
+
+        Ok(true)
+    }
+
+    /// Check if block meets difficulty target, i.e. its hash read as a
+    /// 256-bit integer is at most the header's target
     pub fn meets_difficulty_target(&self) -> bool {
-        let hash = self.hash();
-        let leading_zeros = hash.chars().take_while(|&c| c == '0').count();
-        leading_zeros >= self.header.difficulty as usize
+        self.header.is_met_by(&self.hash_bytes())
     }
 
     /// Set the nonce (used during mining)
@@ -322,6 +555,39 @@ impl Block {
     }
 }
 
+/// Merkle root of a list of hashes, pairing the last hash with itself on
+/// odd levels; all zeros for an empty list
+pub(crate) fn merkle_root(mut hashes: Vec<String>) -> String {
+    if hashes.is_empty() {
+        return "0".repeat(64);
+    }
+
+    while hashes.len() > 1 {
+        let mut next_level = Vec::new();
+        
+        for chunk in hashes.chunks(2) {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(chunk[0].as_bytes());
+            if chunk.len() > 1 {
+                hasher.update(chunk[1].as_bytes());
+            } else {
+                hasher.update(chunk[0].as_bytes()); // Duplicate if odd number
+            }
+            next_level.push(hasher.finalize().to_hex().to_string());
+        }
+        
+        hashes = next_level;
+    }
+
+    hashes[0].clone()
+}
+
+/// JSON-encoded length of `value`, the encoding network messages use;
+/// anything that fails to encode counts as too large
+fn serialized_size<T: Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map_or(usize::MAX, |bytes| bytes.len())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -350,14 +616,31 @@ mod tests {
         assert!(tx.validate().unwrap());
     }
 
+    #[test]
+    fn test_transaction_hash_commits_to_every_field() {
+        let tx = create_test_transaction();
+
+        let mut operation = tx.clone();
+        operation.operation = TriangleOperation::Subdivide;
+        let mut gas_fee = tx.clone();
+        gas_fee.gas_fee = Decimal::new(2, 2);
+        let mut signature = tx.clone();
+        signature.signature = vec![1; 64];
+
+        for edited in [operation, gas_fee, signature] {
+            assert_ne!(edited.hash(), tx.hash());
+        }
+    }
+
     #[test]
     fn test_block_creation() {
         let transactions = vec![create_test_transaction()];
         let block = Block::new(
             "previous_hash".to_string(),
             transactions,
+            &FractalStructure::new(),
             "miner_address".to_string(),
-            4,
+            Target::from_legacy_difficulty(4),
         );
         
         assert!(!block.hash().is_empty());
@@ -365,16 +648,100 @@ mod tests {
         assert_eq!(block.header.triangle_count, 1);
     }
 
+    #[test]
+    fn test_legacy_header_keeps_its_difficulty() {
+        let block = Block::new(
+            "previous_hash".to_string(),
+            vec![],
+            &FractalStructure::new(),
+            "miner_address".to_string(),
+            Target::from_legacy_difficulty(12),
+        );
+        assert_eq!(block.header.difficulty(), 12);
+
+        // Headers from before compact targets named the field `difficulty`
+        // and stored leading zero bits there
+        let mut encoded = serde_json::to_value(&block.header).unwrap();
+        let header = encoded.as_object_mut().unwrap();
+        header.remove("bits");
+        header.insert("difficulty".to_string(), 12.into());
+        header.insert("version".to_string(), 1.into());
+        let legacy: BlockHeader = serde_json::from_value(encoded).unwrap();
+        assert_eq!(legacy.target().unwrap(), Target::from_legacy_difficulty(12));
+        assert_eq!(legacy.difficulty(), 12);
+    }
+
     #[test]
     fn test_merkle_root_calculation() {
         let tx1 = create_test_transaction();
         let tx2 = create_test_transaction();
         
-        let root1 = Block::calculate_merkle_root(&[tx1.clone()]);
+        let root1 = Block::calculate_merkle_root(std::slice::from_ref(&tx1));
         let root2 = Block::calculate_merkle_root(&[tx1, tx2]);
         
         assert_ne!(root1, root2);
         assert!(!root1.is_empty());
         assert!(!root2.is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_fractal_state_root_tracks_triangle_state() {
+        use crate::core::{
+            fractal::FractalTriangle,
+            genesis::genesis_triangle,
+            state::TriangleState,
+            subdivision::subdivide_to_depth,
+        };
+
+        let mut state = subdivide_to_depth(FractalTriangle::genesis(genesis_triangle().unwrap()), 2).unwrap();
+        let block = Block::new("previous_hash".to_string(), Vec::new(), &state, "miner".to_string(), Target::from_legacy_difficulty(4));
+        assert_eq!(block.header.fractal_state_root, state.state_root());
+        assert!(block.validate_with_state(&state).unwrap());
+
+        // Ids are left out, so an identical structure built elsewhere agrees
+        let rebuilt = subdivide_to_depth(FractalTriangle::genesis(genesis_triangle().unwrap()), 2).unwrap();
+        assert_eq!(rebuilt.state_root(), state.state_root());
+
+        let id = state.triangles().find(|t| t.state == TriangleState::Active).unwrap().id;
+        state.get_triangle_mut(&id).unwrap().change_state_default(TriangleState::Inactive).unwrap();
+        assert_ne!(state.state_root(), block.header.fractal_state_root);
+        assert!(block.validate_with_state(&state).is_err());
+
+        // The root is covered by the block hash
+        let mut forged = block.clone();
+        forged.header.fractal_state_root = state.state_root();
+        assert_ne!(forged.hash(), block.hash());
+    }
+
+    /// Hash with the given leading bytes and every other byte 0xff
+    fn hash_with_prefix(prefix: &[u8]) -> [u8; 32] {
+        let mut hash = [0xff; 32];
+        hash[..prefix.len()].copy_from_slice(prefix);
+        hash
+    }
+
+    #[test]
+    fn test_hash_meets_difficulty_at_bit_granularity() {
+        let cases: [(&[u8], u32, bool); 9] = [
+            (&[0x00, 0xff], 8, true),
+            (&[0x01], 8, false),
+            (&[0x00, 0x0f], 12, true),
+            (&[0x00, 0x10], 12, false),
+            // 13 zero bits beats a 12-bit target
+            (&[0x00, 0x07], 12, true),
+            (&[0x00, 0x00, 0x0f], 20, true),
+            (&[0x00, 0x00, 0x10], 20, false),
+            (&[0x00, 0x01], 20, false),
+            (&[0x00, 0x00, 0x00, 0x01], 20, true),
+        ];
+        for (prefix, difficulty, expected) in cases {
+            assert_eq!(
+                Target::from_legacy_difficulty(difficulty).is_met_by(&hash_with_prefix(prefix)),
+                expected,
+                "{:02x?} at difficulty {}",
+                prefix,
+                difficulty
+            );
+        }
+    }
+}
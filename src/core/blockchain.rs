@@ -1,16 +1,234 @@
 //! Blockchain implementation for TriadChain cryptocurrency
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
+use tracing::{info, warn};
+use uuid::Uuid;
 
 use crate::core::{
-    block::{Block, TriangleTransaction, TriangleOperation},
+    block::{Block, TriangleTransaction, TriangleOperation, MAX_BLOCK_BYTES, MAX_FUTURE_BLOCK_SECS},
+    economics::{EconomicsEngine, EconomicsStats},
     fractal::{FractalStructure, FractalTriangle},
     address::TriangleAddress,
     errors::{SierpinskiError, SierpinskiResult},
+    snapshot::{self, ChainSnapshot},
+    state::TriangleState,
+    subdivision::subdivide_and_add_to_structure,
+    target::Target,
+    genesis::MIN_TRIANGLE_AREA,
+    validation::ValidationResult,
+    wallet::{FeePriority, TriadChainWallet, FEE_HISTORY_BLOCKS, MIN_GAS_FEE},
 };
 
+/// Default age after which pending transactions are evicted (one hour)
+pub const DEFAULT_MAX_MEMPOOL_AGE_SECS: u64 = 3600;
+
+/// Default upper bound on the number of pending transactions
+pub const DEFAULT_MAX_MEMPOOL_SIZE: usize = 10_000;
+
+/// Default age after which transactions can no longer be included in a
+/// block (one day)
+pub const DEFAULT_TRANSACTION_EXPIRY_SECS: u64 = 24 * 60 * 60;
+
+/// A block must be timestamped after the median of this many previous blocks
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Most blocks held in the orphan pool while their parents are missing
+pub const MAX_ORPHAN_BLOCKS: usize = 100;
+
+/// Most orphans held from any one peer
+pub const MAX_ORPHANS_PER_PEER: usize = 20;
+
+/// Seconds an orphan is held before its parent is given up on
+pub const MAX_ORPHAN_AGE_SECS: u64 = 20 * 60;
+
+/// Most recent chain states kept for reorganizations to replay from; forks
+/// deeper than these replay from the snapshot or genesis instead
+pub const MAX_STATE_CHECKPOINTS: usize = 16;
+
+/// Largest factor by which a single retarget may scale the target
+const MAX_RETARGET_FACTOR: u64 = 4;
+
+/// Tokens credited to the genesis miner
+const GENESIS_SUPPLY: Decimal = Decimal::from_parts(1_000_000, 0, 0, false, 0); // 1 million initial tokens
+
+/// Default cap on total supply, matching `EconomicsEngine`
+pub const DEFAULT_MAX_SUPPLY: Decimal = Decimal::from_parts(21_000_000, 0, 0, false, 0);
+
+/// Rewards wallets can claim on chain with `ClaimReward`
+///
+/// Derived from chain data alone, so replaying the same blocks rebuilds the
+/// same ledger on every node: the gas fees a block collects fund the pool and
+/// accrue to the block's miner.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RewardLedger {
+    /// Rewards ever accrued per wallet
+    pub accrued: HashMap<String, Decimal>,
+    /// Rewards claimed on chain per wallet
+    pub claimed: HashMap<String, Decimal>,
+    /// Tokens ever placed in the rewards pool; already part of total supply
+    pub pool_funded: Decimal,
+    /// Tokens paid out of the pool by claims
+    pub pool_paid: Decimal,
+}
+
+impl RewardLedger {
+    /// Rewards a wallet has accrued but not yet claimed
+    pub fn claimable(&self, wallet: &str) -> Decimal {
+        let accrued = self.accrued.get(wallet).copied().unwrap_or(Decimal::ZERO);
+        let claimed = self.claimed.get(wallet).copied().unwrap_or(Decimal::ZERO);
+        accrued - claimed
+    }
+
+    /// Tokens left in the rewards pool
+    pub fn pool_balance(&self) -> Decimal {
+        self.pool_funded - self.pool_paid
+    }
+}
+
+/// Parameters controlling difficulty retargeting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DifficultyParams {
+    /// Number of blocks between retargets, also the span that is timed
+    pub window: usize,
+    /// Desired seconds between blocks
+    pub target_block_time_secs: u64,
+    /// Bounds on the target, as legacy difficulties in leading zero bits
+    pub min_difficulty: u32,
+    pub max_difficulty: u32,
+}
+
+impl Default for DifficultyParams {
+    fn default() -> Self {
+        DifficultyParams {
+            window: 10,
+            target_block_time_secs: 60,
+            min_difficulty: 1,
+            max_difficulty: 80,
+        }
+    }
+}
+
+/// Parameters of the mempool's gas fee floor, which rises with the depth
+/// of the triangle a transaction targets, see `minimum_gas_for`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasFloorParams {
+    /// Floor for transactions targeting the genesis triangle
+    pub base: Decimal,
+    /// Fraction of `base` added for every level of depth
+    pub depth_factor: Decimal,
+}
+
+impl GasFloorParams {
+    /// Floor for a transaction targeting `address`: `base`, raised by
+    /// `depth_factor` of itself for each level below the genesis
+    pub fn minimum_for(&self, address: &TriangleAddress) -> Decimal {
+        self.base * (Decimal::ONE + self.depth_factor * Decimal::from(address.depth()))
+    }
+}
+
+impl Default for GasFloorParams {
+    fn default() -> Self {
+        GasFloorParams {
+            base: MIN_GAS_FEE,
+            depth_factor: Decimal::from_parts(25, 0, 0, false, 2), // 0.25
+        }
+    }
+}
+
+/// Changes made by the transactions of a block checked so far, which the
+/// next one is checked against
+#[derive(Default)]
+struct PendingEffects {
+    /// New owner of each triangle transferred
+    owners: HashMap<TriangleAddress, String>,
+    /// Amount leaving each payer's balance so far, in gas fees and stakes,
+    /// less reward claims paid to it
+    spent: HashMap<String, Decimal>,
+    /// Triangles subdivided
+    subdivided: std::collections::HashSet<TriangleAddress>,
+    /// Addresses of triangles created
+    created: std::collections::HashSet<TriangleAddress>,
+}
+
+/// Chain state as it stood once the block at `height` had applied, kept so
+/// a reorganization forking after it replays only the blocks since
+///
+/// Blocks and the mempool are left out, and the transaction index and
+/// ownership history, which only grow block by block, are rewound by height
+/// instead.
+#[derive(Debug, Clone)]
+struct StateCheckpoint {
+    height: u64,
+    fractal_state: FractalStructure,
+    target: Target,
+    total_supply: Decimal,
+    balances: HashMap<String, Decimal>,
+    triangle_owners: HashMap<TriangleAddress, String>,
+    cumulative_difficulty: u128,
+    rewards: RewardLedger,
+    economics: EconomicsEngine,
+}
+
+impl StateCheckpoint {
+    /// Copy the state of a chain at its tip
+    fn capture(chain: &TriadChainBlockchain) -> Self {
+        StateCheckpoint {
+            height: chain.tip_height(),
+            fractal_state: chain.fractal_state.clone(),
+            target: chain.target,
+            total_supply: chain.total_supply,
+            balances: chain.balances.clone(),
+            triangle_owners: chain.triangle_owners.clone(),
+            cumulative_difficulty: chain.cumulative_difficulty,
+            rewards: chain.rewards.clone(),
+            economics: chain.economics.clone(),
+        }
+    }
+
+    /// Trade this state for the one on `chain`
+    fn swap(&mut self, chain: &mut TriadChainBlockchain) {
+        std::mem::swap(&mut self.fractal_state, &mut chain.fractal_state);
+        std::mem::swap(&mut self.target, &mut chain.target);
+        std::mem::swap(&mut self.total_supply, &mut chain.total_supply);
+        std::mem::swap(&mut self.balances, &mut chain.balances);
+        std::mem::swap(&mut self.triangle_owners, &mut chain.triangle_owners);
+        std::mem::swap(&mut self.cumulative_difficulty, &mut chain.cumulative_difficulty);
+        std::mem::swap(&mut self.rewards, &mut chain.rewards);
+        std::mem::swap(&mut self.economics, &mut chain.economics);
+    }
+}
+
+/// Transaction index and ownership history entries of the blocks above
+/// some height, taken off a chain while it is rewound
+struct LaterRecords {
+    transactions: Vec<(Uuid, u64)>,
+    ownership: Vec<(TriangleAddress, Vec<(String, u64)>)>,
+}
+
+/// Triangles a block actually created and subdivided, which the token
+/// economics advance by
+#[derive(Default)]
+struct BlockActivity {
+    created: u32,
+    subdivided: u32,
+}
+
+/// Which block bodies a chain keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChainMode {
+    /// Keep every block's transactions
+    #[default]
+    Full,
+    /// Keep transactions only for the most recent `keep_recent` blocks;
+    /// older blocks keep their header, and so their hash and merkle root
+    HeaderOnly { keep_recent: u64 },
+}
+
 /// The main blockchain structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TriadChainBlockchain {
@@ -20,27 +238,131 @@ pub struct TriadChainBlockchain {
     pub fractal_state: FractalStructure,
     /// Pending transactions waiting to be mined
     pub mempool: Vec<TriangleTransaction>,
-    /// Current mining difficulty
-    pub difficulty: u32,
+    /// Target the next block's hash must meet; chains saved before compact
+    /// targets stored a legacy `difficulty` here
+    #[serde(alias = "difficulty")]
+    pub target: Target,
     /// Total tokens in circulation
     pub total_supply: Decimal,
     /// Balance tracking by address
     pub balances: HashMap<String, Decimal>,
     /// Triangle ownership mapping
+    #[serde(with = "address_map_serde")]
     pub triangle_owners: HashMap<TriangleAddress, String>,
+    /// Every owner each triangle has had, oldest first, with the height of
+    /// the block that made them owner; a chain restored from a snapshot only
+    /// knows the owners since
+    #[serde(default, with = "address_map_serde")]
+    pub ownership_history: HashMap<TriangleAddress, Vec<(String, u64)>>,
+    /// Height of the block including each transaction, for every block kept
+    /// in `blocks`
+    #[serde(default)]
+    pub transaction_index: HashMap<Uuid, u64>,
+    /// Pending transactions older than this many seconds are evicted
+    #[serde(default = "default_max_mempool_age_secs")]
+    pub max_mempool_age_secs: u64,
+    /// Maximum number of pending transactions
+    #[serde(default = "default_max_mempool_size")]
+    pub max_mempool_size: usize,
+    /// Transactions older than this many seconds at a block's timestamp
+    /// make the block invalid, and are evicted from the mempool
+    #[serde(default = "default_transaction_expiry_secs")]
+    pub transaction_expiry_secs: u64,
+    /// Proof-of-work summed over every block, see `total_work`
+    ///
+    /// Snapshots written before this field existed load it as zero; call
+    /// `recompute_total_work` after loading one.
+    #[serde(default)]
+    pub cumulative_difficulty: u128,
+    /// Difficulty retarget settings
+    #[serde(default)]
+    pub difficulty_params: DifficultyParams,
+    /// Snapshot this chain was bootstrapped from, if any; blocks before it
+    /// are not kept, so reorganizations replay from it instead of genesis
+    #[serde(default)]
+    pub snapshot_base: Option<ChainSnapshot>,
+    /// Claimable rewards and the pool paying them
+    #[serde(default)]
+    pub rewards: RewardLedger,
+    /// Total supply is never minted past this
+    #[serde(default = "default_max_supply")]
+    pub max_supply: Decimal,
+    /// Depth-scaled fee floor enforced by `add_transaction`, which
+    /// `estimate_gas_fee_for` never suggests going below
+    #[serde(default)]
+    pub gas_floor: GasFloorParams,
+    /// Token economics, advanced by every block applied
+    #[serde(default)]
+    pub economics: EconomicsEngine,
+    /// Blocks received before their parents, keyed by the missing parent's
+    /// hash; see `add_block_or_orphan`
+    #[serde(skip)]
+    pub orphan_pool: HashMap<String, Vec<Orphan>>,
+    /// Whether old block bodies are pruned, see `set_mode`
+    #[serde(default)]
+    pub mode: ChainMode,
+    /// Blocks below this height have had their transactions pruned
+    #[serde(default)]
+    pub pruned_below: u64,
+    /// Callbacks told about state changes, see `subscribe`
+    #[serde(skip)]
+    hooks: EventHooks,
+    /// States before the most recent blocks appended, oldest first
+    #[serde(skip)]
+    checkpoints: VecDeque<StateCheckpoint>,
+}
+
+fn default_max_supply() -> Decimal {
+    DEFAULT_MAX_SUPPLY
+}
+
+fn default_max_mempool_age_secs() -> u64 {
+    DEFAULT_MAX_MEMPOOL_AGE_SECS
+}
+
+fn default_max_mempool_size() -> usize {
+    DEFAULT_MAX_MEMPOOL_SIZE
+}
+
+fn default_transaction_expiry_secs() -> u64 {
+    DEFAULT_TRANSACTION_EXPIRY_SECS
 }
 
 impl TriadChainBlockchain {
     /// Create a new blockchain with genesis block
     pub fn new() -> SierpinskiResult<Self> {
+        Self::with_target(Target::from_legacy_difficulty(16)) // Start with 16 leading zero bits
+    }
+
+    /// Create a new blockchain whose genesis block, and so every block up
+    /// to the first retarget, is mined at `target`, such as an easy one for
+    /// a test network
+    pub fn with_target(target: Target) -> SierpinskiResult<Self> {
         let mut blockchain = TriadChainBlockchain {
             blocks: Vec::new(),
             fractal_state: FractalStructure::new(),
             mempool: Vec::new(),
-            difficulty: 4, // Start with 4 leading zeros
+            target,
             total_supply: Decimal::ZERO,
             balances: HashMap::new(),
             triangle_owners: HashMap::new(),
+            ownership_history: HashMap::new(),
+            transaction_index: HashMap::new(),
+            max_mempool_age_secs: DEFAULT_MAX_MEMPOOL_AGE_SECS,
+            max_mempool_size: DEFAULT_MAX_MEMPOOL_SIZE,
+            transaction_expiry_secs: DEFAULT_TRANSACTION_EXPIRY_SECS,
+            cumulative_difficulty: 0,
+            difficulty_params: DifficultyParams::default(),
+            snapshot_base: None,
+            rewards: RewardLedger::default(),
+            max_supply: DEFAULT_MAX_SUPPLY,
+            gas_floor: GasFloorParams::default(),
+            economics: EconomicsEngine::new(),
+            orphan_pool: HashMap::new(),
+            mode: ChainMode::Full,
+            pruned_below: 0,
+            hooks: EventHooks::default(),
+            checkpoints: VecDeque::new(),
         };
 
         blockchain.create_genesis_block()?;
@@ -50,53 +372,83 @@ impl TriadChainBlockchain {
     /// Create the genesis block with initial triangle
     fn create_genesis_block(&mut self) -> SierpinskiResult<()> {
         // Create genesis triangle
-        let genesis_triangle = crate::core::genesis::genesis_fractal_triangle()?;
-        let genesis_address = genesis_triangle.address.clone();
-        
-        // Set genesis in fractal state
-        self.fractal_state.set_genesis(genesis_triangle.clone())?;
+        let genesis_triangle = crate::core::genesis::genesis_triangle()?;
 
         // Create genesis transaction
         let genesis_tx = TriangleTransaction::new(
             None,
-            genesis_address.clone(),
+            TriangleAddress::genesis(),
             TriangleOperation::Create,
-            Some(genesis_triangle.triangle.clone()),
+            Some(genesis_triangle),
             Decimal::ZERO, // No gas fee for genesis
         );
 
-        // Create genesis block
-        let mut genesis_block = Block::new(
+        // Create genesis block, its coinbase minting the initial supply
+        let mut genesis_block = Block::new_with_reward(
             "0".repeat(64), // Previous hash for genesis is all zeros
             vec![genesis_tx],
+            &self.fractal_state,
             "genesis_miner".to_string(),
-            self.difficulty,
+            self.target,
+            GENESIS_SUPPLY,
         );
         
         genesis_block.height = 0;
-        
+
+        self.apply_genesis_block(genesis_block)
+    }
+
+    /// Seed the state from a genesis block and make it the first block
+    fn apply_genesis_block(&mut self, genesis_block: Block) -> SierpinskiResult<()> {
+        let triangle = genesis_block.triangle_transactions
+            .iter()
+            .find_map(|tx| tx.triangle_data.clone())
+            .ok_or_else(|| SierpinskiError::validation("Genesis block has no genesis triangle"))?;
+        let initial_supply = match genesis_block.coinbase().map(|tx| &tx.operation) {
+            Some(TriangleOperation::Coinbase { amount }) => *amount,
+            _ => return Err(SierpinskiError::validation("Genesis block has no coinbase")),
+        };
+
+        // Set genesis in fractal state
+        let genesis_triangle = FractalTriangle::genesis(triangle);
+        let genesis_address = genesis_triangle.address.clone();
+        self.fractal_state.set_genesis(genesis_triangle)?;
+
         // Add initial supply
-        let genesis_reward = Decimal::new(1000000, 0); // 1 million initial tokens
-        self.total_supply = genesis_reward;
-        self.balances.insert("genesis_miner".to_string(), genesis_reward);
-        self.triangle_owners.insert(genesis_address, "genesis_miner".to_string());
+        self.total_supply = initial_supply;
+        self.balances.insert(genesis_block.miner_address.clone(), initial_supply);
+        self.set_owner(genesis_address, genesis_block.miner_address.clone(), genesis_block.height);
 
+        self.cumulative_difficulty = block_work(&genesis_block);
+        self.index_transactions(&genesis_block);
         self.blocks.push(genesis_block);
         Ok(())
     }
 
     /// Add a transaction to the mempool
     pub fn add_transaction(&mut self, transaction: TriangleTransaction) -> SierpinskiResult<()> {
-        // Validate transaction
-        transaction.validate()?;
-        
-        // Check if sender has sufficient balance for gas fee
-        if let Some(from_addr) = &transaction.from_address {
-            let from_str = from_addr.to_string();
-            let balance = self.balances.get(&from_str).unwrap_or(&Decimal::ZERO);
-            
-            if *balance < transaction.gas_fee {
-                return Err(SierpinskiError::validation("Insufficient balance for gas fee"));
+        if let Some(error) = self.admission_errors(&transaction).into_iter().next() {
+            return Err(error);
+        }
+
+        // When the pool is full, only a better-paying transaction gets in,
+        // displacing the cheapest pending one
+        if self.mempool.len() >= self.max_mempool_size {
+            let cheapest = self.mempool
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, tx)| tx.gas_fee)
+                .map(|(index, tx)| (index, tx.gas_fee));
+
+            match cheapest {
+                Some((index, min_fee)) if transaction.gas_fee > min_fee => {
+                    self.mempool.remove(index);
+                }
+                _ => {
+                    return Err(SierpinskiError::validation(
+                        "Mempool is full and transaction fee is below the current minimum",
+                    ));
+                }
             }
         }
 
@@ -105,35 +457,196 @@ impl TriadChainBlockchain {
         Ok(())
     }
 
-    /// Mine a new block with pending transactions
-    pub fn mine_block(&mut self, miner_address: String, max_transactions: usize) -> SierpinskiResult<Block> {
-        if self.blocks.is_empty() {
-            return Err(SierpinskiError::validation("Cannot mine without genesis block"));
+    /// Smallest gas fee the mempool accepts for a transaction targeting
+    /// `address`, see `GasFloorParams::minimum_for`
+    pub fn minimum_gas_for(&self, address: &TriangleAddress) -> Decimal {
+        self.gas_floor.minimum_for(address)
+    }
+
+    /// Every reason the mempool would turn a transaction away, short of
+    /// being full; empty when it would be accepted
+    fn admission_errors(&self, transaction: &TriangleTransaction) -> Vec<SierpinskiError> {
+        let mut errors = Vec::new();
+
+        // Validate transaction
+        if let Err(error) = transaction.validate() {
+            errors.push(error);
+        }
+        if matches!(transaction.operation, TriangleOperation::Coinbase { .. }) {
+            errors.push(SierpinskiError::validation("Coinbase transactions are created by miners"));
+        }
+        if let Err(error) = Self::verify_sender_signature(transaction) {
+            errors.push(error);
+        }
+
+        // The payer's balance must cover the gas fee on top of what its
+        // pending transactions already spend
+        if let Some(payer) = transaction.fee_payer() {
+            if self.get_balance(&payer) - self.pending_outgoing(&payer) < transaction.gas_fee {
+                errors.push(SierpinskiError::validation("Insufficient balance for gas fee"));
+            }
+        }
+
+        // Deeper triangles are worth less, so spamming them costs more
+        let minimum_gas = self.minimum_gas_for(&transaction.to_address);
+        if transaction.gas_fee < minimum_gas {
+            errors.push(SierpinskiError::validation(format!(
+                "Gas fee {} is below the minimum of {} for {}",
+                transaction.gas_fee, minimum_gas, transaction.to_address
+            )));
         }
 
-        // Select transactions from mempool
-        let transactions: Vec<TriangleTransaction> = self.mempool
+        // Claims already pending count against the claimant's rewards
+        if let (TriangleOperation::ClaimReward { amount }, Some(claimant)) =
+            (&transaction.operation, &transaction.sender)
+        {
+            let pending: Decimal = self.mempool
+                .iter()
+                .filter(|tx| tx.sender.as_ref() == Some(claimant))
+                .filter_map(|tx| match tx.operation {
+                    TriangleOperation::ClaimReward { amount } => Some(amount),
+                    _ => None,
+                })
+                .sum();
+            if let Err(error) = self.claim_payout(claimant, *amount + pending) {
+                errors.push(error);
+            }
+        }
+
+        // A wallet can only transfer, stake on or subdivide triangles it owns
+        if let (
+            TriangleOperation::Transfer | TriangleOperation::Stake { .. } | TriangleOperation::Subdivide,
+            Some(sender),
+            Some(triangle),
+        ) = (&transaction.operation, &transaction.sender, &transaction.from_address)
+        {
+            if self.triangle_owners.get(triangle) != Some(sender) {
+                errors.push(SierpinskiError::validation("Triangle not owned by sender"));
+            }
+        }
+        if let (TriangleOperation::Subdivide, Some(triangle)) = (&transaction.operation, &transaction.from_address) {
+            if let Err(error) = self.check_subdividable(triangle) {
+                errors.push(error);
+            }
+        }
+        if transaction.operation == TriangleOperation::Create {
+            if let Err(error) = self.check_unoccupied(&transaction.to_address) {
+                errors.push(error);
+            }
+        }
+
+        // A stake leaves the sender's balance along with its gas fee
+        if let (TriangleOperation::Stake { amount }, Some(sender), Some(triangle)) =
+            (&transaction.operation, &transaction.sender, &transaction.from_address)
+        {
+            if self.get_balance(sender) - self.pending_outgoing(sender) < *amount + transaction.gas_fee {
+                errors.push(SierpinskiError::validation("Insufficient balance for stake and gas fee"));
+            }
+            if let Err(error) = self.economics.check_stake(triangle, sender, *amount) {
+                errors.push(error);
+            }
+        }
+
+        errors
+    }
+
+    /// Gas fees and stakes that `payer`'s transactions in the mempool will
+    /// take from its balance
+    pub fn pending_outgoing(&self, payer: &str) -> Decimal {
+        self.mempool
             .iter()
-            .take(max_transactions)
-            .cloned()
+            .filter(|tx| tx.fee_payer().as_deref() == Some(payer))
+            .map(|tx| match tx.operation {
+                TriangleOperation::Stake { amount } => tx.gas_fee + amount,
+                _ => tx.gas_fee,
+            })
+            .sum()
+    }
+
+    /// Predict the effect of a transaction if it were mined in the next
+    /// block, without touching this chain
+    ///
+    /// Reports every reason the mempool would reject it. Only when none
+    /// apply is it run against a copy of the state for the payer's balance
+    /// change and any new triangle owners.
+    pub fn simulate_transaction(&self, transaction: &TriangleTransaction) -> SimulationResult {
+        let errors: Vec<String> = self.admission_errors(transaction)
+            .iter()
+            .map(|error| error.to_string())
             .collect();
 
-        if transactions.is_empty() {
-            return Err(SierpinskiError::validation("No transactions to mine"));
+        let payer = transaction.fee_payer();
+        let balance_before = payer.as_ref()
+            .and_then(|payer| self.balances.get(payer))
+            .copied()
+            .unwrap_or(Decimal::ZERO);
+
+        let mut result = SimulationResult {
+            will_succeed: false,
+            balance_delta: Decimal::ZERO,
+            ownership_changes: Vec::new(),
+            errors,
+        };
+        if !result.errors.is_empty() {
+            return result;
         }
 
-        // Get previous block hash
-        let previous_hash = self.blocks.last().unwrap().hash();
+        let mut scratch = self.clone();
+        if let Err(error) = scratch.apply_transaction(transaction, self.tip_height() + 1, &mut BlockActivity::default()) {
+            result.errors.push(error.to_string());
+            return result;
+        }
 
-        // Create new block
-        let mut new_block = Block::new(
-            previous_hash,
-            transactions.clone(),
-            miner_address.clone(),
-            self.difficulty,
-        );
-        
-        new_block.height = self.blocks.len() as u64;
+        if let Some(payer) = &payer {
+            let balance_after = scratch.balances.get(payer).copied().unwrap_or(Decimal::ZERO);
+            result.balance_delta = balance_after - balance_before;
+        }
+        result.ownership_changes = scratch.triangle_owners
+            .into_iter()
+            .filter(|(address, owner)| self.triangle_owners.get(address) != Some(owner))
+            .collect();
+        result.ownership_changes.sort_by(|a, b| a.0.components().cmp(b.0.components()));
+        result.will_succeed = true;
+        result
+    }
+
+    /// Check the signature of a transaction sent from a wallet
+    ///
+    /// Transactions naming a sender or carrying a key must be signed by that
+    /// key, and the key must belong to the sender. Unsigned transactions
+    /// without a sender, such as genesis creation, are left to `validate`.
+    fn verify_sender_signature(transaction: &TriangleTransaction) -> SierpinskiResult<()> {
+        if transaction.sender.is_none() && transaction.sender_public_key.is_none() {
+            return Ok(());
+        }
+
+        let public_key = transaction.sender_public_key.as_ref()
+            .ok_or_else(|| SierpinskiError::validation("Transaction from a wallet must carry its public key"))?;
+        if let Some(sender) = &transaction.sender {
+            if TriadChainWallet::derive_wallet_address(public_key) != *sender {
+                return Err(SierpinskiError::validation("Sender public key does not match sender address"));
+            }
+        }
+        if !TriadChainWallet::verify_transaction_signature(transaction, public_key) {
+            return Err(SierpinskiError::validation("Invalid transaction signature"));
+        }
+        Ok(())
+    }
+
+    /// Drop pending transactions older than `max_mempool_age_secs` or
+    /// expired, returning how many were evicted
+    pub fn evict_stale_transactions(&mut self, now: u64) -> usize {
+        let before = self.mempool.len();
+        let max_age = self.max_mempool_age_secs.min(self.transaction_expiry_secs);
+        self.mempool.retain(|tx| now.saturating_sub(tx.timestamp) <= max_age);
+        before - self.mempool.len()
+    }
+
+    /// Mine a new block with pending transactions, or with just a coinbase
+    /// when none are pending
+    pub fn mine_block(&mut self, miner_address: String, max_transactions: usize) -> SierpinskiResult<Block> {
+        let mut new_block = self.block_template(miner_address, max_transactions)?;
+        self.validate_block_in_context(&new_block)?;
 
         // Perform proof-of-work (simplified for demo)
         let mut nonce = 0u64;
@@ -150,237 +663,3067 @@ impl TriadChainBlockchain {
             }
         }
 
-        // Validate block
-        new_block.validate()?;
-
-        // Apply block to blockchain state
-        self.apply_block(&new_block)?;
-
-        // Remove mined transactions from mempool
-        let mined_tx_ids: Vec<_> = transactions.iter().map(|tx| tx.id).collect();
-        self.mempool.retain(|tx| !mined_tx_ids.contains(&tx.id));
-
-        // Add block to chain
-        self.blocks.push(new_block.clone());
-
+        self.accept_mined_block(new_block.clone())?;
         Ok(new_block)
     }
 
-    /// Apply a block's effects to the blockchain state
-    fn apply_block(&mut self, block: &Block) -> SierpinskiResult<()> {
-        // Process each transaction
-        for transaction in &block.triangle_transactions {
-            self.apply_transaction(transaction)?;
+    /// Candidate next block on our tip, paying `miner_address`, with up to
+    /// `max_transactions` from the mempool; only the nonce is left to find
+    pub fn block_template(&self, miner_address: String, max_transactions: usize) -> SierpinskiResult<Block> {
+        let tip = self.blocks
+            .last()
+            .ok_or_else(|| SierpinskiError::validation("Cannot mine without genesis block"))?;
+
+        // Stamp the block after the median time past even when our clock
+        // lags behind the chain
+        let timestamp = unix_now().max(self.median_time_past() + 1);
+
+        // Select transactions from mempool until the block is full; the
+        // coinbase alone makes a valid block when none are pending
+        let build = |transactions: Vec<TriangleTransaction>| {
+            let reward = self.capped_reward(
+                Block::calculate_block_reward(self.target.leading_zero_bits(), &transactions),
+                self.claims_minted(&transactions),
+            );
+            let mut block = Block::new_with_reward(
+                tip.hash(),
+                transactions,
+                &self.fractal_state,
+                miner_address.clone(),
+                self.target,
+                reward,
+            );
+            block.header.timestamp = timestamp;
+            block
+        };
+        let mut size = build(Vec::new()).serialized_size();
+        let mut transactions = Vec::new();
+        // Pending transactions can conflict, such as two transfers of one
+        // triangle; only the first makes it in
+        let mut effects = PendingEffects::default();
+        let pending = self.mempool
+            .iter()
+            .filter(|transaction| !self.transaction_expired(transaction, timestamp))
+            .filter(|transaction| self.check_in_block(transaction, &mut effects).is_ok())
+            .take(max_transactions);
+        for transaction in pending {
+            // Each further entry in the transaction list costs a comma
+            let added = transaction.serialized_size() + 1;
+            if size + added > MAX_BLOCK_BYTES {
+                break;
+            }
+            size += added;
+            transactions.push(transaction.clone());
         }
 
-        // Award mining reward
-        let current_balance = self.balances
-            .get(&block.miner_address)
-            .unwrap_or(&Decimal::ZERO);
-        
-        self.balances.insert(
-            block.miner_address.clone(),
-            current_balance + block.block_reward,
-        );
-        
-        self.total_supply += block.block_reward;
+        // The estimate ignores header fields growing with the contents, so
+        // drop transactions from the end until the real block fits
+        let mut block = build(transactions.clone());
+        while block.serialized_size() > MAX_BLOCK_BYTES && transactions.pop().is_some() {
+            block = build(transactions.clone());
+        }
+        block.height = tip.height + 1;
+        Ok(block)
+    }
 
-        // Adjust difficulty every 10 blocks
-        if block.height % 10 == 0 && block.height > 0 {
-            self.adjust_difficulty();
+    /// Append a block mined on our tip, such as one built from
+    /// `block_template`, without redoing its proof-of-work
+    ///
+    /// The block must extend the tip at the current target; its
+    /// transactions leave the mempool.
+    pub fn accept_mined_block(&mut self, block: Block) -> SierpinskiResult<()> {
+        if block.header.bits != self.target.to_compact() {
+            return Err(SierpinskiError::validation(format!(
+                "Block {} was mined at target bits {:#010x} instead of {:#010x}",
+                block.height, block.header.bits, self.target.to_compact()
+            )));
         }
 
+        let (height, hash, transactions) = (block.height, block.hash(), block.triangle_transactions.len());
+        self.try_add_block(block)?;
+        info!(height, hash = %hash, transactions, "Block mined");
         Ok(())
     }
 
-    /// Apply a transaction's effects
-    fn apply_transaction(&mut self, transaction: &TriangleTransaction) -> SierpinskiResult<()> {
-        match &transaction.operation {
-            TriangleOperation::Create => {
-                if let Some(triangle_data) = &transaction.triangle_data {
-                    // Create new fractal triangle
-                    let fractal_triangle = FractalTriangle::new(
-                        triangle_data.clone(),
-                        crate::core::state::TriangleState::Active,
-                        transaction.to_address.clone(),
-                        transaction.to_address.depth(),
-                    );
+    /// Switch to a competing chain if it carries more cumulative work
+    ///
+    /// `candidate_blocks` may start anywhere at or before the fork point;
+    /// blocks we already have are skipped and the rest must build on one of
+    /// ours. Blocks that simply extend the tip go through `try_add_block`,
+    /// so those before an invalid one are kept. A real fork is validated by
+    /// rewinding to the state from before the fork point, kept for the last
+    /// `MAX_STATE_CHECKPOINTS` blocks appended, and replaying from there, or
+    /// else by replaying the whole chain from our snapshot or genesis; a
+    /// rejected fork leaves this chain untouched. Returns `Ok(true)` if the chain changed.
+    pub fn try_reorg(&mut self, candidate_blocks: &[Block]) -> SierpinskiResult<bool> {
+        let Some(new_start) = candidate_blocks.iter().position(|block| {
+            self.get_block_by_height(block.height).map(|known| known.hash()) != Some(block.hash())
+        }) else {
+            return Ok(false); // Nothing we don't already have
+        };
 
-                    self.fractal_state.add_triangle(fractal_triangle)?;
-                    
-                    // Set ownership
-                    if let Some(from_addr) = &transaction.from_address {
-                        self.triangle_owners.insert(
-                            transaction.to_address.clone(),
-                            from_addr.to_string(),
-                        );
-                    }
-                }
-            }
-            
-            TriangleOperation::Subdivide => {
-                // Find parent triangle and subdivide it
-                if let Some(parent_triangle) = self.fractal_state.get_triangle_mut(&uuid::Uuid::new_v4()) {
-                    // Subdivide logic would go here
-                    parent_triangle.change_state(crate::core::state::TriangleState::Subdivided)?;
-                }
-            }
-            
-            TriangleOperation::Transfer => {
-                // Transfer triangle ownership
-                if let (Some(from), to) = (&transaction.from_address, &transaction.to_address) {
-                    self.triangle_owners.insert(to.clone(), from.to_string());
-                }
-            }
-            
-            TriangleOperation::Stake { amount } => {
-                // Handle staking
-                if let Some(from_addr) = &transaction.from_address {
-                    let from_str = from_addr.to_string();
-                    let balance = self.balances.get(&from_str).unwrap_or(&Decimal::ZERO);
-                    
-                    if *balance >= *amount {
-                        self.balances.insert(from_str, balance - amount);
-                        // Staking logic would track staked amounts
-                    }
-                }
+        let fork = &candidate_blocks[new_start..];
+        if fork[0].height == 0 {
+            return Err(SierpinskiError::validation("Candidate chain replaces the genesis block"));
+        }
+        if let Some(base) = &self.snapshot_base {
+            if fork[0].height <= base.height {
+                return Err(SierpinskiError::validation("Candidate chain forks before our snapshot"));
             }
-            
-            _ => {} // Handle other operations
         }
 
-        // Deduct gas fees
-        if let Some(from_addr) = &transaction.from_address {
-            let from_str = from_addr.to_string();
-            let balance = self.balances.get(&from_str).unwrap_or(&Decimal::ZERO);
-            self.balances.insert(from_str, balance - transaction.gas_fee);
+        // Blocks before a snapshot are pruned, so positions are offset from heights
+        let fork_index = (fork[0].height - self.blocks[0].height) as usize;
+        let ancestor = self.blocks
+            .get(fork_index - 1)
+            .ok_or_else(|| SierpinskiError::validation("Candidate chain has no common ancestor"))?;
+        if fork[0].header.previous_hash != ancestor.hash() {
+            return Err(SierpinskiError::validation("Candidate chain has no common ancestor"));
         }
 
-        Ok(())
-    }
+        // Blocks extending our tip only add work, so they are appended in
+        // place, one at a time; a header-only chain follows them this way
+        // without the pruned bodies a replay would need
+        if fork_index == self.blocks.len() {
+            for block in fork {
+                self.try_add_block(block.clone())?;
+            }
+            return Ok(true);
+        }
 
-    /// Adjust mining difficulty based on block times
-    fn adjust_difficulty(&mut self) {
-        if self.blocks.len() < 10 {
-            return;
+        // Compare total work, not length: a short run of hard blocks beats
+        // a long run of easy ones
+        let kept_work = self.total_work()
+            .checked_sub(chain_work(&self.blocks[fork_index..]))
+            .ok_or_else(|| SierpinskiError::validation("Chain total work is less than that of its own blocks"))?;
+        let candidate_work = kept_work.saturating_add(chain_work(fork));
+        if candidate_work <= self.total_work() {
+            return Ok(false);
         }
 
-        let recent_blocks = &self.blocks[self.blocks.len() - 10..];
-        let time_span = recent_blocks.last().unwrap().header.timestamp 
-                       - recent_blocks.first().unwrap().header.timestamp;
+        // Replay from the latest state kept before the fork, or failing that
+        // from our snapshot or genesis
+        let checkpoint = self.checkpoints
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.height < fork[0].height)
+            .cloned();
+        let abandoned = match checkpoint {
+            Some(checkpoint) => self.reorg_from_checkpoint(checkpoint, fork)?,
+            None => {
+                let mut blocks = self.blocks[..fork_index].to_vec();
+                blocks.extend_from_slice(fork);
+                let mut rebuilt = self.rebuild_from_blocks(blocks)?;
+                rebuilt.mempool = std::mem::take(&mut self.mempool);
+                rebuilt.orphan_pool = std::mem::take(&mut self.orphan_pool);
+                rebuilt.hooks = std::mem::take(&mut self.hooks);
+                std::mem::replace(self, rebuilt).blocks.split_off(fork_index)
+            }
+        };
+
+        // Transactions from abandoned blocks go back to the mempool unless
+        // the new chain already includes them; their coinbases die with them
+        let included: std::collections::HashSet<_> = fork
+            .iter()
+            .flat_map(|block| &block.triangle_transactions)
+            .map(|tx| tx.id)
+            .collect();
+        let orphaned = abandoned
+            .into_iter()
+            .flat_map(|block| block.triangle_transactions.into_iter().skip(1));
+        self.mempool = orphaned
+            .chain(std::mem::take(&mut self.mempool))
+            .filter(|tx| !included.contains(&tx.id))
+            .collect();
 
-        let target_time = 600; // 10 minutes per block * 10 blocks = 600 seconds
-        
-        if time_span < target_time / 2 {
-            // Too fast, increase difficulty
-            self.difficulty = std::cmp::min(self.difficulty + 1, 20);
-        } else if time_span > target_time * 2 {
-            // Too slow, decrease difficulty
-            self.difficulty = std::cmp::max(self.difficulty.saturating_sub(1), 1);
-        }
+        // The replay ran without subscribers, and events already delivered
+        // for abandoned blocks no longer hold, so subscribers must resync
+        self.hooks.dispatch(&BlockchainEvent::Reorganized { fork_height: fork[0].height });
+        info!(fork_height = fork[0].height, height = self.tip_height(), "Reorganized onto a heavier chain");
+        Ok(true)
     }
 
-    /// Validate the entire blockchain
-    pub fn validate_chain(&self) -> SierpinskiResult<bool> {
-        if self.blocks.is_empty() {
-            return Err(SierpinskiError::validation("Empty blockchain"));
-        }
+    /// Rewind to `checkpoint` and replay our blocks after it, then `fork`,
+    /// returning the blocks the fork abandoned
+    ///
+    /// If any block fails the chain is put back as it was.
+    fn reorg_from_checkpoint(&mut self, mut checkpoint: StateCheckpoint, fork: &[Block]) -> SierpinskiResult<Vec<Block>> {
+        let height = checkpoint.height;
+        let base_index = (height - self.blocks[0].height) as usize;
+        let fork_index = (fork[0].height - self.blocks[0].height) as usize;
+        let replayed: Vec<Block> = self.blocks[base_index + 1..fork_index]
+            .iter()
+            .chain(fork)
+            .cloned()
+            .collect();
+        self.require_bodies(&replayed)?;
 
-        // Validate genesis block
-        if self.blocks[0].header.previous_hash != "0".repeat(64) {
-            return Err(SierpinskiError::validation("Invalid genesis block"));
-        }
+        // After the swap the checkpoint holds our tip state
+        checkpoint.swap(self);
+        let later = self.take_records_above(height);
+        let mut dropped = self.blocks.split_off(base_index + 1);
+        let hooks = std::mem::take(&mut self.hooks);
+        let result = self.replay_blocks(replayed);
+        self.hooks = hooks;
 
-        // Validate chain links
-        for i in 1..self.blocks.len() {
-            let prev_hash = self.blocks[i - 1].hash();
-            if self.blocks[i].header.previous_hash != prev_hash {
-                return Err(SierpinskiError::validation("Broken chain link"));
-            }
-            
-            // Validate individual block
-            self.blocks[i].validate()?;
+        if let Err(e) = result {
+            self.blocks.truncate(base_index + 1);
+            self.blocks.append(&mut dropped);
+            checkpoint.swap(self);
+            self.take_records_above(height);
+            self.put_records(later);
+            return Err(e);
         }
+        self.checkpoints.retain(|kept| kept.height <= height);
+        Ok(dropped.split_off(fork_index - base_index - 1))
+    }
 
-        Ok(true)
+    /// Take the transaction index and ownership history entries of the
+    /// blocks above `height` off the chain
+    fn take_records_above(&mut self, height: u64) -> LaterRecords {
+        let transactions = self.transaction_index
+            .extract_if(|_, included| *included > height)
+            .collect();
+        let mut ownership = Vec::new();
+        self.ownership_history.retain(|address, entries| {
+            // Entries are pushed in block order
+            let later = entries.partition_point(|(_, at)| *at <= height);
+            if later < entries.len() {
+                ownership.push((address.clone(), entries.split_off(later)));
+            }
+            !entries.is_empty()
+        });
+        LaterRecords { transactions, ownership }
     }
 
-    /// Get current blockchain statistics
-    pub fn stats(&self) -> BlockchainStats {
-        BlockchainStats {
-            total_blocks: self.blocks.len(),
-            total_transactions: self.blocks.iter().map(|b| b.triangle_transactions.len()).sum(),
-            total_supply: self.total_supply,
-            current_difficulty: self.difficulty,
-            mempool_size: self.mempool.len(),
-            total_triangles: self.fractal_state.total_triangles(),
-            unique_addresses: self.balances.len(),
+    /// Put back records taken by `take_records_above`
+    fn put_records(&mut self, records: LaterRecords) {
+        self.transaction_index.extend(records.transactions);
+        for (address, entries) in records.ownership {
+            self.ownership_history.entry(address).or_default().extend(entries);
         }
     }
 
-    /// Get balance for an address
-    pub fn get_balance(&self, address: &str) -> Decimal {
-        self.balances.get(address).unwrap_or(&Decimal::ZERO).clone()
+    /// Replay a full chain from its genesis block, or from our snapshot for a
+    /// pruned chain, into fresh state, validating links, proof-of-work and
+    /// every block on the way
+    fn rebuild_from_blocks(&self, blocks: Vec<Block>) -> SierpinskiResult<Self> {
+        let mut rebuilt = self.rebuild_full(blocks)?;
+        rebuilt.mode = self.mode;
+        rebuilt.prune_block_bodies();
+        Ok(rebuilt)
     }
 
-    /// Get triangles owned by an address
-    pub fn get_owned_triangles(&self, owner: &str) -> Vec<TriangleAddress> {
-        self.triangle_owners
-            .iter()
-            .filter(|(_, addr)| *addr == owner)
-            .map(|(triangle_addr, _)| triangle_addr.clone())
-            .collect()
+    /// Take on the mempool limits, supply cap and fee floor configured on
+    /// `other`, which a snapshot does not carry
+    pub fn keep_settings_of(&mut self, other: &Self) {
+        self.max_mempool_age_secs = other.max_mempool_age_secs;
+        self.max_mempool_size = other.max_mempool_size;
+        self.transaction_expiry_secs = other.transaction_expiry_secs;
+        self.max_supply = other.max_supply;
+        self.gas_floor = other.gas_floor.clone();
     }
-}
 
-/// Blockchain statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BlockchainStats {
-    pub total_blocks: usize,
-    pub total_transactions: usize,
-    pub total_supply: Decimal,
-    pub current_difficulty: u32,
-    pub mempool_size: usize,
-    pub total_triangles: usize,
+    fn rebuild_full(&self, blocks: Vec<Block>) -> SierpinskiResult<Self> {
+        if let Some(base) = &self.snapshot_base {
+            let (retained, replayed): (Vec<_>, Vec<_>) = blocks
+                .into_iter()
+                .partition(|block| block.height <= base.height);
+            self.require_bodies(&replayed)?;
+            let mut rebuilt = Self::from_snapshot_with_params(base.clone(), retained, self.difficulty_params.clone())?;
+            rebuilt.keep_settings_of(self);
+            rebuilt.replay_blocks(replayed)?;
+            return Ok(rebuilt);
+        }
+
+        self.require_bodies(&blocks)?;
+        let mut blocks = blocks.into_iter();
+        let genesis_block = blocks
+            .next()
+            .ok_or_else(|| SierpinskiError::validation("Empty blockchain"))?;
+
+        let mut rebuilt = TriadChainBlockchain {
+            blocks: Vec::new(),
+            fractal_state: FractalStructure::new(),
+            mempool: Vec::new(),
+            target: genesis_block.header.target()?,
+            total_supply: Decimal::ZERO,
+            balances: HashMap::new(),
+            triangle_owners: HashMap::new(),
+            ownership_history: HashMap::new(),
+            transaction_index: HashMap::new(),
+            max_mempool_age_secs: self.max_mempool_age_secs,
+            max_mempool_size: self.max_mempool_size,
+            transaction_expiry_secs: self.transaction_expiry_secs,
+            cumulative_difficulty: 0,
+            difficulty_params: self.difficulty_params.clone(),
+            snapshot_base: None,
+            rewards: RewardLedger::default(),
+            max_supply: self.max_supply,
+            gas_floor: self.gas_floor.clone(),
+            economics: self.economics.restarted(),
+            orphan_pool: HashMap::new(),
+            mode: ChainMode::Full,
+            pruned_below: 0,
+            hooks: EventHooks::default(),
+            checkpoints: VecDeque::new(),
+        };
+        rebuilt.apply_genesis_block(genesis_block)?;
+        rebuilt.replay_blocks(blocks)?;
+
+        Ok(rebuilt)
+    }
+
+    /// Validate a block received from a peer and append it to our tip,
+    /// dropping its transactions from the mempool
+    ///
+    /// The block applies in place over a copy of the state taken first, put
+    /// back if the block fails so it leaves no trace, and kept otherwise as
+    /// the checkpoint a later fork after our old tip replays from.
+    /// Competing blocks that fork below the tip go through `try_reorg`.
+    pub fn try_add_block(&mut self, block: Block) -> SierpinskiResult<()> {
+        let included: std::collections::HashSet<_> = block.triangle_transactions
+            .iter()
+            .map(|tx| tx.id)
+            .collect();
+
+        let mut checkpoint = StateCheckpoint::capture(self);
+        if let Err(e) = self.replay_blocks(std::iter::once(block)) {
+            checkpoint.swap(self);
+            self.take_records_above(checkpoint.height);
+            return Err(e);
+        }
+        self.checkpoints.push_back(checkpoint);
+        if self.checkpoints.len() > MAX_STATE_CHECKPOINTS {
+            self.checkpoints.pop_front();
+        }
+
+        self.mempool.retain(|tx| !included.contains(&tx.id));
+        Ok(())
+    }
+
+    /// Append a block received from a peer, or hold it in the orphan pool
+    /// until its parent arrives
+    ///
+    /// A block that connects also connects any orphans waiting on it, in
+    /// turn. Orphans must meet a target no easier than the chain allows, and
+    /// `peer` may hold at most `MAX_ORPHANS_PER_PEER` of them. Orphans older
+    /// than `MAX_ORPHAN_AGE_SECS` are dropped, and the oldest is evicted once
+    /// `MAX_ORPHAN_BLOCKS` are held.
+    pub fn add_block_or_orphan(&mut self, block: Block, peer: &str) -> BlockAcceptance {
+        if self.get_block_by_hash(&block.header.previous_hash).is_none() {
+            return self.store_orphan(block, peer, unix_now());
+        }
+
+        match self.try_add_block(block) {
+            Ok(()) => {
+                self.connect_orphans();
+                BlockAcceptance::Connected
+            }
+            Err(e) => BlockAcceptance::Rejected(e.to_string()),
+        }
+    }
+
+    /// Append orphans that build on the tip until none do, returning how
+    /// many connected; of several orphans on one parent the first valid one
+    /// wins and the rest are dropped
+    ///
+    /// Orphans whose parent is now in the chain below the tip could only
+    /// ever fork it, so they are dropped too.
+    pub fn connect_orphans(&mut self) -> usize {
+        let mut connected = 0;
+        while let Some(children) = self.orphan_pool.remove(&self.blocks.last().unwrap().hash()) {
+            if !children.into_iter().any(|orphan| self.try_add_block(orphan.block).is_ok()) {
+                break;
+            }
+            connected += 1;
+        }
+
+        let stale: Vec<String> = self.orphan_pool
+            .keys()
+            .filter(|parent| self.get_block_by_hash(parent).is_some())
+            .cloned()
+            .collect();
+        for parent in stale {
+            self.orphan_pool.remove(&parent);
+        }
+        connected
+    }
+
+    /// Number of blocks held in the orphan pool
+    pub fn orphan_count(&self) -> usize {
+        self.orphan_pool.values().map(Vec::len).sum()
+    }
+
+    fn store_orphan(&mut self, block: Block, peer: &str, now: u64) -> BlockAcceptance {
+        if !block.meets_difficulty_target() {
+            return BlockAcceptance::Rejected(format!("Orphan block {} does not meet its difficulty target", block.height));
+        }
+        let easiest = Target::from_legacy_difficulty(self.difficulty_params.min_difficulty);
+        if block.header.target().map_or(true, |target| target > easiest) {
+            return BlockAcceptance::Rejected(format!("Orphan block {} is easier than any allowed target", block.height));
+        }
+        let hash = block.hash();
+        let siblings = self.orphan_pool.get(&block.header.previous_hash);
+        if siblings.is_some_and(|siblings| siblings.iter().any(|orphan| orphan.block.hash() == hash)) {
+            return BlockAcceptance::Orphaned;
+        }
+
+        self.expire_orphans(now);
+        let from_peer = self.orphan_pool.values().flatten().filter(|orphan| orphan.peer == peer).count();
+        if from_peer >= MAX_ORPHANS_PER_PEER {
+            return BlockAcceptance::Rejected(format!("Peer {} already holds {} orphans", peer, from_peer));
+        }
+        if self.orphan_count() >= MAX_ORPHAN_BLOCKS {
+            self.evict_oldest_orphan();
+        }
+
+        self.orphan_pool
+            .entry(block.header.previous_hash.clone())
+            .or_default()
+            .push(Orphan { block, peer: peer.to_string(), received_at: now });
+        BlockAcceptance::Orphaned
+    }
+
+    /// Drop orphans held longer than `MAX_ORPHAN_AGE_SECS`
+    fn expire_orphans(&mut self, now: u64) {
+        for children in self.orphan_pool.values_mut() {
+            children.retain(|orphan| now.saturating_sub(orphan.received_at) <= MAX_ORPHAN_AGE_SECS);
+        }
+        self.orphan_pool.retain(|_, children| !children.is_empty());
+    }
+
+    /// Drop the orphan received first
+    fn evict_oldest_orphan(&mut self) {
+        let oldest = self.orphan_pool
+            .iter()
+            .flat_map(|(parent, children)| children.iter().enumerate().map(move |(i, orphan)| (orphan.received_at, parent, i)))
+            .min()
+            .map(|(_, parent, i)| (parent.clone(), i));
+        if let Some((parent, i)) = oldest {
+            let children = self.orphan_pool.get_mut(&parent).unwrap();
+            children.remove(i);
+            if children.is_empty() {
+                self.orphan_pool.remove(&parent);
+            }
+        }
+    }
+
+    /// Validate and apply blocks on top of the current tip
+    fn replay_blocks(&mut self, blocks: impl IntoIterator<Item = Block>) -> SierpinskiResult<()> {
+        for block in blocks {
+            let previous = self.blocks.last().unwrap();
+            if block.height != previous.height + 1 {
+                return Err(SierpinskiError::validation(format!(
+                    "Unexpected block height {} after {}",
+                    block.height, previous.height
+                )));
+            }
+            if block.header.previous_hash != previous.hash() {
+                return Err(SierpinskiError::validation("Broken chain link"));
+            }
+            // The target is retargeted as blocks apply, so it is the one
+            // this block had to be mined at
+            if block.header.bits != self.target.to_compact() {
+                return Err(SierpinskiError::validation(format!(
+                    "Block {} was mined at target bits {:#010x} instead of {:#010x}",
+                    block.height, block.header.bits, self.target.to_compact()
+                )));
+            }
+            if !block.meets_difficulty_target() {
+                return Err(SierpinskiError::validation(format!(
+                    "Block {} does not meet its difficulty target",
+                    block.height
+                )));
+            }
+            block.validate_with_state(&self.fractal_state)?;
+            self.validate_block_in_context(&block)?;
+
+            self.apply_block(&block)?;
+            self.blocks.push(block);
+        }
+
+        self.prune_block_bodies();
+        Ok(())
+    }
+
+    /// Switch between keeping all block bodies and keeping recent ones only,
+    /// pruning straight away in header-only mode
+    ///
+    /// Pruned bodies are gone for good: switching back to `Full` keeps
+    /// later bodies, and reorganizations that would replay pruned blocks
+    /// fail with `PrunedDataUnavailable`.
+    pub fn set_mode(&mut self, mode: ChainMode) -> usize {
+        self.mode = mode;
+        self.prune_block_bodies()
+    }
+
+    /// Drop the transactions of blocks older than the mode keeps, returning
+    /// how many blocks were pruned
+    ///
+    /// Headers stay, so links, hashes and merkle roots can still be
+    /// checked, and so does `transaction_index`, so a pruned transaction
+    /// still counts as confirmed.
+    pub fn prune_block_bodies(&mut self) -> usize {
+        let ChainMode::HeaderOnly { keep_recent } = self.mode else { return 0 };
+        // The tip always keeps its body
+        let keep_from = self.chain_length().saturating_sub(keep_recent.max(1));
+        if keep_from <= self.pruned_below {
+            return 0;
+        }
+
+        let mut pruned = 0;
+        for block in self.blocks.iter_mut().filter(|block| block.height < keep_from) {
+            if !block.triangle_transactions.is_empty() {
+                block.triangle_transactions = Vec::new();
+                pruned += 1;
+            }
+        }
+        self.pruned_below = keep_from;
+        pruned
+    }
+
+    /// Whether the transactions of the block at `height` were pruned
+    pub fn is_pruned(&self, height: u64) -> bool {
+        height < self.pruned_below
+    }
+
+    /// Fail if any of `blocks` is one of ours whose body was pruned
+    fn require_bodies(&self, blocks: &[Block]) -> SierpinskiResult<()> {
+        match blocks.iter().find(|block| self.is_pruned(block.height)) {
+            Some(block) => Err(SierpinskiError::PrunedDataUnavailable { height: block.height }),
+            None => Ok(()),
+        }
+    }
+
+    /// Median timestamp of the last `MEDIAN_TIME_SPAN` blocks, which the
+    /// next block's timestamp must exceed
+    pub fn median_time_past(&self) -> u64 {
+        let mut timestamps: Vec<u64> = self.blocks
+            .iter()
+            .rev()
+            .take(MEDIAN_TIME_SPAN)
+            .map(|block| block.header.timestamp)
+            .collect();
+        timestamps.sort_unstable();
+        timestamps.get(timestamps.len() / 2).copied().unwrap_or(0)
+    }
+
+    /// Check the parts of a block that depend on the chain it extends: its
+    /// timestamp must lie after the median time past and at most
+    /// `MAX_FUTURE_BLOCK_SECS` ahead of local time, none of its
+    /// transactions may have expired by then, and each must pass
+    /// `check_in_block` after the ones before it
+    pub fn validate_block_in_context(&self, block: &Block) -> SierpinskiResult<()> {
+        let median = self.median_time_past();
+        if block.header.timestamp <= median {
+            return Err(SierpinskiError::validation(format!(
+                "Block {} timestamp {} is not after the median time past {}",
+                block.height, block.header.timestamp, median
+            )));
+        }
+        if block.header.timestamp > unix_now() + MAX_FUTURE_BLOCK_SECS {
+            return Err(SierpinskiError::validation(format!(
+                "Block {} timestamp too far in future",
+                block.height
+            )));
+        }
+
+        if let Some(expired) = block.triangle_transactions
+            .iter()
+            .find(|transaction| self.transaction_expired(transaction, block.header.timestamp))
+        {
+            return Err(SierpinskiError::validation(format!(
+                "Transaction {} expired before block {}",
+                expired.id, block.height
+            )));
+        }
+
+        let mut effects = PendingEffects::default();
+        for transaction in block.triangle_transactions.iter().skip(1) {
+            self.check_in_block(transaction, &mut effects)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check what a block cannot vouch for by itself: the sender's
+    /// signature and, for a transfer, stake or subdivision, that the sender
+    /// owns the triangle once the transactions before it in the block,
+    /// recorded in `effects`, have applied; the payer must be able to cover
+    /// the gas fee, and any stake, after what those transactions spent, and
+    /// a triangle can be subdivided only once and an address created only
+    /// once
+    fn check_in_block(&self, transaction: &TriangleTransaction, effects: &mut PendingEffects) -> SierpinskiResult<()> {
+        Self::verify_sender_signature(transaction)?;
+
+        if transaction.operation == TriangleOperation::Create {
+            if !effects.created.insert(transaction.to_address.clone()) {
+                return Err(SierpinskiError::validation(format!(
+                    "Transaction {} creates triangle {} again",
+                    transaction.id, transaction.to_address
+                )));
+            }
+            self.check_unoccupied(&transaction.to_address)?;
+        }
+
+        if let (TriangleOperation::Transfer, Some(triangle), Some(sender), Some(recipient)) = (
+            &transaction.operation,
+            &transaction.from_address,
+            &transaction.sender,
+            &transaction.recipient,
+        ) {
+            let owner = effects.owners.get(triangle).or_else(|| self.triangle_owners.get(triangle));
+            if owner != Some(sender) {
+                return Err(SierpinskiError::validation(format!(
+                    "Transaction {} transfers triangle {} not owned by its sender",
+                    transaction.id, triangle
+                )));
+            }
+            effects.owners.insert(triangle.clone(), recipient.clone());
+        }
+
+        if let (TriangleOperation::Stake { amount }, Some(triangle), Some(sender)) =
+            (&transaction.operation, &transaction.from_address, &transaction.sender)
+        {
+            let owner = effects.owners.get(triangle).or_else(|| self.triangle_owners.get(triangle));
+            if owner != Some(sender) {
+                return Err(SierpinskiError::validation(format!(
+                    "Transaction {} stakes on triangle {} not owned by its sender",
+                    transaction.id, triangle
+                )));
+            }
+            self.economics.check_stake(triangle, sender, *amount)?;
+        }
+
+        // The payer covers the gas fee, and the amount of a stake, from what
+        // the transactions before it left; a reward claim pays it first
+        if let Some(payer) = transaction.fee_payer() {
+            let (outgoing, incoming) = match transaction.operation {
+                TriangleOperation::Stake { amount } => (transaction.gas_fee + amount, Decimal::ZERO),
+                TriangleOperation::ClaimReward { amount } => (transaction.gas_fee, amount),
+                _ => (transaction.gas_fee, Decimal::ZERO),
+            };
+            let spent = effects.spent.entry(payer.clone()).or_insert(Decimal::ZERO);
+            if self.get_balance(&payer) - *spent + incoming < outgoing {
+                return Err(SierpinskiError::validation(format!(
+                    "Transaction {} spends more than {} holds",
+                    transaction.id, payer
+                )));
+            }
+            *spent += outgoing - incoming;
+        }
+
+        if let (TriangleOperation::Subdivide, Some(triangle), Some(sender)) =
+            (&transaction.operation, &transaction.from_address, &transaction.sender)
+        {
+            let owner = effects.owners.get(triangle).or_else(|| self.triangle_owners.get(triangle));
+            if owner != Some(sender) {
+                return Err(SierpinskiError::validation(format!(
+                    "Transaction {} subdivides triangle {} not owned by its sender",
+                    transaction.id, triangle
+                )));
+            }
+            if !effects.subdivided.insert(triangle.clone()) {
+                return Err(SierpinskiError::validation(format!(
+                    "Transaction {} subdivides triangle {} again",
+                    transaction.id, triangle
+                )));
+            }
+            self.check_subdividable(triangle)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether a transaction is too old to be included at `timestamp`
+    fn transaction_expired(&self, transaction: &TriangleTransaction, timestamp: u64) -> bool {
+        timestamp.saturating_sub(transaction.timestamp) > self.transaction_expiry_secs
+    }
+
+    /// Capture the current state so a new node can start from our tip
+    pub fn snapshot(&self) -> ChainSnapshot {
+        let tip = self.blocks.last().expect("blockchain always has a genesis block");
+        let mut snapshot = ChainSnapshot {
+            height: tip.height,
+            block_hash: tip.hash(),
+            target: self.target,
+            total_supply: self.total_supply,
+            cumulative_difficulty: self.cumulative_difficulty,
+            balances: snapshot::sorted_balances(&self.balances),
+            triangle_owners: snapshot::sorted_owners(&self.triangle_owners),
+            fractal_state: self.fractal_state.clone(),
+            rewards: self.rewards.clone(),
+            economics: self.economics.clone(),
+            content_hash: String::new(),
+        };
+        snapshot.content_hash = snapshot.compute_hash();
+        snapshot
+    }
+
+    /// Rebuild a working chain from a snapshot and the blocks leading up to
+    /// it, keeping only those blocks instead of the full history
+    ///
+    /// `recent_blocks` must be consecutive and end at the snapshot block;
+    /// `DEFAULT_SNAPSHOT_RECENT_BLOCKS` of them keep difficulty adjustment
+    /// working. Each must meet its target, and be mined at the target the
+    /// default `DifficultyParams` retarget to.
+    pub fn from_snapshot(snapshot: ChainSnapshot, recent_blocks: Vec<Block>) -> SierpinskiResult<Self> {
+        Self::from_snapshot_with_params(snapshot, recent_blocks, DifficultyParams::default())
+    }
+
+    /// `from_snapshot` for a chain retargeting by `difficulty_params`
+    pub fn from_snapshot_with_params(
+        snapshot: ChainSnapshot,
+        recent_blocks: Vec<Block>,
+        difficulty_params: DifficultyParams,
+    ) -> SierpinskiResult<Self> {
+        snapshot.verify()?;
+
+        let tip = recent_blocks
+            .last()
+            .ok_or_else(|| SierpinskiError::validation("Snapshot needs at least its own block"))?;
+        if tip.height != snapshot.height || tip.hash() != snapshot.block_hash {
+            return Err(SierpinskiError::validation("Recent blocks do not end at the snapshot block"));
+        }
+        for pair in recent_blocks.windows(2) {
+            if pair[1].height != pair[0].height + 1 || pair[1].header.previous_hash != pair[0].hash() {
+                return Err(SierpinskiError::validation("Broken chain link"));
+            }
+        }
+        verify_block_targets(&recent_blocks, snapshot.target, &difficulty_params)?;
+
+        let transaction_index = recent_blocks
+            .iter()
+            .flat_map(|block| block.triangle_transactions.iter().map(|tx| (tx.id, block.height)))
+            .collect();
+
+        Ok(TriadChainBlockchain {
+            blocks: recent_blocks,
+            fractal_state: snapshot.fractal_state.clone(),
+            mempool: Vec::new(),
+            target: snapshot.target,
+            total_supply: snapshot.total_supply,
+            balances: snapshot.balances.iter().cloned().collect(),
+            triangle_owners: snapshot.triangle_owners.iter().cloned().collect(),
+            ownership_history: HashMap::new(),
+            transaction_index,
+            max_mempool_age_secs: DEFAULT_MAX_MEMPOOL_AGE_SECS,
+            max_mempool_size: DEFAULT_MAX_MEMPOOL_SIZE,
+            transaction_expiry_secs: DEFAULT_TRANSACTION_EXPIRY_SECS,
+            cumulative_difficulty: snapshot.cumulative_difficulty,
+            difficulty_params,
+            rewards: snapshot.rewards.clone(),
+            max_supply: DEFAULT_MAX_SUPPLY,
+            gas_floor: GasFloorParams::default(),
+            economics: snapshot.economics.clone(),
+            orphan_pool: HashMap::new(),
+            mode: ChainMode::Full,
+            pruned_below: 0,
+            hooks: EventHooks::default(),
+            checkpoints: VecDeque::new(),
+            snapshot_base: Some(snapshot),
+        })
+    }
+
+    /// Encode the whole chain, including the mempool, for storage on disk
+    pub fn to_bytes(&self) -> SierpinskiResult<Vec<u8>> {
+        serde_json::to_vec(self)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to encode blockchain: {}", e)))
+    }
+
+    /// Decode a chain written by `to_bytes`
+    ///
+    /// The cumulative work is recomputed from the blocks rather than trusted
+    /// from the file.
+    pub fn from_bytes(bytes: &[u8]) -> SierpinskiResult<Self> {
+        let mut blockchain: Self = serde_json::from_slice(bytes)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to decode blockchain: {}", e)))?;
+        blockchain.recompute_total_work();
+        Ok(blockchain)
+    }
+
+    /// Hash of balances, ownership, fractal state, supply and difficulty,
+    /// equal on any two nodes that applied the same blocks
+    pub fn state_hash(&self) -> String {
+        snapshot::state_hash(
+            &snapshot::sorted_balances(&self.balances),
+            &snapshot::sorted_owners(&self.triangle_owners),
+            &self.fractal_state,
+            self.total_supply,
+            &self.target,
+        )
+    }
+
+    /// Call `callback` with every state change from now on
+    ///
+    /// Events are delivered once the block causing them has been applied,
+    /// the block's own `BlockMined` last; a panicking callback is logged and
+    /// skipped. Subscriptions stay with this chain and are not cloned.
+    pub fn subscribe(&mut self, callback: EventCallback) {
+        self.hooks.callbacks.get_mut().unwrap_or_else(PoisonError::into_inner).push(callback);
+    }
+
+    /// Apply a block's effects to the blockchain state
+    fn apply_block(&mut self, block: &Block) -> SierpinskiResult<()> {
+        // Events of a block that failed to apply are never delivered
+        self.hooks.pending.clear();
+
+        let coinbase = block.coinbase()
+            .ok_or_else(|| SierpinskiError::validation("Block must start with a coinbase"))?;
+
+        // The coinbase mints at most the mining reward, after any claims in
+        // the block and no further once max supply is reached; checked
+        // before any transaction changes the state
+        let transactions = &block.triangle_transactions[1..];
+        let allowed_reward = self.capped_reward(block.scheduled_reward(), self.claims_minted(transactions));
+        if !matches!(coinbase.operation, TriangleOperation::Coinbase { amount } if amount <= allowed_reward) {
+            return Err(SierpinskiError::validation(format!(
+                "Block {} coinbase pays more than the scheduled reward of {}",
+                block.height, allowed_reward
+            )));
+        }
+
+        // Process each transaction
+        let mut activity = BlockActivity::default();
+        for transaction in transactions {
+            self.apply_transaction(transaction, block.height, &mut activity)?;
+        }
+        self.apply_transaction(coinbase, block.height, &mut activity)?;
+        self.accrue_block_fees(block);
+        self.claim_by_mining(&block.header.previous_hash, &block.miner_address, block.height, &mut activity)?;
+
+        // Advance the token economics by the triangles the block actually
+        // created and subdivided
+        self.economics.update_supply_after_block(activity.created, activity.subdivided)?;
+        self.index_transactions(block);
+
+        self.cumulative_difficulty = self.cumulative_difficulty.saturating_add(block_work(block));
+
+        if retargets_after(block.height, &self.difficulty_params) {
+            self.adjust_difficulty();
+        }
+
+        self.hooks.pending.push(BlockchainEvent::BlockMined(block.height));
+        self.hooks.flush();
+        Ok(())
+    }
+
+    /// Record the height of every transaction in a block being applied
+    fn index_transactions(&mut self, block: &Block) {
+        for transaction in &block.triangle_transactions {
+            self.transaction_index.insert(transaction.id, block.height);
+        }
+    }
+
+    /// Triangle the next block after `previous_hash` hands to its miner
+    ///
+    /// Picked deterministically from the hash among active triangles nobody
+    /// owns. When there are none, the pick is among the non-void children of
+    /// a shallowest leaf, which that block subdivides first. None once no
+    /// leaf can be subdivided any further.
+    pub fn claimable_address(&self, previous_hash: &str) -> Option<TriangleAddress> {
+        let mut unowned: Vec<TriangleAddress> = self.fractal_state
+            .leaves()
+            .into_iter()
+            .filter(|triangle| triangle.state == TriangleState::Active)
+            .filter(|triangle| !self.triangle_owners.contains_key(&triangle.address))
+            .map(|triangle| triangle.address.clone())
+            .collect();
+        if !unowned.is_empty() {
+            unowned.sort_by_key(|address| (address.depth(), address.components().to_vec()));
+            return Some(pick_by_hash(previous_hash, &unowned).clone());
+        }
+
+        let leaf = self.subdivision_leaf(previous_hash)?;
+        let children = leaf.children_active();
+        Some(pick_by_hash(previous_hash, &children).clone())
+    }
+
+    /// Shallowest leaf able to be split into children above the minimum
+    /// area, picked by `previous_hash`
+    fn subdivision_leaf(&self, previous_hash: &str) -> Option<TriangleAddress> {
+        let mut leaves: Vec<TriangleAddress> = (0..self.fractal_state.max_depth_limit())
+            .map(|depth| {
+                self.fractal_state
+                    .frontier_at_depth(depth)
+                    .into_iter()
+                    .filter(|triangle| triangle.can_subdivide())
+                    .filter(|triangle| triangle.area().is_ok_and(|area| area / Decimal::from(4) >= MIN_TRIANGLE_AREA))
+                    .map(|triangle| triangle.address.clone())
+                    .collect::<Vec<_>>()
+            })
+            .find(|leaves| !leaves.is_empty())?;
+        leaves.sort_by_key(|address| address.components().to_vec());
+        Some(pick_by_hash(previous_hash, &leaves).clone())
+    }
+
+    /// Give the block's miner the triangle `claimable_address` picks,
+    /// subdividing its parent first when the triangle does not exist yet
+    fn claim_by_mining(
+        &mut self,
+        previous_hash: &str,
+        miner: &str,
+        height: u64,
+        activity: &mut BlockActivity,
+    ) -> SierpinskiResult<()> {
+        let Some(address) = self.claimable_address(previous_hash) else {
+            return Ok(());
+        };
+
+        if self.fractal_state.find_by_address(&address).is_none() {
+            let parent = address
+                .parent()
+                .ok_or_else(|| SierpinskiError::validation(format!("No parent to subdivide for {}", address)))?;
+            self.subdivide_at(&parent)?;
+            activity.subdivided += 1;
+        }
+
+        self.set_owner(address, miner.to_string(), height);
+        Ok(())
+    }
+
+    /// Split the triangle at `address` into its children
+    fn subdivide_at(&mut self, address: &TriangleAddress) -> SierpinskiResult<()> {
+        let parent_id = self.fractal_state
+            .find_by_address(address)
+            .map(|parent| parent.id)
+            .ok_or_else(|| SierpinskiError::validation(format!("No triangle to subdivide at {}", address)))?;
+        subdivide_and_add_to_structure(&mut self.fractal_state, &parent_id)?;
+        self.hooks.pending.push(BlockchainEvent::TriangleSubdivided(address.clone()));
+        Ok(())
+    }
+
+    /// Fail unless the triangle at `address` exists and can be subdivided
+    fn check_subdividable(&self, address: &TriangleAddress) -> SierpinskiResult<()> {
+        match self.fractal_state.find_by_address(address) {
+            Some(triangle) if triangle.can_subdivide() => Ok(()),
+            Some(_) => Err(SierpinskiError::validation(format!("Triangle {} cannot be subdivided", address))),
+            None => Err(SierpinskiError::validation(format!("No triangle to subdivide at {}", address))),
+        }
+    }
+
+    /// Fail if a resident triangle already holds `address`, as lookups by
+    /// address could then pick either one
+    fn check_unoccupied(&self, address: &TriangleAddress) -> SierpinskiResult<()> {
+        match self.fractal_state.find_by_address(address) {
+            Some(_) => Err(SierpinskiError::validation(format!("Address {} already holds a triangle", address))),
+            None => Ok(()),
+        }
+    }
+
+    /// Limit a block reward to what remains below max supply once
+    /// `minted_first` more tokens have been minted
+    fn capped_reward(&self, scheduled: Decimal, minted_first: Decimal) -> Decimal {
+        scheduled.min(self.max_supply - self.total_supply - minted_first).max(Decimal::ZERO)
+    }
+
+    /// Tokens the reward claims among `transactions` would mint once the
+    /// rewards pool has paid what it can
+    fn claims_minted(&self, transactions: &[TriangleTransaction]) -> Decimal {
+        let mut pool = self.rewards.pool_balance();
+        transactions
+            .iter()
+            .filter_map(|tx| match tx.operation {
+                TriangleOperation::ClaimReward { amount } => Some(amount),
+                _ => None,
+            })
+            .map(|amount| {
+                let from_pool = amount.min(pool);
+                pool -= from_pool;
+                amount - from_pool
+            })
+            .sum()
+    }
+
+    /// Move the gas fees a block collected into the rewards pool, claimable
+    /// by the block's miner
+    fn accrue_block_fees(&mut self, block: &Block) {
+        let fees: Decimal = block.triangle_transactions[1..]
+            .iter()
+            .filter(|tx| tx.fee_payer().is_some())
+            .map(|tx| tx.gas_fee)
+            .sum();
+        if fees <= Decimal::ZERO {
+            return;
+        }
+        self.rewards.pool_funded += fees;
+        *self.rewards.accrued.entry(block.miner_address.clone()).or_insert(Decimal::ZERO) += fees;
+    }
+
+    /// Split a claim into the part paid from the rewards pool and the part
+    /// newly minted, rejecting over-claims and claims past max supply
+    fn claim_payout(&self, claimant: &str, amount: Decimal) -> SierpinskiResult<(Decimal, Decimal)> {
+        if amount > self.rewards.claimable(claimant) {
+            return Err(SierpinskiError::validation("Claim exceeds accrued rewards"));
+        }
+
+        let from_pool = amount.min(self.rewards.pool_balance());
+        let minted = amount - from_pool;
+        if self.total_supply + minted > self.max_supply {
+            return Err(SierpinskiError::validation("Claim would exceed maximum supply"));
+        }
+        Ok((from_pool, minted))
+    }
+
+    /// Make `owner` the owner of a triangle as of block `height`
+    fn set_owner(&mut self, address: TriangleAddress, owner: String, height: u64) {
+        self.ownership_history.entry(address.clone()).or_default().push((owner.clone(), height));
+        let from = self.triangle_owners.insert(address.clone(), owner.clone());
+        if from.as_ref() != Some(&owner) {
+            self.hooks.pending.push(BlockchainEvent::OwnershipChanged { address, from, to: owner });
+        }
+    }
+
+    /// Apply the effects of a transaction in the block at `height`, counting
+    /// the triangles it creates and subdivides in `activity`
+    fn apply_transaction(
+        &mut self,
+        transaction: &TriangleTransaction,
+        height: u64,
+        activity: &mut BlockActivity,
+    ) -> SierpinskiResult<()> {
+        match &transaction.operation {
+            TriangleOperation::Create => {
+                self.check_unoccupied(&transaction.to_address)?;
+                if let Some(triangle_data) = &transaction.triangle_data {
+                    // Create new fractal triangle
+                    let fractal_triangle = FractalTriangle::new(
+                        triangle_data.clone(),
+                        crate::core::state::TriangleState::Active,
+                        transaction.to_address.clone(),
+                        transaction.to_address.depth(),
+                    );
+
+                    self.fractal_state.add_triangle(fractal_triangle)?;
+                    activity.created += 1;
+                    
+                    // Set ownership
+                    if let Some(from_addr) = &transaction.from_address {
+                        self.set_owner(transaction.to_address.clone(), from_addr.to_string(), height);
+                    }
+                }
+            }
+            
+            TriangleOperation::Subdivide => {
+                let (Some(triangle), Some(sender)) = (&transaction.from_address, &transaction.sender) else {
+                    return Err(SierpinskiError::validation("Subdivide requires a triangle and sender"));
+                };
+                if self.triangle_owners.get(triangle) != Some(sender) {
+                    return Err(SierpinskiError::validation(format!("Triangle {} not owned by sender", triangle)));
+                }
+
+                // The owner of a triangle owns the children it splits into
+                self.subdivide_at(triangle)?;
+                for child in triangle.children_active() {
+                    self.set_owner(child, sender.clone(), height);
+                }
+                activity.subdivided += 1;
+            }
+            
+            TriangleOperation::Transfer => {
+                let (Some(triangle), Some(sender), Some(recipient)) =
+                    (&transaction.from_address, &transaction.sender, &transaction.recipient)
+                else {
+                    return Err(SierpinskiError::validation("Transfer requires a triangle, sender and recipient"));
+                };
+                if self.triangle_owners.get(triangle) != Some(sender) {
+                    return Err(SierpinskiError::validation(format!("Triangle {} not owned by sender", triangle)));
+                }
+                self.set_owner(triangle.clone(), recipient.clone(), height);
+
+                // A priced transfer sets the triangle's market price
+                if let Some(price) = transaction.price {
+                    self.economics.market_prices.insert(triangle.clone(), price);
+                }
+            }
+            
+            TriangleOperation::Stake { amount } => {
+                let (Some(triangle), Some(sender)) = (&transaction.from_address, &transaction.sender) else {
+                    return Err(SierpinskiError::validation("Stake requires a triangle and sender"));
+                };
+                if self.triangle_owners.get(triangle) != Some(sender) {
+                    return Err(SierpinskiError::validation(format!("Triangle {} not owned by sender", triangle)));
+                }
+                let balance = self.get_balance(sender);
+                if balance < *amount {
+                    return Err(SierpinskiError::validation(format!(
+                        "Balance {} of {} cannot cover a stake of {}",
+                        balance, sender, amount
+                    )));
+                }
+
+                // The stake moves from the sender's balance into the pool
+                self.economics.record_stake(triangle, sender, *amount, transaction.timestamp)?;
+                self.balances.insert(sender.clone(), balance - amount);
+            }
+            
+            TriangleOperation::Coinbase { amount } => {
+                let miner = transaction.recipient.as_ref()
+                    .ok_or_else(|| SierpinskiError::validation("Coinbase requires a recipient"))?;
+                *self.balances.entry(miner.clone()).or_insert(Decimal::ZERO) += *amount;
+                self.total_supply += *amount;
+            }
+
+            TriangleOperation::ClaimReward { amount } => {
+                let claimant = transaction.sender.as_ref()
+                    .ok_or_else(|| SierpinskiError::validation("Reward claim requires a sender"))?;
+                let (from_pool, minted) = self.claim_payout(claimant, *amount)?;
+
+                *self.rewards.claimed.entry(claimant.clone()).or_insert(Decimal::ZERO) += *amount;
+                self.rewards.pool_paid += from_pool;
+                self.total_supply += minted;
+                *self.balances.entry(claimant.clone()).or_insert(Decimal::ZERO) += *amount;
+            }
+
+            _ => {} // Handle other operations
+        }
+
+        // Deduct gas fees
+        if let Some(payer) = transaction.fee_payer() {
+            let balance = self.get_balance(&payer);
+            if balance < transaction.gas_fee {
+                return Err(SierpinskiError::validation(format!(
+                    "Balance {} of {} cannot cover a gas fee of {}",
+                    balance, payer, transaction.gas_fee
+                )));
+            }
+            self.balances.insert(payer, balance - transaction.gas_fee);
+        }
+
+        self.hooks.pending.push(BlockchainEvent::TransactionApplied(transaction.id));
+        Ok(())
+    }
+
+    /// Scale the target by how far the last window of blocks strayed from
+    /// the target time, see `retarget`
+    fn adjust_difficulty(&mut self) {
+        if let Some(adjusted) = retarget(self.target, &self.blocks, &self.difficulty_params) {
+            self.target = adjusted;
+        }
+    }
+
+    /// Validate the entire blockchain
+    pub fn validate_chain(&self) -> SierpinskiResult<bool> {
+        if self.blocks.is_empty() {
+            return Err(SierpinskiError::validation("Empty blockchain"));
+        }
+
+        // Validate genesis block, unless it was pruned by a snapshot
+        if self.blocks[0].height == 0 && self.blocks[0].header.previous_hash != "0".repeat(64) {
+            return Err(SierpinskiError::validation("Invalid genesis block"));
+        }
+
+        // Validate chain links
+        for i in 1..self.blocks.len() {
+            let prev_hash = self.blocks[i - 1].hash();
+            if self.blocks[i].header.previous_hash != prev_hash {
+                return Err(SierpinskiError::validation("Broken chain link"));
+            }
+            
+            // Validate individual block; a pruned one only has its header
+            // and the link to it left to check
+            if !self.is_pruned(self.blocks[i].height) {
+                self.blocks[i].validate()?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Validate the chain beyond its links: check every transaction
+    /// signature, replay the blocks from genesis (or the snapshot) to check
+    /// each block's fractal-state root, and compare the replayed balances,
+    /// owners, supply and fractal state with the stored ones
+    pub fn deep_validate(&self) -> ValidationResult {
+        let mut result = ValidationResult::success();
+        if let Err(e) = self.validate_chain() {
+            result.add_error(format!("Chain: {}", e));
+        }
+
+        for block in &self.blocks {
+            for transaction in &block.triangle_transactions {
+                if let Err(e) = Self::verify_sender_signature(transaction) {
+                    result.add_error(format!("Block {} transaction {}: {}", block.height, transaction.id, e));
+                }
+            }
+        }
+
+        let replayed = match self.rebuild_from_blocks(self.blocks.clone()) {
+            Ok(replayed) => replayed,
+            // Header-only chains keep no bodies to replay; the links, work
+            // and recent bodies checked above are all there is to check
+            Err(SierpinskiError::PrunedDataUnavailable { height }) => {
+                result.add_warning(format!("Replay skipped: transactions of block {} were pruned", height));
+                return result;
+            }
+            Err(e) => {
+                result.add_error(format!("Replay: {}", e));
+                return result;
+            }
+        };
+
+        let mut wallets: Vec<&String> = self.balances.keys().chain(replayed.balances.keys()).collect();
+        wallets.sort();
+        wallets.dedup();
+        for wallet in wallets {
+            let stored = self.balances.get(wallet).copied().unwrap_or_default();
+            let derived = replayed.balances.get(wallet).copied().unwrap_or_default();
+            if stored != derived {
+                result.add_error(format!("Balance of {} is {} but the blocks give {}", wallet, stored, derived));
+            }
+        }
+
+        let mut addresses: Vec<&TriangleAddress> = self.triangle_owners.keys().chain(replayed.triangle_owners.keys()).collect();
+        addresses.sort_by(|a, b| a.components().cmp(b.components()));
+        addresses.dedup();
+        for address in addresses {
+            let (stored, derived) = (self.triangle_owners.get(address), replayed.triangle_owners.get(address));
+            if stored != derived {
+                result.add_error(format!("Owner of {} is {:?} but the blocks give {:?}", address, stored, derived));
+            }
+        }
+
+        if self.total_supply != replayed.total_supply {
+            result.add_error(format!(
+                "Total supply is {} but the blocks give {}",
+                self.total_supply, replayed.total_supply
+            ));
+        }
+        if self.fractal_state.state_root() != replayed.fractal_state.state_root() {
+            result.add_error("Fractal state does not match the one the blocks give".to_string());
+        }
+
+        result
+    }
+
+    /// Token economics as advanced by the blocks applied so far
+    pub fn economics_stats(&self) -> EconomicsStats {
+        self.economics.get_economics_stats()
+    }
+
+    /// Get current blockchain statistics
+    pub fn stats(&self) -> BlockchainStats {
+        BlockchainStats {
+            total_blocks: self.blocks.len(),
+            total_transactions: self.blocks.iter().map(|b| b.triangle_transactions.len()).sum(),
+            total_supply: self.total_supply,
+            current_difficulty: self.target.difficulty(),
+            mempool_size: self.mempool.len(),
+            total_triangles: self.fractal_state.total_triangles(),
+            unique_addresses: self.balances.len(),
+        }
+    }
+
+    /// Total proof-of-work of the chain, used to pick the heaviest chain
+    pub fn total_work(&self) -> u128 {
+        self.cumulative_difficulty
+    }
+
+    /// Work of the chain ending in `recent_blocks` that this chain can check
+    /// for itself, unlike the total a snapshot declares: ours up to where
+    /// the blocks fork from us plus theirs after it, or theirs alone when
+    /// they do not connect to our chain; zero when none of them are new
+    pub fn verifiable_work(&self, recent_blocks: &[Block]) -> u128 {
+        let Some(new_start) = recent_blocks
+            .iter()
+            .position(|block| self.get_block_by_hash(&block.hash()).is_none())
+        else {
+            return 0;
+        };
+        let new_blocks = &recent_blocks[new_start..];
+        let shared = self.blocks
+            .iter()
+            .position(|block| block.hash() == new_blocks[0].header.previous_hash)
+            .map_or(0, |index| self.total_work().saturating_sub(chain_work(&self.blocks[index + 1..])));
+        shared.saturating_add(chain_work(new_blocks))
+    }
+
+    /// Recalculate the cumulative work from the blocks, e.g. after loading a
+    /// snapshot that predates the `cumulative_difficulty` field
+    pub fn recompute_total_work(&mut self) {
+        self.cumulative_difficulty = match &self.snapshot_base {
+            Some(base) => {
+                let later = self.blocks.iter().filter(|block| block.height > base.height);
+                later.map(block_work).fold(base.cumulative_difficulty, u128::saturating_add)
+            }
+            None => chain_work(&self.blocks),
+        };
+    }
+
+    /// Height of the latest block
+    pub fn tip_height(&self) -> u64 {
+        self.blocks.last().map_or(0, |block| block.height)
+    }
+
+    /// Number of blocks in the chain, counting any pruned by a snapshot
+    pub fn chain_length(&self) -> u64 {
+        self.blocks.last().map_or(0, |block| block.height + 1)
+    }
+
+    /// Target a block at `height` must be mined at when it follows this
+    /// chain's blocks below it; None past the next block
+    pub fn target_at_height(&self, height: u64) -> Option<Target> {
+        if height == self.chain_length() {
+            return Some(self.target);
+        }
+        self.get_block_by_height(height)?.header.target().ok()
+    }
+
+    /// Target for the block after one at `height` mined at `target`, where
+    /// `timestamp_at` gives the timestamps of the blocks up to it; None when
+    /// a retarget needs a timestamp it does not know
+    pub fn target_after(&self, height: u64, target: Target, timestamp_at: impl Fn(u64) -> Option<u64>) -> Option<Target> {
+        let params = &self.difficulty_params;
+        if params.window < 2 || !retargets_after(height, params) {
+            return Some(target);
+        }
+        let first = timestamp_at(height + 1 - params.window as u64)?;
+        let last = timestamp_at(height)?;
+        Some(retarget_span(target, first, last, params))
+    }
+
+    /// Get the block at a given height
+    pub fn get_block_by_height(&self, height: u64) -> Option<&Block> {
+        self.blocks.iter().find(|block| block.height == height)
+    }
+
+    /// Height of the block that included a transaction, if it is on the
+    /// chain
+    pub fn transaction_height(&self, id: &Uuid) -> Option<u64> {
+        self.transaction_index.get(id).copied()
+    }
+
+    /// Get a transaction included on the chain, failing with
+    /// `PrunedDataUnavailable` if its block's body was pruned
+    pub fn get_transaction(&self, id: &Uuid) -> SierpinskiResult<Option<&TriangleTransaction>> {
+        let Some(height) = self.transaction_height(id) else { return Ok(None) };
+        Ok(self.block_transactions(height)?.iter().find(|tx| tx.id == *id))
+    }
+
+    /// Transactions of the block at `height`, failing with
+    /// `PrunedDataUnavailable` if its body was pruned
+    pub fn block_transactions(&self, height: u64) -> SierpinskiResult<&[TriangleTransaction]> {
+        if self.is_pruned(height) {
+            return Err(SierpinskiError::PrunedDataUnavailable { height });
+        }
+        self.get_block_by_height(height)
+            .map(|block| block.triangle_transactions.as_slice())
+            .ok_or_else(|| SierpinskiError::validation(format!("No block at height {}", height)))
+    }
+
+    /// Get the block with a given hash
+    pub fn get_block_by_hash(&self, hash: &str) -> Option<&Block> {
+        self.blocks.iter().find(|block| block.hash() == hash)
+    }
+
+    /// Get the triangle living at a fractal address
+    pub fn get_triangle(&self, address: &TriangleAddress) -> Option<&FractalTriangle> {
+        self.fractal_state
+            .triangles_at_depth(address.depth())
+            .into_iter()
+            .find(|triangle| triangle.address == *address)
+    }
+
+    /// Suggest a gas fee for a new transaction
+    ///
+    /// Takes the priority's percentile of the fees paid in recent blocks and
+    /// offered by pending transactions, never going below the base fee. A
+    /// deep target needs more, see `estimate_gas_fee_for`.
+    pub fn estimate_gas_fee(&self, priority: FeePriority) -> Decimal {
+        self.recent_fee(priority).map_or(self.gas_floor.base, |fee| fee.max(self.gas_floor.base))
+    }
+
+    /// Suggest a gas fee for a new transaction targeting `address`, never
+    /// going below what the mempool accepts for it, see `minimum_gas_for`
+    pub fn estimate_gas_fee_for(&self, address: &TriangleAddress, priority: FeePriority) -> Decimal {
+        let floor = self.minimum_gas_for(address);
+        self.recent_fee(priority).map_or(floor, |fee| fee.max(floor))
+    }
+
+    /// The priority's percentile of recently paid and pending fees
+    fn recent_fee(&self, priority: FeePriority) -> Option<Decimal> {
+        // The genesis block carries no real fees, nor do coinbases
+        let mined = self.blocks
+            .iter()
+            .skip(1)
+            .rev()
+            .take(FEE_HISTORY_BLOCKS)
+            .flat_map(|block| block.triangle_transactions.iter().skip(1));
+        let mut fees: Vec<Decimal> = mined
+            .chain(&self.mempool)
+            .map(|tx| tx.gas_fee)
+            .collect();
+        fees.sort();
+
+        priority.pick(&fees)
+    }
+
+    /// Get balance for an address
+    pub fn get_balance(&self, address: &str) -> Decimal {
+        *self.balances.get(address).unwrap_or(&Decimal::ZERO)
+    }
+
+    /// Owners a triangle has had, oldest first, each with the height of the
+    /// block that made them owner
+    pub fn ownership_history(&self, address: &TriangleAddress) -> &[(String, u64)] {
+        self.ownership_history.get(address).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get triangles owned by an address
+    pub fn get_owned_triangles(&self, owner: &str) -> Vec<TriangleAddress> {
+        self.triangle_owners
+            .iter()
+            .filter(|(_, addr)| *addr == owner)
+            .map(|(triangle_addr, _)| triangle_addr.clone())
+            .collect()
+    }
+}
+
+/// Seconds since the Unix epoch by the local clock
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Choose an item from a non-empty list using a block hash
+fn pick_by_hash<'a, T>(hash: &str, items: &'a [T]) -> &'a T {
+    let digest = blake3::hash(hash.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest.as_bytes()[..8]);
+    &items[(u64::from_le_bytes(bytes) % items.len() as u64) as usize]
+}
+
+/// Serde helper storing maps keyed by triangle address as a sorted list of
+/// pairs, since JSON map keys must be strings
+pub(crate) mod address_map_serde {
+    use super::*;
+    use serde::{de::DeserializeOwned, Deserializer, Serializer};
+
+    pub fn serialize<S, V>(map: &HashMap<TriangleAddress, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        V: Serialize,
+    {
+        let mut pairs: Vec<(&TriangleAddress, &V)> = map.iter().collect();
+        pairs.sort_by(|a, b| a.0.components().cmp(b.0.components()));
+        pairs.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D, V>(deserializer: D) -> Result<HashMap<TriangleAddress, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: DeserializeOwned,
+    {
+        let pairs: Vec<(TriangleAddress, V)> = Deserialize::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
+/// Proof-of-work represented by a block, the expected number of hashes to
+/// meet its target
+fn block_work(block: &Block) -> u128 {
+    block.header.target().map_or(0, |target| target.work())
+}
+
+/// Cumulative proof-of-work of a run of blocks
+fn chain_work(blocks: &[Block]) -> u128 {
+    blocks.iter().map(block_work).fold(0, u128::saturating_add)
+}
+
+/// Whether the target is retargeted once the block at `height` applies,
+/// which happens once per window
+fn retargets_after(height: u64, params: &DifficultyParams) -> bool {
+    let window = params.window as u64;
+    window > 0 && height > 0 && height.is_multiple_of(window)
+}
+
+/// `target` scaled by how far the last window of `blocks` strayed from the
+/// target time, by at most `MAX_RETARGET_FACTOR` and within the configured
+/// bounds; None when `blocks` do not fill a window
+fn retarget(target: Target, blocks: &[Block], params: &DifficultyParams) -> Option<Target> {
+    if params.window < 2 || blocks.len() < params.window {
+        return None;
+    }
+
+    let recent_blocks = &blocks[blocks.len() - params.window..];
+    Some(retarget_span(
+        target,
+        recent_blocks.first().unwrap().header.timestamp,
+        recent_blocks.last().unwrap().header.timestamp,
+        params,
+    ))
+}
+
+/// `target` scaled by how far a window of blocks timestamped from `first`
+/// to `last` strayed from the target time, see `retarget`
+fn retarget_span(target: Target, first: u64, last: u64, params: &DifficultyParams) -> Target {
+    let time_span = last.saturating_sub(first).max(1);
+    // A window of N blocks spans N - 1 block intervals
+    let target_time = params.target_block_time_secs * (params.window as u64 - 1);
+
+    // Blocks arriving twice as fast as targeted halve the target
+    let time_span = time_span.clamp(target_time / MAX_RETARGET_FACTOR, target_time * MAX_RETARGET_FACTOR);
+    let adjusted = target.scaled(time_span, target_time);
+
+    adjusted.clamp(
+        Target::from_legacy_difficulty(params.max_difficulty),
+        Target::from_legacy_difficulty(params.min_difficulty),
+    )
+}
+
+/// Check the proof-of-work of a run of consecutive blocks and that each was
+/// mined at the target the ones before it set, ending at `next_target`
+///
+/// The blocks are all that is known of the chain, so the first block's
+/// target, and any set by a retarget whose window reaches back past the
+/// first block, are taken as given.
+fn verify_block_targets(blocks: &[Block], next_target: Target, params: &DifficultyParams) -> SierpinskiResult<()> {
+    let mut expected = None;
+    for (index, block) in blocks.iter().enumerate() {
+        if !block.meets_difficulty_target() {
+            return Err(SierpinskiError::validation(format!(
+                "Block {} does not meet its difficulty target",
+                block.height
+            )));
+        }
+        if let Some(expected) = expected.filter(|expected: &Target| block.header.bits != expected.to_compact()) {
+            return Err(SierpinskiError::validation(format!(
+                "Block {} was mined at target bits {:#010x} instead of {:#010x}",
+                block.height, block.header.bits, expected.to_compact()
+            )));
+        }
+
+        let target = block.header.target()?;
+        expected = if retargets_after(block.height, params) {
+            retarget(target, &blocks[..=index], params)
+        } else {
+            Some(target)
+        };
+    }
+
+    match expected {
+        Some(expected) if expected.to_compact() != next_target.to_compact() => Err(SierpinskiError::validation(
+            "Snapshot target does not follow from its recent blocks",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Blockchain shared between tasks, such as the network's connection
+/// handlers
+///
+/// A panic while the chain is locked poisons a plain `Mutex` and every later
+/// `lock().unwrap()` panics in turn. Access through this wrapper instead
+/// recovers the chain from a poisoned lock, logging a warning, so one failed
+/// handler does not take the others down with it.
+#[derive(Debug, Clone, Default)]
+pub struct SharedBlockchain {
+    inner: Arc<Mutex<TriadChainBlockchain>>,
+}
+
+impl SharedBlockchain {
+    pub fn new(blockchain: TriadChainBlockchain) -> Self {
+        SharedBlockchain {
+            inner: Arc::new(Mutex::new(blockchain)),
+        }
+    }
+
+    /// Lock the chain, recovering it if a previous holder panicked
+    pub fn lock(&self) -> MutexGuard<'_, TriadChainBlockchain> {
+        self.inner.lock().unwrap_or_else(|poisoned| {
+            warn!("Recovering blockchain lock poisoned by a panicked task");
+            self.inner.clear_poison();
+            poisoned.into_inner()
+        })
+    }
+
+    /// Run `f` with shared access to the chain
+    pub fn read<R>(&self, f: impl FnOnce(&TriadChainBlockchain) -> R) -> R {
+        f(&self.lock())
+    }
+
+    /// Run `f` with exclusive access to the chain
+    pub fn with<R>(&self, f: impl FnOnce(&mut TriadChainBlockchain) -> R) -> R {
+        f(&mut self.lock())
+    }
+}
+
+impl From<TriadChainBlockchain> for SharedBlockchain {
+    fn from(blockchain: TriadChainBlockchain) -> Self {
+        SharedBlockchain::new(blockchain)
+    }
+}
+
+/// A block held in the orphan pool until its parent arrives
+#[derive(Debug, Clone)]
+pub struct Orphan {
+    pub block: Block,
+    /// Peer the block came from
+    pub peer: String,
+    /// Local time the block was received, in seconds since the Unix epoch
+    pub received_at: u64,
+}
+
+/// What `add_block_or_orphan` did with a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockAcceptance {
+    /// Appended to the chain, along with any orphans it unblocked
+    Connected,
+    /// Held until its parent arrives
+    Orphaned,
+    /// Refused, with the reason
+    Rejected(String),
+}
+
+/// Change to chain state delivered to `TriadChainBlockchain::subscribe` callbacks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockchainEvent {
+    /// A block was appended at this height, mined locally or received
+    BlockMined(u64),
+    /// A transaction in an appended block took effect
+    TransactionApplied(Uuid),
+    /// The triangle at this address was split into children
+    TriangleSubdivided(TriangleAddress),
+    /// A triangle got a new owner; `from` is None for its first owner
+    OwnershipChanged {
+        address: TriangleAddress,
+        from: Option<String>,
+        to: String,
+    },
+    /// The chain switched to a heavier fork replacing every block from
+    /// this height; events delivered for the old blocks there no longer
+    /// hold, and none are sent for the new ones, so state should be reread
+    Reorganized { fork_height: u64 },
+}
+
+/// Callback registered with `TriadChainBlockchain::subscribe`
+pub type EventCallback = Box<dyn Fn(&BlockchainEvent) + Send>;
+
+/// Subscribed callbacks and the events of the block being applied
+///
+/// The callbacks sit behind a mutex only so the chain stays `Sync`; they
+/// are called with it held.
+#[derive(Default)]
+struct EventHooks {
+    callbacks: Mutex<Vec<EventCallback>>,
+    pending: Vec<BlockchainEvent>,
+}
+
+impl EventHooks {
+    /// Deliver the pending events in order
+    fn flush(&mut self) {
+        for event in std::mem::take(&mut self.pending) {
+            self.dispatch(&event);
+        }
+    }
+
+    /// Call every callback with `event`, isolating panics
+    fn dispatch(&self, event: &BlockchainEvent) {
+        let callbacks = self.callbacks.lock().unwrap_or_else(PoisonError::into_inner);
+        for callback in callbacks.iter() {
+            if std::panic::catch_unwind(AssertUnwindSafe(|| callback(event))).is_err() {
+                warn!(?event, "Blockchain event callback panicked");
+            }
+        }
+    }
+}
+
+impl Clone for EventHooks {
+    /// Subscriptions belong to one chain; a clone starts without any
+    fn clone(&self) -> Self {
+        EventHooks::default()
+    }
+}
+
+impl std::fmt::Debug for EventHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let subscribers = self.callbacks.lock().unwrap_or_else(PoisonError::into_inner).len();
+        f.debug_struct("EventHooks").field("subscribers", &subscribers).finish()
+    }
+}
+
+/// Predicted outcome of a transaction, from `simulate_transaction`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub will_succeed: bool,
+    /// Change in the fee payer's balance, gas fee included
+    pub balance_delta: Decimal,
+    /// Triangles that would get a new owner, with that owner
+    pub ownership_changes: Vec<(TriangleAddress, String)>,
+    pub errors: Vec<String>,
+}
+
+/// Blockchain statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockchainStats {
+    pub total_blocks: usize,
+    pub total_transactions: usize,
+    pub total_supply: Decimal,
+    /// `Target::difficulty` of the current target, for display
+    pub current_difficulty: f64,
+    pub mempool_size: usize,
+    pub total_triangles: usize,
     pub unique_addresses: usize,
 }
 
-impl Default for TriadChainBlockchain {
-    fn default() -> Self {
-        Self::new().unwrap()
+impl Default for TriadChainBlockchain {
+    fn default() -> Self {
+        Self::new().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Address no test transaction has created yet, below the void child
+    /// of the genesis triangle, which mining never subdivides
+    fn unused_address() -> TriangleAddress {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+        let next = NEXT.fetch_add(1, Ordering::Relaxed);
+        let digits = (0..8).map(|digit| (next >> (2 * digit) & 3) as u8);
+        TriangleAddress::new([3, 3].into_iter().chain(digits).collect()).unwrap()
+    }
+
+    /// Unsigned creation of a triangle at an unused address paying
+    /// `gas_fee`, which must clear the floor at that depth
+    fn pending_transaction(gas_fee: Decimal) -> TriangleTransaction {
+        TriangleTransaction::new(
+            None,
+            unused_address(),
+            TriangleOperation::Create,
+            Some(crate::core::genesis::genesis_triangle().unwrap()),
+            gas_fee,
+        )
+    }
+
+    #[test]
+    fn test_blockchain_creation() {
+        let blockchain = TriadChainBlockchain::new().unwrap();
+        assert_eq!(blockchain.blocks.len(), 1); // Genesis block
+        assert!(blockchain.total_supply > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_blockchain_validation() {
+        let blockchain = TriadChainBlockchain::new().unwrap();
+        assert!(blockchain.validate_chain().unwrap());
+    }
+
+    #[test]
+    fn test_mempool_operations() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        
+        let tx = TriangleTransaction::new(
+            None,
+            TriangleAddress::new(vec![3]).unwrap(),
+            TriangleOperation::Create,
+            Some(crate::core::genesis::genesis_triangle().unwrap()),
+            Decimal::new(2, 2),
+        );
+        
+        blockchain.add_transaction(tx).unwrap();
+        assert_eq!(blockchain.mempool.len(), 1);
+    }
+
+    #[test]
+    fn test_evict_stale_transactions() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        blockchain.max_mempool_age_secs = 60;
+
+        let mut old = pending_transaction(Decimal::ONE);
+        old.timestamp = 1_000;
+        let mut fresh = pending_transaction(Decimal::ONE);
+        fresh.timestamp = 1_100;
+        blockchain.add_transaction(old).unwrap();
+        blockchain.add_transaction(fresh.clone()).unwrap();
+
+        assert_eq!(blockchain.evict_stale_transactions(1_120), 1);
+        assert_eq!(blockchain.mempool.len(), 1);
+        assert_eq!(blockchain.mempool[0].id, fresh.id);
+        assert_eq!(blockchain.evict_stale_transactions(1_120), 0);
+    }
+
+    /// Find a nonce meeting the block's difficulty
+    fn solve(block: &mut Block) {
+        while !block.meets_difficulty_target() {
+            block.set_nonce(block.header.nonce + 1);
+        }
+    }
+
+    #[test]
+    fn test_backdated_block_is_rejected() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        for _ in 0..3 {
+            blockchain.mine_block("miner".to_string(), 10).unwrap();
+        }
+
+        let mut backdated = blockchain.block_template("miner".to_string(), 10).unwrap();
+        assert!(backdated.header.timestamp > blockchain.median_time_past());
+        backdated.header.timestamp = blockchain.median_time_past();
+        solve(&mut backdated);
+        assert!(blockchain.validate_block_in_context(&backdated).is_err());
+        assert!(blockchain.try_add_block(backdated).is_err());
+
+        let mut ancient = blockchain.block_template("miner".to_string(), 10).unwrap();
+        ancient.header.timestamp = 0;
+        solve(&mut ancient);
+        assert!(blockchain.try_add_block(ancient).is_err());
+
+        let mut future = blockchain.block_template("miner".to_string(), 10).unwrap();
+        future.header.timestamp = unix_now() + MAX_FUTURE_BLOCK_SECS + 60;
+        assert!(blockchain.validate_block_in_context(&future).is_err());
+
+        assert_eq!(blockchain.tip_height(), 3);
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+    }
+
+    #[test]
+    fn test_peer_blocks_must_use_the_expected_target() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(4)).unwrap();
+        let mut fork = blockchain.clone();
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+
+        // Mined at the easiest target, it passes its own bits but not ours
+        let mut easy = fork.block_template("cheat".to_string(), 10).unwrap();
+        easy.header.bits = Target::MAX.to_compact();
+        solve(&mut easy);
+        assert!(easy.meets_difficulty_target());
+        assert!(matches!(blockchain.clone().add_block_or_orphan(easy.clone(), "peer"), BlockAcceptance::Rejected(_)));
+
+        // Nor do enough of them to outweigh our chain get in by a reorg
+        fork.blocks.push(easy);
+        while chain_work(&fork.blocks[1..]) <= chain_work(&blockchain.blocks[1..]) {
+            let mut next = fork.block_template("cheat".to_string(), 10).unwrap();
+            next.header.bits = Target::MAX.to_compact();
+            solve(&mut next);
+            fork.blocks.push(next);
+        }
+        let error = blockchain.try_reorg(&fork.blocks).unwrap_err();
+        assert!(error.to_string().contains("target bits"), "{}", error);
+        assert_eq!(blockchain.tip_height(), 1);
+    }
+
+    #[test]
+    fn test_expired_transactions_are_evicted_and_rejected() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        blockchain.max_mempool_age_secs = u64::MAX;
+
+        let mut expired = pending_transaction(Decimal::new(5, 2));
+        expired.timestamp = unix_now() - DEFAULT_TRANSACTION_EXPIRY_SECS - 60;
+        blockchain.add_transaction(expired.clone()).unwrap();
+
+        // Templates leave it out, and a block including it is invalid
+        let template = blockchain.block_template("miner".to_string(), 10).unwrap();
+        assert_eq!(template.triangle_transactions.len(), 1);
+
+        let tip = blockchain.blocks.last().unwrap();
+        let mut block = Block::new(tip.hash(), vec![expired], &blockchain.fractal_state, "miner".to_string(), blockchain.target);
+        block.height = 1;
+        block.header.timestamp = template.header.timestamp;
+        solve(&mut block);
+        let error = blockchain.try_add_block(block).unwrap_err();
+        assert!(error.to_string().contains("expired"));
+
+        assert_eq!(blockchain.evict_stale_transactions(unix_now()), 1);
+        assert!(blockchain.mempool.is_empty());
+    }
+
+    #[test]
+    fn test_simulate_transfer_of_unowned_triangle() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let mut sender = TriadChainWallet::from_seed(&[1; 32]);
+        let recipient = TriadChainWallet::from_seed(&[2; 32]);
+        let triangle = TriangleAddress::from_string_representation("0.1").unwrap();
+        blockchain.triangle_owners.insert(triangle.clone(), recipient.wallet_id.clone());
+
+        let mut transfer = TriangleTransaction::new(
+            Some(triangle.clone()),
+            triangle.clone(),
+            TriangleOperation::Transfer,
+            None,
+            Decimal::new(5, 2),
+        );
+        transfer.sender = Some(sender.wallet_id.clone());
+        transfer.recipient = Some(recipient.wallet_id.clone());
+        sender.sign_transaction(&mut transfer).unwrap();
+
+        let before = blockchain.state_hash();
+        let result = blockchain.simulate_transaction(&transfer);
+        assert!(!result.will_succeed);
+        assert!(result.errors.iter().any(|error| error.contains("not owned")));
+        assert!(result.errors.iter().any(|error| error.contains("Insufficient balance")));
+        assert_eq!(blockchain.state_hash(), before);
+
+        let mut tampered = transfer.clone();
+        tampered.gas_fee = Decimal::new(1, 2);
+        let result = blockchain.simulate_transaction(&tampered);
+        assert!(result.errors.iter().any(|error| error.contains("signature")));
+
+        // Once owned and funded, the transfer goes through
+        blockchain.triangle_owners.insert(triangle.clone(), sender.wallet_id.clone());
+        blockchain.balances.insert(sender.wallet_id.clone(), Decimal::ONE);
+        let result = blockchain.simulate_transaction(&transfer);
+        assert!(result.will_succeed, "{:?}", result.errors);
+        assert_eq!(result.balance_delta, -Decimal::new(5, 2));
+        assert_eq!(result.ownership_changes, vec![(triangle, recipient.wallet_id.clone())]);
+        assert!(blockchain.mempool.is_empty());
+    }
+
+    #[test]
+    fn test_simulate_stake() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let mut staker = TriadChainWallet::from_seed(&[6; 32]);
+        let triangle = TriangleAddress::from_string_representation("0").unwrap();
+        blockchain.triangle_owners.insert(triangle.clone(), staker.wallet_id.clone());
+        blockchain.balances.insert(staker.wallet_id.clone(), Decimal::from(100));
+        // A flat floor, so the fee is simply the base
+        blockchain.gas_floor.depth_factor = Decimal::ZERO;
+        let staker_id = staker.wallet_id.clone();
+
+        let mut stake = |amount: i64| {
+            let mut transaction = TriangleTransaction::new(
+                Some(triangle.clone()),
+                triangle.clone(),
+                TriangleOperation::Stake { amount: Decimal::from(amount) },
+                None,
+                Decimal::new(1, 2),
+            );
+            transaction.sender = Some(staker_id.clone());
+            staker.sign_transaction(&mut transaction).unwrap();
+            transaction
+        };
+
+        let result = blockchain.simulate_transaction(&stake(40));
+        assert!(result.will_succeed, "{:?}", result.errors);
+        assert_eq!(result.balance_delta, -Decimal::new(4001, 2));
+        assert!(result.ownership_changes.is_empty());
+        assert_eq!(blockchain.get_balance(&staker_id), Decimal::from(100));
+        assert_eq!(blockchain.economics.stakes_of(&staker_id).count(), 0);
+
+        let result = blockchain.simulate_transaction(&stake(100));
+        assert!(!result.will_succeed);
+        assert_eq!(result.balance_delta, Decimal::ZERO);
+        assert!(result.errors.iter().any(|error| error.contains("Insufficient balance for stake")), "{:?}", result.errors);
+
+        // Only the owner of the triangle may stake on it
+        blockchain.triangle_owners.insert(triangle.clone(), "someone else".to_string());
+        assert!(!blockchain.simulate_transaction(&stake(40)).will_succeed);
+    }
+
+    #[test]
+    fn test_full_mempool_rejects_low_fees() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        blockchain.max_mempool_size = 2;
+
+        blockchain.add_transaction(pending_transaction(Decimal::new(5, 2))).unwrap();
+        blockchain.add_transaction(pending_transaction(Decimal::new(10, 2))).unwrap();
+
+        let result = blockchain.add_transaction(pending_transaction(Decimal::new(5, 2)));
+        assert!(matches!(result, Err(SierpinskiError::ValidationError { .. })));
+        assert_eq!(blockchain.mempool.len(), 2);
+
+        // A higher fee displaces the cheapest pending transaction
+        blockchain.add_transaction(pending_transaction(Decimal::new(20, 2))).unwrap();
+        let mut fees: Vec<_> = blockchain.mempool.iter().map(|tx| tx.gas_fee).collect();
+        fees.sort();
+        assert_eq!(fees, vec![Decimal::new(10, 2), Decimal::new(20, 2)]);
+    }
+
+    #[test]
+    fn test_longer_fork_triggers_reorg() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut fork = blockchain.clone();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        blockchain.subscribe(Box::new(move |event| recorded.lock().unwrap().push(event.clone())));
+
+        blockchain.add_transaction(pending_transaction(Decimal::new(5, 2))).unwrap();
+        blockchain.mine_block("main_miner".to_string(), 10).unwrap();
+        let orphaned_id = blockchain.blocks[1].triangle_transactions[1].id;
+
+        for _ in 0..3 {
+            fork.add_transaction(pending_transaction(Decimal::new(5, 2))).unwrap();
+            fork.mine_block("fork_miner".to_string(), 10).unwrap();
+        }
+
+        // A fork that is not heavier than our own suffix is ignored
+        assert!(!blockchain.try_reorg(&fork.blocks[1..2]).unwrap());
+        assert_eq!(blockchain.get_balance("fork_miner"), Decimal::ZERO);
+
+        events.lock().unwrap().clear();
+        assert!(blockchain.try_reorg(&fork.blocks).unwrap());
+        assert_eq!(*events.lock().unwrap(), vec![BlockchainEvent::Reorganized { fork_height: 1 }]);
+        assert_eq!(blockchain.blocks.len(), 4);
+        assert_eq!(blockchain.blocks.last().unwrap().hash(), fork.blocks.last().unwrap().hash());
+        assert!(blockchain.validate_chain().unwrap());
+
+        assert_eq!(blockchain.get_balance("main_miner"), Decimal::ZERO);
+        assert_eq!(blockchain.get_balance("fork_miner"), fork.get_balance("fork_miner"));
+        assert_eq!(blockchain.total_supply, fork.total_supply);
+        assert_eq!(blockchain.fractal_state.total_triangles(), fork.fractal_state.total_triangles());
+
+        // The abandoned block's transaction is pending again
+        assert_eq!(blockchain.mempool.len(), 1);
+        assert_eq!(blockchain.mempool[0].id, orphaned_id);
+
+        // Replaying the same chain is a no-op
+        assert!(!blockchain.try_reorg(&fork.blocks).unwrap());
+    }
+
+    #[test]
+    fn test_total_work_is_recomputed_when_loaded() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut fork = blockchain.clone();
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        fork.mine_block("rival".to_string(), 10).unwrap();
+        fork.mine_block("rival".to_string(), 10).unwrap();
+        let expected = blockchain.total_work();
+
+        // A stored total below the blocks' own work is not trusted
+        blockchain.cumulative_difficulty = 0;
+        let restored = TriadChainBlockchain::from_bytes(&blockchain.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.total_work(), expected);
+
+        // Nor does it underflow when comparing forks
+        assert!(blockchain.try_reorg(&fork.blocks).is_err());
+    }
+
+    #[test]
+    fn test_heavier_short_chain_outranks_longer_chain() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(4)).unwrap();
+        blockchain.difficulty_params = DifficultyParams {
+            window: 2,
+            target_block_time_secs: 60,
+            min_difficulty: 1,
+            max_difficulty: 8,
+        };
+        let mut fork = blockchain.clone();
+        let genesis_work = blockchain.total_work();
+
+        // Slow blocks ease the target by two bits at the retarget after
+        // the second block
+        let start = blockchain.blocks[0].header.timestamp;
+        for i in 1..=4 {
+            let mut block = blockchain.block_template("easy_miner".to_string(), 10).unwrap();
+            block.header.timestamp = start + 240 * i;
+            solve(&mut block);
+            blockchain.accept_mined_block(block).unwrap();
+        }
+        assert_eq!(blockchain.total_work(), genesis_work + 16 + 16 + 4 + 4);
+
+        // Fast ones harden it by two bits instead
+        for _ in 0..3 {
+            fork.add_transaction(pending_transaction(Decimal::new(5, 2))).unwrap();
+            fork.mine_block("hard_miner".to_string(), 10).unwrap();
+        }
+        assert_eq!(fork.total_work(), genesis_work + 16 + 16 + 64);
+
+        assert!(blockchain.try_reorg(&fork.blocks).unwrap());
+        assert_eq!(blockchain.blocks.len(), 4);
+        assert_eq!(blockchain.total_work(), fork.total_work());
+
+        // Snapshots that lost the field can rebuild it from the blocks
+        let mut restored = blockchain.clone();
+        restored.cumulative_difficulty = 0;
+        restored.recompute_total_work();
+        assert_eq!(restored.total_work(), blockchain.total_work());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_snapshot_restore_continues_identically() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        // Test blocks arrive instantly; keep retargets cheap to mine
+        blockchain.difficulty_params.max_difficulty = 2;
+        for _ in 0..20 {
+            blockchain.add_transaction(pending_transaction(Decimal::new(5, 2))).unwrap();
+            blockchain.mine_block("miner".to_string(), 10).unwrap();
+        }
+
+        let snapshot = blockchain.snapshot();
+        assert_eq!(snapshot.height, 20);
+        assert!(snapshot.verify().is_ok());
+        assert_eq!(snapshot.state_hash(), blockchain.state_hash());
+
+        let mut tampered = snapshot.clone();
+        tampered.total_supply += Decimal::ONE;
+        assert!(tampered.verify().is_err());
+
+        let recent_start = blockchain.blocks.len() - crate::core::snapshot::DEFAULT_SNAPSHOT_RECENT_BLOCKS;
+        let recent = blockchain.blocks[recent_start..].to_vec();
+        assert!(TriadChainBlockchain::from_snapshot(snapshot.clone(), recent[..3].to_vec()).is_err());
+        // Blocks retargeted by other parameters do not check out
+        assert!(TriadChainBlockchain::from_snapshot(snapshot.clone(), recent.clone()).is_err());
+
+        // Nor do blocks short of their proof-of-work
+        let params = blockchain.difficulty_params.clone();
+        let mut unworked = recent.clone();
+        let first = &mut unworked[0];
+        while first.meets_difficulty_target() {
+            first.set_nonce(first.header.nonce + 1);
+        }
+        let error = verify_block_targets(&unworked, snapshot.target, &params).unwrap_err();
+        assert!(error.to_string().contains("difficulty target"), "{}", error);
+
+        // Only the work of blocks a node can see counts towards adopting it
+        let fresh = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        assert_eq!(fresh.verifiable_work(&recent), chain_work(&recent));
+        assert_eq!(blockchain.verifiable_work(&recent), 0);
+
+        let mut restored = TriadChainBlockchain::from_snapshot_with_params(snapshot, recent, params).unwrap();
+        assert_eq!(restored.blocks.len(), crate::core::snapshot::DEFAULT_SNAPSHOT_RECENT_BLOCKS);
+        assert_eq!(restored.chain_length(), blockchain.chain_length());
+        assert_eq!(restored.total_work(), blockchain.total_work());
+        assert!(restored.validate_chain().unwrap());
+
+        let transaction = pending_transaction(Decimal::new(5, 2));
+        blockchain.add_transaction(transaction.clone()).unwrap();
+        restored.add_transaction(transaction).unwrap();
+        let original_block = blockchain.mine_block("miner".to_string(), 10).unwrap();
+        let restored_block = restored.mine_block("miner".to_string(), 10).unwrap();
+
+        assert_eq!(original_block.height, 21);
+        assert_eq!(restored_block.height, 21);
+        assert_eq!(restored.state_hash(), blockchain.state_hash());
+        assert_eq!(restored.total_work(), blockchain.total_work());
+    }
+
+    /// Append `count` blocks spaced `interval` seconds apart, bypassing mining
+    fn push_timed_blocks(blockchain: &mut TriadChainBlockchain, count: usize, interval: u64) {
+        for _ in 0..count {
+            let previous = blockchain.blocks.last().unwrap();
+            let timestamp = previous.header.timestamp + interval;
+            let mut block = Block::new(
+                previous.hash(),
+                Vec::new(),
+                &blockchain.fractal_state,
+                "miner".to_string(),
+                blockchain.target,
+            );
+            block.header.timestamp = timestamp;
+            blockchain.blocks.push(block);
+        }
+    }
+
+    fn assert_difficulty(blockchain: &TriadChainBlockchain, expected: f64) {
+        let difficulty = blockchain.target.difficulty();
+        assert!((difficulty - expected).abs() < 1e-6, "difficulty {} instead of {}", difficulty, expected);
+    }
 
     #[test]
-    fn test_blockchain_creation() {
-        let blockchain = TriadChainBlockchain::new().unwrap();
-        assert_eq!(blockchain.blocks.len(), 1); // Genesis block
-        assert!(blockchain.total_supply > Decimal::ZERO);
+    fn test_fast_blocks_raise_difficulty_proportionally() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(2)).unwrap();
+
+        // Twice as fast as the 60s target
+        push_timed_blocks(&mut blockchain, 10, 30);
+        blockchain.adjust_difficulty();
+        assert_eq!(blockchain.target, Target::from_legacy_difficulty(3));
+
+        // Ten times too fast is capped at a 4x change
+        push_timed_blocks(&mut blockchain, 10, 6);
+        blockchain.adjust_difficulty();
+        assert_eq!(blockchain.target, Target::from_legacy_difficulty(5));
+
+        // Twice as slow doubles the target again
+        push_timed_blocks(&mut blockchain, 10, 120);
+        blockchain.adjust_difficulty();
+        assert_difficulty(&blockchain, 4.0);
+
+        // A third too slow scales it by 4/3 rather than a whole bit
+        push_timed_blocks(&mut blockchain, 10, 80);
+        blockchain.adjust_difficulty();
+        assert_difficulty(&blockchain, 4.0 - (4.0f64 / 3.0).log2());
+
+        // On target leaves it alone
+        let target = blockchain.target;
+        push_timed_blocks(&mut blockchain, 10, 60);
+        blockchain.adjust_difficulty();
+        assert_eq!(blockchain.target, target);
     }
 
     #[test]
-    fn test_blockchain_validation() {
-        let blockchain = TriadChainBlockchain::new().unwrap();
+    fn test_difficulty_stays_within_bounds() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        blockchain.difficulty_params = DifficultyParams {
+            window: 5,
+            target_block_time_secs: 60,
+            min_difficulty: 3,
+            max_difficulty: 6,
+        };
+        blockchain.target = Target::from_legacy_difficulty(4);
+
+        for _ in 0..3 {
+            push_timed_blocks(&mut blockchain, 5, 1);
+            blockchain.adjust_difficulty();
+            assert!(blockchain.target >= Target::from_legacy_difficulty(6));
+        }
+        assert_eq!(blockchain.target, Target::from_legacy_difficulty(6));
+
+        for _ in 0..3 {
+            push_timed_blocks(&mut blockchain, 5, 10_000);
+            blockchain.adjust_difficulty();
+            assert!(blockchain.target <= Target::from_legacy_difficulty(3));
+        }
+        assert_eq!(blockchain.target, Target::from_legacy_difficulty(3));
+    }
+
+    #[test]
+    fn test_claim_reward_accounting() {
+        use crate::core::wallet::TriadChainWallet;
+
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut claimant = TriadChainWallet::from_seed(&[5; 32]);
+        let mut seller = TriadChainWallet::from_seed(&[4; 32]);
+        blockchain.mine_block(seller.wallet_id.clone(), 10).unwrap();
+        let triangle = blockchain.triangle_owners
+            .iter()
+            .find(|(_, owner)| **owner == seller.wallet_id)
+            .map(|(address, _)| address.clone())
+            .unwrap();
+
+        // The fees a block collects fund the pool and accrue to its miner
+        let fee = Decimal::new(5, 1);
+        let mut transfer = TriangleTransaction::new(
+            Some(triangle.clone()),
+            triangle,
+            TriangleOperation::Transfer,
+            None,
+            fee,
+        );
+        transfer.sender = Some(seller.wallet_id.clone());
+        transfer.recipient = Some(claimant.wallet_id.clone());
+        seller.sign_transaction(&mut transfer).unwrap();
+        blockchain.add_transaction(transfer).unwrap();
+        let mined = blockchain.mine_block(claimant.wallet_id.clone(), 10).unwrap();
+        assert_eq!(blockchain.rewards.claimable(&claimant.wallet_id), fee);
+        assert_eq!(blockchain.rewards.pool_balance(), fee);
+
+        // Paid from the pool, so nothing new is minted
+        let supply_before = blockchain.total_supply;
+        let claim_fee = Decimal::new(1, 2);
+        blockchain.add_transaction(claimant.create_claim_transaction(fee, claim_fee).unwrap()).unwrap();
+        let paid = blockchain.mine_block("miner".to_string(), 10).unwrap();
+        assert_eq!(blockchain.get_balance(&claimant.wallet_id), mined.block_reward + fee - claim_fee);
+        assert_eq!(blockchain.total_supply, supply_before + paid.block_reward);
+        assert_eq!(blockchain.rewards.claimable(&claimant.wallet_id), Decimal::ZERO);
+        assert_eq!(blockchain.rewards.claimable("miner"), claim_fee);
+
+        // Nothing left to claim
+        let again = claimant.create_claim_transaction(Decimal::ONE, claim_fee).unwrap();
+        assert!(blockchain.add_transaction(again).is_err());
+
+        // Every node replaying the blocks derives the same ledger
+        let replayed = blockchain.rebuild_full(blockchain.blocks.clone()).unwrap();
+        assert_eq!(replayed.rewards, blockchain.rewards);
+
+        // Minting stops at max supply
+        blockchain.max_supply = blockchain.total_supply;
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        assert_eq!(blockchain.total_supply, blockchain.max_supply);
         assert!(blockchain.validate_chain().unwrap());
     }
 
     #[test]
-    fn test_mempool_operations() {
-        let mut blockchain = SierpinskiBlockchain::new().unwrap();
-        
-        let tx = TriangleTransaction::new(
+    fn test_add_transaction_verifies_signature() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let mut sender = TriadChainWallet::from_seed(&[1; 32]);
+        let mut recipient = TriadChainWallet::from_seed(&[2; 32]);
+        let triangle = TriangleAddress::from_string_representation("0.1").unwrap();
+        blockchain.triangle_owners.insert(triangle.clone(), sender.wallet_id.clone());
+        blockchain.balances.insert(sender.wallet_id.clone(), Decimal::ONE);
+
+        let mut signed = TriangleTransaction::new(
+            Some(triangle.clone()),
+            triangle,
+            TriangleOperation::Transfer,
             None,
-            TriangleAddress::genesis(),
+            Decimal::new(5, 2),
+        );
+        signed.sender = Some(sender.wallet_id.clone());
+        signed.recipient = Some(recipient.wallet_id.clone());
+        sender.sign_transaction(&mut signed).unwrap();
+
+        let mut cheaper = signed.clone();
+        cheaper.gas_fee = Decimal::new(1, 2);
+        assert!(blockchain.add_transaction(cheaper).is_err());
+
+        let mut redirected = signed.clone();
+        redirected.recipient = Some(sender.wallet_id.clone());
+        assert!(blockchain.add_transaction(redirected).is_err());
+
+        let mut keyless = signed.clone();
+        keyless.sender_public_key = None;
+        assert!(blockchain.add_transaction(keyless).is_err());
+
+        // Re-signed by another wallet in the sender's name
+        let mut impersonated = signed.clone();
+        recipient.sign_transaction(&mut impersonated).unwrap();
+        assert!(blockchain.add_transaction(impersonated).is_err());
+
+        blockchain.add_transaction(signed).unwrap();
+        assert_eq!(blockchain.mempool.len(), 1);
+    }
+
+    #[test]
+    fn test_gas_fees_are_covered_cumulatively() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut sender = TriadChainWallet::from_seed(&[1; 32]);
+        let fee = Decimal::new(5, 2);
+        let transfers: Vec<TriangleTransaction> = ["0.1", "0.2"]
+            .iter()
+            .map(|address| {
+                let triangle = TriangleAddress::from_string_representation(address).unwrap();
+                blockchain.triangle_owners.insert(triangle.clone(), sender.wallet_id.clone());
+                let mut transfer = TriangleTransaction::new(
+                    Some(triangle.clone()),
+                    triangle,
+                    TriangleOperation::Transfer,
+                    None,
+                    fee,
+                );
+                transfer.sender = Some(sender.wallet_id.clone());
+                transfer.recipient = Some("recipient".to_string());
+                sender.sign_transaction(&mut transfer).unwrap();
+                transfer
+            })
+            .collect();
+        // Enough for one fee, not both
+        blockchain.balances.insert(sender.wallet_id.clone(), fee + fee / Decimal::TWO);
+
+        blockchain.add_transaction(transfers[0].clone()).unwrap();
+        assert_eq!(blockchain.pending_outgoing(&sender.wallet_id), fee);
+        assert!(blockchain.add_transaction(transfers[1].clone()).is_err());
+
+        // Nor does a block carrying both apply
+        let mut block = Block::new(
+            blockchain.blocks[0].hash(),
+            transfers.clone(),
+            &blockchain.fractal_state,
+            "miner".to_string(),
+            blockchain.target,
+        );
+        block.height = 1;
+        block.header.timestamp = blockchain.median_time_past() + 1;
+        solve(&mut block);
+        let error = blockchain.try_add_block(block).unwrap_err();
+        assert!(error.to_string().contains("spends more than"), "{}", error);
+
+        // Applying a transaction never takes a balance below zero
+        blockchain.balances.insert(sender.wallet_id.clone(), fee / Decimal::TWO);
+        assert!(blockchain.apply_transaction(&transfers[1], 1, &mut BlockActivity::default()).is_err());
+
+        blockchain.balances.insert(sender.wallet_id.clone(), fee + fee / Decimal::TWO);
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        assert_eq!(blockchain.get_balance(&sender.wallet_id), fee / Decimal::TWO);
+    }
+
+    #[test]
+    fn test_mine_empty_mempool_with_coinbase() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let genesis_coinbase = blockchain.blocks[0].coinbase().unwrap();
+        assert_eq!(genesis_coinbase.operation, TriangleOperation::Coinbase { amount: GENESIS_SUPPLY });
+
+        let block = blockchain.mine_block("miner".to_string(), 10).unwrap();
+        assert_eq!(block.triangle_transactions.len(), 1);
+        let coinbase = block.coinbase().unwrap();
+        assert_eq!(coinbase.operation, TriangleOperation::Coinbase { amount: block.block_reward });
+        assert_eq!(coinbase.recipient.as_deref(), Some("miner"));
+        assert_eq!(blockchain.get_balance("miner"), block.block_reward);
+
+        // Only miners create coinbase transactions
+        let coinbase = TriangleTransaction::coinbase("miner", Decimal::ONE);
+        assert!(blockchain.add_transaction(coinbase).is_err());
+    }
+
+    #[test]
+    fn test_mining_claims_unowned_triangles() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        assert_eq!(blockchain.fractal_state.total_triangles(), 1);
+
+        // Only the owned genesis exists, so the first block splits it
+        let first_claim = blockchain.claimable_address(&blockchain.blocks[0].hash()).unwrap();
+        assert_eq!(first_claim.depth(), 1);
+        assert!(!first_claim.is_void());
+        let first = blockchain.mine_block("miner".to_string(), 10).unwrap();
+        assert_eq!(blockchain.fractal_state.total_triangles(), 5);
+        assert_eq!(blockchain.triangle_owners.get(&first_claim).map(String::as_str), Some("miner"));
+
+        // Two corners are still unowned, so the fractal does not grow
+        let second_claim = blockchain.claimable_address(&first.hash()).unwrap();
+        assert_eq!(second_claim.depth(), 1);
+        assert_ne!(second_claim, first_claim);
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        assert_eq!(blockchain.fractal_state.total_triangles(), 5);
+
+        let mut owned = blockchain.get_owned_triangles("miner");
+        owned.sort_by_key(|address| address.components().to_vec());
+        let mut expected = vec![first_claim, second_claim];
+        expected.sort_by_key(|address| address.components().to_vec());
+        assert_eq!(owned, expected);
+    }
+
+    #[test]
+    fn test_gas_floor_rises_with_depth() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let base = blockchain.gas_floor.base;
+        let deep = TriangleAddress::new(vec![0, 1, 2, 0, 1, 2]).unwrap();
+        assert_eq!(blockchain.minimum_gas_for(&TriangleAddress::genesis()), base);
+        assert_eq!(blockchain.minimum_gas_for(&deep), base * Decimal::new(25, 1));
+
+        let mut shallow_transaction = pending_transaction(base);
+        shallow_transaction.to_address = TriangleAddress::new(vec![3]).unwrap();
+        shallow_transaction.gas_fee = blockchain.minimum_gas_for(&shallow_transaction.to_address);
+        blockchain.add_transaction(shallow_transaction).unwrap();
+
+        let mut deep_transaction = pending_transaction(base);
+        deep_transaction.to_address = deep.clone();
+        let error = blockchain.add_transaction(deep_transaction.clone()).unwrap_err();
+        assert!(matches!(error, SierpinskiError::ValidationError { .. }));
+        deep_transaction.gas_fee = blockchain.minimum_gas_for(&deep);
+        blockchain.add_transaction(deep_transaction).unwrap();
+
+        blockchain.gas_floor = GasFloorParams { base: Decimal::ONE, depth_factor: Decimal::ONE };
+        assert_eq!(blockchain.minimum_gas_for(&deep), Decimal::from(7));
+    }
+
+    #[test]
+    fn test_estimate_gas_fee_by_priority() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        for priority in [FeePriority::Low, FeePriority::Normal, FeePriority::High] {
+            assert_eq!(blockchain.estimate_gas_fee(priority), MIN_GAS_FEE);
+        }
+
+        blockchain.gas_floor.base = Decimal::new(5, 2);
+        for cents in 1..=20 {
+            blockchain.mempool.push(TriangleTransaction::new(
+                None,
+                TriangleAddress::genesis(),
+                TriangleOperation::Transfer,
+                None,
+                Decimal::new(cents * 10, 2),
+            ));
+        }
+
+        let low = blockchain.estimate_gas_fee(FeePriority::Low);
+        let normal = blockchain.estimate_gas_fee(FeePriority::Normal);
+        let high = blockchain.estimate_gas_fee(FeePriority::High);
+        assert!(high >= normal && normal >= low);
+        assert_eq!(low, Decimal::new(50, 2));
+        assert_eq!(high, Decimal::new(180, 2));
+
+        // The base fee holds even when every pending fee is below it
+        blockchain.gas_floor.base = Decimal::from(5);
+        assert_eq!(blockchain.estimate_gas_fee(FeePriority::High), Decimal::from(5));
+    }
+
+    #[test]
+    fn test_estimate_gas_fee_for_scales_with_depth() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let genesis = TriangleAddress::genesis();
+        let deep = TriangleAddress::new(vec![0, 1, 2, 0]).unwrap();
+        assert_eq!(blockchain.estimate_gas_fee_for(&genesis, FeePriority::Low), MIN_GAS_FEE);
+        assert_eq!(blockchain.estimate_gas_fee_for(&deep, FeePriority::Low), blockchain.minimum_gas_for(&deep));
+
+        for cents in 1..=20 {
+            blockchain.mempool.push(TriangleTransaction::new(
+                None,
+                TriangleAddress::genesis(),
+                TriangleOperation::Transfer,
+                None,
+                Decimal::new(cents * 10, 2),
+            ));
+        }
+        let high = blockchain.estimate_gas_fee(FeePriority::High);
+        assert_eq!(blockchain.estimate_gas_fee_for(&deep, FeePriority::High), high);
+
+        // The depth-scaled floor wins once it passes what others pay
+        blockchain.gas_floor.base = Decimal::from(5);
+        assert_eq!(blockchain.estimate_gas_fee_for(&deep, FeePriority::High), blockchain.minimum_gas_for(&deep));
+        assert!(blockchain.minimum_gas_for(&deep) > blockchain.estimate_gas_fee(FeePriority::High));
+    }
+
+    #[test]
+    fn test_blocks_cannot_transfer_unowned_triangles() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let mut thief = TriadChainWallet::from_seed(&[3; 32]);
+        let triangle = TriangleAddress::new(vec![1]).unwrap();
+        blockchain.triangle_owners.insert(triangle.clone(), "owner".to_string());
+
+        let mut unsigned = TriangleTransaction::new(
+            Some(triangle.clone()),
+            triangle.clone(),
+            TriangleOperation::Transfer,
+            None,
+            MIN_GAS_FEE,
+        );
+        unsigned.recipient = Some(thief.wallet_id.clone());
+        assert!(unsigned.validate().is_err());
+
+        let mut stolen = unsigned.clone();
+        stolen.sender = Some(thief.wallet_id.clone());
+        thief.sign_transaction(&mut stolen).unwrap();
+
+        // Slipped into a relayed block rather than through the mempool
+        let mut block = blockchain.block_template("miner".to_string(), 10).unwrap();
+        block.triangle_transactions.push(stolen.clone());
+        let error = blockchain.validate_block_in_context(&block).unwrap_err();
+        assert!(error.to_string().contains("not owned"), "{}", error);
+        assert!(blockchain.apply_transaction(&stolen, 1, &mut BlockActivity::default()).is_err());
+        assert_eq!(blockchain.triangle_owners.get(&triangle), Some(&"owner".to_string()));
+    }
+
+    #[test]
+    fn test_ownership_history_records_create_then_transfer() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let address = TriangleAddress::new(vec![0, 2]).unwrap();
+        let creator = TriangleAddress::new(vec![1]).unwrap();
+        let triangle = crate::core::genesis::genesis_triangle().unwrap();
+
+        let create = TriangleTransaction::new(
+            Some(creator.clone()),
+            address.clone(),
             TriangleOperation::Create,
+            Some(triangle),
+            Decimal::ZERO,
+        );
+        blockchain.apply_transaction(&create, 1, &mut BlockActivity::default()).unwrap();
+
+        let mut transfer = TriangleTransaction::new(
+            Some(address.clone()),
+            address.clone(),
+            TriangleOperation::Transfer,
             None,
-            Decimal::new(1, 2),
+            Decimal::ZERO,
+        );
+        transfer.sender = Some(creator.to_string());
+        transfer.recipient = Some("recipient".to_string());
+        blockchain.apply_transaction(&transfer, 4, &mut BlockActivity::default()).unwrap();
+
+        assert_eq!(
+            blockchain.ownership_history(&address),
+            &[(creator.to_string(), 1), ("recipient".to_string(), 4)]
+        );
+        assert!(blockchain.ownership_history(&TriangleAddress::new(vec![2, 2]).unwrap()).is_empty());
+
+        // The history survives a round trip through storage
+        let restored = TriadChainBlockchain::from_bytes(&blockchain.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.ownership_history(&address), blockchain.ownership_history(&address));
+    }
+
+    type EventLog = Vec<(tracing::Level, HashMap<String, String>)>;
+
+    /// Records every event's level and fields
+    #[derive(Clone, Default)]
+    struct CapturedEvents(std::sync::Arc<std::sync::Mutex<EventLog>>);
+
+    struct FieldRecorder(HashMap<String, String>);
+
+    impl tracing::field::Visit for FieldRecorder {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for CapturedEvents {
+        fn on_event(&self, event: &tracing::Event<'_>, _: tracing_subscriber::layer::Context<'_, S>) {
+            let mut fields = FieldRecorder(HashMap::new());
+            event.record(&mut fields);
+            self.0.lock().unwrap().push((*event.metadata().level(), fields.0));
+        }
+    }
+
+    #[test]
+    fn test_mined_block_logs_info_event_with_height() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+
+        let events = CapturedEvents::default();
+        let subscriber = tracing_subscriber::registry().with(events.clone());
+        let block = tracing::subscriber::with_default(subscriber, || {
+            blockchain.mine_block("miner".to_string(), 10).unwrap()
+        });
+
+        let events = events.0.lock().unwrap();
+        let height = block.height.to_string();
+        assert!(events.iter().any(|(level, fields)| {
+            *level == tracing::Level::INFO && fields.get("height") == Some(&height)
+        }));
+    }
+
+    #[test]
+    fn test_deep_validation_catches_tampered_balances() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        let result = blockchain.deep_validate();
+        assert!(result.is_valid, "{:?}", result.errors);
+
+        *blockchain.balances.get_mut("miner").unwrap() += Decimal::ONE;
+        blockchain.balances.insert("forger".to_string(), Decimal::TEN);
+        let result = blockchain.deep_validate();
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 2);
+        assert!(result.errors.iter().any(|error| error.contains("forger")));
+        // Link checks alone do not notice
+        assert!(blockchain.validate_chain().unwrap());
+    }
+
+    #[test]
+    fn test_mining_fires_events_and_survives_panicking_callback() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        blockchain.subscribe(Box::new(|_| panic!("faulty subscriber")));
+        let recorded = Arc::clone(&events);
+        blockchain.subscribe(Box::new(move |event| recorded.lock().unwrap().push(event.clone())));
+
+        let block = blockchain.mine_block("miner".to_string(), 10).unwrap();
+        let events = events.lock().unwrap().clone();
+        assert_eq!(events.last(), Some(&BlockchainEvent::BlockMined(1)));
+        assert!(events.contains(&BlockchainEvent::TransactionApplied(block.triangle_transactions[0].id)));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            BlockchainEvent::OwnershipChanged { to, .. } if to == "miner"
+        )));
+
+        // Clones do not inherit subscriptions
+        assert_eq!(format!("{:?}", blockchain.clone().hooks), "EventHooks { subscribers: 0 }");
+    }
+
+    #[test]
+    fn test_header_only_mode_prunes_old_bodies() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        // Test blocks arrive instantly; keep retargets cheap to mine
+        blockchain.difficulty_params.max_difficulty = 2;
+        blockchain.add_transaction(pending_transaction(Decimal::new(5, 2))).unwrap();
+        for _ in 0..49 {
+            blockchain.mine_block("miner".to_string(), 10).unwrap();
+        }
+        assert_eq!(blockchain.chain_length(), 50);
+        let old_id = blockchain.blocks[1].triangle_transactions[1].id;
+        let recent_id = blockchain.blocks[45].triangle_transactions[0].id;
+        let balances = blockchain.balances.clone();
+        let hashes: Vec<String> = blockchain.blocks.iter().map(Block::hash).collect();
+
+        assert_eq!(blockchain.set_mode(ChainMode::HeaderOnly { keep_recent: 10 }), 40);
+        assert!(blockchain.validate_chain().unwrap());
+        assert_eq!(blockchain.balances, balances);
+        assert_eq!(blockchain.blocks.iter().map(Block::hash).collect::<Vec<_>>(), hashes);
+        assert!(blockchain.blocks[..40].iter().all(|block| block.triangle_transactions.is_empty()));
+
+        assert!(matches!(
+            blockchain.get_transaction(&old_id),
+            Err(SierpinskiError::PrunedDataUnavailable { height: 1 })
+        ));
+        assert_eq!(blockchain.transaction_height(&old_id), Some(1));
+        assert_eq!(blockchain.get_transaction(&recent_id).unwrap().unwrap().id, recent_id);
+        assert!(blockchain.get_transaction(&Uuid::new_v4()).unwrap().is_none());
+
+        // New blocks keep the window moving
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        assert!(blockchain.is_pruned(40));
+        assert!(matches!(
+            blockchain.block_transactions(40),
+            Err(SierpinskiError::PrunedDataUnavailable { height: 40 })
+        ));
+        assert_eq!(blockchain.block_transactions(41).unwrap().len(), 1);
+        assert!(blockchain.validate_chain().unwrap());
+
+        // Deep validation cannot replay pruned bodies but does not fail for it
+        let result = blockchain.deep_validate();
+        assert!(result.is_valid, "{:?}", result.errors);
+        assert!(result.warnings.iter().any(|warning| warning.contains("pruned")));
+    }
+
+    #[test]
+    fn test_reorg_replays_only_blocks_after_the_fork() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        for _ in 0..5 {
+            blockchain.mine_block("miner".to_string(), 10).unwrap();
+        }
+        blockchain.set_mode(ChainMode::HeaderOnly { keep_recent: 2 });
+        let mut fork = blockchain.clone();
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        for _ in 0..2 {
+            fork.mine_block("rival".to_string(), 10).unwrap();
+        }
+
+        // Without the state from before the fork, the replay would need
+        // bodies long pruned
+        let mut reloaded = TriadChainBlockchain::from_bytes(&blockchain.to_bytes().unwrap()).unwrap();
+        assert!(matches!(
+            reloaded.try_reorg(&fork.blocks[6..]),
+            Err(SierpinskiError::PrunedDataUnavailable { height: 0 })
+        ));
+
+        assert!(blockchain.is_pruned(3));
+        assert!(blockchain.try_reorg(&fork.blocks[6..]).unwrap());
+        assert_eq!(blockchain.blocks.last().unwrap().hash(), fork.blocks.last().unwrap().hash());
+        assert_eq!(blockchain.state_hash(), fork.state_hash());
+        assert_eq!(blockchain.total_work(), fork.total_work());
+    }
+
+    #[test]
+    fn test_out_of_order_blocks_wait_in_orphan_pool() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut source = blockchain.clone();
+        let first = source.mine_block("miner".to_string(), 10).unwrap();
+        let second = source.mine_block("miner".to_string(), 10).unwrap();
+
+        assert_eq!(blockchain.add_block_or_orphan(second.clone(), "peer"), BlockAcceptance::Orphaned);
+        assert_eq!(blockchain.add_block_or_orphan(second.clone(), "peer"), BlockAcceptance::Orphaned);
+        assert_eq!(blockchain.tip_height(), 0);
+        assert_eq!(blockchain.orphan_count(), 1);
+
+        assert_eq!(blockchain.add_block_or_orphan(first.clone(), "peer"), BlockAcceptance::Connected);
+        assert_eq!(blockchain.tip_height(), second.height);
+        assert_eq!(blockchain.blocks.last().unwrap().hash(), second.hash());
+        assert_eq!(blockchain.orphan_count(), 0);
+        assert_eq!(blockchain.state_hash(), source.state_hash());
+
+        // A block whose parent is no longer the tip is a fork, not an orphan
+        assert!(matches!(blockchain.add_block_or_orphan(first, "peer"), BlockAcceptance::Rejected(_)));
+    }
+
+    #[test]
+    fn test_orphan_pool_limits() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut source = blockchain.clone();
+        let parent = source.mine_block("miner".to_string(), 10).unwrap();
+        let mut branch = source.clone();
+        let blocks: Vec<Block> = (0..MAX_ORPHANS_PER_PEER + 1)
+            .map(|_| source.mine_block("miner".to_string(), 10).unwrap())
+            .collect();
+
+        // Nothing easier than the chain's easiest target is held
+        let mut easy = blocks[0].clone();
+        easy.header.bits = Target::MAX.to_compact();
+        solve(&mut easy);
+        assert!(matches!(blockchain.add_block_or_orphan(easy, "peer"), BlockAcceptance::Rejected(_)));
+
+        // One peer cannot fill the pool
+        for block in &blocks[..MAX_ORPHANS_PER_PEER] {
+            assert_eq!(blockchain.add_block_or_orphan(block.clone(), "greedy"), BlockAcceptance::Orphaned);
+        }
+        let last = blocks[MAX_ORPHANS_PER_PEER].clone();
+        assert!(matches!(blockchain.add_block_or_orphan(last.clone(), "greedy"), BlockAcceptance::Rejected(_)));
+        assert_eq!(blockchain.add_block_or_orphan(last.clone(), "other"), BlockAcceptance::Orphaned);
+
+        // Old orphans expire, and a full pool evicts its oldest
+        let now = unix_now();
+        blockchain.orphan_pool.clear();
+        assert_eq!(blockchain.store_orphan(blocks[0].clone(), "peer", now - MAX_ORPHAN_AGE_SECS - 1), BlockAcceptance::Orphaned);
+        assert_eq!(blockchain.store_orphan(blocks[1].clone(), "peer", now), BlockAcceptance::Orphaned);
+        assert_eq!(blockchain.orphan_count(), 1);
+        for i in 1..MAX_ORPHAN_BLOCKS {
+            let mut filler = blocks[2].clone();
+            filler.header.previous_hash = format!("missing-{}", i);
+            blockchain.orphan_pool
+                .entry(filler.header.previous_hash.clone())
+                .or_default()
+                .push(Orphan { block: filler, peer: format!("peer-{}", i), received_at: now + 1 });
+        }
+        assert_eq!(blockchain.store_orphan(blocks[3].clone(), "peer", now + 2), BlockAcceptance::Orphaned);
+        assert_eq!(blockchain.orphan_count(), MAX_ORPHAN_BLOCKS);
+        assert!(!blockchain.orphan_pool.contains_key(&blocks[1].header.previous_hash));
+
+        // An orphan whose parent ends up below the tip is dropped
+        blockchain.orphan_pool.clear();
+        assert_eq!(blockchain.add_block_or_orphan(blocks[0].clone(), "peer"), BlockAcceptance::Orphaned);
+        let rival = branch.mine_block("rival".to_string(), 10).unwrap();
+        assert!(blockchain.try_reorg(&[parent, rival]).unwrap());
+        assert_eq!(blockchain.connect_orphans(), 0);
+        assert_eq!(blockchain.orphan_count(), 0);
+    }
+
+    #[test]
+    fn test_block_with_doubled_coinbase_is_rejected() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let previous_hash = blockchain.blocks[0].hash();
+
+        let honest = Block::new(previous_hash.clone(), Vec::new(), &blockchain.fractal_state, "miner".to_string(), blockchain.target);
+        let mut doubled = Block::new_with_reward(
+            previous_hash,
+            Vec::new(),
+            &blockchain.fractal_state,
+            "miner".to_string(),
+            blockchain.target,
+            honest.block_reward * Decimal::TWO,
+        );
+        doubled.height = 1;
+        while !doubled.meets_difficulty_target() {
+            doubled.set_nonce(doubled.header.nonce + 1);
+        }
+
+        assert!(doubled.validate().is_err());
+        assert!(blockchain.try_add_block(doubled).is_err());
+        assert_eq!(blockchain.tip_height(), 0);
+
+        // Raising the coinbase alone breaks the Merkle root
+        let mut tampered = honest.clone();
+        tampered.triangle_transactions[0].operation = TriangleOperation::Coinbase { amount: honest.block_reward * Decimal::TWO };
+        assert!(tampered.validate().is_err());
+        assert!(honest.validate().is_ok());
+    }
+
+    #[test]
+    fn test_block_may_underpay_its_coinbase() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let previous_hash = blockchain.blocks[0].hash();
+        let scheduled = Block::new(previous_hash.clone(), Vec::new(), &blockchain.fractal_state, "miner".to_string(), blockchain.target).block_reward;
+
+        let mut modest = Block::new_with_reward(
+            previous_hash,
+            Vec::new(),
+            &blockchain.fractal_state,
+            "miner".to_string(),
+            blockchain.target,
+            scheduled - Decimal::ONE,
+        );
+        modest.height = 1;
+        modest.header.timestamp = blockchain.median_time_past() + 1;
+        solve(&mut modest);
+
+        // Block::validate and the chain agree that a lower reward is fine
+        assert!(modest.validate().is_ok());
+        blockchain.try_add_block(modest).unwrap();
+        assert_eq!(blockchain.get_balance("miner"), scheduled - Decimal::ONE);
+        assert_eq!(blockchain.total_supply, GENESIS_SUPPLY + scheduled - Decimal::ONE);
+    }
+
+    #[test]
+    fn test_rejected_block_leaves_state_untouched() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let address = TriangleAddress::new(vec![1, 1]).unwrap();
+        let create = TriangleTransaction::new(
+            None,
+            address.clone(),
+            TriangleOperation::Create,
+            Some(crate::core::genesis::genesis_triangle().unwrap()),
+            Decimal::ZERO,
+        );
+        // Nothing has accrued to the claimant, so the claim only fails once
+        // the creation before it has applied
+        let mut claimant = TriadChainWallet::from_seed(&[3; 32]);
+        let claim = claimant.create_claim_transaction(Decimal::ONE, Decimal::new(1, 2)).unwrap();
+        let create_id = create.id;
+
+        let mut block = Block::new(
+            blockchain.blocks[0].hash(),
+            vec![create, claim],
+            &blockchain.fractal_state,
+            "miner".to_string(),
+            blockchain.target,
+        );
+        block.height = 1;
+        block.header.timestamp = blockchain.median_time_past() + 1;
+        solve(&mut block);
+
+        let state_hash = blockchain.state_hash();
+        let balances = blockchain.balances.clone();
+        let circulating = blockchain.economics_stats().circulating_supply;
+        let error = blockchain.try_add_block(block).unwrap_err();
+        assert!(error.to_string().contains("Claim exceeds accrued rewards"));
+        assert_eq!(blockchain.state_hash(), state_hash);
+        assert_eq!(blockchain.balances, balances);
+        assert_eq!(blockchain.economics_stats().circulating_supply, circulating);
+        assert_eq!(blockchain.tip_height(), 0);
+        assert!(blockchain.fractal_state.find_by_address(&address).is_none());
+        assert!(!blockchain.transaction_index.contains_key(&create_id));
+    }
+
+    #[test]
+    fn test_create_on_occupied_address_is_rejected() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut occupied = pending_transaction(MIN_GAS_FEE);
+        occupied.to_address = TriangleAddress::genesis();
+        let error = blockchain.add_transaction(occupied.clone()).unwrap_err();
+        assert!(error.to_string().contains("already holds a triangle"));
+
+        // A block may not create the same address twice either
+        let first = pending_transaction(Decimal::new(5, 2));
+        let mut second = pending_transaction(Decimal::new(5, 2));
+        second.to_address = first.to_address.clone();
+        for transactions in [vec![occupied], vec![first, second]] {
+            let mut block = Block::new(
+                blockchain.blocks[0].hash(),
+                transactions,
+                &blockchain.fractal_state,
+                "miner".to_string(),
+                blockchain.target,
+            );
+            block.height = 1;
+            block.header.timestamp = blockchain.median_time_past() + 1;
+            solve(&mut block);
+            assert!(blockchain.try_add_block(block).is_err());
+        }
+        assert_eq!(blockchain.tip_height(), 0);
+    }
+
+    /// Unsigned stake whose signature bytes pad it to `target` or one
+    /// byte below
+    fn padded_transaction(target: usize) -> TriangleTransaction {
+        let mut transaction = TriangleTransaction::new(
+            None,
+            unused_address(),
+            TriangleOperation::Create,
+            Some(crate::core::genesis::genesis_triangle().unwrap()),
+            Decimal::ONE,
+        );
+        // Each zero in the JSON byte array adds "0," less one trailing comma
+        let base = transaction.serialized_size();
+        transaction.signature = vec![0; (target + 1 - base) / 2];
+        transaction
+    }
+
+    #[test]
+    fn test_transaction_size_limit() {
+        use crate::core::block::MAX_TRANSACTION_BYTES;
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+
+        let under = padded_transaction(MAX_TRANSACTION_BYTES);
+        assert!(under.serialized_size() <= MAX_TRANSACTION_BYTES);
+        assert!(under.serialized_size() >= MAX_TRANSACTION_BYTES - 1);
+        blockchain.add_transaction(under).unwrap();
+
+        let over = padded_transaction(MAX_TRANSACTION_BYTES + 2);
+        let (id, size) = (over.id, over.serialized_size());
+        assert!(size > MAX_TRANSACTION_BYTES);
+        assert_eq!(
+            blockchain.add_transaction(over),
+            Err(SierpinskiError::SizeLimitExceeded {
+                item: format!("Transaction {}", id),
+                size,
+                limit: MAX_TRANSACTION_BYTES,
+            })
         );
-        
-        blockchain.add_transaction(tx).unwrap();
         assert_eq!(blockchain.mempool.len(), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_mine_block_packs_up_to_size_limit() {
+        use crate::core::block::MAX_TRANSACTION_BYTES;
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        blockchain.max_mempool_size = 1000;
+        let per_block = MAX_BLOCK_BYTES / MAX_TRANSACTION_BYTES;
+        for _ in 0..per_block + 8 {
+            blockchain.add_transaction(padded_transaction(MAX_TRANSACTION_BYTES)).unwrap();
+        }
+
+        let block = blockchain.mine_block("miner".to_string(), 1000).unwrap();
+        let size = block.serialized_size();
+        assert!(size <= MAX_BLOCK_BYTES);
+        // Full: the next pending transaction would not have fit
+        assert!(!blockchain.mempool.is_empty());
+        assert!(size + blockchain.mempool[0].serialized_size() > MAX_BLOCK_BYTES);
+        assert!(block.triangle_transactions.len() >= per_block - 1);
+    }
+
+    #[test]
+    fn test_shared_blockchain_survives_panicking_holder() {
+        let shared = SharedBlockchain::new(TriadChainBlockchain::new().unwrap());
+
+        let panicking = shared.clone();
+        let outcome = std::thread::spawn(move || {
+            panicking.with(|chain| {
+                chain.target = Target::from_legacy_difficulty(1);
+                panic!("handler failed while holding the chain");
+            })
+        })
+        .join();
+        assert!(outcome.is_err());
+
+        // Later access recovers the chain, keeping what was written before the panic
+        assert_eq!(shared.read(|chain| chain.target), Target::from_legacy_difficulty(1));
+        let block = shared.with(|chain| chain.mine_block("miner".to_string(), 10)).unwrap();
+        assert_eq!(shared.read(|chain| chain.tip_height()), block.height);
+    }
+
+    #[test]
+    fn test_blocks_advance_economics() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let config = blockchain.economics.config.clone();
+        assert_eq!(blockchain.economics_stats().circulating_supply, config.initial_supply);
+        let mut seller = TriadChainWallet::from_seed(&[1; 32]);
+
+        // Created triangles leave the supply alone; each subdivision burns
+        // a share of what is left
+        let advance = |supply: Decimal, subdivided: u32| {
+            let mut supply = supply + supply * config.block_inflation_rate;
+            for _ in 0..subdivided {
+                supply -= supply * config.subdivision_deflation_rate;
+            }
+            supply
+        };
+
+        // Mining the first block splits genesis to hand its miner a child
+        blockchain.mine_block(seller.wallet_id.clone(), 10).unwrap();
+        let mut expected = advance(config.initial_supply, 1);
+        assert_eq!(blockchain.economics_stats().circulating_supply, expected);
+
+        // Subdividing that child burns once more, while creating a triangle
+        // and the next claim, of a triangle that already exists, do not
+        let create = TriangleTransaction::new(
+            None,
+            TriangleAddress::new(vec![2, 2, 2]).unwrap(),
+            TriangleOperation::Create,
+            Some(crate::core::genesis::genesis_triangle().unwrap()),
+            MIN_GAS_FEE * Decimal::TWO,
+        );
+        blockchain.add_transaction(create).unwrap();
+        let child = blockchain.get_owned_triangles(&seller.wallet_id).pop().unwrap();
+        let subdivide = |seller: &mut TriadChainWallet, blockchain: &TriadChainBlockchain| {
+            let mut transaction = TriangleTransaction::new(
+                Some(child.clone()),
+                child.clone(),
+                TriangleOperation::Subdivide,
+                None,
+                blockchain.minimum_gas_for(&child),
+            );
+            transaction.sender = Some(seller.wallet_id.clone());
+            seller.sign_transaction(&mut transaction).unwrap();
+            transaction
+        };
+        blockchain.add_transaction(subdivide(&mut seller, &blockchain)).unwrap();
+
+        // Unsigned, a subdivision has nobody to pay for it
+        let mut unsigned = TriangleTransaction::new(
+            Some(child.clone()),
+            child.clone(),
+            TriangleOperation::Subdivide,
+            None,
+            blockchain.minimum_gas_for(&child),
+        );
+        unsigned.sender = None;
+        assert!(blockchain.add_transaction(unsigned).is_err());
+
+        blockchain.triangle_owners.insert(TriangleAddress::genesis(), seller.wallet_id.clone());
+        let mut transfer = TriangleTransaction::new(
+            Some(TriangleAddress::genesis()),
+            TriangleAddress::genesis(),
+            TriangleOperation::Transfer,
+            None,
+            MIN_GAS_FEE,
+        );
+        transfer.sender = Some(seller.wallet_id.clone());
+        transfer.recipient = Some("buyer".to_string());
+        transfer.price = Some(Decimal::from(250));
+        seller.sign_transaction(&mut transfer).unwrap();
+        blockchain.add_transaction(transfer).unwrap();
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        expected = advance(expected, 1);
+        assert_eq!(blockchain.economics_stats().circulating_supply, expected);
+        let children = child.children_active();
+        assert!(children.iter().all(|address| blockchain.triangle_owners.get(address) == Some(&seller.wallet_id)));
+
+        // A triangle splits only once
+        assert!(blockchain.add_transaction(subdivide(&mut seller, &blockchain)).is_err());
+        assert_eq!(blockchain.economics.market_prices.get(&TriangleAddress::genesis()), Some(&Decimal::from(250)));
+
+        // The engine travels with the chain
+        let restored = TriadChainBlockchain::from_bytes(&blockchain.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.economics_stats().circulating_supply, expected);
+        assert_eq!(restored.economics_stats().average_triangle_value, Decimal::from(250));
+    }
+}
@@ -6,10 +6,15 @@ use rust_decimal::Decimal;
 
 use crate::core::{
     address::TriangleAddress,
+    fractal::FractalStructure,
     triangle::Triangle,
     errors::{SierpinskiError, SierpinskiResult},
 };
 
+/// Hourly reward rate of a pool opened by the first stake mined on its
+/// triangle
+pub const DEFAULT_STAKING_REWARD_RATE: Decimal = Decimal::from_parts(1, 0, 0, false, 4);
+
 /// Token economics configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenEconomics {
@@ -61,6 +66,24 @@ pub struct StakingPool {
     pub minimum_stake: Decimal,
     pub lock_period: u64, // in seconds
     pub participants: HashMap<String, StakePosition>,
+    /// Fraction of a stake burned when it is withdrawn before its lock
+    /// expires
+    #[serde(default)]
+    pub early_unstake_penalty: Decimal,
+    /// Slashes and early-unstake penalties, oldest first
+    #[serde(default)]
+    pub events: Vec<SlashEvent>,
+}
+
+/// Part of a stake burned by `slash` or an early unstake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashEvent {
+    pub staker_address: String,
+    /// Fraction of the position burned
+    pub fraction: Decimal,
+    pub amount_burned: Decimal,
+    pub reason: String,
+    pub timestamp: u64,
 }
 
 /// Individual stake position
@@ -74,9 +97,13 @@ pub struct StakePosition {
 }
 
 /// Main economics engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EconomicsEngine {
     pub config: TokenEconomics,
+    #[serde(with = "crate::core::blockchain::address_map_serde")]
     pub staking_pools: HashMap<TriangleAddress, StakingPool>,
+    /// Last price each triangle was transferred at
+    #[serde(with = "crate::core::blockchain::address_map_serde")]
     pub market_prices: HashMap<TriangleAddress, Decimal>,
 }
 
@@ -104,6 +131,18 @@ impl EconomicsEngine {
         }
     }
 
+    /// Engine with the same configuration and supply back at its initial
+    /// value, for replaying a chain from genesis
+    pub fn restarted(&self) -> Self {
+        let mut config = self.config.clone();
+        config.circulating_supply = config.initial_supply;
+        EconomicsEngine {
+            config,
+            staking_pools: HashMap::new(),
+            market_prices: HashMap::new(),
+        }
+    }
+
     /// Calculate the intrinsic value of a triangle
     pub fn calculate_triangle_value(&self, 
         triangle: &Triangle, 
@@ -114,14 +153,16 @@ impl EconomicsEngine {
         let area = triangle.area()?;
         let base_area_value = area * self.config.area_value_curve.base_value_per_area;
 
-        // Depth bonus (exponential increase with depth)
+        // Depth bonus (exponential increase with depth); priced per unit of
+        // base value rather than per area, which shrinks faster than the
+        // multiplier grows and would make deeper triangles worth less
         let depth = address.depth();
-        let depth_bonus = base_area_value * {
+        let depth_bonus = self.config.area_value_curve.base_value_per_area * {
             let mut multiplier = Decimal::ONE;
             for _ in 0..depth {
                 multiplier *= self.config.area_value_curve.depth_multiplier;
             }
-            multiplier
+            multiplier - Decimal::ONE
         };
 
         // Rarity bonus based on triangle properties
@@ -152,6 +193,27 @@ impl EconomicsEngine {
         })
     }
 
+    /// Value every triangle that can be owned: the structure's leaves, i.e.
+    /// the active ones and the genesis while it is undivided
+    pub fn value_structure(&self, structure: &FractalStructure) -> SierpinskiResult<HashMap<TriangleAddress, TriangleValue>> {
+        structure
+            .leaves()
+            .into_iter()
+            .map(|triangle| {
+                let value = self.calculate_triangle_value(&triangle.triangle, &triangle.address, triangle.created_at)?;
+                Ok((triangle.address.clone(), value))
+            })
+            .collect()
+    }
+
+    /// Sum of the estimated values of every triangle in `value_structure`
+    pub fn total_market_cap(&self, structure: &FractalStructure) -> SierpinskiResult<Decimal> {
+        Ok(self.value_structure(structure)?
+            .values()
+            .map(|value| value.total_estimated_value)
+            .sum())
+    }
+
     /// Calculate rarity bonus for special triangle properties
     fn calculate_rarity_bonus(&self, triangle: &Triangle, address: &TriangleAddress) -> SierpinskiResult<Decimal> {
         let mut bonus = Decimal::ZERO;
@@ -210,6 +272,8 @@ impl EconomicsEngine {
             minimum_stake,
             lock_period: 7 * 24 * 3600, // 7 days default lock
             participants: HashMap::new(),
+            early_unstake_penalty: Decimal::ZERO,
+            events: Vec::new(),
         };
 
         self.staking_pools.insert(triangle_address, pool);
@@ -229,10 +293,7 @@ impl EconomicsEngine {
             return Err(SierpinskiError::validation("Amount below minimum stake"));
         }
 
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let current_time = current_time();
 
         let stake_position = StakePosition {
             staker_address: staker_address.clone(),
@@ -248,7 +309,65 @@ impl EconomicsEngine {
         Ok(())
     }
 
-    /// Calculate staking rewards for a position
+    /// Record a stake mined on chain at `timestamp`
+    ///
+    /// The first stake on a triangle opens its pool with the default rate
+    /// and no minimum. A further stake by the same staker tops up their
+    /// position, banking the rewards earned so far and restarting its lock.
+    pub fn record_stake(&mut self,
+        triangle_address: &TriangleAddress,
+        staker_address: &str,
+        amount: Decimal,
+        timestamp: u64
+    ) -> SierpinskiResult<()> {
+        self.check_stake(triangle_address, staker_address, amount)?;
+        if !self.staking_pools.contains_key(triangle_address) {
+            self.create_staking_pool(triangle_address.clone(), DEFAULT_STAKING_REWARD_RATE, Decimal::ZERO)?;
+        }
+        let pool = self.staking_pools.get_mut(triangle_address).unwrap();
+        let earned = pool.participants
+            .get(staker_address)
+            .map_or(Decimal::ZERO, |position| pending_rewards(pool, position, timestamp));
+
+        let position = pool.participants
+            .entry(staker_address.to_string())
+            .or_insert_with(|| StakePosition {
+                staker_address: staker_address.to_string(),
+                amount_staked: Decimal::ZERO,
+                stake_timestamp: timestamp,
+                lock_expires: timestamp,
+                accumulated_rewards: Decimal::ZERO,
+            });
+        position.accumulated_rewards += earned;
+        position.amount_staked += amount;
+        position.stake_timestamp = timestamp;
+        position.lock_expires = timestamp + pool.lock_period;
+        pool.total_staked += amount;
+
+        Ok(())
+    }
+
+    /// Check that `record_stake` would accept a stake: the position it
+    /// leaves must reach the pool's minimum, if the triangle has a pool
+    pub fn check_stake(&self,
+        triangle_address: &TriangleAddress,
+        staker_address: &str,
+        amount: Decimal
+    ) -> SierpinskiResult<()> {
+        let Some(pool) = self.staking_pools.get(triangle_address) else {
+            return Ok(());
+        };
+        let staked = pool.participants
+            .get(staker_address)
+            .map_or(Decimal::ZERO, |position| position.amount_staked);
+        if staked + amount < pool.minimum_stake {
+            return Err(SierpinskiError::validation("Amount below minimum stake"));
+        }
+        Ok(())
+    }
+
+    /// Calculate staking rewards for a position: those accumulated before
+    /// its last slash plus those earned by the current stake since
     pub fn calculate_staking_rewards(&self,
         triangle_address: &TriangleAddress,
         staker_address: &str
@@ -259,16 +378,83 @@ impl EconomicsEngine {
         let position = pool.participants.get(staker_address)
             .ok_or_else(|| SierpinskiError::validation("Stake position not found"))?;
 
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        Ok(position.accumulated_rewards + pending_rewards(pool, position, current_time()))
+    }
+
+    /// Set the fraction of a stake burned when it is withdrawn early
+    pub fn set_early_unstake_penalty(&mut self,
+        triangle_address: &TriangleAddress,
+        fraction: Decimal
+    ) -> SierpinskiResult<()> {
+        validate_fraction(fraction)?;
+        let pool = self.staking_pools.get_mut(triangle_address)
+            .ok_or_else(|| SierpinskiError::validation("Staking pool not found"))?;
+        pool.early_unstake_penalty = fraction;
+        Ok(())
+    }
+
+    /// Burn `fraction` of a stake, reducing the pool and the circulating
+    /// supply, and return the amount burned
+    ///
+    /// Rewards earned so far are kept; later rewards accrue on what is left.
+    pub fn slash(&mut self,
+        triangle_address: &TriangleAddress,
+        staker_address: &str,
+        fraction: Decimal,
+        reason: impl Into<String>
+    ) -> SierpinskiResult<Decimal> {
+        validate_fraction(fraction)?;
+        let pool = self.staking_pools.get_mut(triangle_address)
+            .ok_or_else(|| SierpinskiError::validation("Staking pool not found"))?;
+        if !pool.participants.contains_key(staker_address) {
+            return Err(SierpinskiError::validation("Stake position not found"));
+        }
+
+        let burned = burn_stake(pool, staker_address, fraction, reason.into(), current_time());
+        self.config.circulating_supply = (self.config.circulating_supply - burned).max(Decimal::ZERO);
+        Ok(burned)
+    }
+
+    /// Withdraw a stake, returning the amount released: what is left of the
+    /// stake together with every reward it earned, including those banked
+    /// by slashes. Before the lock expires the pool's early-unstake penalty
+    /// is burned from the stake first.
+    pub fn unstake_tokens(&mut self,
+        triangle_address: &TriangleAddress,
+        staker_address: &str
+    ) -> SierpinskiResult<Decimal> {
+        let pool = self.staking_pools.get_mut(triangle_address)
+            .ok_or_else(|| SierpinskiError::validation("Staking pool not found"))?;
+        let position = pool.participants.get(staker_address)
+            .ok_or_else(|| SierpinskiError::validation("Stake position not found"))?;
 
-        let staking_duration = current_time - position.stake_timestamp;
-        let reward_periods = Decimal::from(staking_duration / 3600); // Hourly rewards
+        let now = current_time();
+        let mut burned = Decimal::ZERO;
+        if now < position.lock_expires && pool.early_unstake_penalty > Decimal::ZERO {
+            let penalty = pool.early_unstake_penalty;
+            burned = burn_stake(pool, staker_address, penalty, "early unstake".to_string(), now);
+        }
 
-        let rewards = position.amount_staked * pool.staking_reward_rate * reward_periods;
-        Ok(rewards)
+        let position = pool.participants.remove(staker_address).unwrap();
+        let rewards = position.accumulated_rewards + pending_rewards(pool, &position, now);
+        pool.total_staked = (pool.total_staked - position.amount_staked).max(Decimal::ZERO);
+        self.config.circulating_supply = (self.config.circulating_supply - burned).max(Decimal::ZERO);
+        Ok(position.amount_staked + rewards)
+    }
+
+    /// Amount `staker` has staked in each pool they take part in
+    pub fn stakes_of<'a>(&'a self, staker: &'a str) -> impl Iterator<Item = (&'a TriangleAddress, Decimal)> + 'a {
+        self.staking_pools.iter().filter_map(move |(address, pool)| {
+            pool.participants.get(staker).map(|position| (address, position.amount_staked))
+        })
+    }
+
+    /// Slashes and penalties applied in a pool, oldest first; empty for an
+    /// unknown pool
+    pub fn pool_events(&self, triangle_address: &TriangleAddress) -> &[SlashEvent] {
+        self.staking_pools
+            .get(triangle_address)
+            .map_or(&[], |pool| pool.events.as_slice())
     }
 
     /// Update token supply after block mining
@@ -279,12 +465,14 @@ impl EconomicsEngine {
         // Add inflation from block rewards
         let inflation = self.config.circulating_supply * self.config.block_inflation_rate;
         
-        // Subtract deflation from subdivisions (tokens burned)
-        let deflation = Decimal::from(subdivisions_performed) * 
-            self.config.circulating_supply * self.config.subdivision_deflation_rate;
+        // Each subdivision burns its share of what the ones before it left,
+        // so no number of them takes the supply below zero
+        let mut new_supply = self.config.circulating_supply + inflation;
+        for _ in 0..subdivisions_performed {
+            new_supply -= new_supply * self.config.subdivision_deflation_rate;
+        }
 
         // Update circulating supply
-        let new_supply = self.config.circulating_supply + inflation - deflation;
         self.config.circulating_supply = new_supply.min(self.config.max_supply);
 
         Ok(())
@@ -316,6 +504,49 @@ impl EconomicsEngine {
     }
 }
 
+fn current_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Rewards a position has earned since its stake was last changed
+fn pending_rewards(pool: &StakingPool, position: &StakePosition, now: u64) -> Decimal {
+    let staking_duration = now.saturating_sub(position.stake_timestamp);
+    let reward_periods = Decimal::from(staking_duration / 3600); // Hourly rewards
+    position.amount_staked * pool.staking_reward_rate * reward_periods
+}
+
+fn validate_fraction(fraction: Decimal) -> SierpinskiResult<()> {
+    if fraction < Decimal::ZERO || fraction > Decimal::ONE {
+        return Err(SierpinskiError::validation(format!("Fraction {} must lie between 0 and 1", fraction)));
+    }
+    Ok(())
+}
+
+/// Burn part of a position that exists, banking its rewards so far and
+/// logging the event; the position never goes below zero
+fn burn_stake(pool: &mut StakingPool, staker_address: &str, fraction: Decimal, reason: String, now: u64) -> Decimal {
+    let position = &pool.participants[staker_address];
+    let earned = pending_rewards(pool, position, now);
+    let position = pool.participants.get_mut(staker_address).unwrap();
+    position.accumulated_rewards += earned;
+    position.stake_timestamp = now;
+
+    let burned = (position.amount_staked * fraction).min(position.amount_staked);
+    position.amount_staked -= burned;
+    pool.total_staked = (pool.total_staked - burned).max(Decimal::ZERO);
+    pool.events.push(SlashEvent {
+        staker_address: staker_address.to_string(),
+        fraction,
+        amount_burned: burned,
+        reason,
+        timestamp: now,
+    });
+    burned
+}
+
 /// Helper function for depth penalty calculation
 fn depth_penalty_factor(depth: u8) -> i64 {
     match depth {
@@ -375,6 +606,34 @@ mod tests {
         assert!(value.base_area_value > Decimal::ZERO);
     }
 
+    #[test]
+    fn test_value_structure_rewards_depth() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_and_add_to_structure};
+
+        // Genesis split once, then its first child split again, leaving
+        // active triangles at depths 1 and 2
+        let mut structure = FractalStructure::new();
+        structure.set_genesis(genesis_fractal_triangle().unwrap()).unwrap();
+        let genesis_id = structure.genesis().unwrap().id;
+        let first = subdivide_and_add_to_structure(&mut structure, &genesis_id).unwrap();
+        subdivide_and_add_to_structure(&mut structure, &first.children[0].id).unwrap();
+
+        let engine = EconomicsEngine::new();
+        let values = engine.value_structure(&structure).unwrap();
+        assert_eq!(values.len(), 2 + 3);
+        assert!(values.keys().all(|address| !address.is_void()));
+
+        let shallow = &values[&TriangleAddress::from_string_representation("1").unwrap()];
+        let deep = &values[&TriangleAddress::from_string_representation("0.1").unwrap()];
+        assert!(deep.depth_bonus > shallow.depth_bonus);
+        assert!(deep.base_area_value < shallow.base_area_value);
+
+        let market_cap = engine.total_market_cap(&structure).unwrap();
+        let summed: Decimal = values.values().map(|value| value.total_estimated_value).sum();
+        // Summation order follows the map, so allow for rounding
+        assert!((market_cap - summed).abs() < Decimal::new(1, 20));
+    }
+
     #[test]
     fn test_staking_pool_creation() {
         let mut engine = EconomicsEngine::new();
@@ -389,4 +648,96 @@ mod tests {
         assert!(result.is_ok());
         assert!(engine.staking_pools.contains_key(&address));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_slash_burns_stake_and_supply() {
+        let mut engine = EconomicsEngine::new();
+        let address = TriangleAddress::genesis();
+        engine.create_staking_pool(address.clone(), Decimal::new(1, 3), Decimal::new(100, 0)).unwrap();
+        engine.stake_tokens(&address, "validator".to_string(), Decimal::new(1000, 0)).unwrap();
+        let supply = engine.config.circulating_supply;
+
+        // Ten hours staked before the slash
+        let position = engine.staking_pools.get_mut(&address).unwrap().participants.get_mut("validator").unwrap();
+        position.stake_timestamp -= 10 * 3600;
+        let burned = engine.slash(&address, "validator", Decimal::new(1, 1), "double signing").unwrap();
+        assert_eq!(burned, Decimal::new(100, 0));
+
+        let pool = &engine.staking_pools[&address];
+        assert_eq!(pool.participants["validator"].amount_staked, Decimal::new(900, 0));
+        assert_eq!(pool.total_staked, Decimal::new(900, 0));
+        assert_eq!(engine.config.circulating_supply, supply - burned);
+        assert_eq!(engine.get_economics_stats().total_staked_value, Decimal::new(900, 0));
+        assert_eq!(engine.pool_events(&address).len(), 1);
+        assert_eq!(engine.pool_events(&address)[0].reason, "double signing");
+
+        // Earlier rewards are kept; later ones accrue on the slashed stake
+        let before = engine.calculate_staking_rewards(&address, "validator").unwrap();
+        assert_eq!(before, Decimal::new(10, 0));
+        let position = engine.staking_pools.get_mut(&address).unwrap().participants.get_mut("validator").unwrap();
+        position.stake_timestamp -= 5 * 3600;
+        let after = engine.calculate_staking_rewards(&address, "validator").unwrap();
+        assert_eq!(after - before, Decimal::new(45, 1));
+
+        // A full slash empties the position without going negative
+        engine.slash(&address, "validator", Decimal::ONE, "downtime").unwrap();
+        engine.slash(&address, "validator", Decimal::ONE, "downtime").unwrap();
+        assert_eq!(engine.staking_pools[&address].participants["validator"].amount_staked, Decimal::ZERO);
+        assert_eq!(engine.staking_pools[&address].total_staked, Decimal::ZERO);
+        assert_eq!(engine.config.circulating_supply, supply - Decimal::new(1000, 0));
+    }
+
+    #[test]
+    fn test_slash_rejects_missing_positions_and_bad_fractions() {
+        let mut engine = EconomicsEngine::new();
+        let address = TriangleAddress::genesis();
+        let supply = engine.config.circulating_supply;
+        assert!(engine.slash(&address, "nobody", Decimal::new(1, 1), "no pool").is_err());
+
+        engine.create_staking_pool(address.clone(), Decimal::new(1, 3), Decimal::new(100, 0)).unwrap();
+        assert!(engine.slash(&address, "nobody", Decimal::new(1, 1), "no position").is_err());
+        engine.stake_tokens(&address, "validator".to_string(), Decimal::new(1000, 0)).unwrap();
+        assert!(engine.slash(&address, "validator", Decimal::new(2, 0), "too much").is_err());
+        assert!(engine.pool_events(&address).is_empty());
+        assert_eq!(engine.config.circulating_supply, supply);
+    }
+
+    #[test]
+    fn test_early_unstake_penalty() {
+        let mut engine = EconomicsEngine::new();
+        let address = TriangleAddress::genesis();
+        engine.create_staking_pool(address.clone(), Decimal::new(1, 3), Decimal::new(100, 0)).unwrap();
+        engine.set_early_unstake_penalty(&address, Decimal::new(25, 2)).unwrap();
+        engine.stake_tokens(&address, "early".to_string(), Decimal::new(1000, 0)).unwrap();
+        engine.stake_tokens(&address, "patient".to_string(), Decimal::new(1000, 0)).unwrap();
+        let supply = engine.config.circulating_supply;
+
+        assert_eq!(engine.unstake_tokens(&address, "early").unwrap(), Decimal::new(750, 0));
+        engine.staking_pools.get_mut(&address).unwrap().participants.get_mut("patient").unwrap().lock_expires = 0;
+        assert_eq!(engine.unstake_tokens(&address, "patient").unwrap(), Decimal::new(1000, 0));
+
+        assert_eq!(engine.staking_pools[&address].total_staked, Decimal::ZERO);
+        assert_eq!(engine.config.circulating_supply, supply - Decimal::new(250, 0));
+        assert_eq!(engine.pool_events(&address).len(), 1);
+    }
+
+    #[test]
+    fn test_unstake_pays_out_banked_rewards() {
+        let mut engine = EconomicsEngine::new();
+        let address = TriangleAddress::genesis();
+        engine.create_staking_pool(address.clone(), Decimal::new(1, 3), Decimal::new(100, 0)).unwrap();
+        engine.stake_tokens(&address, "validator".to_string(), Decimal::new(1000, 0)).unwrap();
+
+        // Ten hours at 1000 bank 10 on the slash, five more at 900 earn 4.5
+        let position = engine.staking_pools.get_mut(&address).unwrap().participants.get_mut("validator").unwrap();
+        position.stake_timestamp -= 10 * 3600;
+        engine.slash(&address, "validator", Decimal::new(1, 1), "downtime").unwrap();
+        let position = engine.staking_pools.get_mut(&address).unwrap().participants.get_mut("validator").unwrap();
+        position.stake_timestamp -= 5 * 3600;
+        position.lock_expires = 0;
+
+        let released = engine.unstake_tokens(&address, "validator").unwrap();
+        assert_eq!(released, Decimal::new(900, 0) + Decimal::new(145, 1));
+        assert!(engine.staking_pools[&address].participants.is_empty());
+    }
+}
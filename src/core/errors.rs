@@ -40,6 +40,15 @@ pub enum SierpinskiError {
 
     #[error("Address path component out of range: {component}")]
     AddressComponentOutOfRange { component: u8 },
+
+    #[error("Checksum mismatch in address {address}: expected suffix {expected}")]
+    ChecksumMismatch { address: String, expected: String },
+
+    #[error("{item} is {size} bytes, over the {limit} byte limit")]
+    SizeLimitExceeded { item: String, size: usize, limit: usize },
+
+    #[error("Transactions of block {height} were pruned and are unavailable")]
+    PrunedDataUnavailable { height: u64 },
 }
 
 /// Result type alias for Sierpinski operations
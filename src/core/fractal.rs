@@ -1,16 +1,31 @@
 //! Fractal triangle implementation with hierarchical structure
 
+use rust_decimal::Decimal;
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
 
 use crate::core::{
     triangle::Triangle,
-    state::TriangleState,
+    geometry::{BoundingBox, Point},
+    state::{StateTransition, TriangleState},
+    state_tree::StateTree,
     address::TriangleAddress,
     errors::{SierpinskiError, SierpinskiResult},
+    validation::ValidationResult,
 };
 
+/// Number of state transitions kept per triangle; older ones are dropped
+pub const MAX_STATE_HISTORY: usize = 32;
+
+/// Largest difference per coordinate allowed between a grafted subtree's
+/// root and the triangle it replaces
+pub const GRAFT_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 12);
+
+/// Reason recorded by `change_state_default`
+const DEFAULT_STATE_CHANGE_REASON: &str = "unspecified";
+
 /// A triangle within the Sierpinski fractal system with metadata
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FractalTriangle {
@@ -32,15 +47,15 @@ pub struct FractalTriangle {
     pub created_at: u64,
     /// Last state change timestamp
     pub updated_at: u64,
+    /// Most recent state transitions, oldest first (at most `MAX_STATE_HISTORY`)
+    #[serde(default)]
+    pub history: Vec<StateTransition>,
 }
 
 impl FractalTriangle {
     /// Create a new fractal triangle
     pub fn new(triangle: Triangle, state: TriangleState, address: TriangleAddress, depth: u8) -> Self {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+        let now = crate::core::unix_now();
 
         FractalTriangle {
             id: Uuid::new_v4(),
@@ -52,6 +67,7 @@ impl FractalTriangle {
             child_ids: Vec::new(),
             created_at: now,
             updated_at: now,
+            history: Vec::new(),
         }
     }
 
@@ -88,8 +104,46 @@ impl FractalTriangle {
         Ok(child)
     }
 
-    /// Change the state of the triangle
-    pub fn change_state(&mut self, new_state: TriangleState) -> SierpinskiResult<()> {
+    /// Create the genesis triangle with an id derived from its geometry, so
+    /// every node building the same genesis agrees on its id
+    pub fn genesis_deterministic(triangle: Triangle) -> Self {
+        let id = Self::deterministic_id(&[triangle.canonical_hash().as_bytes()]);
+        let mut genesis = FractalTriangle::genesis(triangle);
+        genesis.id = id;
+        genesis
+    }
+
+    /// Create a child triangle whose id is derived from the parent id, the
+    /// child index and the child's geometry instead of being random
+    pub fn child_deterministic(
+        triangle: Triangle,
+        parent: &FractalTriangle,
+        child_index: u8,
+    ) -> SierpinskiResult<Self> {
+        let id = Self::deterministic_id(&[
+            parent.id.as_bytes(),
+            &[child_index],
+            triangle.canonical_hash().as_bytes(),
+        ]);
+        let mut child = FractalTriangle::child(triangle, parent, child_index)?;
+        child.id = id;
+        Ok(child)
+    }
+
+    /// Build a UUID from the blake3 hash of the given parts
+    fn deterministic_id(parts: &[&[u8]]) -> Uuid {
+        let mut hasher = blake3::Hasher::new();
+        for part in parts {
+            hasher.update(part);
+        }
+
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hasher.finalize().as_bytes()[..16]);
+        uuid::Builder::from_custom_bytes(bytes).into_uuid()
+    }
+
+    /// Change the state of the triangle, recording why in its history
+    pub fn change_state(&mut self, new_state: TriangleState, reason: impl Into<String>) -> SierpinskiResult<()> {
         if !self.state.can_transition_to(new_state) {
             return Err(SierpinskiError::StateTransitionError {
                 from: self.state.to_string(),
@@ -97,15 +151,44 @@ impl FractalTriangle {
             });
         }
 
+        let transition = StateTransition::new(self.state, new_state, reason.into());
         self.state = new_state;
-        self.updated_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+        self.updated_at = transition.timestamp;
+
+        if self.history.len() >= MAX_STATE_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(transition);
 
         Ok(())
     }
 
+    /// Change the state of the triangle without giving a reason
+    pub fn change_state_default(&mut self, new_state: TriangleState) -> SierpinskiResult<()> {
+        self.change_state(new_state, DEFAULT_STATE_CHANGE_REASON)
+    }
+
+    /// Get the recorded state transitions, oldest first
+    pub fn state_history(&self) -> &[StateTransition] {
+        &self.history
+    }
+
+    /// Get the state the triangle was in at a given time, or None if it did
+    /// not exist yet
+    ///
+    /// Times before the oldest retained transition report that transition's
+    /// starting state.
+    pub fn state_at(&self, timestamp: u64) -> Option<TriangleState> {
+        if timestamp < self.created_at {
+            return None;
+        }
+
+        match self.history.iter().rev().find(|t| t.timestamp <= timestamp) {
+            Some(transition) => Some(transition.to),
+            None => Some(self.history.first().map_or(self.state, |t| t.from)),
+        }
+    }
+
     /// Add a child triangle ID
     pub fn add_child(&mut self, child_id: Uuid) {
         if !self.child_ids.contains(&child_id) {
@@ -124,7 +207,7 @@ impl FractalTriangle {
     }
 
     /// Get the total area covered by this triangle
-    pub fn area(&self) -> SierpinskiResult<rust_decimal::Decimal> {
+    pub fn area(&self) -> SierpinskiResult<Decimal> {
         self.triangle.area()
     }
 
@@ -139,10 +222,10 @@ impl FractalTriangle {
     }
 
     /// Calculate the theoretical area ratio compared to genesis
-    pub fn area_ratio_to_genesis(&self) -> rust_decimal::Decimal {
+    pub fn area_ratio_to_genesis(&self) -> Decimal {
         // Each subdivision reduces area by 3/4
-        let three_fourths = rust_decimal::Decimal::new(3, 0) / rust_decimal::Decimal::new(4, 0);
-        let mut ratio = rust_decimal::Decimal::ONE;
+        let three_fourths = Decimal::new(3, 0) / Decimal::new(4, 0);
+        let mut ratio = Decimal::ONE;
         
         for _ in 0..self.depth {
             ratio *= three_fourths;
@@ -155,23 +238,169 @@ impl FractalTriangle {
     pub fn hash(&self) -> String {
         let mut hasher = blake3::Hasher::new();
         hasher.update(self.id.as_bytes());
-        hasher.update(self.triangle.hash().as_bytes());
+        hasher.update(self.triangle.canonical_hash().as_bytes());
+        hasher.update(&[self.depth]);
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Hash of the address, geometry and state, leaving out the id so that
+    /// nodes creating the same triangle agree on it
+    pub fn state_hash(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.address.to_string().as_bytes());
+        hasher.update(self.triangle.canonical_hash().as_bytes());
+        hasher.update(format!("{:?}", self.state).as_bytes());
         hasher.update(&[self.depth]);
         hasher.finalize().to_hex().to_string()
     }
 }
 
+/// Triangle counts and area for a single depth of a fractal structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthStats {
+    pub depth: u8,
+    /// Active triangles, counting an unsubdivided genesis
+    pub active_count: usize,
+    pub subdivided_count: usize,
+    pub void_count: usize,
+    /// Combined area of every triangle at this depth
+    pub total_area: Decimal,
+}
+
+/// Which triangles `FractalStructure::prune` moves to the archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrunePolicy {
+    /// Archive every Void triangle
+    DropVoids,
+    /// Archive Subdivided triangles shallower than the given depth
+    ArchiveSubdividedAbove(u8),
+}
+
+/// Counts of triangles archived by a prune
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub voids_archived: usize,
+    pub subdivided_archived: usize,
+}
+
+/// What remains of a pruned triangle: enough to check parent-child links and
+/// find it by address, without geometry or history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedTriangle {
+    pub id: Uuid,
+    pub address: TriangleAddress,
+    pub depth: u8,
+    pub state: TriangleState,
+    pub parent_id: Option<Uuid>,
+    pub child_ids: Vec<Uuid>,
+}
+
+impl From<&FractalTriangle> for ArchivedTriangle {
+    fn from(triangle: &FractalTriangle) -> Self {
+        ArchivedTriangle {
+            id: triangle.id,
+            address: triangle.address.clone(),
+            depth: triangle.depth,
+            state: triangle.state,
+            parent_id: triangle.parent_id,
+            child_ids: triangle.child_ids.clone(),
+        }
+    }
+}
+
 /// A collection of fractal triangles forming the complete fractal structure
+///
+/// A structure may be pruned, moving triangles to a compact archive. Queries
+/// such as `get_triangle`, `triangles` and `total_triangles` then see only
+/// resident triangles; `genesis` is None once the genesis is archived.
+/// `max_depth` is the deepest depth ever reached and is unaffected.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredFractalStructure")]
 pub struct FractalStructure {
-    /// Map of triangle ID to fractal triangle
+    /// Map of triangle ID to fractal triangle, serialized as an array
+    /// ordered by depth
+    #[serde(with = "triangle_array")]
     triangles: HashMap<Uuid, FractalTriangle>,
     /// Genesis triangle ID
     genesis_id: Option<Uuid>,
     /// Maximum depth reached
     max_depth: u8,
-    /// Total number of triangles
+    /// Deepest depth triangles may be added at; `MAX_SUBDIVISION_DEPTH`
+    /// stays the ceiling
+    #[serde(default = "default_max_depth_limit")]
+    max_depth_limit: u8,
+    /// Total number of resident triangles
     total_count: usize,
+    /// Triangles removed by `prune`
+    #[serde(default, with = "triangle_array")]
+    archived: HashMap<Uuid, ArchivedTriangle>,
+    /// Bounding box of every triangle, resident or archived, computed on
+    /// insertion; queries recompute missing entries after deserialization
+    #[serde(skip)]
+    bounds: HashMap<Uuid, BoundingBox>,
+    /// For a structure made by `extract_subtree`, the address its genesis
+    /// had in the structure it came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subtree_root: Option<TriangleAddress>,
+    /// Ids of the leaves, see `leaves`, by depth; None after
+    /// deserialization until the next mutation rebuilds it, with queries
+    /// scanning meanwhile
+    #[serde(skip)]
+    frontier: Option<HashMap<u8, HashSet<Uuid>>>,
+    /// Id of the resident triangle at each address; entries left behind
+    /// when `get_triangle_mut` moved a triangle are ignored by lookups
+    #[serde(skip)]
+    by_address: HashMap<TriangleAddress, Uuid>,
+    /// Commitment to the resident triangles behind `state_root`
+    #[serde(skip)]
+    state_tree: StateTree,
+    /// Triangle last handed out by `get_triangle_mut`, which may have
+    /// joined or left the frontier, or changed state, since it was indexed
+    #[serde(skip)]
+    last_mutated: Option<Uuid>,
+}
+
+/// Serialized fields of `FractalStructure`; deserializing goes through it
+/// to rebuild the state tree and the counts derived from the triangles
+#[derive(Deserialize)]
+struct StoredFractalStructure {
+    #[serde(with = "triangle_array")]
+    triangles: HashMap<Uuid, FractalTriangle>,
+    genesis_id: Option<Uuid>,
+    #[serde(default = "default_max_depth_limit")]
+    max_depth_limit: u8,
+    #[serde(default, with = "triangle_array")]
+    archived: HashMap<Uuid, ArchivedTriangle>,
+    #[serde(default)]
+    subtree_root: Option<TriangleAddress>,
+}
+
+impl From<StoredFractalStructure> for FractalStructure {
+    fn from(stored: StoredFractalStructure) -> Self {
+        // The stored max depth and count are not trusted: per-depth tables
+        // are sized by the depth
+        let max_depth = stored.triangles
+            .values()
+            .map(|triangle| triangle.depth)
+            .chain(stored.archived.values().map(|archived| archived.depth))
+            .max()
+            .unwrap_or(0);
+
+        FractalStructure {
+            state_tree: StateTree::from_triangles(stored.triangles.values()),
+            by_address: address_index(&stored.triangles),
+            total_count: stored.triangles.len(),
+            triangles: stored.triangles,
+            genesis_id: stored.genesis_id,
+            max_depth,
+            max_depth_limit: stored.max_depth_limit,
+            archived: stored.archived,
+            bounds: HashMap::new(),
+            subtree_root: stored.subtree_root,
+            frontier: None,
+            last_mutated: None,
+        }
+    }
 }
 
 impl FractalStructure {
@@ -181,8 +410,43 @@ impl FractalStructure {
             triangles: HashMap::new(),
             genesis_id: None,
             max_depth: 0,
+            max_depth_limit: crate::MAX_SUBDIVISION_DEPTH,
             total_count: 0,
+            archived: HashMap::new(),
+            bounds: HashMap::new(),
+            subtree_root: None,
+            frontier: Some(HashMap::new()),
+            by_address: HashMap::new(),
+            state_tree: StateTree::new(),
+            last_mutated: None,
+        }
+    }
+
+    /// Deepest depth triangles may be added at
+    pub fn max_depth_limit(&self) -> u8 {
+        self.max_depth_limit
+    }
+
+    /// Cap the depth of this structure below `MAX_SUBDIVISION_DEPTH`, e.g.
+    /// to bound resource use
+    ///
+    /// Fails if `limit` exceeds `MAX_SUBDIVISION_DEPTH` or is below the
+    /// depth already reached.
+    pub fn set_max_depth_limit(&mut self, limit: u8) -> SierpinskiResult<()> {
+        if limit > crate::MAX_SUBDIVISION_DEPTH {
+            return Err(SierpinskiError::MaxDepthExceeded {
+                max_depth: crate::MAX_SUBDIVISION_DEPTH,
+            });
+        }
+        if limit < self.max_depth {
+            return Err(SierpinskiError::validation(format!(
+                "Depth limit {} is below the depth {} already reached",
+                limit, self.max_depth
+            )));
         }
+
+        self.max_depth_limit = limit;
+        Ok(())
     }
 
     /// Add the genesis triangle
@@ -193,14 +457,28 @@ impl FractalStructure {
             ));
         }
 
-        self.genesis_id = Some(triangle.id);
-        self.triangles.insert(triangle.id, triangle);
+        self.sync_indexes();
+        let id = triangle.id;
+        self.genesis_id = Some(id);
+        self.bounds.insert(id, triangle.triangle.bounding_box());
+        self.triangles.insert(id, triangle);
         self.total_count = 1;
+        self.index_address(&id);
+        self.index_frontier(&id);
+        self.index_state(&id);
         Ok(())
     }
 
     /// Add a triangle to the structure
     pub fn add_triangle(&mut self, triangle: FractalTriangle) -> SierpinskiResult<()> {
+        if triangle.depth > self.max_depth_limit {
+            return Err(SierpinskiError::MaxDepthExceeded {
+                max_depth: self.max_depth_limit,
+            });
+        }
+
+        self.sync_indexes();
+
         // Update max depth
         if triangle.depth > self.max_depth {
             self.max_depth = triangle.depth;
@@ -213,11 +491,108 @@ impl FractalStructure {
             }
         }
 
-        self.triangles.insert(triangle.id, triangle);
+        let (id, parent_id) = (triangle.id, triangle.parent_id);
+        self.bounds.insert(id, triangle.triangle.bounding_box());
+        self.triangles.insert(id, triangle);
         self.total_count = self.triangles.len();
+        self.index_address(&id);
+        self.index_frontier(&id);
+        self.index_state(&id);
+        if let Some(parent_id) = parent_id {
+            self.index_frontier(&parent_id);
+        }
         Ok(())
     }
 
+    /// Remove a resident triangle without children, unlinking it from its
+    /// parent
+    pub fn remove_triangle(&mut self, id: &Uuid) -> SierpinskiResult<FractalTriangle> {
+        let triangle = self.triangles.get(id)
+            .ok_or_else(|| SierpinskiError::validation(format!("Triangle {} not found", id)))?;
+        if triangle.has_children() {
+            return Err(SierpinskiError::validation(format!("Triangle {} still has children", id)));
+        }
+
+        self.sync_indexes();
+        let triangle = self.triangles.remove(id).unwrap();
+        if let Some(parent) = triangle.parent_id.and_then(|parent_id| self.triangles.get_mut(&parent_id)) {
+            parent.child_ids.retain(|child_id| child_id != id);
+        }
+        if self.genesis_id == Some(*id) {
+            self.genesis_id = None;
+        }
+        if self.by_address.get(&triangle.address) == Some(id) {
+            self.by_address.remove(&triangle.address);
+        }
+        self.bounds.remove(id);
+        self.total_count = self.triangles.len();
+        self.index_frontier(id);
+        self.index_state(id);
+        if let Some(parent_id) = triangle.parent_id {
+            self.index_frontier(&parent_id);
+        }
+        Ok(triangle)
+    }
+
+    /// Recompute every triangle's `child_ids` from the `parent_id` back-links,
+    /// along with `max_depth` and the resident count
+    ///
+    /// Repairs links lost when a child was added before its parent. Archived
+    /// triangles take part, so pruning is not undone. Triangles whose parent
+    /// is neither resident nor archived are reported as errors.
+    pub fn rebuild_relationships(&mut self) -> ValidationResult {
+        let mut result = ValidationResult::success();
+
+        let mut links: Vec<(Uuid, Option<Uuid>, &TriangleAddress, u8)> = self
+            .triangles
+            .values()
+            .map(|triangle| (triangle.id, triangle.parent_id, &triangle.address, triangle.depth))
+            .chain(self.archived.values().map(|archived| {
+                (archived.id, archived.parent_id, &archived.address, archived.depth)
+            }))
+            .collect();
+        // Children end up listed in address order, as subdivision adds them
+        links.sort_by(|a, b| a.2.components().cmp(b.2.components()));
+
+        let mut children: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for &(id, parent_id, address, _) in &links {
+            let Some(parent_id) = parent_id else { continue };
+            if self.triangles.contains_key(&parent_id) || self.archived.contains_key(&parent_id) {
+                children.entry(parent_id).or_default().push(id);
+            } else {
+                result.add_error(format!(
+                    "Orphaned triangle {} at {}: parent {} is missing",
+                    id, address, parent_id
+                ));
+            }
+        }
+        let max_depth = links.iter().map(|link| link.3).max().unwrap_or(0);
+
+        let mut repaired = 0;
+        for triangle in self.triangles.values_mut() {
+            let child_ids = children.remove(&triangle.id).unwrap_or_default();
+            if triangle.child_ids != child_ids {
+                triangle.child_ids = child_ids;
+                repaired += 1;
+            }
+        }
+        for archived in self.archived.values_mut() {
+            let child_ids = children.remove(&archived.id).unwrap_or_default();
+            if archived.child_ids != child_ids {
+                archived.child_ids = child_ids;
+                repaired += 1;
+            }
+        }
+        if repaired > 0 {
+            result.add_warning(format!("Repaired child links of {} triangles", repaired));
+        }
+
+        self.max_depth = max_depth;
+        self.total_count = self.triangles.len();
+        self.rebuild_indexes();
+        result
+    }
+
     /// Get a triangle by ID
     pub fn get_triangle(&self, id: &Uuid) -> Option<&FractalTriangle> {
         self.triangles.get(id)
@@ -225,6 +600,8 @@ impl FractalStructure {
 
     /// Get a mutable reference to a triangle by ID
     pub fn get_triangle_mut(&mut self, id: &Uuid) -> Option<&mut FractalTriangle> {
+        self.sync_indexes();
+        self.last_mutated = Some(*id);
         self.triangles.get_mut(id)
     }
 
@@ -233,6 +610,114 @@ impl FractalStructure {
         self.genesis_id.and_then(|id| self.triangles.get(&id))
     }
 
+    /// Iterate over every triangle in the structure, in no particular order
+    pub fn triangles(&self) -> impl Iterator<Item = &FractalTriangle> {
+        self.triangles.values()
+    }
+
+    /// Root of the `StateTree` over the `state_hash` of every resident
+    /// triangle; blocks commit to it
+    ///
+    /// The tree is kept up to date as triangles change, so this takes time
+    /// proportional to the depth rather than the number of triangles.
+    pub fn state_root(&self) -> String {
+        match self.last_mutated.and_then(|id| self.triangles.get(&id)) {
+            Some(triangle) => self.state_tree.root_with(triangle),
+            None => self.state_tree.root(),
+        }
+    }
+
+    /// Stream the structure as JSON to `writer`, triangles ordered by depth
+    /// and then address, so the same structure always gives the same bytes
+    pub fn write_json<W: Write>(&self, writer: W, pretty: bool) -> SierpinskiResult<()> {
+        let result = if pretty {
+            serde_json::to_writer_pretty(writer, self)
+        } else {
+            serde_json::to_writer(writer, self)
+        };
+        result.map_err(|e| SierpinskiError::validation(format!("Failed to encode fractal structure: {}", e)))
+    }
+
+    /// Read a structure written by `write_json`, or by older versions that
+    /// wrote triangles as a map keyed by id
+    pub fn read_json<R: Read>(reader: R) -> SierpinskiResult<Self> {
+        serde_json::from_reader(reader)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to decode fractal structure: {}", e)))
+    }
+
+    /// Get the box enclosing every triangle, or None for an empty structure
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.triangles
+            .values()
+            .map(|triangle| triangle.triangle.bounding_box())
+            .reduce(|bounds, other| bounds.union(&other))
+    }
+
+    /// Get the triangles under the genesis whose bounding box intersects the
+    /// rectangle from `min` to `max`
+    ///
+    /// Descends the subdivision hierarchy from the genesis, skipping every
+    /// subtree whose root box misses the region; children always lie inside
+    /// their parent, so nothing is lost. Archived parents are descended
+    /// through. Triangles not linked to the genesis are not found, use
+    /// `triangles_in_region` for those.
+    pub fn query_region(&self, min: Point, max: Point) -> Vec<&FractalTriangle> {
+        let region = BoundingBox { min, max };
+        self.descend(|bounds| bounds.intersects(&region), &mut 0)
+    }
+
+    /// Get the triangles under the genesis that contain a point, searched
+    /// like `query_region`
+    pub fn query_point(&self, point: &Point) -> Vec<&FractalTriangle> {
+        self.descend(|bounds| bounds.contains(point), &mut 0)
+            .into_iter()
+            .filter(|triangle| triangle.triangle.contains_point(point))
+            .collect()
+    }
+
+    /// Collect resident triangles whose box `hits`, pruning subtrees whose
+    /// box does not; `visited` counts the nodes examined
+    fn descend(&self, hits: impl Fn(&BoundingBox) -> bool, visited: &mut usize) -> Vec<&FractalTriangle> {
+        let mut found = Vec::new();
+        let mut pending: Vec<Uuid> = self.genesis_id.into_iter().collect();
+
+        while let Some(id) = pending.pop() {
+            *visited += 1;
+            let resident = self.triangles.get(&id);
+            let bounds = self.bounds
+                .get(&id)
+                .copied()
+                .or_else(|| resident.map(|triangle| triangle.triangle.bounding_box()));
+            if bounds.is_some_and(|bounds| !hits(&bounds)) {
+                continue;
+            }
+
+            let child_ids = match (resident, self.archived.get(&id)) {
+                (Some(triangle), _) => {
+                    found.push(triangle);
+                    &triangle.child_ids
+                }
+                (None, Some(archived)) => &archived.child_ids,
+                (None, None) => continue,
+            };
+            pending.extend(child_ids);
+        }
+
+        found
+    }
+
+    /// Get all triangles whose bounding box intersects a region, e.g. the
+    /// visible window of a viewport
+    ///
+    /// Scans every resident triangle, including ones detached from the
+    /// genesis; prefer `query_region` for structures built by subdivision.
+    pub fn triangles_in_region(&self, region: &BoundingBox) -> Vec<&FractalTriangle> {
+        self.triangles
+            .values()
+            .filter(|triangle| triangle.triangle.bounding_box().intersects(region))
+            .collect()
+    }
+
     /// Get all triangles at a specific depth
     pub fn triangles_at_depth(&self, depth: u8) -> Vec<&FractalTriangle> {
         self.triangles
@@ -241,6 +726,104 @@ impl FractalStructure {
             .collect()
     }
 
+    /// Active or genesis triangles without children, in no particular order
+    pub fn leaves(&self) -> Vec<&FractalTriangle> {
+        match &self.frontier {
+            Some(frontier) => self.resolve_frontier(frontier.values().flatten(), |_| true),
+            None => self.triangles.values().filter(|t| is_leaf(t)).collect(),
+        }
+    }
+
+    /// Leaves at `depth`, in no particular order
+    pub fn frontier_at_depth(&self, depth: u8) -> Vec<&FractalTriangle> {
+        match &self.frontier {
+            Some(frontier) => self.resolve_frontier(frontier.get(&depth).into_iter().flatten(), |t| t.depth == depth),
+            None => self.triangles.values().filter(|t| t.depth == depth && is_leaf(t)).collect(),
+        }
+    }
+
+    /// Leaves that may still be subdivided, i.e. those above both
+    /// `MAX_SUBDIVISION_DEPTH` and the structure's depth limit
+    pub fn subdividable(&self) -> Vec<&FractalTriangle> {
+        (0..self.max_depth_limit)
+            .flat_map(|depth| self.frontier_at_depth(depth))
+            .filter(|t| t.can_subdivide())
+            .collect()
+    }
+
+    /// Look up indexed frontier ids, rechecking the triangle last handed out
+    /// mutably, which may have changed since it was indexed
+    fn resolve_frontier<'a>(
+        &'a self,
+        indexed: impl Iterator<Item = &'a Uuid>,
+        keep: impl Fn(&FractalTriangle) -> bool,
+    ) -> Vec<&'a FractalTriangle> {
+        indexed
+            .filter(|id| Some(**id) != self.last_mutated)
+            .chain(self.last_mutated.as_ref())
+            .filter_map(|id| self.triangles.get(id))
+            .filter(|t| is_leaf(t) && keep(t))
+            .collect()
+    }
+
+    /// Index the triangle last handed out by `get_triangle_mut`, or build
+    /// the frontier index if deserialization left it missing
+    fn sync_indexes(&mut self) {
+        if let Some(id) = self.last_mutated {
+            self.index_state(&id);
+            self.index_address(&id);
+        }
+        if self.frontier.is_none() {
+            self.rebuild_frontier();
+        } else if let Some(id) = self.last_mutated.take() {
+            self.index_frontier(&id);
+        }
+    }
+
+    /// Rebuild the frontier, address index and state tree from every
+    /// resident triangle
+    fn rebuild_indexes(&mut self) {
+        self.state_tree = StateTree::from_triangles(self.triangles.values());
+        self.by_address = address_index(&self.triangles);
+        self.rebuild_frontier();
+    }
+
+    /// Rebuild the frontier index from every resident triangle
+    fn rebuild_frontier(&mut self) {
+        let mut frontier: HashMap<u8, HashSet<Uuid>> = HashMap::new();
+        for triangle in self.triangles.values().filter(|t| is_leaf(t)) {
+            frontier.entry(triangle.depth).or_default().insert(triangle.id);
+        }
+        self.frontier = Some(frontier);
+        self.last_mutated = None;
+    }
+
+    /// Update one triangle's entry in the state tree
+    fn index_state(&mut self, id: &Uuid) {
+        match self.triangles.get(id) {
+            Some(triangle) => self.state_tree.insert(triangle),
+            None => self.state_tree.remove(id),
+        }
+    }
+
+    /// Point the address index at a resident triangle
+    fn index_address(&mut self, id: &Uuid) {
+        if let Some(triangle) = self.triangles.get(id) {
+            self.by_address.insert(triangle.address.clone(), *id);
+        }
+    }
+
+    /// Update one triangle's entry in the frontier index
+    fn index_frontier(&mut self, id: &Uuid) {
+        let Some(frontier) = &mut self.frontier else { return };
+        for ids in frontier.values_mut() {
+            ids.remove(id);
+        }
+        if let Some(triangle) = self.triangles.get(id).filter(|t| is_leaf(t)) {
+            frontier.entry(triangle.depth).or_default().insert(*id);
+        }
+    }
+
     /// Get triangles by state
     pub fn triangles_by_state(&self, state: TriangleState) -> Vec<&FractalTriangle> {
         self.triangles
@@ -249,7 +832,133 @@ impl FractalStructure {
             .collect()
     }
 
-    /// Get the total number of triangles
+    /// Count triangles by state and sum their area for every depth from 0 to
+    /// `max_depth`, in a single pass over the structure
+    pub fn depth_breakdown(&self) -> SierpinskiResult<Vec<DepthStats>> {
+        let empty = |depth: usize| DepthStats {
+            depth: depth as u8,
+            active_count: 0,
+            subdivided_count: 0,
+            void_count: 0,
+            total_area: Decimal::ZERO,
+        };
+        let mut breakdown: Vec<DepthStats> = (0..=self.max_depth as usize).map(empty).collect();
+
+        for triangle in self.triangles.values() {
+            // Triangles edited through get_triangle_mut may sit deeper than
+            // the recorded max depth
+            let depth = triangle.depth as usize;
+            while breakdown.len() <= depth {
+                breakdown.push(empty(breakdown.len()));
+            }
+            let entry = &mut breakdown[depth];
+            match triangle.state {
+                TriangleState::Active | TriangleState::Genesis => entry.active_count += 1,
+                TriangleState::Subdivided => entry.subdivided_count += 1,
+                TriangleState::Void => entry.void_count += 1,
+                TriangleState::Inactive => {}
+            }
+            entry.total_area += triangle.area()?;
+        }
+
+        Ok(breakdown)
+    }
+
+    /// Move triangles selected by `policy` into the archive
+    pub fn prune(&mut self, policy: PrunePolicy) -> PruneReport {
+        let selected: Vec<Uuid> = self
+            .triangles
+            .values()
+            .filter(|triangle| match policy {
+                PrunePolicy::DropVoids => triangle.state == TriangleState::Void,
+                PrunePolicy::ArchiveSubdividedAbove(depth) => {
+                    triangle.state == TriangleState::Subdivided && triangle.depth < depth
+                }
+            })
+            .map(|triangle| triangle.id)
+            .collect();
+
+        let mut report = PruneReport::default();
+        for id in selected {
+            if let Some(triangle) = self.triangles.remove(&id) {
+                match triangle.state {
+                    TriangleState::Void => report.voids_archived += 1,
+                    _ => report.subdivided_archived += 1,
+                }
+                self.archived.insert(id, ArchivedTriangle::from(&triangle));
+            }
+        }
+        self.total_count = self.triangles.len();
+        self.rebuild_indexes();
+        report
+    }
+
+    /// Restore archived triangles from a full copy of the structure, e.g. one
+    /// reloaded from disk, returning how many were restored
+    pub fn unprune(&mut self, full: &FractalStructure) -> SierpinskiResult<usize> {
+        for (id, archived) in &self.archived {
+            let triangle = full.get_triangle(id).ok_or_else(|| {
+                SierpinskiError::validation(format!("Archived triangle {} missing from full structure", id))
+            })?;
+            if triangle.address != archived.address {
+                return Err(SierpinskiError::validation(format!(
+                    "Archived triangle {} is at {} but the full structure has it at {}",
+                    id, archived.address, triangle.address
+                )));
+            }
+        }
+
+        let restored = self.archived.len();
+        for (id, _) in self.archived.drain() {
+            if let Some(triangle) = full.get_triangle(&id) {
+                self.bounds.entry(id).or_insert_with(|| triangle.triangle.bounding_box());
+                self.triangles.insert(id, triangle.clone());
+            }
+        }
+        self.total_count = self.triangles.len();
+        self.rebuild_indexes();
+        Ok(restored)
+    }
+
+    /// Get an archived triangle by ID
+    pub fn archived_triangle(&self, id: &Uuid) -> Option<&ArchivedTriangle> {
+        self.archived.get(id)
+    }
+
+    /// Iterate over archived triangles, in no particular order
+    pub fn archived_triangles(&self) -> impl Iterator<Item = &ArchivedTriangle> {
+        self.archived.values()
+    }
+
+    /// Find a resident triangle by address
+    pub fn find_by_address(&self, address: &TriangleAddress) -> Option<&FractalTriangle> {
+        // The triangle last handed out for mutation may have moved since
+        // it was indexed
+        let indexed = self.by_address.get(address).into_iter();
+        self.last_mutated
+            .iter()
+            .chain(indexed)
+            .filter_map(|id| self.triangles.get(id))
+            .find(|triangle| &triangle.address == address)
+    }
+
+    /// Find an archived triangle by address
+    pub fn archived_by_address(&self, address: &TriangleAddress) -> Option<&ArchivedTriangle> {
+        self.archived.values().find(|archived| &archived.address == address)
+    }
+
+    /// Check whether the genesis triangle has been archived
+    pub fn is_genesis_archived(&self) -> bool {
+        self.genesis_id.is_some_and(|id| self.archived.contains_key(&id))
+    }
+
+    /// Number of triangles in the archive
+    pub fn archived_count(&self) -> usize {
+        self.archived.len()
+    }
+
+    /// Get the number of resident triangles; add `archived_count` for the
+    /// size of the whole fractal
     pub fn total_triangles(&self) -> usize {
         self.total_count
     }
@@ -260,8 +969,8 @@ impl FractalStructure {
     }
 
     /// Calculate total area of all active triangles
-    pub fn total_active_area(&self) -> SierpinskiResult<rust_decimal::Decimal> {
-        let mut total = rust_decimal::Decimal::ZERO;
+    pub fn total_active_area(&self) -> SierpinskiResult<Decimal> {
+        let mut total = Decimal::ZERO;
         
         for triangle in self.triangles.values() {
             if triangle.state == TriangleState::Active || triangle.state == TriangleState::Genesis {
@@ -272,6 +981,165 @@ impl FractalStructure {
         Ok(total)
     }
 
+    /// Approximate total area of all active triangles in f64, the fast path
+    /// for bulk statistics; see `total_active_area` for the exact value
+    pub fn approx_total_active_area_f64(&self) -> f64 {
+        self.triangles
+            .values()
+            .filter(|triangle| matches!(triangle.state, TriangleState::Active | TriangleState::Genesis))
+            .map(|triangle| triangle.triangle.area_f64())
+            .sum()
+    }
+
+    /// Address the genesis had in the structure this one was extracted
+    /// from, or None if it is not a subtree
+    pub fn subtree_root(&self) -> Option<&TriangleAddress> {
+        self.subtree_root.as_ref()
+    }
+
+    /// Translate an address in this structure to the address it has in the
+    /// structure this subtree was extracted from
+    pub fn original_address(&self, address: &TriangleAddress) -> SierpinskiResult<TriangleAddress> {
+        match &self.subtree_root {
+            Some(root) => TriangleAddress::new([root.components(), address.components()].concat()),
+            None => Ok(address.clone()),
+        }
+    }
+
+    /// Copy the triangle at `root` and everything below it into a standalone
+    /// structure whose genesis is that triangle
+    ///
+    /// Addresses and depths are made relative to `root`, which is recorded
+    /// as `subtree_root` so `original_address` can translate them back. Ids
+    /// are kept, and the root keeps its state. Fails if `root` is not
+    /// resident or part of its subtree has been pruned.
+    pub fn extract_subtree(&self, root: &TriangleAddress) -> SierpinskiResult<FractalStructure> {
+        let root_triangle = self
+            .find_by_address(root)
+            .ok_or_else(|| SierpinskiError::validation(format!("No resident triangle at {}", root)))?;
+        let offset = root_triangle.depth;
+
+        let mut subtree = FractalStructure::new();
+        subtree.max_depth_limit = self.max_depth_limit.saturating_sub(offset);
+        subtree.subtree_root = Some(self.original_address(root)?);
+        subtree.genesis_id = Some(root_triangle.id);
+
+        let mut pending = vec![root_triangle.id];
+        while let Some(id) = pending.pop() {
+            let triangle = self.triangles.get(&id).ok_or_else(|| {
+                SierpinskiError::validation(format!("Triangle {} below {} has been pruned", id, root))
+            })?;
+            pending.extend(&triangle.child_ids);
+
+            let mut copy = triangle.clone();
+            copy.address = TriangleAddress::new(triangle.address.components()[offset as usize..].to_vec())?;
+            copy.depth -= offset;
+            if id == root_triangle.id {
+                copy.parent_id = None;
+            }
+
+            subtree.max_depth = subtree.max_depth.max(copy.depth);
+            subtree.bounds.insert(id, copy.triangle.bounding_box());
+            subtree.triangles.insert(id, copy);
+        }
+        subtree.total_count = subtree.triangles.len();
+        subtree.rebuild_indexes();
+
+        Ok(subtree)
+    }
+
+    /// Replace the triangle at `at` and everything below it with a subtree,
+    /// such as one from `extract_subtree`
+    ///
+    /// The subtree's root must have the same corners as the triangle at
+    /// `at`, within `GRAFT_TOLERANCE`, and must fit under the depth limit.
+    /// The root takes over the target's id and parent; other subtree ids are
+    /// kept unless already used here, in which case they are renumbered.
+    pub fn graft_subtree(&mut self, at: &TriangleAddress, subtree: FractalStructure) -> SierpinskiResult<()> {
+        let target = self
+            .find_by_address(at)
+            .ok_or_else(|| SierpinskiError::validation(format!("No resident triangle at {}", at)))?;
+        let root = subtree
+            .genesis()
+            .ok_or_else(|| SierpinskiError::validation("Subtree has no root triangle"))?;
+        if subtree.archived_count() > 0 {
+            return Err(SierpinskiError::validation("Cannot graft a pruned subtree"));
+        }
+        if !corners_match(&target.triangle, &root.triangle, GRAFT_TOLERANCE) {
+            return Err(SierpinskiError::validation(format!(
+                "Subtree root does not match the triangle at {}",
+                at
+            )));
+        }
+        let offset = target.depth;
+        if offset as u16 + subtree.max_depth as u16 > self.max_depth_limit as u16 {
+            return Err(SierpinskiError::MaxDepthExceeded {
+                max_depth: self.max_depth_limit,
+            });
+        }
+
+        // Everything below the target makes way for the subtree
+        let mut replaced = HashSet::new();
+        let mut pending = target.child_ids.clone();
+        while let Some(id) = pending.pop() {
+            if let Some(triangle) = self.triangles.get(&id) {
+                pending.extend(&triangle.child_ids);
+            } else if let Some(archived) = self.archived.get(&id) {
+                pending.extend(&archived.child_ids);
+            }
+            replaced.insert(id);
+        }
+
+        let (target_id, target_parent, target_triangle) = (target.id, target.parent_id, target.triangle.clone());
+        let root_id = root.id;
+        let ids: HashMap<Uuid, Uuid> = subtree
+            .triangles
+            .keys()
+            .map(|&id| {
+                let taken = (self.triangles.contains_key(&id) || self.archived.contains_key(&id))
+                    && !replaced.contains(&id);
+                let mapped = if id == root_id {
+                    target_id
+                } else if taken {
+                    Uuid::new_v4()
+                } else {
+                    id
+                };
+                (id, mapped)
+            })
+            .collect();
+
+        let mut grafted = Vec::with_capacity(subtree.triangles.len());
+        for (id, mut triangle) in subtree.triangles {
+            triangle.id = ids[&id];
+            triangle.address = TriangleAddress::new([at.components(), triangle.address.components()].concat())?;
+            triangle.depth += offset;
+            triangle.child_ids = triangle.child_ids.iter().filter_map(|child| ids.get(child).copied()).collect();
+            if id == root_id {
+                triangle.parent_id = target_parent;
+                triangle.triangle = target_triangle.clone();
+            } else {
+                triangle.parent_id = triangle.parent_id.and_then(|parent| ids.get(&parent).copied());
+            }
+            grafted.push(triangle);
+        }
+
+        for id in replaced {
+            self.triangles.remove(&id);
+            self.archived.remove(&id);
+            self.bounds.remove(&id);
+        }
+        for triangle in grafted {
+            self.max_depth = self.max_depth.max(triangle.depth);
+            self.bounds.insert(triangle.id, triangle.triangle.bounding_box());
+            self.triangles.insert(triangle.id, triangle);
+        }
+        self.total_count = self.triangles.len();
+        self.rebuild_indexes();
+
+        Ok(())
+    }
+
     /// Get children of a triangle
     pub fn children(&self, parent_id: &Uuid) -> Vec<&FractalTriangle> {
         if let Some(parent) = self.triangles.get(parent_id) {
@@ -286,6 +1154,115 @@ impl FractalStructure {
     }
 }
 
+/// Whether two triangles have the same corners, in any order, to within
+/// `tolerance` on each coordinate
+fn corners_match(a: &Triangle, b: &Triangle, tolerance: Decimal) -> bool {
+    let (a, b) = (a.canonical(), b.canonical());
+    a.vertices()
+        .iter()
+        .zip(b.vertices())
+        .all(|(p, q)| (p.x - q.x).abs() <= tolerance && (p.y - q.y).abs() <= tolerance)
+}
+
+/// Id of the triangle at each address
+fn address_index(triangles: &HashMap<Uuid, FractalTriangle>) -> HashMap<TriangleAddress, Uuid> {
+    triangles.values().map(|triangle| (triangle.address.clone(), triangle.id)).collect()
+}
+
+/// Whether a triangle belongs on the frontier: subdividable by state and not
+/// yet subdivided
+fn is_leaf(triangle: &FractalTriangle) -> bool {
+    triangle.state.can_subdivide() && !triangle.has_children()
+}
+
+/// Serde for the triangle maps of `FractalStructure`: an array ordered by
+/// depth, then address, then id; a map keyed by id is still accepted
+mod triangle_array {
+    use super::*;
+    use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+    use serde::ser::{SerializeSeq, Serializer};
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    /// A triangle that can be stored in the array
+    pub(super) trait Entry {
+        fn id(&self) -> Uuid;
+        fn sort_key(&self) -> (u8, &[u8], Uuid);
+    }
+
+    impl Entry for FractalTriangle {
+        fn id(&self) -> Uuid {
+            self.id
+        }
+
+        fn sort_key(&self) -> (u8, &[u8], Uuid) {
+            (self.depth, self.address.components(), self.id)
+        }
+    }
+
+    impl Entry for ArchivedTriangle {
+        fn id(&self) -> Uuid {
+            self.id
+        }
+
+        fn sort_key(&self) -> (u8, &[u8], Uuid) {
+            (self.depth, self.address.components(), self.id)
+        }
+    }
+
+    pub fn serialize<S, T>(map: &HashMap<Uuid, T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Entry + Serialize,
+    {
+        let mut entries: Vec<&T> = map.values().collect();
+        entries.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        let mut seq = serializer.serialize_seq(Some(entries.len()))?;
+        for entry in entries {
+            seq.serialize_element(entry)?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<HashMap<Uuid, T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Entry + Deserialize<'de>,
+    {
+        deserializer.deserialize_any(EntriesVisitor(PhantomData))
+    }
+
+    struct EntriesVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Entry + Deserialize<'de>> Visitor<'de> for EntriesVisitor<T> {
+        type Value = HashMap<Uuid, T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("an array of triangles or a map of id to triangle")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut map = HashMap::new();
+            while let Some(entry) = seq.next_element::<T>()? {
+                map.insert(entry.id(), entry);
+            }
+            Ok(map)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+            let mut map = HashMap::new();
+            while let Some((id, entry)) = access.next_entry::<Uuid, T>()? {
+                map.insert(id, entry);
+            }
+            Ok(map)
+        }
+    }
+}
+
+fn default_max_depth_limit() -> u8 {
+    crate::MAX_SUBDIVISION_DEPTH
+}
+
 impl Default for FractalStructure {
     fn default() -> Self {
         Self::new()
@@ -344,16 +1321,445 @@ mod tests {
         assert_eq!(structure.genesis().unwrap().id, genesis_id);
     }
 
+    #[test]
+    fn test_rebuild_relationships_links_child_added_first() {
+        let parent = FractalTriangle::genesis(create_test_triangle());
+        let [a, b, c] = *parent.triangle.vertices();
+        let first = FractalTriangle::child(Triangle::new(a, a.midpoint(&b), c.midpoint(&a)).unwrap(), &parent, 0).unwrap();
+        let second = FractalTriangle::child(Triangle::new(a.midpoint(&b), b, b.midpoint(&c)).unwrap(), &parent, 1).unwrap();
+        let grandchild = FractalTriangle::child(create_test_triangle(), &second, 2).unwrap();
+
+        let mut structure = FractalStructure::new();
+        structure.add_triangle(second.clone()).unwrap();
+        structure.add_triangle(first.clone()).unwrap();
+        structure.set_genesis(parent.clone()).unwrap();
+        structure.add_triangle(grandchild.clone()).unwrap();
+        assert!(structure.get_triangle(&parent.id).unwrap().child_ids.is_empty());
+
+        let result = structure.rebuild_relationships();
+        assert!(result.is_valid, "{:?}", result.errors);
+        assert_eq!(structure.get_triangle(&parent.id).unwrap().child_ids, vec![first.id, second.id]);
+        assert_eq!(structure.get_triangle(&second.id).unwrap().child_ids, vec![grandchild.id]);
+        assert_eq!(structure.max_depth(), 2);
+        assert_eq!(structure.total_triangles(), 4);
+
+        // A triangle whose parent never arrives is reported
+        let stray = FractalTriangle::child(create_test_triangle(), &grandchild, 0).unwrap();
+        let mut partial = FractalStructure::new();
+        partial.add_triangle(stray.clone()).unwrap();
+        let result = partial.rebuild_relationships();
+        assert!(!result.is_valid);
+        assert!(result.errors[0].contains(&stray.id.to_string()));
+    }
+
     #[test]
     fn test_state_transitions() {
         let triangle = create_test_triangle();
         let mut fractal_triangle = FractalTriangle::genesis(triangle);
         
         // Genesis can transition to Subdivided
-        assert!(fractal_triangle.change_state(TriangleState::Subdivided).is_ok());
+        assert!(fractal_triangle.change_state_default(TriangleState::Subdivided).is_ok());
         assert_eq!(fractal_triangle.state, TriangleState::Subdivided);
         
         // Subdivided cannot transition back
-        assert!(fractal_triangle.change_state(TriangleState::Active).is_err());
+        assert!(fractal_triangle.change_state_default(TriangleState::Active).is_err());
+        assert_eq!(fractal_triangle.history.len(), 1);
+    }
+
+    #[test]
+    fn test_approx_total_active_area_matches_decimal() {
+        use rust_decimal::prelude::ToPrimitive;
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_to_depth};
+
+        let structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 4).unwrap();
+        let exact = structure.total_active_area().unwrap().to_f64().unwrap();
+        assert!((structure.approx_total_active_area_f64() - exact).abs() <= 1e-9 * exact);
+    }
+
+    #[test]
+    fn test_depth_breakdown() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_to_depth};
+
+        let structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 2).unwrap();
+        let breakdown = structure.depth_breakdown().unwrap();
+        assert_eq!(breakdown.len(), 3);
+
+        let counts: Vec<_> = breakdown
+            .iter()
+            .map(|d| (d.depth, d.active_count, d.subdivided_count, d.void_count))
+            .collect();
+        assert_eq!(counts, vec![(0, 0, 1, 0), (1, 0, 3, 1), (2, 9, 0, 3)]);
+
+        // Depth 1 tiles the genesis; depth 2 leaves the depth-1 void uncovered
+        let genesis_area = structure.genesis().unwrap().area().unwrap();
+        let tolerance = Decimal::new(1, 20);
+        assert_eq!(breakdown[0].total_area, genesis_area);
+        assert!((breakdown[1].total_area - genesis_area).abs() < tolerance);
+        assert!((breakdown[2].total_area - genesis_area * Decimal::new(75, 2)).abs() < tolerance);
+    }
+
+    #[test]
+    fn test_state_history() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_triangle};
+
+        let genesis = genesis_fractal_triangle().unwrap();
+        let result = subdivide_triangle(&genesis).unwrap();
+        let parent = &result.parent;
+
+        assert_eq!(parent.state_history().len(), 1);
+        let transition = &parent.state_history()[0];
+        assert_eq!(transition.from, TriangleState::Genesis);
+        assert_eq!(transition.to, TriangleState::Subdivided);
+        assert_eq!(transition.reason, "subdivided");
+        assert!(transition.timestamp >= parent.created_at);
+        assert_eq!(parent.updated_at, transition.timestamp);
+
+        // Timestamps never go backwards as a triangle keeps changing state
+        let mut child = result.children[0].clone();
+        child.change_state(TriangleState::Inactive, "owner paused").unwrap();
+        child.change_state(TriangleState::Active, "owner resumed").unwrap();
+        child.change_state(TriangleState::Subdivided, "mined").unwrap();
+        assert!(child.history.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        assert_eq!(child.history.iter().map(|t| t.reason.as_str()).collect::<Vec<_>>(), ["owner paused", "owner resumed", "mined"]);
+
+        // The void starts out Void, with no transition to record
+        assert_eq!(result.void_triangle.state, TriangleState::Void);
+        assert!(result.void_triangle.state_history().is_empty());
+
+        assert_eq!(parent.state_at(parent.created_at.saturating_sub(1)), None);
+        assert_eq!(parent.state_at(transition.timestamp), Some(TriangleState::Subdivided));
+
+        let json = serde_json::to_string(parent).unwrap();
+        let restored: FractalTriangle = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.history, parent.history);
+    }
+
+    #[test]
+    fn test_structure_bounding_box() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_to_depth};
+
+        assert!(FractalStructure::new().bounding_box().is_none());
+
+        let genesis = genesis_fractal_triangle().unwrap();
+        let genesis_bounds = genesis.triangle.bounding_box();
+        let structure = subdivide_to_depth(genesis, 1).unwrap();
+
+        // The children tile the genesis triangle, so the union is its box
+        let bounds = structure.bounding_box().unwrap();
+        assert_eq!(bounds, genesis_bounds);
+        for triangle in structure.triangles_at_depth(1) {
+            let child_bounds = triangle.triangle.bounding_box();
+            assert!(bounds.contains(&child_bounds.min) && bounds.contains(&child_bounds.max));
+        }
+    }
+
+    #[test]
+    fn test_triangles_in_region() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_to_depth};
+        use Decimal;
+
+        let genesis = genesis_fractal_triangle().unwrap();
+        let genesis_id = genesis.id;
+        let structure = subdivide_to_depth(genesis, 2).unwrap();
+
+        let children = structure.children(&genesis_id);
+        let left = children[0];
+        let right = children[1];
+
+        // Just inside the left child's box, clear of the right and top children
+        let inset = Decimal::new(1, 3);
+        let left_bounds = left.triangle.bounding_box();
+        let region = BoundingBox {
+            min: Point::new(left_bounds.min.x + inset, left_bounds.min.y + inset),
+            max: Point::new(left_bounds.max.x - inset, left_bounds.max.y - inset),
+        };
+
+        let found: Vec<Uuid> = structure.triangles_in_region(&region).iter().map(|t| t.id).collect();
+        assert!(found.contains(&genesis_id));
+        assert!(found.contains(&left.id));
+        for grandchild in structure.children(&left.id) {
+            assert!(found.contains(&grandchild.id));
+        }
+
+        assert!(!found.contains(&right.id));
+        for grandchild in structure.children(&right.id) {
+            assert!(!found.contains(&grandchild.id));
+        }
+
+        let far_away = BoundingBox {
+            min: Point::from_f64(10.0, 10.0).unwrap(),
+            max: Point::from_f64(11.0, 11.0).unwrap(),
+        };
+        assert!(structure.triangles_in_region(&far_away).is_empty());
+    }
+
+    #[test]
+    fn test_leaves_and_frontier() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::{subdivide_and_add_to_structure, subdivide_to_depth}};
+
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 3).unwrap();
+        assert_eq!(structure.leaves().len(), 27);
+        assert_eq!(structure.frontier_at_depth(3).len(), 27);
+        assert!(structure.frontier_at_depth(2).is_empty());
+        assert_eq!(structure.subdividable().len(), 27);
+
+        let leaf = structure.frontier_at_depth(3)[0].id;
+        subdivide_and_add_to_structure(&mut structure, &leaf).unwrap();
+        assert_eq!(structure.leaves().len(), 29);
+        assert_eq!(structure.frontier_at_depth(3).len(), 26);
+        assert_eq!(structure.frontier_at_depth(4).len(), 3);
+        assert!(structure.frontier_at_depth(3).iter().all(|t| t.id != leaf));
+
+        // Changes through get_triangle_mut show up straight away
+        let retired = structure.frontier_at_depth(4)[0].id;
+        structure.get_triangle_mut(&retired).unwrap().change_state_default(TriangleState::Inactive).unwrap();
+        assert_eq!(structure.frontier_at_depth(4).len(), 2);
+        structure.set_max_depth_limit(4).unwrap();
+        assert_eq!(structure.subdividable().len(), 26);
+
+        // A deserialized structure answers the same before and after reindexing
+        let mut restored: FractalStructure = serde_json::from_str(&serde_json::to_string(&structure).unwrap()).unwrap();
+        assert_eq!(restored.leaves().len(), 28);
+        restored.get_triangle_mut(&leaf).unwrap();
+        assert_eq!(restored.leaves().len(), 28);
+        assert_eq!(restored.frontier_at_depth(4).len(), 2);
+    }
+
+    #[test]
+    fn test_loaded_counts_come_from_the_triangles() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::{subdivide_to_depth, SubdivisionStats}};
+
+        let structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 2).unwrap();
+        let mut json = serde_json::to_value(&structure).unwrap();
+        json["max_depth"] = 0.into();
+        json["total_count"] = 1.into();
+
+        let mut loaded = FractalStructure::read_json(json.to_string().as_bytes()).unwrap();
+        assert_eq!(loaded.max_depth(), 2);
+        assert_eq!(loaded.total_triangles(), structure.total_triangles());
+        assert_eq!(loaded.depth_breakdown().unwrap(), structure.depth_breakdown().unwrap());
+
+        // A triangle moved deeper behind the structure's back gets a row too
+        let leaf = loaded.leaves()[0].id;
+        loaded.get_triangle_mut(&leaf).unwrap().depth = 4;
+        assert_eq!(loaded.depth_breakdown().unwrap().len(), 5);
+        assert_eq!(SubdivisionStats::per_depth(&loaded).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_find_by_address_follows_changes() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_to_depth};
+
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 2).unwrap();
+        let from = TriangleAddress::new(vec![0, 1]).unwrap();
+        let to = TriangleAddress::new(vec![3, 3]).unwrap();
+        let id = structure.find_by_address(&from).unwrap().id;
+
+        // A triangle moved through get_triangle_mut is found at its new
+        // address only
+        structure.get_triangle_mut(&id).unwrap().address = to.clone();
+        assert!(structure.find_by_address(&from).is_none());
+        assert_eq!(structure.find_by_address(&to).unwrap().id, id);
+
+        structure.remove_triangle(&id).unwrap();
+        assert!(structure.find_by_address(&to).is_none());
+
+        let restored: FractalStructure = serde_json::from_str(&serde_json::to_string(&structure).unwrap()).unwrap();
+        let address = TriangleAddress::new(vec![2, 2]).unwrap();
+        assert_eq!(restored.find_by_address(&address).unwrap().id, structure.find_by_address(&address).unwrap().id);
+    }
+
+    #[test]
+    fn test_incremental_state_root_matches_recomputation() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_and_add_to_structure};
+
+        let mut structure = FractalStructure::new();
+        structure.set_genesis(genesis_fractal_triangle().unwrap()).unwrap();
+        // Each subdivision adds four triangles and changes its parent's state
+        while structure.total_triangles() < 101 {
+            let leaf = structure.leaves()[0].id;
+            subdivide_and_add_to_structure(&mut structure, &leaf).unwrap();
+            let recomputed = StateTree::from_triangles(structure.triangles()).root();
+            assert_eq!(structure.state_root(), recomputed);
+        }
+
+        // A pending change through get_triangle_mut counts straight away
+        let before = structure.state_root();
+        let leaf = structure.leaves()[0].id;
+        structure.get_triangle_mut(&leaf).unwrap().change_state_default(TriangleState::Inactive).unwrap();
+        assert_ne!(structure.state_root(), before);
+        assert_eq!(structure.state_root(), StateTree::from_triangles(structure.triangles()).root());
+
+        let removed = structure.remove_triangle(&leaf).unwrap();
+        assert!(structure.get_triangle(&removed.id).is_none());
+        assert_eq!(structure.state_root(), StateTree::from_triangles(structure.triangles()).root());
+        let restored: FractalStructure = serde_json::from_str(&serde_json::to_string(&structure).unwrap()).unwrap();
+        assert_eq!(restored.state_root(), structure.state_root());
+    }
+
+    #[test]
+    fn test_streamed_json_round_trip() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_to_depth};
+
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 3).unwrap();
+        structure.prune(PrunePolicy::DropVoids);
+
+        let mut written = Vec::new();
+        structure.write_json(&mut written, false).unwrap();
+        let restored = FractalStructure::read_json(written.as_slice()).unwrap();
+        assert_eq!(restored.total_triangles(), structure.total_triangles());
+        assert_eq!(restored.archived_count(), structure.archived_count());
+        assert_eq!(restored.state_root(), structure.state_root());
+        for triangle in structure.triangles() {
+            assert_eq!(restored.get_triangle(&triangle.id), Some(triangle));
+        }
+
+        // Same structure, same bytes, whatever the map iteration order
+        let mut rewritten = Vec::new();
+        restored.write_json(&mut rewritten, false).unwrap();
+        assert_eq!(rewritten, written);
+        let mut pretty = Vec::new();
+        structure.write_json(&mut pretty, true).unwrap();
+        let mut pretty_again = Vec::new();
+        structure.clone().write_json(&mut pretty_again, true).unwrap();
+        assert_eq!(pretty, pretty_again);
+
+        // Triangles come out by depth, and the old map layout still loads
+        let mut value: serde_json::Value = serde_json::from_slice(&written).unwrap();
+        let depths: Vec<u64> = value["triangles"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|triangle| triangle["depth"].as_u64().unwrap())
+            .collect();
+        assert!(depths.windows(2).all(|pair| pair[0] <= pair[1]));
+        let legacy: serde_json::Map<String, serde_json::Value> = value["triangles"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|triangle| (triangle["id"].as_str().unwrap().to_string(), triangle.clone()))
+            .collect();
+        value["triangles"] = serde_json::Value::Object(legacy);
+        let legacy = FractalStructure::read_json(value.to_string().as_bytes()).unwrap();
+        assert_eq!(legacy.state_root(), structure.state_root());
+    }
+
+    #[test]
+    fn test_prune_and_unprune() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::{subdivide_to_depth, total_triangles_to_depth}};
+
+        let full = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 8).unwrap();
+        let mut structure = full.clone();
+        // Every triangle above depth 8 was subdivided, each leaving one void
+        let subdivided = total_triangles_to_depth(7) as usize;
+
+        let report = structure.prune(PrunePolicy::DropVoids);
+        assert_eq!(report, PruneReport { voids_archived: subdivided, subdivided_archived: 0 });
+
+        // Depths 0 to 3
+        let report = structure.prune(PrunePolicy::ArchiveSubdividedAbove(4));
+        assert_eq!(report.subdivided_archived, total_triangles_to_depth(3) as usize);
+
+        assert_eq!(structure.total_triangles() + structure.archived_count(), full.total_triangles());
+        assert_eq!(structure.max_depth(), 8);
+        assert!(structure.genesis().is_none());
+        assert!(structure.is_genesis_archived());
+        let archived = structure.archived_by_address(&TriangleAddress::from_string_representation("0.1").unwrap());
+        assert_eq!(archived.unwrap().child_ids.len(), 4);
+
+        let archived_count = structure.archived_count();
+        assert_eq!(structure.unprune(&full).unwrap(), archived_count);
+        assert_eq!(structure.total_triangles(), full.total_triangles());
+        assert_eq!(structure.archived_count(), 0);
+        assert!(structure.genesis().is_some());
+    }
+
+    #[test]
+    fn test_query_region_prunes_subtrees() {
+        use crate::core::{genesis::genesis_fractal_triangle, subdivision::subdivide_to_depth};
+
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 8).unwrap();
+        let sorted_ids = |triangles: Vec<&FractalTriangle>| {
+            let mut ids: Vec<Uuid> = triangles.iter().map(|t| t.id).collect();
+            ids.sort();
+            ids
+        };
+
+        // A small window near the bottom-left corner of the genesis
+        let min = Point::from_f64(-0.45, -0.29).unwrap();
+        let max = Point::from_f64(-0.4, -0.25).unwrap();
+        let region = BoundingBox { min, max };
+
+        let mut visited = 0;
+        let found = structure.descend(|bounds| bounds.intersects(&region), &mut visited);
+        assert!(!found.is_empty());
+        assert!(visited * 10 < structure.total_triangles(), "visited {} nodes", visited);
+        assert_eq!(sorted_ids(structure.query_region(min, max)), sorted_ids(structure.triangles_in_region(&region)));
+
+        let point = Point::from_f64(-0.42, -0.27).unwrap();
+        let brute_force: Vec<&FractalTriangle> = structure
+            .triangles()
+            .filter(|t| t.triangle.contains_point(&point))
+            .collect();
+        assert!(!brute_force.is_empty());
+        assert_eq!(sorted_ids(structure.query_point(&point)), sorted_ids(brute_force));
+
+        // Archived parents are descended through
+        structure.prune(PrunePolicy::ArchiveSubdividedAbove(8));
+        assert_eq!(sorted_ids(structure.query_region(min, max)), sorted_ids(structure.triangles_in_region(&region)));
+    }
+
+    #[test]
+    fn test_extract_and_graft_subtree() {
+        use crate::core::{
+            genesis::genesis_fractal_triangle,
+            subdivision::subdivide_to_depth,
+            validation::validate_fractal_structure,
+        };
+
+        let addresses = |structure: &FractalStructure| {
+            let mut addresses: Vec<Vec<u8>> = structure
+                .triangles()
+                .map(|triangle| structure.original_address(&triangle.address).unwrap().components().to_vec())
+                .collect();
+            addresses.sort();
+            addresses
+        };
+        let original = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 4).unwrap();
+        let root = TriangleAddress::from_string_representation("0.1").unwrap();
+
+        let subtree = original.extract_subtree(&root).unwrap();
+        assert_eq!(subtree.total_triangles(), 17);
+        assert_eq!(subtree.max_depth(), 2);
+        assert_eq!(subtree.subtree_root(), Some(&root));
+        assert!(subtree.genesis().unwrap().address.is_genesis());
+        let below_root: Vec<Vec<u8>> = addresses(&original)
+            .into_iter()
+            .filter(|components| components.starts_with(&[0, 1]))
+            .collect();
+        assert_eq!(addresses(&subtree), below_root);
+
+        let json = serde_json::to_string(&subtree).unwrap();
+        let subtree: FractalStructure = serde_json::from_str(&json).unwrap();
+
+        // A fresh copy, with its own ids, takes it in place of its own 0.1
+        let mut copy = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 4).unwrap();
+        copy.graft_subtree(&root, subtree.clone()).unwrap();
+        assert_eq!(copy.total_triangles(), original.total_triangles());
+        assert_eq!(addresses(&copy), addresses(&original));
+        assert!(validate_fractal_structure(&copy).is_valid);
+
+        // A shallower copy gains the levels the subtree carries
+        let mut shallow = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 2).unwrap();
+        let before = shallow.total_triangles();
+        shallow.graft_subtree(&root, subtree.clone()).unwrap();
+        assert_eq!(shallow.total_triangles(), before + 16);
+        assert_eq!(shallow.max_depth(), 4);
+        assert!(validate_fractal_structure(&shallow).is_valid);
+
+        // Only the matching triangle can take it
+        let elsewhere = TriangleAddress::from_string_representation("0.2").unwrap();
+        assert!(copy.graft_subtree(&elsewhere, subtree).is_err());
+        assert_eq!(addresses(&copy), addresses(&original));
     }
 }
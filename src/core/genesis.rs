@@ -1,20 +1,32 @@
 //! Genesis triangle creation and management
 
 use rust_decimal::Decimal;
+use std::sync::LazyLock;
 use crate::core::{
-    geometry::Point,
+    geometry::{decimal_sqrt, Point},
     triangle::Triangle,
     fractal::FractalTriangle,
-    errors::SierpinskiResult,
+    errors::{SierpinskiError, SierpinskiResult},
 };
 
+/// Smallest triangle area that can still be constructed: anything smaller is
+/// rejected as collinear (half of `COLLINEARITY_THRESHOLD`, which bounds the
+/// doubled area)
+pub const MIN_TRIANGLE_AREA: Decimal = Decimal::from_parts(5, 0, 0, false, 11);
+
+/// Height of an equilateral triangle with unit sides, sqrt(3)/2, computed
+/// once at full `DECIMAL_PRECISION`
+pub static GENESIS_HEIGHT_FACTOR: LazyLock<Decimal> = LazyLock::new(|| {
+    decimal_sqrt(Decimal::from(3)).expect("square root of a positive constant") / Decimal::from(2)
+});
+
 /// Create the perfect equilateral genesis triangle
 pub fn genesis_triangle() -> SierpinskiResult<Triangle> {
     // Create a perfect equilateral triangle with side length 1
     // Centered at origin with one vertex pointing up
     
     let side_length = Decimal::ONE;
-    let height = side_length * Decimal::new(866, 3); // sqrt(3)/2 ≈ 0.866
+    let height = side_length * *GENESIS_HEIGHT_FACTOR;
     let half_side = side_length / Decimal::from(2);
     
     let bottom_left = Point::new(-half_side, -height / Decimal::from(3));
@@ -35,7 +47,7 @@ pub fn genesis_triangle_with_size(
     center: Point,
     side_length: Decimal,
 ) -> SierpinskiResult<Triangle> {
-    let height = side_length * Decimal::new(866, 3); // sqrt(3)/2
+    let height = side_length * *GENESIS_HEIGHT_FACTOR;
     let half_side = side_length / Decimal::from(2);
     let third_height = height / Decimal::from(3);
     
@@ -65,9 +77,11 @@ pub fn genesis_triangle_bounded(
     let width = max_x - min_x;
     let height = max_y - min_y;
     
-    // Calculate the maximum side length that fits
+    // Calculate the maximum side length that fits; the apex sits two thirds
+    // of the triangle's height above the centered centroid, so that height
+    // may fill only three quarters of the bounds
     let max_side_from_width = width;
-    let max_side_from_height = height * Decimal::from(2) / Decimal::new(866, 3); // height / (sqrt(3)/2)
+    let max_side_from_height = height * Decimal::from(3) / Decimal::from(4) / *GENESIS_HEIGHT_FACTOR;
     
     let side_length = if max_side_from_width < max_side_from_height {
         max_side_from_width
@@ -83,6 +97,51 @@ pub fn genesis_triangle_bounded(
     genesis_triangle_with_size(center, side_length)
 }
 
+/// Lay out `rows * cols` genesis triangles on a grid, row by row
+///
+/// Each triangle has side length `cell_size` and is centered in its cell;
+/// neighbouring cells are `spacing` apart.
+pub fn genesis_grid(
+    rows: u32,
+    cols: u32,
+    cell_size: Decimal,
+    spacing: Decimal,
+) -> SierpinskiResult<Vec<Triangle>> {
+    if cell_size <= Decimal::ZERO {
+        return Err(SierpinskiError::invalid_triangle(format!(
+            "Grid cell size must be positive, got {}",
+            cell_size
+        )));
+    }
+    if spacing < Decimal::ZERO {
+        return Err(SierpinskiError::validation(format!(
+            "Grid spacing must not be negative, got {}",
+            spacing
+        )));
+    }
+
+    let pitch = cell_size + spacing;
+    let mut triangles = Vec::with_capacity(rows as usize * cols as usize);
+
+    for row in 0..rows {
+        for col in 0..cols {
+            let center = Point::new(Decimal::from(col) * pitch, Decimal::from(row) * pitch);
+            let triangle = genesis_triangle_with_size(center, cell_size)?;
+
+            if !validate_genesis_triangle(&triangle)? {
+                return Err(SierpinskiError::invalid_triangle(format!(
+                    "Grid cell ({}, {}) does not produce a valid genesis triangle",
+                    row, col
+                )));
+            }
+
+            triangles.push(triangle);
+        }
+    }
+
+    Ok(triangles)
+}
+
 /// Validate that a triangle is suitable as a genesis triangle
 pub fn validate_genesis_triangle(triangle: &Triangle) -> SierpinskiResult<bool> {
     // Check if triangle is equilateral
@@ -111,16 +170,15 @@ pub fn validate_genesis_triangle(triangle: &Triangle) -> SierpinskiResult<bool>
 pub fn max_theoretical_depth(triangle: &Triangle) -> SierpinskiResult<u8> {
     let area = triangle.area()?;
     
-    // Each subdivision reduces area by factor of 3/4
-    // We stop when area becomes smaller than minimum representable decimal
-    let min_area = Decimal::new(1, 28); // Smallest representable area
-    let reduction_factor = Decimal::new(3, 0) / Decimal::new(4, 0);
+    // Each child covers a quarter of its parent's area
+    // We stop when a child would be too small to construct
+    let reduction_factor = Decimal::new(4, 0);
     
     let mut current_area = area;
     let mut depth = 0u8;
     
-    while current_area > min_area && depth < crate::MAX_SUBDIVISION_DEPTH {
-        current_area *= reduction_factor;
+    while current_area / reduction_factor >= MIN_TRIANGLE_AREA && depth < crate::MAX_SUBDIVISION_DEPTH {
+        current_area /= reduction_factor;
         depth += 1;
     }
     
@@ -171,6 +229,32 @@ mod tests {
         assert!(triangle.area().unwrap() > Decimal::ZERO);
     }
 
+    #[test]
+    fn test_genesis_sides_equal_at_full_precision() {
+        let tolerance = Decimal::new(1, 20);
+        let sized = genesis_triangle_with_size(Point::new(Decimal::ONE, Decimal::ONE), Decimal::from(3)).unwrap();
+
+        for triangle in [genesis_triangle().unwrap(), sized] {
+            let [a, b, c] = triangle.side_lengths().unwrap();
+            assert!((a - b).abs() < tolerance, "{} vs {}", a, b);
+            assert!((b - c).abs() < tolerance, "{} vs {}", b, c);
+            assert!((c - a).abs() < tolerance, "{} vs {}", c, a);
+        }
+    }
+
+    #[test]
+    fn test_deep_genesis_child_sides_agree_absolutely() {
+        // A wide genesis keeps the depth-15 child large; with sqrt(3)/2 at
+        // full precision its sides still agree to an absolute 1e-10
+        let side = Decimal::from(1u64 << 40);
+        let mut triangle = genesis_triangle_with_size(Point::new(Decimal::ZERO, Decimal::ZERO), side).unwrap();
+        for _ in 0..15 {
+            let [m12, _, m31] = triangle.side_midpoints();
+            triangle = Triangle::new(triangle.vertices()[0], m12, m31).unwrap();
+        }
+        assert!(triangle.is_equilateral_with_tolerance(Decimal::new(1, 10)).unwrap());
+    }
+
     #[test]
     fn test_genesis_validation() {
         let triangle = genesis_triangle().unwrap();
@@ -208,6 +292,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bounded_genesis_fits_short_bounds() {
+        let (min_x, max_x) = (Decimal::from(-100), Decimal::from(100));
+        let (min_y, max_y) = (Decimal::ZERO, Decimal::ONE);
+
+        let triangle = genesis_triangle_bounded(min_x, max_x, min_y, max_y).unwrap();
+        for vertex in triangle.vertices() {
+            assert!(vertex.x >= min_x && vertex.x <= max_x);
+            assert!(vertex.y >= min_y && vertex.y <= max_y, "{} outside the bounds", vertex.y);
+        }
+    }
+
     #[test]
     fn test_genesis_properties() {
         let triangle = genesis_triangle().unwrap();
@@ -218,4 +314,21 @@ mod tests {
         assert!(properties.perimeter > Decimal::ZERO);
         assert!(properties.max_depth > 0);
     }
+
+    #[test]
+    fn test_genesis_grid() {
+        let triangles = genesis_grid(3, 3, Decimal::from(2), Decimal::new(5, 1)).unwrap();
+        assert_eq!(triangles.len(), 9);
+
+        for (i, triangle) in triangles.iter().enumerate() {
+            assert!(triangle.is_equilateral().unwrap());
+
+            for other in &triangles[i + 1..] {
+                assert_ne!(triangle.centroid(), other.centroid());
+                assert!(!triangle.intersects(other));
+            }
+        }
+
+        assert!(genesis_grid(2, 2, Decimal::ZERO, Decimal::ONE).is_err());
+    }
 }
@@ -2,9 +2,54 @@
 
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
 
 use crate::core::errors::{SierpinskiError, SierpinskiResult};
+use crate::core::trig::{decimal_acos, decimal_sin_cos};
+
+/// Points whose doubled triangle area falls below this are treated as collinear
+pub const COLLINEARITY_THRESHOLD: Decimal = Decimal::from_parts(1, 0, 0, false, 10);
+
+/// Tolerances for geometric comparisons
+///
+/// `collinearity` is absolute, like `COLLINEARITY_THRESHOLD`. The others are
+/// relative to the size of the triangles compared, so a check behaves the
+/// same at every subdivision depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeometricTolerances {
+    /// Doubled triangle area below which three points count as collinear
+    pub collinearity: Decimal,
+    /// Largest difference between sides counted as equal, relative to the longest side
+    pub side_equality: Decimal,
+    /// Largest mismatch between a parent's area and its children's, relative to the parent
+    pub area_conservation_relative: Decimal,
+    /// Largest area of a child lying outside its parent or shared with a
+    /// sibling, relative to the parent
+    pub containment: Decimal,
+}
+
+impl GeometricTolerances {
+    pub const DEFAULT: GeometricTolerances = GeometricTolerances {
+        collinearity: COLLINEARITY_THRESHOLD,
+        side_equality: Decimal::from_parts(1, 0, 0, false, 9),
+        area_conservation_relative: Decimal::from_parts(1, 0, 0, false, 6),
+        containment: Decimal::from_parts(1, 0, 0, false, 6),
+    };
+
+    /// Absolute tolerance for comparing `sides`, scaled by the longest
+    pub fn side_tolerance(&self, sides: &[Decimal; 3]) -> Decimal {
+        let longest = sides.iter().copied().max().unwrap_or(Decimal::ZERO);
+        longest * self.side_equality
+    }
+}
+
+impl Default for GeometricTolerances {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
 
 /// A point in 2D space using precise decimal coordinates
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -46,19 +91,26 @@ impl Point {
 
     /// Calculate the midpoint between two points
     pub fn midpoint(&self, other: &Point) -> Point {
-        let two = Decimal::from(2);
-        Point::new(
-            (self.x + other.x) / two,
-            (self.y + other.y) / two,
-        )
+        (*self + *other) / Decimal::from(2)
     }
 
     /// Check if three points are collinear (lie on the same line)
     pub fn are_collinear(p1: &Point, p2: &Point, p3: &Point) -> bool {
+        Self::are_collinear_with_tolerance(p1, p2, p3, COLLINEARITY_THRESHOLD)
+    }
+
+    /// Check if three points are collinear, treating a doubled triangle area
+    /// below `threshold` as zero
+    pub fn are_collinear_with_tolerance(p1: &Point, p2: &Point, p3: &Point, threshold: Decimal) -> bool {
         // Calculate the area of the triangle formed by the three points
         // If area is zero, points are collinear
         let area = (p1.x * (p2.y - p3.y) + p2.x * (p3.y - p1.y) + p3.x * (p1.y - p2.y)).abs();
-        area < Decimal::new(1, 10) // Very small threshold for floating point comparison
+        area < threshold
+    }
+
+    /// Total order on points: by x, then by y
+    pub fn canonical_cmp(&self, other: &Point) -> Ordering {
+        self.x.cmp(&other.x).then_with(|| self.y.cmp(&other.y))
     }
 
     /// Calculate the cross product of vectors (self->p1) and (self->p2)
@@ -70,30 +122,35 @@ impl Point {
         v1x * v2y - v1y * v2x
     }
 
-    /// Simple decimal square root using Newton's method
+    /// Simple decimal square root using Newton's method, see `decimal_sqrt`
     pub fn decimal_sqrt(&self, value: Decimal) -> SierpinskiResult<Decimal> {
-        if value < Decimal::ZERO {
-            return Err(SierpinskiError::ArithmeticOverflow);
-        }
-        
-        if value == Decimal::ZERO {
-            return Ok(Decimal::ZERO);
-        }
+        decimal_sqrt(value)
+    }
+}
 
-        let mut guess = value / Decimal::from(2);
-        let two = Decimal::from(2);
-        let precision = Decimal::new(1, 15); // High precision
+/// Simple decimal square root using Newton's method
+pub fn decimal_sqrt(value: Decimal) -> SierpinskiResult<Decimal> {
+    if value < Decimal::ZERO {
+        return Err(SierpinskiError::ArithmeticOverflow);
+    }
+    
+    if value == Decimal::ZERO {
+        return Ok(Decimal::ZERO);
+    }
 
-        for _ in 0..50 { // Maximum iterations
-            let new_guess = (guess + value / guess) / two;
-            if (new_guess - guess).abs() < precision {
-                return Ok(new_guess);
-            }
-            guess = new_guess;
-        }
+    let mut guess = value / Decimal::from(2);
+    let two = Decimal::from(2);
+    let precision = Decimal::new(1, 15); // High precision
 
-        Ok(guess)
+    for _ in 0..50 { // Maximum iterations
+        let new_guess = (guess + value / guess) / two;
+        if (new_guess - guess).abs() < precision {
+            return Ok(new_guess);
+        }
+        guess = new_guess;
     }
+
+    Ok(guess)
 }
 
 impl fmt::Display for Point {
@@ -102,6 +159,52 @@ impl fmt::Display for Point {
     }
 }
 
+/// Componentwise sum, treating points as position vectors
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+/// Componentwise difference
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+/// Scale both coordinates about the origin
+impl Mul<Decimal> for Point {
+    type Output = Point;
+
+    fn mul(self, factor: Decimal) -> Point {
+        Point::new(self.x * factor, self.y * factor)
+    }
+}
+
+/// Divide both coordinates; callers must not pass zero
+impl Div<Decimal> for Point {
+    type Output = Point;
+
+    fn div(self, divisor: Decimal) -> Point {
+        debug_assert!(!divisor.is_zero(), "dividing a point by zero");
+        Point::new(self.x / divisor, self.y / divisor)
+    }
+}
+
+/// Reflect through the origin
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
 /// Vector operations for geometric calculations
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Vector2D {
@@ -139,11 +242,93 @@ impl Vector2D {
     pub fn dot(&self, other: &Vector2D) -> Decimal {
         self.x * other.x + self.y * other.y
     }
+
+    /// Calculate the z component of the 3D cross product; positive when
+    /// `other` lies counter-clockwise of `self`
+    pub fn cross(&self, other: &Vector2D) -> Decimal {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Calculate the unsigned angle to another vector in radians, in [0, π]
+    pub fn angle_between(&self, other: &Vector2D) -> SierpinskiResult<Decimal> {
+        let magnitudes = self.magnitude()? * other.magnitude()?;
+        if magnitudes == Decimal::ZERO {
+            return Err(SierpinskiError::ArithmeticOverflow);
+        }
+
+        // Rounding can push the cosine slightly outside [-1, 1]
+        let cosine = (self.dot(other) / magnitudes).clamp(-Decimal::ONE, Decimal::ONE);
+        decimal_acos(cosine)
+    }
+
+    /// Rotate the vector counter-clockwise by an angle in radians
+    pub fn rotate(&self, radians: Decimal) -> SierpinskiResult<Vector2D> {
+        let (sin, cos) = decimal_sin_cos(radians);
+        let x = self.x.checked_mul(cos)
+            .zip(self.y.checked_mul(sin))
+            .and_then(|(a, b)| a.checked_sub(b))
+            .ok_or(SierpinskiError::ArithmeticOverflow)?;
+        let y = self.x.checked_mul(sin)
+            .zip(self.y.checked_mul(cos))
+            .and_then(|(a, b)| a.checked_add(b))
+            .ok_or(SierpinskiError::ArithmeticOverflow)?;
+        Ok(Vector2D::new(x, y))
+    }
+}
+
+/// Axis-aligned rectangle enclosing a set of points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    /// Smallest box containing every given point, or None if there are none
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Point>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        Some(points.fold(BoundingBox { min: first, max: first }, |bounds, point| {
+            bounds.union(&BoundingBox { min: *point, max: *point })
+        }))
+    }
+
+    /// Width along the x axis
+    pub fn width(&self) -> Decimal {
+        self.max.x - self.min.x
+    }
+
+    /// Height along the y axis
+    pub fn height(&self) -> Decimal {
+        self.max.y - self.min.y
+    }
+
+    /// Check if a point lies inside the box or on its boundary
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    /// Check if two boxes share any point; boxes touching along an edge or
+    /// at a corner intersect
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x
+            && self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+
+    /// Smallest box containing both boxes
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min: Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::trig::{HALF_PI, PI, TWO_PI};
 
     #[test]
     fn test_point_creation() {
@@ -168,6 +353,17 @@ mod tests {
         assert_eq!(mid.y, Decimal::from(1));
     }
 
+    #[test]
+    fn test_point_operators() {
+        let a = Point::new(Decimal::new(15, 1), Decimal::new(-3, 0));
+        let b = Point::new(Decimal::new(7, 3), Decimal::new(1, 0));
+
+        assert_eq!((a + b) / Decimal::from(2), a.midpoint(&b));
+        assert_eq!(a - b + b, a);
+        assert_eq!(a * Decimal::from(2), a + a);
+        assert_eq!(-a + a, Point::new(Decimal::ZERO, Decimal::ZERO));
+    }
+
     #[test]
     fn test_collinear_points() {
         let p1 = Point::new(Decimal::from(0), Decimal::from(0));
@@ -175,4 +371,31 @@ mod tests {
         let p3 = Point::new(Decimal::from(2), Decimal::from(2));
         assert!(Point::are_collinear(&p1, &p2, &p3));
     }
+
+    #[test]
+    fn test_vector_cross_angle_and_rotation() {
+        let tolerance = Decimal::new(1, 10);
+        let x_axis = Vector2D::new(Decimal::ONE, Decimal::ZERO);
+        let y_axis = Vector2D::new(Decimal::ZERO, Decimal::ONE);
+
+        assert_eq!(x_axis.cross(&y_axis), Decimal::ONE);
+        assert_eq!(y_axis.cross(&x_axis), -Decimal::ONE);
+        assert_eq!(x_axis.cross(&x_axis), Decimal::ZERO);
+
+        let right_angle = x_axis.angle_between(&y_axis).unwrap();
+        assert!((right_angle - HALF_PI).abs() < tolerance);
+        assert!(x_axis.angle_between(&x_axis).unwrap().abs() < tolerance);
+        let opposite = Vector2D::new(-Decimal::ONE, Decimal::ZERO);
+        assert!((x_axis.angle_between(&opposite).unwrap() - PI).abs() < tolerance);
+        let diagonal = Vector2D::new(Decimal::ONE, Decimal::ONE);
+        assert!((x_axis.angle_between(&diagonal).unwrap() - HALF_PI / Decimal::TWO).abs() < tolerance);
+        assert!(x_axis.angle_between(&Vector2D::new(Decimal::ZERO, Decimal::ZERO)).is_err());
+
+        let rotated = x_axis.rotate(HALF_PI).unwrap();
+        assert!((rotated.x - y_axis.x).abs() < tolerance);
+        assert!((rotated.y - y_axis.y).abs() < tolerance);
+        let full_turn = diagonal.rotate(TWO_PI * Decimal::from(3)).unwrap();
+        assert!((full_turn.x - diagonal.x).abs() < tolerance);
+        assert!((full_turn.y - diagonal.y).abs() < tolerance);
+    }
 }
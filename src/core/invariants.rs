@@ -0,0 +1,198 @@
+//! Invariants every fractal structure should hold, whatever sequence of
+//! subdivisions built it
+//!
+//! `validate_fractal_structure` checks a structure is well formed; these
+//! check it is still a Sierpinski subdivision of its genesis.
+
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::core::{
+    address::TriangleAddress,
+    fractal::{FractalStructure, FractalTriangle},
+    geometry::GeometricTolerances,
+    state::TriangleState,
+    triangle::PointLocation,
+};
+
+/// A broken structural invariant
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InvariantViolation {
+    /// The leaves cover more area than the genesis they were cut from
+    LeafAreaExceedsGenesis { leaf_area: Decimal, genesis_area: Decimal },
+    /// A triangle's centroid is not strictly inside its parent
+    CentroidOutsideParent { address: TriangleAddress, parent: TriangleAddress },
+    /// A triangle's depth field disagrees with its address
+    DepthMismatch { address: TriangleAddress, depth: u8 },
+    /// The child at `index` of a parent is not at `parent.child(index)`
+    ChildAddressMismatch { parent: TriangleAddress, index: usize, address: TriangleAddress },
+    /// A void triangle has children
+    VoidWithChildren { address: TriangleAddress, children: usize },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::LeafAreaExceedsGenesis { leaf_area, genesis_area } => {
+                write!(f, "Leaf area {} exceeds genesis area {}", leaf_area, genesis_area)
+            }
+            InvariantViolation::CentroidOutsideParent { address, parent } => {
+                write!(f, "Centroid of {} lies outside its parent {}", address, parent)
+            }
+            InvariantViolation::DepthMismatch { address, depth } => {
+                write!(f, "Triangle {} has depth {} but its address has depth {}", address, depth, address.depth())
+            }
+            InvariantViolation::ChildAddressMismatch { parent, index, address } => {
+                write!(f, "Child {} of {} is at {}", index, parent, address)
+            }
+            InvariantViolation::VoidWithChildren { address, children } => {
+                write!(f, "Void triangle {} has {} children", address, children)
+            }
+        }
+    }
+}
+
+/// Check every invariant, returning all violations found
+pub fn check_all(structure: &FractalStructure) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+    check_leaf_area(structure, &mut violations);
+    for triangle in sorted_triangles(structure) {
+        check_depth(triangle, &mut violations);
+        check_centroid(structure, triangle, &mut violations);
+        check_children(structure, triangle, &mut violations);
+    }
+    violations
+}
+
+/// Resident triangles by address, so violations come out in a stable order
+fn sorted_triangles(structure: &FractalStructure) -> Vec<&FractalTriangle> {
+    let mut triangles: Vec<_> = structure.triangles().collect();
+    triangles.sort_by(|a, b| a.address.components().cmp(b.address.components()));
+    triangles
+}
+
+/// Subdivision only ever cuts area away, so the leaves can never cover
+/// more than the genesis, give or take rounding
+fn check_leaf_area(structure: &FractalStructure, violations: &mut Vec<InvariantViolation>) {
+    let Some(genesis_area) = structure.genesis().and_then(|genesis| genesis.area().ok()) else {
+        return;
+    };
+    let leaf_area: Decimal = structure.leaves().iter().filter_map(|leaf| leaf.area().ok()).sum();
+    let slack = genesis_area * GeometricTolerances::DEFAULT.area_conservation_relative;
+    if leaf_area > genesis_area + slack {
+        violations.push(InvariantViolation::LeafAreaExceedsGenesis { leaf_area, genesis_area });
+    }
+}
+
+fn check_depth(triangle: &FractalTriangle, violations: &mut Vec<InvariantViolation>) {
+    if triangle.address.depth() != triangle.depth {
+        violations.push(InvariantViolation::DepthMismatch {
+            address: triangle.address.clone(),
+            depth: triangle.depth,
+        });
+    }
+}
+
+fn check_centroid(structure: &FractalStructure, triangle: &FractalTriangle, violations: &mut Vec<InvariantViolation>) {
+    let Some(parent) = triangle.parent_id.and_then(|id| structure.get_triangle(&id)) else {
+        return;
+    };
+    if parent.triangle.classify_point(&triangle.triangle.centroid()) != PointLocation::Inside {
+        violations.push(InvariantViolation::CentroidOutsideParent {
+            address: triangle.address.clone(),
+            parent: parent.address.clone(),
+        });
+    }
+}
+
+/// Children are checked wherever they live, resident or archived by a prune
+fn check_children(structure: &FractalStructure, triangle: &FractalTriangle, violations: &mut Vec<InvariantViolation>) {
+    if triangle.state == TriangleState::Void && triangle.has_children() {
+        violations.push(InvariantViolation::VoidWithChildren {
+            address: triangle.address.clone(),
+            children: triangle.child_ids.len(),
+        });
+    }
+
+    for (index, child_id) in triangle.child_ids.iter().enumerate() {
+        let address = match (structure.get_triangle(child_id), structure.archived_triangle(child_id)) {
+            (Some(child), _) => &child.address,
+            (None, Some(archived)) => &archived.address,
+            (None, None) => continue,
+        };
+        let expected = u8::try_from(index).ok().and_then(|index| triangle.address.child(index).ok());
+        if expected.as_ref() != Some(address) {
+            violations.push(InvariantViolation::ChildAddressMismatch {
+                parent: triangle.address.clone(),
+                index,
+                address: address.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        genesis::genesis_fractal_triangle,
+        subdivision::{subdivide_and_add_to_structure, subdivide_to_depth},
+    };
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+
+    /// Subdivide the genesis down to `depth` at most, each step picking a
+    /// leaf among those still subdividable
+    fn random_structure(depth: u8, picks: &[Index]) -> FractalStructure {
+        let mut structure = FractalStructure::new();
+        structure.set_genesis(genesis_fractal_triangle().unwrap()).unwrap();
+        structure.set_max_depth_limit(depth).unwrap();
+        for pick in picks {
+            let mut candidates: Vec<_> = structure.subdividable().into_iter().map(|t| (t.address.clone(), t.id)).collect();
+            if candidates.is_empty() {
+                break;
+            }
+            candidates.sort_by(|a, b| a.0.components().cmp(b.0.components()));
+            let (_, id) = candidates[pick.index(candidates.len())];
+            subdivide_and_add_to_structure(&mut structure, &id).unwrap();
+        }
+        structure
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_random_subdivisions_hold_invariants(
+            depth in 1u8..=6,
+            picks in prop::collection::vec(any::<Index>(), 0..40),
+        ) {
+            let structure = random_structure(depth, &picks);
+            prop_assert_eq!(check_all(&structure), Vec::new());
+        }
+    }
+
+    #[test]
+    fn test_check_all_reports_tampering() {
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 2).unwrap();
+        assert!(check_all(&structure).is_empty());
+
+        let genesis_id = structure.genesis().unwrap().id;
+        let first_child = structure.genesis().unwrap().child_ids[0];
+        structure.get_triangle_mut(&genesis_id).unwrap().child_ids.swap(0, 1);
+        let tampered = structure.get_triangle_mut(&first_child).unwrap();
+        tampered.depth = 5;
+        let tampered_address = tampered.address.clone();
+
+        let violations = check_all(&structure);
+        assert!(violations.contains(&InvariantViolation::DepthMismatch { address: tampered_address.clone(), depth: 5 }));
+        assert!(violations.contains(&InvariantViolation::ChildAddressMismatch {
+            parent: TriangleAddress::genesis(),
+            index: 1,
+            address: tampered_address,
+        }));
+    }
+}
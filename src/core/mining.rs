@@ -5,27 +5,48 @@ use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
+use tracing::{debug, error};
 
 use crate::core::{
-    block::{Block, TriangleTransaction, GeometricProof},
+    block::{Block, BlockHeader, GeometricProof},
     blockchain::TriadChainBlockchain,
     fractal::FractalTriangle,
-    subdivision::{subdivide_triangle, SubdivisionResult, validate_subdivision},
+    subdivision::{subdivide_triangle_deterministic, SubdivisionResult, validate_subdivision},
     triangle::Triangle,
     address::TriangleAddress,
-    geometry::Point,
+    geometry::{GeometricTolerances, Point},
     errors::{SierpinskiError, SierpinskiResult},
 };
 
+/// Decimal places of the area constraint in default challenges
+pub const DEFAULT_GEOMETRIC_PRECISION: u32 = 10;
+
 /// Mining challenge based on geometric operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeometricChallenge {
     pub target_triangle: Triangle,
+    /// Leading zero bits of the chain's target
     pub difficulty: u32,
     pub required_subdivisions: u8,
     pub area_constraint: Option<Decimal>,
     pub timestamp: u64,
     pub challenge_id: String,
+    /// Triangle the mined block hands to its miner, see
+    /// `TriadChainBlockchain::claimable_address`; None once the fractal
+    /// cannot grow
+    pub claimable_address: Option<TriangleAddress>,
+    /// Header of the block template the challenge was issued for, so peers
+    /// without the template can solve it; see `GeometricMiner::issue_challenge`
+    #[serde(default)]
+    pub block_header: Option<BlockHeader>,
+}
+
+/// Nonce and geometric proof hash solving a peer's challenge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeSolution {
+    pub challenge_id: String,
+    pub nonce: u64,
+    pub proof_hash: String,
 }
 
 /// Result of a geometric mining operation
@@ -56,7 +77,7 @@ impl Default for MinerConfig {
             max_threads: num_cpus::get(),
             target_block_time: Duration::from_secs(60), // 1 minute blocks
             max_nonce: 1_000_000,
-            geometric_precision: 10,
+            geometric_precision: DEFAULT_GEOMETRIC_PRECISION,
         }
     }
 }
@@ -65,8 +86,8 @@ impl Default for MinerConfig {
 pub struct GeometricMiner {
     config: MinerConfig,
     is_mining: Arc<AtomicBool>,
-    #[allow(dead_code)]
-    current_challenge: Option<GeometricChallenge>,
+    /// Challenge from a peer being solved, see `set_external_challenge`
+    external_challenge: Mutex<Option<GeometricChallenge>>,
     hashrate: f64,
 }
 
@@ -76,7 +97,7 @@ impl GeometricMiner {
         GeometricMiner {
             config,
             is_mining: Arc::new(AtomicBool::new(false)),
-            current_challenge: None,
+            external_challenge: Mutex::new(None),
             hashrate: 0.0,
         }
     }
@@ -99,38 +120,45 @@ impl GeometricMiner {
             let mut operations_count = 0u64;
             
             while is_mining.load(Ordering::Relaxed) {
-                // Get current mining target
-                let (challenge, transactions) = {
+                // Get current mining target and a candidate block on our tip
+                let (challenge, template) = {
                     let blockchain_guard = blockchain.lock().unwrap();
+                    if blockchain_guard.mempool.is_empty() {
+                        drop(blockchain_guard);
+                        thread::sleep(Duration::from_secs(1));
+                        continue;
+                    }
                     let challenge = Self::generate_challenge(&blockchain_guard, config.geometric_precision);
-                    let transactions = blockchain_guard.mempool.clone();
-                    (challenge, transactions)
+                    let max_transactions = blockchain_guard.mempool.len();
+                    match blockchain_guard.block_template(reward_address.clone(), max_transactions) {
+                        Ok(template) => (challenge, template),
+                        Err(e) => {
+                            error!(error = %e, "Failed to build block template");
+                            drop(blockchain_guard);
+                            thread::sleep(Duration::from_secs(1));
+                            continue;
+                        }
+                    }
                 };
                 
-                if transactions.is_empty() {
-                    thread::sleep(Duration::from_secs(1));
-                    continue;
-                }
-                
                 // Attempt to mine block
                 match Self::mine_geometric_block(
                     &challenge,
-                    &transactions,
-                    &reward_address,
+                    &template,
                     nonce,
                     config.max_nonce,
                 ) {
-                    Ok(_block) => {
-                        // Successfully mined block
+                    Ok(block) => {
+                        // Submit the block we found; the chain logs it on success
+                        let height = block.height;
                         let mut blockchain_guard = blockchain.lock().unwrap();
-                        match blockchain_guard.mine_block(reward_address.clone(), transactions.len()) {
-                            Ok(mined_block) => {
-                                println!("✅ Block mined! Height: {}, Hash: {}", 
-                                        mined_block.height, 
-                                        mined_block.hash()[..16].to_string());
+                        match blockchain_guard.accept_mined_block(block) {
+                            Ok(()) => {
+                                debug!(height, nonce, "Mining thread found a block");
                             }
                             Err(e) => {
-                                println!("❌ Failed to add block to chain: {}", e);
+                                // Usually the tip moved while we were mining
+                                debug!(height, error = %e, "Discarding stale mined block");
                             }
                         }
                         nonce = 0; // Reset nonce for next block
@@ -142,7 +170,7 @@ impl GeometricMiner {
                         // Print hashrate stats every 10 seconds
                         if last_stats.elapsed() >= Duration::from_secs(10) {
                             let hashrate = operations_count as f64 / last_stats.elapsed().as_secs_f64();
-                            println!("⛏️  Mining... Hashrate: {:.2} H/s, Nonce: {}", hashrate, nonce);
+                            debug!(hashrate, nonce, "Mining");
                             operations_count = 0;
                             last_stats = Instant::now();
                         }
@@ -180,90 +208,165 @@ impl GeometricMiner {
         };
 
         let challenge_id = format!("{}-{}", 
-                                  latest_block.hash()[..8].to_string(),
+                                  &latest_block.hash()[..8],
                                   SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs());
 
         GeometricChallenge {
             target_triangle,
-            difficulty: blockchain.difficulty,
-            required_subdivisions: std::cmp::min(blockchain.difficulty / 2, 10) as u8,
+            difficulty: blockchain.target.leading_zero_bits(),
+            required_subdivisions: std::cmp::min(blockchain.target.leading_zero_bits() / 8, 10) as u8,
             area_constraint: Some(Decimal::new(1, precision)),
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
             challenge_id,
+            claimable_address: blockchain.claimable_address(&latest_block.hash()),
+            block_header: None,
+        }
+    }
+
+    /// Issue a challenge to peers for mining `template`, a block template on
+    /// `blockchain`'s tip
+    pub fn issue_challenge(blockchain: &TriadChainBlockchain, template: &Block) -> GeometricChallenge {
+        let mut challenge = Self::generate_challenge(blockchain, DEFAULT_GEOMETRIC_PRECISION);
+        // Several challenges may be issued on one tip within a second
+        challenge.challenge_id = format!("{}-{}", challenge.challenge_id, uuid::Uuid::new_v4().simple());
+        challenge.block_header = Some(template.header.clone());
+        challenge
+    }
+
+    /// Take on a challenge issued by a peer, replacing any older one still
+    /// being solved
+    ///
+    /// Returns false, ignoring the challenge, if it carries no block header,
+    /// is the current one or is older than it.
+    pub fn set_external_challenge(&self, challenge: GeometricChallenge) -> bool {
+        if challenge.block_header.is_none() {
+            return false;
+        }
+
+        let mut current = self.external_challenge.lock().unwrap();
+        if let Some(existing) = current.as_ref() {
+            if existing.challenge_id == challenge.challenge_id || existing.timestamp > challenge.timestamp {
+                return false;
+            }
         }
+        *current = Some(challenge);
+        true
     }
 
-    /// Attempt to mine a block using geometric proof-of-work
+    /// Drop the external challenge, stopping any solve in progress
+    pub fn clear_external_challenge(&self) {
+        *self.external_challenge.lock().unwrap() = None;
+    }
+
+    /// Solve the current external challenge
+    ///
+    /// Gives up if the challenge is replaced or cleared meanwhile, or if no
+    /// nonce up to `max_nonce` works.
+    pub fn solve_external_challenge(&self) -> Option<ChallengeSolution> {
+        let challenge = self.external_challenge.lock().unwrap().clone()?;
+        let header = challenge.block_header.as_ref()?;
+        let is_current = || {
+            self.external_challenge
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|current| current.challenge_id == challenge.challenge_id)
+        };
+
+        let (nonce, proof_hash) = Self::solve_header(&challenge, header, 0, self.config.max_nonce, is_current)?;
+        // Done with it, unless a newer one has arrived
+        let mut current = self.external_challenge.lock().unwrap();
+        if current.as_ref().is_some_and(|current| current.challenge_id == challenge.challenge_id) {
+            *current = None;
+        }
+
+        Some(ChallengeSolution {
+            challenge_id: challenge.challenge_id,
+            nonce,
+            proof_hash,
+        })
+    }
+
+    /// Attempt to mine `template` using geometric proof-of-work
+    ///
+    /// The template already links to the tip (see
+    /// `TriadChainBlockchain::block_template`); only the nonce and the
+    /// geometric proof are filled in here.
     fn mine_geometric_block(
         challenge: &GeometricChallenge,
-        transactions: &[TriangleTransaction],
-        miner_address: &str,
+        template: &Block,
         start_nonce: u64,
         max_iterations: u64,
     ) -> SierpinskiResult<Block> {
-        let _start_time = Instant::now();
-        
+        Self::solve_header(challenge, &template.header, start_nonce, max_iterations, || true)
+            .map(|(nonce, proof_hash)| Self::assemble_block(template, nonce, proof_hash))
+            .ok_or_else(|| SierpinskiError::subdivision("Failed to find valid geometric proof".to_string()))
+    }
+
+    /// Search nonces from `start_nonce` for one whose geometric proof makes a
+    /// block with `header` meet its difficulty target, returning the nonce
+    /// and proof hash; stops early once `keep_going` returns false
+    fn solve_header(
+        challenge: &GeometricChallenge,
+        header: &BlockHeader,
+        start_nonce: u64,
+        max_iterations: u64,
+        keep_going: impl Fn() -> bool,
+    ) -> Option<(u64, String)> {
+        let mut header = header.clone();
         for nonce_offset in 0..max_iterations {
+            if !keep_going() {
+                return None;
+            }
             let nonce = start_nonce.wrapping_add(nonce_offset);
-            
-            // Create candidate block
-            let mut block = Block::new(
-                "previous_hash".to_string(), // Will be set properly in real implementation
-                transactions.to_vec(),
-                miner_address.to_string(),
-                challenge.difficulty,
-            );
-            
-            block.set_nonce(nonce);
-            
-            // Perform geometric proof-of-work
-            match Self::verify_geometric_work(challenge, &block, nonce) {
-                Ok(mining_result) => {
-                    if mining_result.total_area_preserved && mining_result.triangles_generated > 0 {
-                        // Update block with geometric proof
-                        block.geometric_proof = GeometricProof {
-                            triangle_hash: mining_result.geometric_hash,
-                            subdivision_valid: true,
-                            area_conservation: mining_result.total_area_preserved,
-                            merkle_root: block.header.merkle_root.clone(),
-                            nonce,
-                            difficulty: challenge.difficulty,
-                        };
-                        
-                        // Check if block meets difficulty target
-                        if block.meets_difficulty_target() {
-                            return Ok(block);
-                        }
-                    }
-                }
-                Err(_) => {
-                    // Invalid geometric proof, continue with next nonce
-                    continue;
-                }
+
+            // Perform geometric proof-of-work; an invalid proof moves on to the next nonce
+            let Ok(mining_result) = Self::verify_geometric_work(challenge, nonce) else {
+                continue;
+            };
+            if !mining_result.total_area_preserved || mining_result.triangles_generated == 0 {
+                continue;
+            }
+
+            // Check if the block would meet its difficulty target
+            header.nonce = nonce;
+            let hash = Block::compute_hash(&header, &mining_result.geometric_hash);
+            if header.is_met_by(&hash) {
+                return Some((nonce, mining_result.geometric_hash));
             }
         }
-        
-        Err(SierpinskiError::subdivision("Failed to find valid geometric proof".to_string()))
+
+        None
+    }
+
+    /// Fill in `template` with a solved nonce and its geometric proof hash
+    pub fn assemble_block(template: &Block, nonce: u64, proof_hash: String) -> Block {
+        let mut block = template.clone();
+        block.set_nonce(nonce);
+        block.geometric_proof = GeometricProof {
+            triangle_hash: proof_hash,
+            subdivision_valid: true,
+            area_conservation: true,
+            merkle_root: block.header.merkle_root.clone(),
+            nonce,
+            difficulty: block.header.difficulty(),
+        };
+        block
     }
 
     /// Verify geometric proof-of-work
-    fn verify_geometric_work(
+    pub fn verify_geometric_work(
         challenge: &GeometricChallenge,
-        _block: &Block,
         nonce: u64,
     ) -> SierpinskiResult<MiningResult> {
         let start_time = Instant::now();
         
-        // Create fractal triangle from challenge
-        let fractal_triangle = FractalTriangle::new(
-            challenge.target_triangle.clone(),
-            crate::core::state::TriangleState::Active,
-            TriangleAddress::genesis(),
-            0,
-        );
+        // Create fractal triangle from challenge; ids follow from the
+        // geometry so any node reproduces the same proof hash
+        let fractal_triangle = FractalTriangle::genesis_deterministic(challenge.target_triangle.clone());
         
         // Perform subdivision as proof-of-work
-        let subdivision_result = subdivide_triangle(&fractal_triangle)?;
+        let subdivision_result = subdivide_triangle_deterministic(&fractal_triangle)?;
         
         // Validate subdivision
         let is_valid = validate_subdivision(&subdivision_result)?;
@@ -274,15 +377,9 @@ impl GeometricMiner {
         // Calculate geometric hash incorporating nonce
         let geometric_hash = Self::calculate_geometric_hash(&subdivision_result, nonce);
         
-        // Check area conservation
-        let parent_area = subdivision_result.parent.area()?;
-        let children_area: Decimal = subdivision_result.children
-            .iter()
-            .map(|child| child.area().unwrap_or(Decimal::ZERO))
-            .sum();
-        let void_area = subdivision_result.void_triangle.area()?;
-        
-        let total_area_preserved = (parent_area - (children_area + void_area)).abs() < Decimal::new(1, 10);
+        // Check area conservation, to the tolerance validation uses
+        let total_area_preserved = subdivision_result
+            .is_area_conserved(GeometricTolerances::DEFAULT.area_conservation_relative);
         
         Ok(MiningResult {
             nonce,
@@ -368,7 +465,7 @@ impl MiningPool {
 
         for miner_id in &self.miners {
             // In a real implementation, we'd track each miner's contribution
-            let share = total_reward / Decimal::try_from(self.miners.len()).unwrap();
+            let share = total_reward / Decimal::from(self.miners.len());
             self.reward_distribution.insert(miner_id.clone(), share);
         }
     }
@@ -377,6 +474,7 @@ impl MiningPool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::target::Target;
 
     #[test]
     fn test_miner_creation() {
@@ -392,6 +490,61 @@ mod tests {
         
         assert!(!challenge.challenge_id.is_empty());
         assert!(challenge.difficulty > 0);
+        // A fresh chain has nothing unowned, so the claim is a corner of the genesis
+        assert_eq!(challenge.claimable_address.map(|address| address.depth()), Some(1));
+    }
+
+    #[test]
+    fn test_verification_shares_validation_area_tolerance() {
+        let blockchain = TriadChainBlockchain::new().unwrap();
+        let challenge = GeometricMiner::generate_challenge(&blockchain, 10);
+        let work = GeometricMiner::verify_geometric_work(&challenge, 0).unwrap();
+
+        let proof = &work.subdivision_proof;
+        let tolerance = GeometricTolerances::DEFAULT.area_conservation_relative;
+        assert!(work.total_area_preserved);
+        assert_eq!(work.total_area_preserved, proof.is_area_conserved(tolerance));
+        assert_eq!(work.total_area_preserved, validate_subdivision(proof).unwrap());
+    }
+
+    #[test]
+    fn test_mined_block_is_accepted_without_remining() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let height = blockchain.tip_height();
+
+        let challenge = GeometricMiner::generate_challenge(&blockchain, 10);
+        let template = blockchain.block_template("miner".to_string(), 10).unwrap();
+        let block = GeometricMiner::mine_geometric_block(&challenge, &template, 0, 10_000).unwrap();
+        assert_eq!(block.header.previous_hash, blockchain.blocks.last().unwrap().hash());
+        assert!(block.geometric_proof.subdivision_valid);
+
+        let hash = block.hash();
+        blockchain.accept_mined_block(block).unwrap();
+        assert_eq!(blockchain.tip_height(), height + 1);
+        assert_eq!(blockchain.blocks.last().unwrap().hash(), hash);
+    }
+
+    #[test]
+    fn test_external_challenge_solution_completes_template() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let template = blockchain.block_template("pool".to_string(), 10).unwrap();
+        let challenge = GeometricMiner::issue_challenge(&blockchain, &template);
+
+        let miner = GeometricMiner::new(MinerConfig::default());
+        assert!(miner.set_external_challenge(challenge.clone()));
+        assert!(!miner.set_external_challenge(challenge.clone()));
+        let mut older = GeometricMiner::issue_challenge(&blockchain, &template);
+        older.timestamp = challenge.timestamp - 1;
+        assert!(!miner.set_external_challenge(older));
+
+        let solution = miner.solve_external_challenge().unwrap();
+        assert_eq!(solution.challenge_id, challenge.challenge_id);
+        let work = GeometricMiner::verify_geometric_work(&challenge, solution.nonce).unwrap();
+        assert_eq!(work.geometric_hash, solution.proof_hash);
+
+        let block = GeometricMiner::assemble_block(&template, solution.nonce, solution.proof_hash);
+        blockchain.accept_mined_block(block).unwrap();
+        assert_eq!(blockchain.tip_height(), 1);
     }
 
     #[test]
@@ -2,23 +2,30 @@
 
 pub mod errors;
 pub mod geometry;
+pub mod trig;
 pub mod triangle;
 pub mod fractal;
+pub mod state_tree;
 pub mod genesis;
 pub mod subdivision;
 pub mod address;
 pub mod validation;
+pub mod invariants;
 pub mod state;
+pub mod target;
 pub mod block;
 pub mod blockchain;
+pub mod snapshot;
 pub mod mining;
 pub mod wallet;
+#[cfg(feature = "node")]
 pub mod network;
 pub mod economics;
 
 // Re-export all core types
 pub use errors::*;
 pub use geometry::*;
+pub use trig::*;
 pub use triangle::*;
 pub use fractal::*;
 pub use genesis::*;
@@ -26,3 +33,21 @@ pub use subdivision::*;
 pub use address::*;
 pub use validation::*;
 pub use state::*;
+// Both modules have a `DepthStats`; the fractal one stays under its module
+pub use subdivision::DepthStats;
+
+/// Seconds since the Unix epoch, from the browser's clock on wasm32, which
+/// has no system clock
+pub(crate) fn unix_now() -> u64 {
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+    #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
@@ -1,17 +1,26 @@
 //! Peer-to-peer networking for Sierpinski Triangle cryptocurrency
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
+use tracing::{debug, debug_span, error, field, info, info_span, warn, Instrument, Span};
 use uuid::Uuid;
 
 use crate::core::{
-    block::Block,
-    blockchain::TriadChainBlockchain,
-    mining::GeometricChallenge,
+    block::{Block, BlockHeader, TriangleTransaction},
+    blockchain::{BlockAcceptance, BlockchainStats, SharedBlockchain, TriadChainBlockchain},
+    target::Target,
+    snapshot::{ChainSnapshot, DEFAULT_SNAPSHOT_RECENT_BLOCKS},
+    mining::{ChallengeSolution, GeometricChallenge, GeometricMiner},
     errors::{SierpinskiError, SierpinskiResult},
 };
 
@@ -23,6 +32,16 @@ pub enum NetworkMessage {
         peer_id: String,
         version: String,
         blockchain_height: u64,
+        #[serde(default)]
+        total_work: u128,
+    },
+    /// Reply to a handshake, completing the exchange
+    HandshakeAck {
+        peer_id: String,
+        version: String,
+        blockchain_height: u64,
+        #[serde(default)]
+        total_work: u128,
     },
     /// Request blockchain data
     BlockRequest {
@@ -33,6 +52,15 @@ pub enum NetworkMessage {
     BlockResponse {
         blocks: Vec<Block>,
     },
+    /// Request block headers only, for header-first sync
+    HeaderRequest {
+        start_height: u64,
+        count: u32,
+    },
+    /// Headers of consecutive blocks from the requested height
+    HeaderResponse {
+        headers: Vec<SyncHeader>,
+    },
     /// Announce new block
     NewBlock {
         block: Block,
@@ -46,16 +74,198 @@ pub enum NetworkMessage {
     MiningChallenge {
         challenge: GeometricChallenge,
     },
+    /// Nonce solving a `MiningChallenge`, sent back to the node that issued it
+    ChallengeSolution {
+        challenge_id: String,
+        nonce: u64,
+        proof_hash: String,
+    },
     /// Peer discovery
     PeerDiscovery {
         known_peers: Vec<SocketAddr>,
     },
+    /// Ask for a state snapshot at the peer's tip to fast-sync from
+    SnapshotRequest {
+        recent_blocks: u32,
+    },
+    /// State snapshot plus the blocks leading up to it
+    SnapshotResponse {
+        snapshot: Box<ChainSnapshot>,
+        recent_blocks: Vec<Block>,
+    },
+    /// Query the node's chain statistics
+    GetStats,
+    /// Chain statistics answering `GetStats`
+    StatsResponse {
+        stats: BlockchainStats,
+    },
+    /// Query a wallet's balance
+    GetBalance {
+        address: String,
+    },
+    /// Balance answering `GetBalance`; zero for unknown addresses
+    BalanceResponse {
+        address: String,
+        balance: Decimal,
+    },
+    /// Query the block at a height
+    GetBlock {
+        height: u64,
+    },
+    /// Block answering `GetBlock`, if the node has one at that height
+    GetBlockResponse {
+        height: u64,
+        block: Option<Block>,
+    },
+    /// Polite notice that the sender is closing the connection
+    Disconnect {
+        reason: String,
+    },
     /// Ping/keepalive
     Ping,
     /// Pong response
     Pong,
 }
 
+impl NetworkMessage {
+    /// Wrap a transaction for gossip
+    pub fn transaction_broadcast(transaction: &TriangleTransaction) -> SierpinskiResult<Self> {
+        let transaction_data = serde_json::to_vec(transaction)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to encode transaction: {}", e)))?;
+        Ok(NetworkMessage::TransactionBroadcast {
+            transaction_id: transaction.id.to_string(),
+            transaction_data,
+        })
+    }
+}
+
+/// Block header as exchanged during header-first sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncHeader {
+    pub height: u64,
+    pub hash: String,
+    pub header: BlockHeader,
+    /// Geometric proof hash, which the block hash covers alongside the header
+    pub proof_hash: String,
+}
+
+impl SyncHeader {
+    pub fn from_block(block: &Block) -> Self {
+        SyncHeader {
+            height: block.height,
+            hash: block.hash(),
+            header: block.header.clone(),
+            proof_hash: block.geometric_proof.triangle_hash.clone(),
+        }
+    }
+
+    /// Check that `hash` belongs to the header, that the header was mined at
+    /// the `expected` target and that the hash meets it
+    pub fn verify(&self, expected: Target) -> SierpinskiResult<()> {
+        if self.header.bits != expected.to_compact() {
+            return Err(SierpinskiError::validation(format!(
+                "Header {} was mined at target bits {:#010x} instead of {:#010x}",
+                self.height, self.header.bits, expected.to_compact()
+            )));
+        }
+        let hash = Block::compute_hash(&self.header, &self.proof_hash);
+        if hex::encode(hash) != self.hash {
+            return Err(SierpinskiError::validation(format!("Header {} hash mismatch", self.height)));
+        }
+        if !self.header.is_met_by(&hash) {
+            return Err(SierpinskiError::validation(format!(
+                "Header {} does not meet its difficulty target",
+                self.height
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Stage of a header-first sync
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SyncPhase {
+    /// No sync has been started
+    #[default]
+    Idle,
+    /// Fetching and validating headers
+    Headers,
+    /// Fetching the blocks missing after the fork point
+    Blocks,
+    /// Our chain matches the peer's
+    Complete,
+    /// The sync stopped; carries the reason
+    Failed(String),
+}
+
+/// Progress of the current or most recent sync
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncStatus {
+    pub phase: SyncPhase,
+    /// Peer being synced from
+    pub peer: Option<SocketAddr>,
+    /// Chain length the peer announced
+    pub target_height: u64,
+    pub headers_received: u64,
+    /// First height at which the peer's chain differs from ours
+    pub fork_height: Option<u64>,
+    pub blocks_applied: u64,
+}
+
+impl SyncStatus {
+    /// Check whether the sync has completed or failed
+    pub fn is_finished(&self) -> bool {
+        matches!(self.phase, SyncPhase::Complete | SyncPhase::Failed(_))
+    }
+}
+
+/// Bookkeeping for a header-first sync in progress
+#[derive(Debug, Default)]
+struct SyncSession {
+    status: SyncStatus,
+    /// Height of the first header requested
+    start_height: u64,
+    /// Validated headers; from the fork point on once blocks are requested
+    headers: Vec<SyncHeader>,
+    /// Target the next header must have been mined at, once known
+    next_target: Option<Target>,
+    /// Index into `headers` of the next block expected
+    next_block: usize,
+    /// Whether the missing blocks extend our tip and can be applied as they arrive
+    extends_tip: bool,
+    /// Blocks of a competing fork, held until the whole fork has arrived
+    fork_blocks: Vec<Block>,
+}
+
+impl SyncSession {
+    fn is_awaiting(&self, phase: SyncPhase, addr: &SocketAddr) -> bool {
+        self.status.phase == phase && self.status.peer == Some(*addr)
+    }
+
+    fn fail(&mut self, reason: impl Into<String>) {
+        let reason = reason.into();
+        warn!(%reason, "Sync failed");
+        self.status.phase = SyncPhase::Failed(reason);
+        self.headers.clear();
+        self.fork_blocks.clear();
+    }
+
+    fn complete(&mut self) {
+        self.status.phase = SyncPhase::Complete;
+        self.headers.clear();
+        self.fork_blocks.clear();
+    }
+
+    /// Ask for the next run of blocks listed in `headers`
+    fn next_block_request(&self, batch: u32) -> NetworkMessage {
+        let remaining = self.headers.len() - self.next_block;
+        NetworkMessage::BlockRequest {
+            start_height: self.headers[self.next_block].height,
+            count: batch.min(remaining as u32),
+        }
+    }
+}
+
 /// Peer information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeerInfo {
@@ -63,11 +273,140 @@ pub struct PeerInfo {
     pub address: SocketAddr,
     pub version: String,
     pub blockchain_height: u64,
+    /// Cumulative proof-of-work the peer announced
+    pub total_work: u128,
     pub last_seen: u64,
     pub reputation_score: f64,
     pub connection_state: ConnectionState,
 }
 
+/// A peer remembered across restarts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownPeer {
+    pub address: SocketAddr,
+    pub last_seen: u64,
+    pub reputation_score: f64,
+}
+
+/// Peers this node has talked to, kept on disk so a restarted node can
+/// reconnect to them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerStore {
+    peers: HashMap<SocketAddr, KnownPeer>,
+}
+
+impl PeerStore {
+    /// Read a store written by `save`, forgetting peers not seen within
+    /// `ttl_secs`; a missing file is an empty store
+    pub fn load(path: &Path, ttl_secs: u64) -> SierpinskiResult<Self> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(SierpinskiError::validation(format!(
+                    "Failed to read peers file {}: {}", path.display(), e
+                )))
+            }
+        };
+        let mut store: Self = serde_json::from_str(&json)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to decode peers file: {}", e)))?;
+        store.evict_stale(unix_now(), ttl_secs);
+        Ok(store)
+    }
+
+    /// Write the store as JSON
+    pub fn save(&self, path: &Path) -> SierpinskiResult<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to encode peers file: {}", e)))?;
+        std::fs::write(path, json).map_err(|e| {
+            SierpinskiError::validation(format!("Failed to write peers file {}: {}", path.display(), e))
+        })
+    }
+
+    /// Remember a peer's address, last contact and reputation
+    pub fn record(&mut self, peer: &PeerInfo) {
+        let known = self.peers.entry(peer.address).or_insert_with(|| KnownPeer {
+            address: peer.address,
+            last_seen: peer.last_seen,
+            reputation_score: peer.reputation_score,
+        });
+        known.last_seen = known.last_seen.max(peer.last_seen);
+        known.reputation_score = peer.reputation_score;
+    }
+
+    /// Forget peers last seen more than `ttl_secs` before `now`, returning
+    /// how many were dropped
+    pub fn evict_stale(&mut self, now: u64, ttl_secs: u64) -> usize {
+        let before = self.peers.len();
+        self.peers.retain(|_, peer| now.saturating_sub(peer.last_seen) <= ttl_secs);
+        before - self.peers.len()
+    }
+
+    /// The `count` most recently seen peers with at least `min_reputation`
+    pub fn bootstrap_candidates(&self, count: usize, min_reputation: f64) -> Vec<SocketAddr> {
+        let mut candidates: Vec<&KnownPeer> = self.peers
+            .values()
+            .filter(|peer| peer.reputation_score >= min_reputation)
+            .collect();
+        candidates.sort_by_key(|peer| std::cmp::Reverse(peer.last_seen));
+        candidates.into_iter().take(count).map(|peer| peer.address).collect()
+    }
+
+    /// Remember an address heard about from another peer, returning
+    /// whether it was new; known peers keep their record
+    pub fn add_address(&mut self, address: SocketAddr, now: u64) -> bool {
+        if self.peers.contains_key(&address) {
+            return false;
+        }
+        self.peers.insert(address, KnownPeer {
+            address,
+            last_seen: now,
+            reputation_score: NEUTRAL_REPUTATION,
+        });
+        true
+    }
+
+    /// Add the peers of another store, keeping the more recent record of
+    /// each, and return how many were new
+    pub fn merge(&mut self, other: PeerStore) -> usize {
+        let mut added = 0;
+        for (address, peer) in other.peers {
+            match self.peers.get(&address) {
+                Some(known) if known.last_seen >= peer.last_seen => {}
+                Some(_) => {
+                    self.peers.insert(address, peer);
+                }
+                None => {
+                    self.peers.insert(address, peer);
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+
+    /// The remembered entry for `address`
+    pub fn get(&self, address: &SocketAddr) -> Option<&KnownPeer> {
+        self.peers.get(address)
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConnectionState {
     Disconnected,
@@ -77,173 +416,762 @@ pub enum ConnectionState {
     Ready,
 }
 
+/// Limits applied to incoming traffic on every peer connection, and batch
+/// sizes for requests we make
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Largest message accepted from a peer, in bytes
+    pub max_message_size: usize,
+    /// Sustained number of messages per second a peer may send
+    pub messages_per_second: f64,
+    /// Messages a peer may send at once before the sustained rate applies
+    pub message_burst: f64,
+    /// Sustained number of bytes per second a peer may send
+    pub bytes_per_second: f64,
+    /// Bytes a peer may send at once; at least `max_message_size`
+    pub byte_burst: f64,
+    /// Reputation deducted from a peer disconnected for exceeding a limit
+    pub limit_violation_penalty: f64,
+    /// Headers requested at a time during sync, at most `MAX_HEADERS_PER_RESPONSE`
+    pub sync_header_batch: u32,
+    /// Blocks requested at a time during sync
+    pub sync_block_batch: u32,
+    /// File remembering peers across restarts, if any
+    pub peers_file: Option<PathBuf>,
+    /// Remembered peers not seen for this many seconds are forgotten on load
+    pub peer_ttl_secs: u64,
+    /// Remembered peers dialled at startup
+    pub bootstrap_peer_count: usize,
+    /// Remembered peers below this reputation are not dialled at startup
+    pub min_bootstrap_reputation: f64,
+    /// Seconds between writes of the peers file
+    pub peer_flush_interval_secs: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            max_message_size: 16 * 1024 * 1024,
+            messages_per_second: 50.0,
+            message_burst: 200.0,
+            bytes_per_second: 1024.0 * 1024.0,
+            byte_burst: 16.0 * 1024.0 * 1024.0,
+            limit_violation_penalty: 0.25,
+            sync_header_batch: 500,
+            sync_block_batch: 50,
+            peers_file: None,
+            peer_ttl_secs: 7 * 24 * 60 * 60,
+            bootstrap_peer_count: 8,
+            min_bootstrap_reputation: 0.5,
+            peer_flush_interval_secs: 60,
+        }
+    }
+}
+
+/// Delay before the first reconnection attempt; doubled after every failure
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Number of reconnection attempts before an outbound peer is dropped
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+/// How long to wait for the remote side to acknowledge our handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many blocks below the shared height sync re-requests to cover forks
+const SYNC_REORG_WINDOW: u64 = 100;
+
+/// How long shutdown waits for network tasks before abandoning them
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound on the recent blocks served with a snapshot
+const MAX_SNAPSHOT_RECENT_BLOCKS: u32 = 100;
+
+/// Upper bound on the headers served in one response
+pub const MAX_HEADERS_PER_RESPONSE: u32 = 2000;
+
+/// Upper bound on the headers accepted over one sync
+pub const MAX_SYNC_HEADERS: usize = 200_000;
+
+/// Block hashes remembered for gossip deduplication before the set is reset
+const MAX_SEEN_BLOCKS: usize = 10_000;
+
+/// Most addresses taken from one `PeerDiscovery` message
+const MAX_DISCOVERED_PEERS: usize = 100;
+
+/// Reputation of a peer we have not yet talked to
+const NEUTRAL_REPUTATION: f64 = 0.5;
+
+/// Reputation deducted from a peer for a message whose payload is malformed
+const MALFORMED_PAYLOAD_PENALTY: f64 = 0.1;
+
+/// Record a block hash as seen, returning false if it already was
+fn mark_block_seen(seen_blocks: &Mutex<HashSet<String>>, hash: String) -> bool {
+    let mut seen = seen_blocks.lock().unwrap();
+    // Blocks seen that long ago are in our chain, so re-adopting one is a no-op
+    if seen.len() >= MAX_SEEN_BLOCKS {
+        seen.clear();
+    }
+    seen.insert(hash)
+}
+
+/// Queue a message on every connection except the one at `except`
+fn queue_for_peers(
+    connections: &Mutex<HashMap<SocketAddr, PeerConnection>>,
+    message: &NetworkMessage,
+    except: Option<&SocketAddr>,
+) {
+    let connections: Vec<PeerConnection> = connections.lock().unwrap()
+        .values()
+        .filter(|connection| Some(&connection.address) != except)
+        .cloned()
+        .collect();
+
+    for connection in connections {
+        if let Err(e) = connection.send(message.clone()) {
+            warn!(peer = %connection.address, error = %e, "Failed to queue message");
+        }
+    }
+}
+
+/// Callback invoked for incoming network messages
+pub type MessageHandler = Box<dyn Fn(&NetworkMessage) + Send + Sync>;
+
+/// Line-framed reader over the receiving half of a peer socket that refuses
+/// lines longer than a limit instead of buffering them
+struct PeerLines {
+    reader: BufReader<OwnedReadHalf>,
+    buffer: Vec<u8>,
+    max_len: usize,
+}
+
+impl PeerLines {
+    fn new(reader: OwnedReadHalf, max_len: usize) -> Self {
+        PeerLines {
+            reader: BufReader::new(reader),
+            buffer: Vec::new(),
+            max_len,
+        }
+    }
+
+    /// Read the next line without its newline, or None at end of stream
+    ///
+    /// Oversized lines fail with `ErrorKind::InvalidData`. Cancel safe:
+    /// partial lines are kept in the buffer.
+    async fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            let available = self.reader.fill_buf().await?;
+            if available.is_empty() {
+                return Ok(None);
+            }
+
+            let (taken, complete) = match available.iter().position(|&byte| byte == b'\n') {
+                Some(end) => (end, true),
+                None => (available.len(), false),
+            };
+            if self.buffer.len() + taken > self.max_len {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("message exceeds {} bytes", self.max_len),
+                ));
+            }
+            self.buffer.extend_from_slice(&available[..taken]);
+            self.reader.consume(if complete { taken + 1 } else { taken });
+
+            if complete {
+                let line = std::mem::take(&mut self.buffer);
+                return String::from_utf8(line)
+                    .map(Some)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+            }
+        }
+    }
+}
+
+/// Token bucket refilled continuously up to its capacity
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(refill_per_second: f64, capacity: f64) -> Self {
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            refill_per_second,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take `amount` tokens if available
+    fn try_take(&mut self, amount: f64) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens < amount {
+            return false;
+        }
+        self.tokens -= amount;
+        true
+    }
+}
+
+/// Message and byte rate limits for one peer connection
+struct PeerRateLimiter {
+    messages: TokenBucket,
+    bytes: TokenBucket,
+}
+
+impl PeerRateLimiter {
+    fn new(config: &NetworkConfig) -> Self {
+        PeerRateLimiter {
+            messages: TokenBucket::new(config.messages_per_second, config.message_burst),
+            bytes: TokenBucket::new(config.bytes_per_second, config.byte_burst),
+        }
+    }
+
+    /// Account for a received message of `len` bytes; false once the peer
+    /// has exceeded either limit
+    fn allow(&mut self, len: usize) -> bool {
+        self.messages.try_take(1.0) && self.bytes.try_take(len as f64)
+    }
+}
+
+/// Handle to the writer task of a persistent peer connection
+#[derive(Debug, Clone)]
+pub struct PeerConnection {
+    pub address: SocketAddr,
+    sender: mpsc::UnboundedSender<NetworkMessage>,
+}
+
+impl PeerConnection {
+    /// Queue a message for delivery over this connection
+    pub fn send(&self, message: NetworkMessage) -> SierpinskiResult<()> {
+        self.sender.send(message).map_err(|_| {
+            SierpinskiError::validation(format!("Connection to {} is closed", self.address))
+        })
+    }
+}
+
+/// Why a connection loop stopped
+enum ConnectionEnd {
+    /// Every sender was dropped; nothing more will be sent
+    Closed,
+    /// The socket failed; carries the message that could not be written, if any
+    Dropped(Option<Box<NetworkMessage>>),
+    /// The peer exceeded a traffic limit and was disconnected
+    Misbehaved,
+}
+
+/// Shared node state handed to every per-peer connection task
+#[derive(Clone)]
+struct NodeContext {
+    node_id: String,
+    peers: Arc<Mutex<HashMap<String, PeerInfo>>>,
+    blockchain: SharedBlockchain,
+    connections: Arc<Mutex<HashMap<SocketAddr, PeerConnection>>>,
+    shutdown: watch::Receiver<bool>,
+    tasks: Arc<Mutex<JoinSet<()>>>,
+    config: NetworkConfig,
+    sync: Arc<Mutex<SyncSession>>,
+    seen_blocks: Arc<Mutex<HashSet<String>>>,
+    peer_store: Arc<Mutex<PeerStore>>,
+    miner: Option<Arc<GeometricMiner>>,
+    challenges: Arc<Mutex<HashMap<String, IssuedChallenge>>>,
+    latest_challenge: Arc<Mutex<Option<GeometricChallenge>>>,
+    snapshot_requests: Arc<Mutex<HashSet<SocketAddr>>>,
+}
+
+/// Mining challenge this node sent to its peers, kept until solved or
+/// superseded by a new tip
+#[derive(Debug, Clone)]
+struct IssuedChallenge {
+    challenge: GeometricChallenge,
+    /// Block the challenge's header belongs to, completed by a solution
+    template: Block,
+}
+
+impl NodeContext {
+    /// Spawn a network task tracked for shutdown, reaping any that already finished
+    fn spawn<F>(&self, task: F)
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let mut tasks = self.tasks.lock().unwrap();
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(task);
+    }
+
+    /// Check whether shutdown has been requested
+    fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    /// Build the handshake announcing this node
+    fn handshake(&self) -> NetworkMessage {
+        let blockchain_guard = self.blockchain.lock();
+        NetworkMessage::Handshake {
+            peer_id: self.node_id.clone(),
+            version: crate::PROTOCOL_VERSION.to_string(),
+            blockchain_height: blockchain_guard.chain_length(),
+            total_work: blockchain_guard.total_work(),
+        }
+    }
+
+    /// Mark every peer known at `addr` with a new connection state
+    fn set_peer_state(&self, addr: &SocketAddr, state: ConnectionState) {
+        let mut peers_guard = self.peers.lock().unwrap();
+        for peer in peers_guard.values_mut().filter(|peer| peer.address == *addr) {
+            peer.connection_state = state.clone();
+        }
+    }
+
+    /// Lower the reputation of every peer known at `addr` and mark it disconnected
+    fn penalize_peer(&self, addr: &SocketAddr) {
+        self.lower_reputation(addr, self.config.limit_violation_penalty);
+        self.set_peer_state(addr, ConnectionState::Disconnected);
+    }
+
+    /// Lower the reputation of every peer known at `addr` by `penalty`
+    fn lower_reputation(&self, addr: &SocketAddr, penalty: f64) {
+        let mut peers_guard = self.peers.lock().unwrap();
+        for peer in peers_guard.values_mut().filter(|peer| peer.address == *addr) {
+            peer.reputation_score = (peer.reputation_score - penalty).max(0.0);
+        }
+    }
+
+    /// Forget the connection and peer entries for `addr`, remembering the
+    /// peer in the peer store
+    fn remove_peer(&self, addr: &SocketAddr) {
+        self.connections.lock().unwrap().remove(addr);
+        let mut store = self.peer_store.lock().unwrap();
+        self.peers.lock().unwrap().retain(|_, peer| {
+            if peer.address != *addr {
+                return true;
+            }
+            store.record(peer);
+            false
+        });
+    }
+
+    /// Record every current peer in the peer store and write it to disk
+    fn flush_peer_store(&self, path: &Path) {
+        if let Err(e) = save_peer_store(&self.peers, &self.peer_store, path) {
+            warn!(error = %e, "Failed to save peers");
+        }
+    }
+}
+
+/// Record every current peer in `store` and write it to `path`
+fn save_peer_store(
+    peers: &Mutex<HashMap<String, PeerInfo>>,
+    store: &Mutex<PeerStore>,
+    path: &Path,
+) -> SierpinskiResult<()> {
+    let mut store = store.lock().unwrap();
+    for peer in peers.lock().unwrap().values() {
+        store.record(peer);
+    }
+    store.save(path)
+}
+
 /// P2P network node
 pub struct NetworkNode {
     pub node_id: String,
     pub listen_address: SocketAddr,
     pub peers: Arc<Mutex<HashMap<String, PeerInfo>>>,
-    pub blockchain: Arc<Mutex<TriadChainBlockchain>>,
-    pub message_handlers: HashMap<String, Box<dyn Fn(&NetworkMessage) + Send + Sync>>,
+    pub blockchain: SharedBlockchain,
+    /// Persistent connections keyed by peer socket address
+    pub connections: Arc<Mutex<HashMap<SocketAddr, PeerConnection>>>,
+    pub message_handlers: HashMap<String, MessageHandler>,
+    /// Limits applied to incoming peer traffic
+    pub config: NetworkConfig,
+    shutdown: Arc<watch::Sender<bool>>,
+    tasks: Arc<Mutex<JoinSet<()>>>,
+    sync: Arc<Mutex<SyncSession>>,
+    /// Hashes of blocks already announced to or by us, so gossip stops
+    seen_blocks: Arc<Mutex<HashSet<String>>>,
+    /// Peers remembered across restarts, loaded from `config.peers_file`
+    pub peer_store: Arc<Mutex<PeerStore>>,
+    /// Miner solving challenges from peers; without one they are ignored
+    miner: Option<Arc<GeometricMiner>>,
+    /// Challenges we issued, by id
+    challenges: Arc<Mutex<HashMap<String, IssuedChallenge>>>,
+    /// Newest challenge received from a peer, for local miners to pick up
+    latest_challenge: Arc<Mutex<Option<GeometricChallenge>>>,
+    /// Peers asked for a snapshot by `fast_sync` that have yet to answer;
+    /// snapshots from anyone else are ignored
+    snapshot_requests: Arc<Mutex<HashSet<SocketAddr>>>,
+}
+
+/// Handle to a running node, used to stop it
+pub struct NodeHandle {
+    /// Address the listener actually bound to
+    pub local_addr: SocketAddr,
+    shutdown: Arc<watch::Sender<bool>>,
+    tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl NodeHandle {
+    /// Stop accepting connections, say goodbye to every peer and wait for
+    /// all network tasks to finish
+    pub async fn shutdown(self) -> SierpinskiResult<()> {
+        self.shutdown.send_replace(true);
+
+        let drained = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+            // Connection tasks may still be registering while the accept loop winds down
+            loop {
+                let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+                if tasks.is_empty() {
+                    break;
+                }
+                while tasks.join_next().await.is_some() {}
+            }
+        })
+        .await;
+
+        drained.map_err(|_| SierpinskiError::validation("Timed out waiting for network tasks to stop"))
+    }
 }
 
 impl NetworkNode {
     /// Create a new network node
-    pub fn new(listen_address: SocketAddr, blockchain: Arc<Mutex<TriadChainBlockchain>>) -> Self {
+    pub fn new(
+        listen_address: SocketAddr,
+        blockchain: SharedBlockchain,
+        config: NetworkConfig,
+    ) -> Self {
+        // A damaged peers file only costs us the remembered peers
+        let peer_store = match &config.peers_file {
+            Some(path) => PeerStore::load(path, config.peer_ttl_secs).unwrap_or_else(|e| {
+                warn!(error = %e, "Ignoring peers file");
+                PeerStore::default()
+            }),
+            None => PeerStore::default(),
+        };
+
         NetworkNode {
             node_id: format!("node_{}", Uuid::new_v4()),
             listen_address,
             peers: Arc::new(Mutex::new(HashMap::new())),
             blockchain,
+            connections: Arc::new(Mutex::new(HashMap::new())),
             message_handlers: HashMap::new(),
+            config,
+            shutdown: Arc::new(watch::channel(false).0),
+            tasks: Arc::new(Mutex::new(JoinSet::new())),
+            sync: Arc::new(Mutex::new(SyncSession::default())),
+            seen_blocks: Arc::new(Mutex::new(HashSet::new())),
+            peer_store: Arc::new(Mutex::new(peer_store)),
+            miner: None,
+            challenges: Arc::new(Mutex::new(HashMap::new())),
+            latest_challenge: Arc::new(Mutex::new(None)),
+            snapshot_requests: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Solve mining challenges from peers with `miner`
+    pub fn with_miner(mut self, miner: Arc<GeometricMiner>) -> Self {
+        self.miner = Some(miner);
+        self
+    }
+
+    /// Snapshot of the shared state used by connection tasks
+    fn context(&self) -> NodeContext {
+        NodeContext {
+            node_id: self.node_id.clone(),
+            peers: Arc::clone(&self.peers),
+            blockchain: self.blockchain.clone(),
+            connections: Arc::clone(&self.connections),
+            shutdown: self.shutdown.subscribe(),
+            tasks: Arc::clone(&self.tasks),
+            config: self.config.clone(),
+            sync: Arc::clone(&self.sync),
+            seen_blocks: Arc::clone(&self.seen_blocks),
+            peer_store: Arc::clone(&self.peer_store),
+            miner: self.miner.clone(),
+            challenges: Arc::clone(&self.challenges),
+            latest_challenge: Arc::clone(&self.latest_challenge),
+            snapshot_requests: Arc::clone(&self.snapshot_requests),
         }
     }
 
     /// Start the network node
-    pub async fn start(&self) -> SierpinskiResult<()> {
+    pub async fn start(&self) -> SierpinskiResult<NodeHandle> {
         let listener = TcpListener::bind(self.listen_address).await
-            .map_err(|e| SierpinskiError::validation(&format!("Failed to bind to address: {}", e)))?;
+            .map_err(|e| SierpinskiError::validation(format!("Failed to bind to address: {}", e)))?;
+        let local_addr = listener.local_addr()
+            .map_err(|e| SierpinskiError::validation(format!("Failed to read bound address: {}", e)))?;
 
-        println!("🌐 Network node {} listening on {}", self.node_id, self.listen_address);
+        info!(node_id = %self.node_id, %local_addr, "Network node listening");
 
         // Start accepting connections
-        tokio::spawn({
-            let peers = Arc::clone(&self.peers);
-            let blockchain = Arc::clone(&self.blockchain);
-            let node_id = self.node_id.clone();
-            
-            async move {
-                loop {
-                    match listener.accept().await {
+        let context = self.context();
+        let mut shutdown = self.shutdown.subscribe();
+        self.context().spawn(async move {
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => match accepted {
                         Ok((stream, addr)) => {
-                            println!("📡 New connection from {}", addr);
-                            
-                            let peers_clone = Arc::clone(&peers);
-                            let blockchain_clone = Arc::clone(&blockchain);
-                            let node_id_clone = node_id.clone();
-                            
-                            tokio::spawn(async move {
-                                if let Err(e) = Self::handle_peer_connection(
-                                    stream, 
-                                    addr, 
-                                    peers_clone, 
-                                    blockchain_clone,
-                                    node_id_clone
-                                ).await {
-                                    println!("❌ Error handling peer {}: {}", addr, e);
-                                }
-                            });
+                            debug!(%addr, "New connection");
+                            let span = info_span!("peer", %addr, peer_id = field::Empty);
+                            context.spawn(Self::handle_peer_connection(stream, addr, context.clone()).instrument(span));
                         }
                         Err(e) => {
-                            println!("❌ Failed to accept connection: {}", e);
+                            error!(error = %e, "Failed to accept connection");
                         }
-                    }
+                    },
+                    _ = wait_for_shutdown(&mut shutdown) => break,
                 }
             }
+            info!(%local_addr, "Stopped accepting connections");
         });
 
-        Ok(())
+        self.spawn_peer_store_tasks();
+
+        Ok(NodeHandle {
+            local_addr,
+            shutdown: Arc::clone(&self.shutdown),
+            tasks: Arc::clone(&self.tasks),
+        })
+    }
+
+    /// Dial the best remembered peers, and keep the peers file up to date
+    /// until shutdown if there is one
+    fn spawn_peer_store_tasks(&self) {
+        let candidates = self.peer_store.lock().unwrap()
+            .bootstrap_candidates(self.config.bootstrap_peer_count, self.config.min_bootstrap_reputation);
+        for addr in candidates {
+            let context = self.context();
+            self.context().spawn(async move {
+                // Failures are logged by `dial`; the peer is simply skipped
+                let _ = Self::dial(addr, &context).await;
+            });
+        }
+
+        let Some(path) = self.config.peers_file.clone() else {
+            return;
+        };
+        let context = self.context();
+        let mut shutdown = self.shutdown.subscribe();
+        let interval = Duration::from_secs(self.config.peer_flush_interval_secs.max(1));
+        self.context().spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => context.flush_peer_store(&path),
+                    _ = wait_for_shutdown(&mut shutdown) => break,
+                }
+            }
+            context.flush_peer_store(&path);
+        });
     }
 
     /// Handle incoming peer connection
-    async fn handle_peer_connection(
-        mut stream: TcpStream,
+    async fn handle_peer_connection(stream: TcpStream, addr: SocketAddr, context: NodeContext) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = PeerLines::new(reader, context.config.max_message_size);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+
+        // Register the inbound socket so replies and broadcasts reuse it
+        context.connections.lock().unwrap().insert(addr, PeerConnection { address: addr, sender });
+
+        match Self::run_connection(&mut lines, &mut writer, &mut receiver, addr, &context).await {
+            // Keep the peer entry so its lowered reputation stays visible
+            ConnectionEnd::Misbehaved => {
+                context.connections.lock().unwrap().remove(&addr);
+            }
+            // Remove peer on disconnection
+            _ => context.remove_peer(&addr),
+        }
+    }
+
+    /// Pump a persistent connection: answer incoming messages and deliver queued ones
+    async fn run_connection(
+        lines: &mut PeerLines,
+        writer: &mut OwnedWriteHalf,
+        receiver: &mut mpsc::UnboundedReceiver<NetworkMessage>,
         addr: SocketAddr,
-        peers: Arc<Mutex<HashMap<String, PeerInfo>>>,
-        blockchain: Arc<Mutex<TriadChainBlockchain>>,
-        node_id: String,
-    ) -> SierpinskiResult<()> {
-        let mut buffer = vec![0; 4096];
-        
+        context: &NodeContext,
+    ) -> ConnectionEnd {
+        let mut shutdown = context.shutdown.clone();
+        let mut limiter = PeerRateLimiter::new(&context.config);
+
         loop {
-            match stream.read(&mut buffer).await {
-                Ok(0) => {
-                    // Connection closed
-                    println!("🔌 Connection closed by {}", addr);
-                    break;
-                }
-                Ok(n) => {
-                    let data = &buffer[..n];
-                    
-                    // Try to deserialize message
-                    if let Ok(message) = serde_json::from_slice::<NetworkMessage>(data) {
-                        let response = Self::handle_message(
-                            &message, 
-                            &addr, 
-                            &peers, 
-                            &blockchain,
-                            &node_id
-                        ).await;
-                        
-                        if let Some(response_msg) = response {
-                            let response_data = serde_json::to_vec(&response_msg)
-                                .map_err(|e| SierpinskiError::validation(&format!("Serialization error: {}", e)))?;
-                            
-                            stream.write_all(&response_data).await
-                                .map_err(|e| SierpinskiError::validation(&format!("Write error: {}", e)))?;
+            tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => {
+                        if !limiter.allow(line.len()) {
+                            return Self::disconnect_misbehaving(writer, addr, context, "Rate limit exceeded").await;
+                        }
+
+                        let Ok(message) = serde_json::from_str::<NetworkMessage>(&line) else {
+                            debug!(%addr, "Dropping undecodable message");
+                            context.lower_reputation(&addr, MALFORMED_PAYLOAD_PENALTY);
+                            continue;
+                        };
+
+                        if let NetworkMessage::Disconnect { reason } = &message {
+                            debug!(%reason, "Peer disconnected");
+                            return ConnectionEnd::Closed;
+                        }
+
+                        if let Some(response) = Self::handle_message(&message, &addr, context) {
+                            if let Err(e) = write_message(writer, &response).await {
+                                warn!(error = %e, "Failed to reply");
+                                return ConnectionEnd::Dropped(None);
+                            }
                         }
                     }
-                }
-                Err(e) => {
-                    println!("❌ Read error from {}: {}", addr, e);
-                    break;
+                    Ok(None) => {
+                        // Connection closed
+                        debug!("Connection closed by peer");
+                        return ConnectionEnd::Dropped(None);
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                        let reason = format!("Invalid message: {}", e);
+                        return Self::disconnect_misbehaving(writer, addr, context, &reason).await;
+                    }
+                    Err(e) => {
+                        warn!(error = %e, "Read error");
+                        return ConnectionEnd::Dropped(None);
+                    }
+                },
+                queued = receiver.recv() => match queued {
+                    Some(message) => {
+                        if let Err(e) = write_message(writer, &message).await {
+                            warn!(error = %e, "Failed to send");
+                            return ConnectionEnd::Dropped(Some(Box::new(message)));
+                        }
+                    }
+                    None => return ConnectionEnd::Closed,
+                },
+                _ = wait_for_shutdown(&mut shutdown) => {
+                    // Best effort: the peer may already be gone
+                    let goodbye = NetworkMessage::Disconnect {
+                        reason: "Node shutting down".to_string(),
+                    };
+                    let _ = write_message(writer, &goodbye).await;
+                    let _ = writer.shutdown().await;
+                    return ConnectionEnd::Closed;
                 }
             }
         }
-        
-        // Remove peer on disconnection
-        {
-            let mut peers_guard = peers.lock().unwrap();
-            peers_guard.retain(|_, peer| peer.address != addr);
-        }
-        
-        Ok(())
     }
 
-    /// Handle network message
-    async fn handle_message(
-        message: &NetworkMessage,
-        sender_addr: &SocketAddr,
-        peers: &Arc<Mutex<HashMap<String, PeerInfo>>>,
-        blockchain: &Arc<Mutex<TriadChainBlockchain>>,
-        node_id: &str,
-    ) -> Option<NetworkMessage> {
-        match message {
-            NetworkMessage::Handshake { peer_id, version, blockchain_height } => {
-                println!("🤝 Handshake from peer {}", peer_id);
-                
-                // Add peer to our list
-                {
-                    let mut peers_guard = peers.lock().unwrap();
-                    peers_guard.insert(peer_id.clone(), PeerInfo {
-                        peer_id: peer_id.clone(),
-                        address: *sender_addr,
-                        version: version.clone(),
-                        blockchain_height: *blockchain_height,
-                        last_seen: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs(),
-                        reputation_score: 0.5, // Neutral starting reputation
-                        connection_state: ConnectionState::Connected,
-                    });
-                }
-                
-                // Respond with our handshake
-                let blockchain_guard = blockchain.lock().unwrap();
-                Some(NetworkMessage::Handshake {
-                    peer_id: node_id.to_string(),
-                    version: "0.1.0".to_string(),
-                    blockchain_height: blockchain_guard.blocks.len() as u64,
+    /// Tell a peer that broke a traffic limit why it is being dropped, close
+    /// the connection and lower its reputation
+    async fn disconnect_misbehaving(
+        writer: &mut OwnedWriteHalf,
+        addr: SocketAddr,
+        context: &NodeContext,
+        reason: &str,
+    ) -> ConnectionEnd {
+        warn!(%addr, %reason, "Disconnecting misbehaving peer");
+        context.penalize_peer(&addr);
+
+        // Best effort: the peer may not be reading
+        let goodbye = NetworkMessage::Disconnect { reason: reason.to_string() };
+        let _ = write_message(writer, &goodbye).await;
+        let _ = writer.shutdown().await;
+        ConnectionEnd::Misbehaved
+    }
+
+    /// Adopt received blocks if they extend our chain or form a heavier
+    /// fork, returning whether our chain changed
+    fn adopt_blocks(blockchain: &SharedBlockchain, blocks: &[Block]) -> bool {
+        if blocks.is_empty() {
+            return false;
+        }
+
+        let mut blockchain_guard = blockchain.lock();
+        match blockchain_guard.try_reorg(blocks) {
+            Ok(true) => {
+                blockchain_guard.connect_orphans();
+                info!(height = blockchain_guard.tip_height(), "Adopted received blocks");
+                true
+            }
+            Ok(false) => {
+                debug!("Received blocks do not improve our chain");
+                false
+            }
+            Err(e) => {
+                warn!(error = %e, "Invalid blocks received");
+                false
+            }
+        }
+    }
+
+    /// Record a peer announced by a handshake or handshake acknowledgement
+    fn register_peer(
+        peers: &Arc<Mutex<HashMap<String, PeerInfo>>>,
+        sender_addr: &SocketAddr,
+        peer_id: &str,
+        version: &str,
+        blockchain_height: u64,
+        total_work: u128,
+    ) {
+        let mut peers_guard = peers.lock().unwrap();
+        peers_guard.insert(peer_id.to_string(), PeerInfo {
+            peer_id: peer_id.to_string(),
+            address: *sender_addr,
+            version: version.to_string(),
+            blockchain_height,
+            total_work,
+            last_seen: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            reputation_score: NEUTRAL_REPUTATION,
+            connection_state: ConnectionState::Connected,
+        });
+    }
+
+    /// Handle network message
+    fn handle_message(
+        message: &NetworkMessage,
+        sender_addr: &SocketAddr,
+        context: &NodeContext,
+    ) -> Option<NetworkMessage> {
+        let peers = &context.peers;
+        let blockchain = &context.blockchain;
+
+        match message {
+            NetworkMessage::Handshake { peer_id, version, blockchain_height, total_work } => {
+                Span::current().record("peer_id", peer_id.as_str());
+                debug!(%peer_id, "Handshake");
+
+                // Add peer to our list
+                Self::register_peer(peers, sender_addr, peer_id, version, *blockchain_height, *total_work);
+
+                // Acknowledge with our own details
+                let blockchain_guard = blockchain.lock();
+                Some(NetworkMessage::HandshakeAck {
+                    peer_id: context.node_id.clone(),
+                    version: crate::PROTOCOL_VERSION.to_string(),
+                    blockchain_height: blockchain_guard.chain_length(),
+                    total_work: blockchain_guard.total_work(),
                 })
             }
 
+            NetworkMessage::HandshakeAck { peer_id, version, blockchain_height, total_work } => {
+                Span::current().record("peer_id", peer_id.as_str());
+                debug!(%peer_id, "Handshake acknowledged");
+                Self::register_peer(peers, sender_addr, peer_id, version, *blockchain_height, *total_work);
+                None
+            }
+
             NetworkMessage::BlockRequest { start_height, count } => {
-                println!("📦 Block request: start={}, count={}", start_height, count);
+                debug!(start_height, count, "Block request");
                 
-                let blockchain_guard = blockchain.lock().unwrap();
+                let blockchain_guard = blockchain.lock();
                 let blocks: Vec<Block> = blockchain_guard.blocks
                     .iter()
-                    .skip(*start_height as usize)
+                    .skip_while(|block| block.height < *start_height)
+                    .take_while(|block| !blockchain_guard.is_pruned(block.height))
                     .take(*count as usize)
                     .cloned()
                     .collect();
@@ -251,21 +1179,207 @@ impl NetworkNode {
                 Some(NetworkMessage::BlockResponse { blocks })
             }
 
+            NetworkMessage::BlockResponse { blocks } => {
+                debug!(count = blocks.len(), "Received blocks");
+                if context.sync.lock().unwrap().is_awaiting(SyncPhase::Blocks, sender_addr) {
+                    return Self::handle_sync_blocks(blocks, sender_addr, context);
+                }
+                Self::adopt_blocks(blockchain, blocks);
+                None
+            }
+
+            NetworkMessage::HeaderRequest { start_height, count } => {
+                debug!(start_height, count, "Header request");
+
+                let blockchain_guard = blockchain.lock();
+                let headers: Vec<SyncHeader> = blockchain_guard.blocks
+                    .iter()
+                    .skip_while(|block| block.height < *start_height)
+                    .take((*count).min(MAX_HEADERS_PER_RESPONSE) as usize)
+                    .map(SyncHeader::from_block)
+                    .collect();
+
+                Some(NetworkMessage::HeaderResponse { headers })
+            }
+
+            NetworkMessage::HeaderResponse { headers } => {
+                debug!(count = headers.len(), "Received headers");
+                Self::handle_sync_headers(headers, sender_addr, context)
+            }
+
             NetworkMessage::NewBlock { block } => {
-                println!("🆕 Received new block at height {}", block.height);
+                // Gossip reaches us along several paths; handle each block
+                // until one copy is accepted, so a copy that failed to
+                // validate does not shadow a good one
+                if context.seen_blocks.lock().unwrap().contains(&block.hash()) {
+                    return None;
+                }
+                let _span = debug_span!("new_block", height = block.height).entered();
+                debug!("Received new block");
+
+                // Hold blocks that arrive ahead of their parents until the
+                // parents do
+                let parent_known = blockchain.read(|chain| chain.get_block_by_hash(&block.header.previous_hash).is_some());
+                if !parent_known {
+                    match blockchain.with(|chain| chain.add_block_or_orphan(block.clone(), &sender_addr.to_string())) {
+                        BlockAcceptance::Rejected(reason) => debug!(%reason, "Dropped orphan block"),
+                        _ => {
+                            mark_block_seen(&context.seen_blocks, block.hash());
+                        }
+                    }
+                    return None;
+                }
                 
-                // Validate and potentially add to blockchain
-                let _blockchain_guard = blockchain.lock().unwrap();
-                if let Err(e) = block.validate() {
-                    println!("❌ Invalid block received: {}", e);
-                } else {
-                    // In a full implementation, we'd verify the block fits our chain
-                    println!("✅ Valid block received (validation successful)");
+                // Extending our tip is just a fork with no competing blocks;
+                // pass on only blocks we accepted
+                if Self::adopt_blocks(blockchain, std::slice::from_ref(block)) {
+                    mark_block_seen(&context.seen_blocks, block.hash());
+                    queue_for_peers(&context.connections, message, Some(sender_addr));
                 }
                 
                 None // No response needed
             }
 
+            NetworkMessage::TransactionBroadcast { transaction_id, transaction_data } => {
+                let transaction = match serde_json::from_slice::<TriangleTransaction>(transaction_data) {
+                    Ok(transaction) if transaction.id.to_string() == *transaction_id => transaction,
+                    _ => {
+                        warn!(%transaction_id, "Malformed transaction broadcast");
+                        context.lower_reputation(sender_addr, MALFORMED_PAYLOAD_PENALTY);
+                        return None;
+                    }
+                };
+                debug!(%transaction_id, "Received transaction");
+
+                // Gossip reaches us along several paths; pass each transaction on once
+                let added = blockchain.with(|chain| {
+                    let known = chain.mempool.iter().any(|pending| pending.id == transaction.id)
+                        || chain.transaction_height(&transaction.id).is_some();
+                    if known {
+                        return Ok(false);
+                    }
+                    chain.add_transaction(transaction).map(|()| true)
+                });
+                match added {
+                    Ok(true) => queue_for_peers(&context.connections, message, Some(sender_addr)),
+                    Ok(false) => {}
+                    Err(e) => debug!(error = %e, "Rejected broadcast transaction"),
+                }
+                None
+            }
+
+            NetworkMessage::MiningChallenge { challenge } => {
+                debug!(challenge_id = %challenge.challenge_id, "Received mining challenge");
+                // Without the header there is no block to solve
+                if challenge.block_header.is_none() {
+                    context.lower_reputation(sender_addr, MALFORMED_PAYLOAD_PENALTY);
+                    return None;
+                }
+                {
+                    let mut latest = context.latest_challenge.lock().unwrap();
+                    if latest.as_ref().is_none_or(|current| current.timestamp <= challenge.timestamp) {
+                        *latest = Some(challenge.clone());
+                    }
+                }
+                Self::solve_peer_challenge(challenge, sender_addr, context);
+                None
+            }
+
+            NetworkMessage::ChallengeSolution { challenge_id, nonce, proof_hash } => {
+                debug!(%challenge_id, nonce, "Received challenge solution");
+                Self::accept_challenge_solution(challenge_id, *nonce, proof_hash, sender_addr, context);
+                None
+            }
+
+            NetworkMessage::SnapshotRequest { recent_blocks } => {
+                debug!(recent_blocks, "Snapshot request");
+
+                let blockchain_guard = blockchain.lock();
+                let count = (*recent_blocks).clamp(1, MAX_SNAPSHOT_RECENT_BLOCKS) as usize;
+                let start = blockchain_guard.blocks
+                    .len()
+                    .saturating_sub(count)
+                    .max(blockchain_guard.blocks.partition_point(|block| blockchain_guard.is_pruned(block.height)));
+
+                Some(NetworkMessage::SnapshotResponse {
+                    snapshot: Box::new(blockchain_guard.snapshot()),
+                    recent_blocks: blockchain_guard.blocks[start..].to_vec(),
+                })
+            }
+
+            NetworkMessage::SnapshotResponse { snapshot, recent_blocks } => {
+                debug!(height = snapshot.height, "Received snapshot");
+
+                if !context.snapshot_requests.lock().unwrap().remove(sender_addr) {
+                    warn!(peer = %sender_addr, "Ignoring snapshot we did not ask for");
+                    return None;
+                }
+
+                // The declared total work cannot be checked, so only the
+                // work of blocks we can see counts
+                let mut blockchain_guard = blockchain.lock();
+                let work = blockchain_guard.verifiable_work(recent_blocks);
+                if work <= blockchain_guard.total_work() {
+                    debug!("Snapshot does not improve our chain");
+                    return None;
+                }
+                let restored = TriadChainBlockchain::from_snapshot_with_params(
+                    (**snapshot).clone(),
+                    recent_blocks.clone(),
+                    blockchain_guard.difficulty_params.clone(),
+                );
+                match restored {
+                    Ok(mut restored) => {
+                        restored.cumulative_difficulty = work;
+                        restored.keep_settings_of(&blockchain_guard);
+                        *blockchain_guard = restored;
+                        info!(height = snapshot.height, "Fast-synced from snapshot");
+                    }
+                    Err(e) => warn!(error = %e, "Invalid snapshot received"),
+                }
+                None
+            }
+
+            // Queries only read the chain
+            NetworkMessage::GetStats => {
+                let stats = blockchain.read(|chain| chain.stats());
+                Some(NetworkMessage::StatsResponse { stats })
+            }
+
+            NetworkMessage::GetBalance { address } => {
+                let balance = blockchain.read(|chain| chain.get_balance(address));
+                Some(NetworkMessage::BalanceResponse { address: address.clone(), balance })
+            }
+
+            NetworkMessage::GetBlock { height } => {
+                // A pruned block would arrive without its transactions
+                let block = blockchain.read(|chain| {
+                    chain.get_block_by_height(*height).filter(|_| !chain.is_pruned(*height)).cloned()
+                });
+                Some(NetworkMessage::GetBlockResponse { height: *height, block })
+            }
+
+            NetworkMessage::PeerDiscovery { known_peers } => {
+                // Peers gossip a bounded list of dialable addresses
+                let malformed = known_peers.len() > MAX_DISCOVERED_PEERS
+                    || known_peers.iter().any(|addr| addr.ip().is_unspecified() || addr.port() == 0);
+                if malformed {
+                    warn!(count = known_peers.len(), "Malformed peer discovery");
+                    context.lower_reputation(sender_addr, MALFORMED_PAYLOAD_PENALTY);
+                }
+
+                let now = unix_now();
+                let mut store = context.peer_store.lock().unwrap();
+                let added = known_peers
+                    .iter()
+                    .take(MAX_DISCOVERED_PEERS)
+                    .filter(|addr| !addr.ip().is_unspecified() && addr.port() != 0)
+                    .filter(|addr| store.add_address(**addr, now))
+                    .count();
+                debug!(added, "Merged discovered peers");
+                None
+            }
+
             NetworkMessage::Ping => {
                 Some(NetworkMessage::Pong)
             }
@@ -284,8 +1398,257 @@ impl NetworkNode {
                 None
             }
 
-            _ => None // Handle other message types
+            // Answers to queries we never send from a node, and goodbyes,
+            // which the connection loop handles before dispatch
+            NetworkMessage::StatsResponse { .. }
+            | NetworkMessage::BalanceResponse { .. }
+            | NetworkMessage::GetBlockResponse { .. }
+            | NetworkMessage::Disconnect { .. } => None,
+        }
+    }
+
+    /// Hand a peer's challenge to our miner, if we have one, and send the
+    /// solution back to the peer once found
+    fn solve_peer_challenge(challenge: &GeometricChallenge, sender_addr: &SocketAddr, context: &NodeContext) {
+        let Some(miner) = &context.miner else {
+            return;
+        };
+        if !miner.set_external_challenge(challenge.clone()) {
+            debug!(challenge_id = %challenge.challenge_id, "Ignoring stale or duplicate challenge");
+            return;
+        }
+
+        let solver = Arc::clone(miner);
+        let miner = Arc::clone(miner);
+        let connections = Arc::clone(&context.connections);
+        let originator = *sender_addr;
+        let mut shutdown = context.shutdown.clone();
+        context.spawn(async move {
+            let solving = tokio::task::spawn_blocking(move || solver.solve_external_challenge());
+            let solution = tokio::select! {
+                solved = solving => solved.ok().flatten(),
+                _ = wait_for_shutdown(&mut shutdown) => {
+                    // Stops the solver at its next nonce
+                    miner.clear_external_challenge();
+                    None
+                }
+            };
+
+            // Unsolved challenges were superseded or ran out of nonces
+            let Some(ChallengeSolution { challenge_id, nonce, proof_hash }) = solution else {
+                return;
+            };
+            debug!(%challenge_id, nonce, "Solved peer challenge");
+            let connection = connections.lock().unwrap().get(&originator).cloned();
+            let sent = connection.map(|connection| {
+                connection.send(NetworkMessage::ChallengeSolution { challenge_id, nonce, proof_hash })
+            });
+            if !matches!(sent, Some(Ok(()))) {
+                warn!(%originator, "Could not return challenge solution");
+            }
+        });
+    }
+
+    /// Complete the block behind one of our challenges with a peer's
+    /// solution, then add it to our chain and announce it
+    ///
+    /// Solutions to unknown challenges, or to ones whose template no longer
+    /// extends our tip, are ignored.
+    fn accept_challenge_solution(
+        challenge_id: &str,
+        nonce: u64,
+        proof_hash: &str,
+        sender_addr: &SocketAddr,
+        context: &NodeContext,
+    ) {
+        let Some(issued) = context.challenges.lock().unwrap().get(challenge_id).cloned() else {
+            debug!(%challenge_id, "Ignoring solution to unknown challenge");
+            return;
+        };
+
+        let mut blockchain_guard = context.blockchain.lock();
+        if blockchain_guard.blocks.last().map(Block::hash) != Some(issued.template.header.previous_hash.clone()) {
+            drop(blockchain_guard);
+            context.challenges.lock().unwrap().remove(challenge_id);
+            debug!(%challenge_id, "Ignoring solution to challenge superseded by a newer block");
+            return;
+        }
+
+        let work = GeometricMiner::verify_geometric_work(&issued.challenge, nonce);
+        if !work.is_ok_and(|work| work.total_area_preserved && work.geometric_hash == proof_hash) {
+            drop(blockchain_guard);
+            warn!(%challenge_id, "Rejecting invalid challenge solution");
+            context.penalize_peer(sender_addr);
+            return;
+        }
+
+        let block = GeometricMiner::assemble_block(&issued.template, nonce, proof_hash.to_string());
+        if let Err(e) = blockchain_guard.accept_mined_block(block.clone()) {
+            warn!(%challenge_id, error = %e, "Challenge solution does not complete a valid block");
+            return;
+        }
+        drop(blockchain_guard);
+
+        // Every outstanding challenge was built on the old tip
+        context.challenges.lock().unwrap().clear();
+        mark_block_seen(&context.seen_blocks, block.hash());
+        queue_for_peers(&context.connections, &NetworkMessage::NewBlock { block }, None);
+    }
+
+    /// Validate a batch of sync headers, then ask for more headers or, once
+    /// all are in, for the blocks after the fork point
+    fn handle_sync_headers(
+        headers: &[SyncHeader],
+        sender_addr: &SocketAddr,
+        context: &NodeContext,
+    ) -> Option<NetworkMessage> {
+        let mut session = context.sync.lock().unwrap();
+        if !session.is_awaiting(SyncPhase::Headers, sender_addr) {
+            return None;
+        }
+        let blockchain_guard = context.blockchain.lock();
+
+        let batch = context.config.sync_header_batch.clamp(1, MAX_HEADERS_PER_RESPONSE);
+        if headers.len() > batch as usize || session.headers.len() + headers.len() > MAX_SYNC_HEADERS {
+            session.fail(format!("Peer sent more headers than requested ({})", headers.len()));
+            return None;
+        }
+
+        for header in headers {
+            let (expected_height, previous_hash) = match session.headers.last() {
+                Some(last) => (last.height + 1, Some(last.hash.clone())),
+                None => {
+                    let start = session.start_height;
+                    let anchor = start.checked_sub(1).and_then(|height| blockchain_guard.get_block_by_height(height));
+                    (start, anchor.map(Block::hash))
+                }
+            };
+
+            if header.height != expected_height {
+                session.fail(format!("Expected header {} but got {}", expected_height, header.height));
+                return None;
+            }
+            let announced = session.status.target_height;
+            if header.height >= announced {
+                session.fail(format!("Header {} is past the announced chain length {}", header.height, announced));
+                return None;
+            }
+            if previous_hash.as_ref() != Some(&header.header.previous_hash) {
+                session.fail(format!("Header {} does not link to the previous block", header.height));
+                return None;
+            }
+
+            // Targets follow from the blocks before each header, ours up to
+            // the start and the peer's after it
+            let Some(expected) = session.next_target.or_else(|| blockchain_guard.target_at_height(header.height)) else {
+                session.fail(format!("No known target for header {}", header.height));
+                return None;
+            };
+            if let Err(e) = header.verify(expected) {
+                session.fail(e.to_string());
+                return None;
+            }
+            session.headers.push(header.clone());
+
+            let start_height = session.start_height;
+            let next_target = blockchain_guard.target_after(header.height, expected, |height| match height.checked_sub(start_height) {
+                Some(index) => session.headers.get(index as usize).map(|header| header.header.timestamp),
+                None => blockchain_guard.get_block_by_height(height).map(|block| block.header.timestamp),
+            });
+            if next_target.is_none() {
+                session.fail(format!("Cannot retarget after header {}", header.height));
+                return None;
+            }
+            session.next_target = next_target;
+        }
+        session.status.headers_received = session.headers.len() as u64;
+
+        if headers.len() == batch as usize {
+            let next_height = session.headers.last().map_or(session.start_height, |last| last.height + 1);
+            return Some(NetworkMessage::HeaderRequest { start_height: next_height, count: batch });
         }
+
+        // Every header is in; skip the ones matching blocks we already have
+        let fork_index = session.headers.iter().position(|header| {
+            blockchain_guard.get_block_by_height(header.height).map(Block::hash).as_ref() != Some(&header.hash)
+        });
+        let Some(fork_index) = fork_index else {
+            info!("Blockchain is up to date");
+            session.complete();
+            return None;
+        };
+
+        session.headers.drain(..fork_index);
+        let fork_height = session.headers[0].height;
+        info!(fork_height, blocks = session.headers.len(), "Peer chain diverges, fetching blocks");
+
+        session.status.fork_height = Some(fork_height);
+        session.status.phase = SyncPhase::Blocks;
+        session.extends_tip = fork_height == blockchain_guard.tip_height() + 1;
+        session.next_block = 0;
+        Some(session.next_block_request(context.config.sync_block_batch.max(1)))
+    }
+
+    /// Check sync blocks against their headers and apply them, asking for the
+    /// next batch until the sync is done
+    fn handle_sync_blocks(
+        blocks: &[Block],
+        sender_addr: &SocketAddr,
+        context: &NodeContext,
+    ) -> Option<NetworkMessage> {
+        let mut session = context.sync.lock().unwrap();
+        if !session.is_awaiting(SyncPhase::Blocks, sender_addr) {
+            return None;
+        }
+        if blocks.is_empty() {
+            session.fail("Peer sent no blocks");
+            return None;
+        }
+        let mut blockchain_guard = context.blockchain.lock();
+
+        for block in blocks {
+            let matches_header = session.headers
+                .get(session.next_block)
+                .is_some_and(|header| header.height == block.height && header.hash == block.hash());
+            if !matches_header {
+                session.fail(format!("Block {} does not match the expected header", block.height));
+                return None;
+            }
+
+            if session.extends_tip {
+                if let Err(e) = blockchain_guard.try_add_block(block.clone()) {
+                    session.fail(format!("Rejected block {}: {}", block.height, e));
+                    return None;
+                }
+                session.status.blocks_applied += 1;
+            } else {
+                session.fork_blocks.push(block.clone());
+            }
+            session.next_block += 1;
+        }
+
+        if session.next_block < session.headers.len() {
+            return Some(session.next_block_request(context.config.sync_block_batch.max(1)));
+        }
+
+        if !session.extends_tip {
+            let fork_blocks = std::mem::take(&mut session.fork_blocks);
+            match blockchain_guard.try_reorg(&fork_blocks) {
+                Ok(true) => session.status.blocks_applied = fork_blocks.len() as u64,
+                Ok(false) => {
+                    session.fail("Peer chain does not carry more work than ours");
+                    return None;
+                }
+                Err(e) => {
+                    session.fail(format!("Rejected fork: {}", e));
+                    return None;
+                }
+            }
+        }
+
+        info!(height = blockchain_guard.tip_height(), "Synced");
+        session.complete();
+        None
     }
 
     /// Find peer ID by address
@@ -300,106 +1663,326 @@ impl NetworkNode {
             .map(|(id, _)| id.clone())
     }
 
-    /// Connect to a peer
+    /// Connect to a peer, keeping the socket open for later sends
     pub async fn connect_to_peer(&self, peer_address: SocketAddr) -> SierpinskiResult<()> {
-        println!("🔗 Connecting to peer at {}", peer_address);
-        
-        match TcpStream::connect(peer_address).await {
-            Ok(mut stream) => {
-                // Send handshake
-                let blockchain_guard = self.blockchain.lock().unwrap();
-                let handshake = NetworkMessage::Handshake {
-                    peer_id: self.node_id.clone(),
-                    version: "0.1.0".to_string(),
-                    blockchain_height: blockchain_guard.blocks.len() as u64,
-                };
-                drop(blockchain_guard);
-                
-                let handshake_data = serde_json::to_vec(&handshake)
-                    .map_err(|e| SierpinskiError::validation(&format!("Serialization error: {}", e)))?;
-                
-                stream.write_all(&handshake_data).await
-                    .map_err(|e| SierpinskiError::validation(&format!("Write error: {}", e)))?;
-                
-                println!("✅ Connected to peer {}", peer_address);
-                Ok(())
+        Self::dial(peer_address, &self.context()).await
+    }
+
+    /// Connect to a seed list, e.g. at startup, returning how many
+    /// connections succeeded; failures are logged and skipped
+    pub async fn bootstrap(&self, addrs: &[SocketAddr]) -> usize {
+        let mut connected = 0;
+        for addr in addrs {
+            match self.connect_to_peer(*addr).await {
+                Ok(()) => connected += 1,
+                Err(e) => warn!(peer = %addr, error = %e, "Could not connect to seed peer"),
             }
+        }
+        connected
+    }
+
+    /// Record every current peer in the peer store and write it to `path`,
+    /// in the format of `config.peers_file`
+    pub fn save_peers(&self, path: &Path) -> SierpinskiResult<()> {
+        save_peer_store(&self.peers, &self.peer_store, path)
+    }
+
+    /// Add the peers saved at `path` by `save_peers` to the peer store,
+    /// skipping those older than `config.peer_ttl_secs`, and return how many
+    /// were new
+    pub fn load_peers(&self, path: &Path) -> SierpinskiResult<usize> {
+        let loaded = PeerStore::load(path, self.config.peer_ttl_secs)?;
+        Ok(self.peer_store.lock().unwrap().merge(loaded))
+    }
+
+    /// Open and register a persistent outbound connection, unless one to
+    /// `peer_address` already exists
+    async fn dial(peer_address: SocketAddr, context: &NodeContext) -> SierpinskiResult<()> {
+        if context.is_shutting_down() {
+            return Err(SierpinskiError::validation("Network node is shutting down"));
+        }
+
+        if context.connections.lock().unwrap().contains_key(&peer_address) {
+            return Ok(());
+        }
+
+        let span = info_span!("peer", addr = %peer_address, peer_id = field::Empty);
+        debug!(parent: &span, "Connecting to peer");
+
+        let (lines, writer) = match Self::open_connection(peer_address, context).instrument(span.clone()).await {
+            Ok(connection) => connection,
             Err(e) => {
-                println!("❌ Failed to connect to {}: {}", peer_address, e);
-                Err(SierpinskiError::validation(&format!("Connection failed: {}", e)))
+                warn!(parent: &span, error = %e, "Failed to connect");
+                return Err(e);
             }
+        };
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        context.connections.lock().unwrap().insert(
+            peer_address,
+            PeerConnection { address: peer_address, sender },
+        );
+
+        context.spawn(Self::maintain_outbound_connection(
+            peer_address,
+            lines,
+            writer,
+            receiver,
+            context.clone(),
+        ).instrument(span.clone()));
+
+        info!(parent: &span, "Connected to peer");
+        Ok(())
+    }
+
+    /// Dial a peer and complete the handshake exchange
+    async fn open_connection(
+        addr: SocketAddr,
+        context: &NodeContext,
+    ) -> SierpinskiResult<(PeerLines, OwnedWriteHalf)> {
+        let stream = TcpStream::connect(addr).await
+            .map_err(|e| SierpinskiError::validation(format!("Connection failed: {}", e)))?;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = PeerLines::new(reader, context.config.max_message_size);
+
+        write_message(&mut writer, &context.handshake()).await?;
+
+        let reply = tokio::time::timeout(HANDSHAKE_TIMEOUT, lines.next_line()).await
+            .map_err(|_| SierpinskiError::validation(format!("Handshake with {} timed out", addr)))?
+            .map_err(|e| SierpinskiError::validation(format!("Read error: {}", e)))?
+            .ok_or_else(|| SierpinskiError::validation(format!("Connection closed by {} during handshake", addr)))?;
+
+        let message: NetworkMessage = serde_json::from_str(&reply)
+            .map_err(|e| SierpinskiError::validation(format!("Deserialization error: {}", e)))?;
+        if !matches!(message, NetworkMessage::HandshakeAck { .. }) {
+            return Err(SierpinskiError::validation("Expected handshake acknowledgement"));
         }
+        Self::handle_message(&message, &addr, context);
+
+        Ok((lines, writer))
+    }
+
+    /// Keep an outbound connection alive, reconnecting with backoff when it drops
+    async fn maintain_outbound_connection(
+        addr: SocketAddr,
+        mut lines: PeerLines,
+        mut writer: OwnedWriteHalf,
+        mut receiver: mpsc::UnboundedReceiver<NetworkMessage>,
+        context: NodeContext,
+    ) {
+        loop {
+            let pending = match Self::run_connection(&mut lines, &mut writer, &mut receiver, addr, &context).await {
+                ConnectionEnd::Closed => {
+                    context.remove_peer(&addr);
+                    return;
+                }
+                ConnectionEnd::Misbehaved => {
+                    context.connections.lock().unwrap().remove(&addr);
+                    return;
+                }
+                ConnectionEnd::Dropped(pending) => pending,
+            };
+
+            context.set_peer_state(&addr, ConnectionState::Connecting);
+
+            let Some((new_lines, mut new_writer)) = Self::reconnect(addr, &context).await else {
+                warn!(attempts = MAX_RECONNECT_ATTEMPTS, "Giving up on peer");
+                context.remove_peer(&addr);
+                return;
+            };
+
+            // Retry the message that was in flight when the socket failed
+            if let Some(message) = pending {
+                if let Err(e) = write_message(&mut new_writer, &message).await {
+                    warn!(error = %e, "Failed to resend");
+                }
+            }
+
+            lines = new_lines;
+            writer = new_writer;
+        }
+    }
+
+    /// Re-dial a dropped peer with exponential backoff
+    async fn reconnect(addr: SocketAddr, context: &NodeContext) -> Option<(PeerLines, OwnedWriteHalf)> {
+        let mut backoff = INITIAL_RECONNECT_BACKOFF;
+        let mut shutdown = context.shutdown.clone();
+
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = wait_for_shutdown(&mut shutdown) => return None,
+            }
+
+            match Self::open_connection(addr, context).await {
+                Ok(connection) => {
+                    info!(attempt, "Reconnected to peer");
+                    return Some(connection);
+                }
+                Err(e) => {
+                    debug!(attempt, error = %e, "Reconnect failed");
+                    backoff *= 2;
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Queue a message for a single connected peer
+    pub fn send_to_peer(&self, peer_address: &SocketAddr, message: NetworkMessage) -> SierpinskiResult<()> {
+        let connection = self.connections.lock().unwrap().get(peer_address).cloned()
+            .ok_or_else(|| SierpinskiError::validation(format!("No connection to {}", peer_address)))?;
+        connection.send(message)
+    }
+
+    /// Ask peers to mine the next block on our tip, paying `miner_address`
+    ///
+    /// Sends a challenge for a fresh block template to every peer. A peer's
+    /// solution completes the block, which we add to our chain and announce.
+    /// Challenges issued on an earlier tip are dropped.
+    pub fn broadcast_mining_challenge(&self, miner_address: String) -> SierpinskiResult<GeometricChallenge> {
+        let (challenge, template) = self.blockchain.read(|chain| {
+            let template = chain.block_template(miner_address, chain.mempool.len())?;
+            Ok::<_, SierpinskiError>((GeometricMiner::issue_challenge(chain, &template), template))
+        })?;
+
+        let mut challenges = self.challenges.lock().unwrap();
+        challenges.retain(|_, issued| issued.template.header.previous_hash == template.header.previous_hash);
+        challenges.insert(challenge.challenge_id.clone(), IssuedChallenge {
+            challenge: challenge.clone(),
+            template,
+        });
+        drop(challenges);
+
+        info!(challenge_id = %challenge.challenge_id, "Broadcasting mining challenge");
+        queue_for_peers(&self.connections, &NetworkMessage::MiningChallenge { challenge: challenge.clone() }, None);
+        Ok(challenge)
+    }
+
+    /// Add a transaction to our mempool and gossip it to every connected peer
+    pub fn broadcast_transaction(&self, transaction: TriangleTransaction) -> SierpinskiResult<()> {
+        let message = NetworkMessage::transaction_broadcast(&transaction)?;
+        self.blockchain.with(|chain| chain.add_transaction(transaction))?;
+        queue_for_peers(&self.connections, &message, None);
+        Ok(())
+    }
+
+    /// Newest mining challenge received from a peer, if any
+    pub fn latest_challenge(&self) -> Option<GeometricChallenge> {
+        self.latest_challenge.lock().unwrap().clone()
     }
 
     /// Broadcast message to all connected peers
     pub async fn broadcast_message(&self, message: NetworkMessage) -> SierpinskiResult<()> {
-        let peers_guard = self.peers.lock().unwrap();
-        let peer_addresses: Vec<SocketAddr> = peers_guard.values()
-            .filter(|peer| matches!(peer.connection_state, ConnectionState::Ready | ConnectionState::Connected))
-            .map(|peer| peer.address)
-            .collect();
-        drop(peers_guard);
-        
-        let message_data = serde_json::to_vec(&message)
-            .map_err(|e| SierpinskiError::validation(&format!("Serialization error: {}", e)))?;
-        
-        for addr in peer_addresses {
-            if let Ok(mut stream) = TcpStream::connect(addr).await {
-                let _ = stream.write_all(&message_data).await;
-            }
+        // Our own block will be gossiped back to us
+        if let NetworkMessage::NewBlock { block } = &message {
+            mark_block_seen(&self.seen_blocks, block.hash());
         }
-        
+
+        queue_for_peers(&self.connections, &message, None);
         Ok(())
     }
 
-    /// Sync blockchain with peers
+    /// Sync blockchain with the heaviest peer, header first
+    ///
+    /// Headers are fetched in batches and their links and proof-of-work
+    /// checked before any block is requested; then only the blocks after the
+    /// point where the peer's chain leaves ours are fetched. The exchange runs
+    /// on the peer's connection task, so this returns once it has started;
+    /// follow it with `sync_status`.
     pub async fn sync_blockchain(&self) -> SierpinskiResult<()> {
-        println!("🔄 Starting blockchain sync...");
+        info!("Starting blockchain sync");
         
-        let peers_guard = self.peers.lock().unwrap();
-        if peers_guard.is_empty() {
-            return Err(SierpinskiError::validation("No peers available for sync"));
+        // Find the peer with the most cumulative work; height alone can
+        // favour a long chain of easy blocks
+        let peer = {
+            let peers_guard = self.peers.lock().unwrap();
+            peers_guard.values()
+                .max_by_key(|peer| peer.total_work)
+                .cloned()
+                .ok_or_else(|| SierpinskiError::validation("No peers available for sync"))?
+        };
+
+        let (our_height, our_work, first_height) = {
+            let blockchain_guard = self.blockchain.lock();
+            let first_height = blockchain_guard.blocks.first().map_or(0, |block| block.height);
+            (blockchain_guard.chain_length(), blockchain_guard.total_work(), first_height)
+        };
+
+        let mut status = SyncStatus {
+            peer: Some(peer.address),
+            target_height: peer.blockchain_height,
+            ..SyncStatus::default()
+        };
+
+        if peer.total_work <= our_work {
+            info!("Blockchain is up to date");
+            status.phase = SyncPhase::Complete;
+            self.sync.lock().unwrap().status = status;
+            return Ok(());
         }
-        
-        // Find peer with highest blockchain height
-        let best_peer = peers_guard.values()
-            .max_by_key(|peer| peer.blockchain_height);
-            
-        if let Some(peer) = best_peer {
-            let our_height = {
-                let blockchain_guard = self.blockchain.lock().unwrap();
-                blockchain_guard.blocks.len() as u64
-            };
-            
-            if peer.blockchain_height > our_height {
-                println!("📥 Syncing from peer {} (height: {})", peer.peer_id, peer.blockchain_height);
-                
-                // Request blocks
-                let _request = NetworkMessage::BlockRequest {
-                    start_height: our_height,
-                    count: (peer.blockchain_height - our_height) as u32,
-                };
-                
-                // In a real implementation, we'd send this request and handle the response
-                println!("📤 Block sync request sent");
-            } else {
-                println!("✅ Blockchain is up to date");
-            }
+
+        info!(peer_id = %peer.peer_id, height = peer.blockchain_height, work = peer.total_work, "Syncing from peer");
+
+        // Start far enough back to find a common ancestor if the heavier
+        // chain forked from ours; the first header must link to a block we hold
+        let start_height = our_height
+            .min(peer.blockchain_height)
+            .saturating_sub(SYNC_REORG_WINDOW)
+            .max(first_height + 1);
+
+        status.phase = SyncPhase::Headers;
+        *self.sync.lock().unwrap() = SyncSession { status, start_height, ..SyncSession::default() };
+
+        let request = NetworkMessage::HeaderRequest {
+            start_height,
+            count: self.config.sync_header_batch.clamp(1, MAX_HEADERS_PER_RESPONSE),
+        };
+        self.send_to_peer(&peer.address, request)
+            .inspect_err(|e| self.sync.lock().unwrap().fail(e.to_string()))
+    }
+
+    /// Progress of the current or most recent `sync_blockchain`
+    pub fn sync_status(&self) -> SyncStatus {
+        self.sync.lock().unwrap().status.clone()
+    }
+
+    /// Bootstrap from a snapshot of the heaviest peer's chain instead of
+    /// replaying every block; the response is handled by that peer's
+    /// connection task
+    pub async fn fast_sync(&self) -> SierpinskiResult<()> {
+        let best_peer = {
+            let peers_guard = self.peers.lock().unwrap();
+            peers_guard.values()
+                .max_by_key(|peer| peer.total_work)
+                .cloned()
+                .ok_or_else(|| SierpinskiError::validation("No peers available for sync"))?
+        };
+
+        let our_work = self.blockchain.read(|chain| chain.total_work());
+        if best_peer.total_work <= our_work {
+            info!("Blockchain is up to date");
+            return Ok(());
         }
-        
-        Ok(())
+
+        info!(peer_id = %best_peer.peer_id, work = best_peer.total_work, "Requesting snapshot");
+        self.snapshot_requests.lock().unwrap().insert(best_peer.address);
+        self.send_to_peer(&best_peer.address, NetworkMessage::SnapshotRequest {
+            recent_blocks: DEFAULT_SNAPSHOT_RECENT_BLOCKS as u32,
+        })
     }
 
     /// Get network statistics
     pub fn get_stats(&self) -> NetworkStats {
         let peers_guard = self.peers.lock().unwrap();
-        let blockchain_guard = self.blockchain.lock().unwrap();
+        let blockchain_guard = self.blockchain.lock();
         
         NetworkStats {
             node_id: self.node_id.clone(),
             listen_address: self.listen_address,
             connected_peers: peers_guard.len(),
-            blockchain_height: blockchain_guard.blocks.len() as u64,
+            blockchain_height: blockchain_guard.chain_length(),
             total_transactions: blockchain_guard.blocks.iter()
                 .map(|b| b.triangle_transactions.len())
                 .sum(),
@@ -407,6 +1990,23 @@ impl NetworkNode {
     }
 }
 
+/// Resolve once shutdown is requested; never resolves if the node was dropped
+async fn wait_for_shutdown(shutdown: &mut watch::Receiver<bool>) {
+    if shutdown.wait_for(|stop| *stop).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Write a single newline-delimited JSON message to a peer socket
+async fn write_message(writer: &mut OwnedWriteHalf, message: &NetworkMessage) -> SierpinskiResult<()> {
+    let mut data = serde_json::to_vec(message)
+        .map_err(|e| SierpinskiError::validation(format!("Serialization error: {}", e)))?;
+    data.push(b'\n');
+
+    writer.write_all(&data).await
+        .map_err(|e| SierpinskiError::validation(format!("Write error: {}", e)))
+}
+
 /// Network statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkStats {
@@ -420,15 +2020,700 @@ pub struct NetworkStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::address::TriangleAddress;
+    use crate::core::block::TriangleOperation;
+    use crate::core::blockchain::ChainMode;
+    use crate::core::target::Target;
     use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Minimal remote peer: acknowledges handshakes, forwards every other
+    /// message to the returned channel and counts accepted connections.
+    /// When `drop_first` is set the first connection is closed right after
+    /// its handshake.
+    async fn spawn_fake_peer(
+        drop_first: bool,
+    ) -> (SocketAddr, Arc<AtomicUsize>, mpsc::UnboundedReceiver<NetworkMessage>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let (forward, received) = mpsc::unbounded_channel();
+
+        let accepted_clone = Arc::clone(&accepted);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let connection_number = accepted_clone.fetch_add(1, Ordering::SeqCst);
+                let forward = forward.clone();
+
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let message: NetworkMessage = serde_json::from_str(&line).unwrap();
+                        if let NetworkMessage::Handshake { .. } = message {
+                            let ack = NetworkMessage::HandshakeAck {
+                                peer_id: "fake_peer".to_string(),
+                                version: "0.1.0".to_string(),
+                                blockchain_height: 1,
+                                total_work: 1,
+                            };
+                            write_message(&mut writer, &ack).await.unwrap();
+
+                            if drop_first && connection_number == 0 {
+                                return;
+                            }
+                        } else {
+                            let _ = forward.send(message);
+                        }
+                    }
+                });
+            }
+        });
+
+        (addr, accepted, received)
+    }
+
+    /// Connect a raw client to `addr` and complete a handshake as `peer_id`
+    async fn handshake_client(
+        addr: SocketAddr,
+        peer_id: &str,
+    ) -> (tokio::io::Lines<BufReader<OwnedReadHalf>>, OwnedWriteHalf) {
+        let (reader, mut writer) = TcpStream::connect(addr).await.unwrap().into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let handshake = NetworkMessage::Handshake {
+            peer_id: peer_id.to_string(),
+            version: "0.1.0".to_string(),
+            blockchain_height: 0,
+            total_work: 0,
+        };
+        write_message(&mut writer, &handshake).await.unwrap();
+        let ack = lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(serde_json::from_str(&ack).unwrap(), NetworkMessage::HandshakeAck { .. }));
+        (lines, writer)
+    }
+
+    /// Wait until the peer registered as `peer_id` has been marked disconnected
+    async fn wait_for_penalty(node: &NetworkNode, peer_id: &str) -> PeerInfo {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(peer) = node.peers.lock().unwrap().get(peer_id) {
+                    if matches!(peer.connection_state, ConnectionState::Disconnected) {
+                        return peer.clone();
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("peer was not disconnected")
+    }
+
+    fn test_node() -> NetworkNode {
+        let blockchain = SharedBlockchain::new(TriadChainBlockchain::new().unwrap());
+        NetworkNode::new("127.0.0.1:0".parse().unwrap(), blockchain, NetworkConfig::default())
+    }
+
+    async fn next_message(received: &mut mpsc::UnboundedReceiver<NetworkMessage>) -> NetworkMessage {
+        tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("timed out waiting for message")
+            .expect("fake peer channel closed")
+    }
 
     #[tokio::test]
     async fn test_network_node_creation() {
-        let blockchain = Arc::new(Mutex::new(TriadChainBlockchain::new().unwrap()));
+        let blockchain = SharedBlockchain::new(TriadChainBlockchain::new().unwrap());
         let addr = "127.0.0.1:8080".parse().unwrap();
-        let node = NetworkNode::new(addr, blockchain);
+        let node = NetworkNode::new(addr, blockchain, NetworkConfig::default());
         
         assert!(!node.node_id.is_empty());
         assert_eq!(node.listen_address, addr);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_connect_processes_handshake_ack() {
+        let (peer_addr, _accepted, _received) = spawn_fake_peer(false).await;
+        let node = test_node();
+
+        node.connect_to_peer(peer_addr).await.unwrap();
+
+        let peers = node.peers.lock().unwrap();
+        let peer = peers.get("fake_peer").expect("peer registered from handshake ack");
+        assert_eq!(peer.address, peer_addr);
+        assert_eq!(peer.blockchain_height, 1);
+        assert_eq!(peer.total_work, 1);
+    }
+
+    #[tokio::test]
+    async fn test_broadcasts_reuse_one_connection() {
+        let (peer_addr, accepted, mut received) = spawn_fake_peer(false).await;
+        let node = test_node();
+
+        node.connect_to_peer(peer_addr).await.unwrap();
+        node.broadcast_message(NetworkMessage::Ping).await.unwrap();
+        node.broadcast_message(NetworkMessage::Pong).await.unwrap();
+
+        assert!(matches!(next_message(&mut received).await, NetworkMessage::Ping));
+        assert!(matches!(next_message(&mut received).await, NetworkMessage::Pong));
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        // Connecting again is a no-op while the connection is registered
+        node.connect_to_peer(peer_addr).await.unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_peer_triggers_reconnection() {
+        let (peer_addr, accepted, mut received) = spawn_fake_peer(true).await;
+        let node = test_node();
+
+        node.connect_to_peer(peer_addr).await.unwrap();
+
+        // Wait for the writer task to notice the drop and dial again
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while accepted.load(Ordering::SeqCst) < 2 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("node did not reconnect");
+
+        node.broadcast_message(NetworkMessage::Ping).await.unwrap();
+        assert!(matches!(next_message(&mut received).await, NetworkMessage::Ping));
+        assert_eq!(node.connections.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_disconnects_peers_and_releases_port() {
+        let (peer_addr, _accepted, mut received) = spawn_fake_peer(false).await;
+        let node = test_node();
+        let handle = node.start().await.unwrap();
+        let local_addr = handle.local_addr;
+
+        // Inbound client completes a handshake with the node
+        let (reader, mut writer) = tokio::net::TcpStream::connect(local_addr).await.unwrap().into_split();
+        let mut client_lines = BufReader::new(reader).lines();
+        let handshake = NetworkMessage::Handshake {
+            peer_id: "client".to_string(),
+            version: "0.1.0".to_string(),
+            blockchain_height: 0,
+            total_work: 0,
+        };
+        write_message(&mut writer, &handshake).await.unwrap();
+        let ack = client_lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(serde_json::from_str(&ack).unwrap(), NetworkMessage::HandshakeAck { .. }));
+
+        // And an outbound connection to a remote peer
+        node.connect_to_peer(peer_addr).await.unwrap();
+
+        handle.shutdown().await.unwrap();
+
+        let goodbye = client_lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(serde_json::from_str(&goodbye).unwrap(), NetworkMessage::Disconnect { .. }));
+        assert!(matches!(next_message(&mut received).await, NetworkMessage::Disconnect { .. }));
+        assert!(node.connections.lock().unwrap().is_empty());
+        assert!(node.connect_to_peer(peer_addr).await.is_err());
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while TcpListener::bind(local_addr).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("listener port was not released");
+    }
+
+    #[tokio::test]
+    async fn test_flooding_peer_is_disconnected() {
+        let node = test_node();
+        let handle = node.start().await.unwrap();
+
+        let (mut good_lines, mut good_writer) = handshake_client(handle.local_addr, "good_client").await;
+        let (mut spam_lines, mut spam_writer) = handshake_client(handle.local_addr, "spammer").await;
+
+        // 1,000 pings in a tight loop, far beyond the burst allowance
+        for _ in 0..1_000 {
+            if write_message(&mut spam_writer, &NetworkMessage::Ping).await.is_err() {
+                break;
+            }
+        }
+
+        let spammer = wait_for_penalty(&node, "spammer").await;
+        assert!(spammer.reputation_score < 0.5);
+        assert!(!node.connections.lock().unwrap().contains_key(&spammer.address));
+
+        // The spammer's socket ends after at most a goodbye and the pongs sent before it
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while let Ok(Some(_)) = spam_lines.next_line().await {}
+        })
+        .await
+        .expect("spammer connection stayed open");
+
+        // The well-behaved peer keeps its connection and reputation
+        write_message(&mut good_writer, &NetworkMessage::Ping).await.unwrap();
+        let reply = good_lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(serde_json::from_str(&reply).unwrap(), NetworkMessage::Pong));
+        let good = node.peers.lock().unwrap().get("good_client").cloned().unwrap();
+        assert_eq!(good.reputation_score, 0.5);
+        assert!(matches!(good.connection_state, ConnectionState::Connected));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_is_rejected() {
+        let blockchain = SharedBlockchain::new(TriadChainBlockchain::new().unwrap());
+        let config = NetworkConfig {
+            max_message_size: 1024,
+            ..NetworkConfig::default()
+        };
+        let node = NetworkNode::new("127.0.0.1:0".parse().unwrap(), blockchain, config);
+        let handle = node.start().await.unwrap();
+
+        let (_lines, mut writer) = handshake_client(handle.local_addr, "big_sender").await;
+        let oversized = NetworkMessage::Disconnect { reason: "x".repeat(4096) };
+        let _ = write_message(&mut writer, &oversized).await;
+
+        let peer = wait_for_penalty(&node, "big_sender").await;
+        assert!(peer.reputation_score < 0.5);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_header_first_sync_catches_up() {
+        let mut genesis_chain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        // Test blocks arrive instantly; keep retargets cheap to mine
+        genesis_chain.difficulty_params.max_difficulty = 2;
+
+        // Both nodes share the genesis block
+        let mut populated = genesis_chain.clone();
+        for created in 0..30u8 {
+            let transaction = TriangleTransaction::new(
+                None,
+                TriangleAddress::new(vec![3, 3, created % 4, created / 4 % 4, created / 16]).unwrap(),
+                TriangleOperation::Create,
+                Some(crate::core::genesis::genesis_triangle().unwrap()),
+                Decimal::new(5, 2),
+            );
+            populated.add_transaction(transaction).unwrap();
+            populated.mine_block("miner".to_string(), 10).unwrap();
+        }
+        let expected_tip = populated.blocks.last().unwrap().hash();
+
+        let source = NetworkNode::new(
+            "127.0.0.1:0".parse().unwrap(),
+            SharedBlockchain::new(populated),
+            NetworkConfig::default(),
+        );
+        let source_handle = source.start().await.unwrap();
+
+        let config = NetworkConfig { sync_header_batch: 8, sync_block_batch: 5, ..NetworkConfig::default() };
+        let fresh = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(genesis_chain), config);
+        fresh.connect_to_peer(source_handle.local_addr).await.unwrap();
+        fresh.sync_blockchain().await.unwrap();
+
+        let status = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let status = fresh.sync_status();
+                if status.is_finished() {
+                    return status;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("sync did not finish");
+
+        assert_eq!(status.phase, SyncPhase::Complete);
+        assert_eq!(status.headers_received, 30);
+        assert_eq!(status.fork_height, Some(1));
+        assert_eq!(status.blocks_applied, 30);
+        assert_eq!(fresh.blockchain.lock().blocks.last().unwrap().hash(), expected_tip);
+
+        source_handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_new_block_gossips_across_nodes_once() {
+        let mut chain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let node_a = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(chain.clone()), NetworkConfig::default());
+        let node_b = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(chain.clone()), NetworkConfig::default());
+        let handle_a = node_a.start().await.unwrap();
+        let handle_b = node_b.start().await.unwrap();
+        let (node_c, _accepted, mut received_c) = spawn_fake_peer(false).await;
+
+        // A and B are linked twice over, so copies loop back to both
+        node_b.connect_to_peer(handle_a.local_addr).await.unwrap();
+        node_a.connect_to_peer(handle_b.local_addr).await.unwrap();
+        node_b.connect_to_peer(node_c).await.unwrap();
+
+        let block = chain.mine_block("miner".to_string(), 10).unwrap();
+        let (_announcer_lines, mut announcer) = handshake_client(handle_a.local_addr, "announcer").await;
+        write_message(&mut announcer, &NetworkMessage::NewBlock { block: block.clone() }).await.unwrap();
+
+        match next_message(&mut received_c).await {
+            NetworkMessage::NewBlock { block: relayed } => assert_eq!(relayed.hash(), block.hash()),
+            other => panic!("expected the relayed block, got {:?}", other),
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(received_c.try_recv().is_err(), "block reached node C more than once");
+
+        for node in [&node_a, &node_b] {
+            assert_eq!(node.blockchain.lock().blocks.last().unwrap().hash(), block.hash());
+        }
+
+        handle_a.shutdown().await.unwrap();
+        handle_b.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_header_only_node_follows_new_block_gossip() {
+        let mut source = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        for _ in 0..5 {
+            source.mine_block("miner".to_string(), 10).unwrap();
+        }
+        let mut pruned = source.clone();
+        pruned.set_mode(ChainMode::HeaderOnly { keep_recent: 2 });
+        let node = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(pruned), NetworkConfig::default());
+        let peer: SocketAddr = "127.0.0.1:9".parse().unwrap();
+
+        for _ in 0..2 {
+            let block = source.mine_block("miner".to_string(), 10).unwrap();
+            NetworkNode::handle_message(&NetworkMessage::NewBlock { block: block.clone() }, &peer, &node.context());
+            assert_eq!(node.blockchain.lock().blocks.last().unwrap().hash(), block.hash());
+        }
+        assert_eq!(node.blockchain.lock().state_hash(), source.state_hash());
+    }
+
+    #[tokio::test]
+    async fn test_query_messages_read_chain_state() {
+        let mut chain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let block = chain.mine_block("miner".to_string(), 10).unwrap();
+        let node = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(chain), NetworkConfig::default());
+        let handle = node.start().await.unwrap();
+
+        let (mut lines, mut writer) = handshake_client(handle.local_addr, "query_client").await;
+        let mut query = async |message: NetworkMessage| {
+            write_message(&mut writer, &message).await.unwrap();
+            let reply = lines.next_line().await.unwrap().unwrap();
+            serde_json::from_str::<NetworkMessage>(&reply).unwrap()
+        };
+
+        match query(NetworkMessage::GetBalance { address: "miner".to_string() }).await {
+            NetworkMessage::BalanceResponse { address, balance } => {
+                assert_eq!(address, "miner");
+                assert_eq!(balance, block.block_reward);
+            }
+            other => panic!("expected a balance, got {:?}", other),
+        }
+        match query(NetworkMessage::GetBlock { height: 1 }).await {
+            NetworkMessage::GetBlockResponse { block: Some(found), .. } => assert_eq!(found.hash(), block.hash()),
+            other => panic!("expected block 1, got {:?}", other),
+        }
+        assert!(matches!(
+            query(NetworkMessage::GetBlock { height: 9 }).await,
+            NetworkMessage::GetBlockResponse { block: None, .. }
+        ));
+        match query(NetworkMessage::GetStats).await {
+            NetworkMessage::StatsResponse { stats } => assert_eq!(stats.total_blocks, 2),
+            other => panic!("expected stats, got {:?}", other),
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_sync_headers_must_follow_expected_targets() {
+        let genesis_chain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut source = genesis_chain.clone();
+        let mut easy = source.block_template("cheat".to_string(), 10).unwrap();
+        for _ in 0..2 {
+            source.mine_block("miner".to_string(), 10).unwrap();
+        }
+        let honest: Vec<SyncHeader> = source.blocks[1..].iter().map(SyncHeader::from_block).collect();
+
+        let node = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(genesis_chain), NetworkConfig::default());
+        let peer: SocketAddr = "127.0.0.1:9".parse().unwrap();
+        let sync_with = |target_height: u64, headers: Vec<SyncHeader>| {
+            let status = SyncStatus { phase: SyncPhase::Headers, peer: Some(peer), target_height, ..SyncStatus::default() };
+            *node.sync.lock().unwrap() = SyncSession { status, start_height: 1, ..SyncSession::default() };
+            NetworkNode::handle_message(&NetworkMessage::HeaderResponse { headers }, &peer, &node.context());
+            node.sync_status().phase
+        };
+
+        // Mined at an easier target than ours, it meets only its own
+        easy.header.bits = Target::MAX.to_compact();
+        while !easy.meets_difficulty_target() {
+            easy.set_nonce(easy.header.nonce + 1);
+        }
+        let phase = sync_with(3, vec![SyncHeader::from_block(&easy)]);
+        assert!(matches!(phase, SyncPhase::Failed(reason) if reason.contains("target bits")));
+
+        // No more headers than the peer announced
+        let phase = sync_with(2, honest.clone());
+        assert!(matches!(phase, SyncPhase::Failed(reason) if reason.contains("announced")));
+
+        assert_eq!(sync_with(3, honest), SyncPhase::Blocks);
+    }
+
+    #[test]
+    fn test_only_requested_snapshots_are_adopted() {
+        let mut source = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        for _ in 0..3 {
+            source.mine_block("miner".to_string(), 10).unwrap();
+        }
+        let response = NetworkMessage::SnapshotResponse {
+            snapshot: Box::new(source.snapshot()),
+            recent_blocks: source.blocks[1..].to_vec(),
+        };
+
+        let mut fresh = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        fresh.gas_floor.base = Decimal::ONE;
+        let node = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(fresh), NetworkConfig::default());
+        let peer: SocketAddr = "127.0.0.1:9".parse().unwrap();
+
+        NetworkNode::handle_message(&response, &peer, &node.context());
+        assert_eq!(node.blockchain.read(|chain| chain.tip_height()), 0);
+
+        node.snapshot_requests.lock().unwrap().insert(peer);
+        NetworkNode::handle_message(&response, &peer, &node.context());
+        assert_eq!(node.blockchain.read(|chain| chain.tip_height()), 3);
+        // Our genesis differs, so only the work of the three blocks, at one
+        // leading zero bit each, is taken rather than the declared total
+        assert_eq!(node.blockchain.read(|chain| chain.total_work()), 3 * 2);
+        assert!(source.total_work() > 3 * 2);
+        // Our fee floor is local policy and survives the switch
+        assert_eq!(node.blockchain.read(|chain| chain.gas_floor.base), Decimal::ONE);
+
+        // A request is answered once
+        node.blockchain.lock().blocks.truncate(1);
+        NetworkNode::handle_message(&response, &peer, &node.context());
+        assert_eq!(node.blockchain.read(|chain| chain.blocks.len()), 1);
+    }
+
+    #[tokio::test]
+    async fn test_peer_solves_broadcast_mining_challenge() {
+        use crate::core::mining::MinerConfig;
+
+        let chain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let originator = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(chain.clone()), NetworkConfig::default());
+        let solver = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(chain), NetworkConfig::default())
+            .with_miner(Arc::new(GeometricMiner::new(MinerConfig::default())));
+        let originator_handle = originator.start().await.unwrap();
+        let solver_handle = solver.start().await.unwrap();
+        originator.connect_to_peer(solver_handle.local_addr).await.unwrap();
+
+        let challenge = originator.broadcast_mining_challenge("pool".to_string()).unwrap();
+        assert!(challenge.block_header.is_some());
+
+        // The originator completes the block; the solver then adopts it from gossip
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while [&originator, &solver].iter().any(|node| node.blockchain.read(|chain| chain.tip_height()) < 1) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("challenge was not solved");
+
+        let block = originator.blockchain.read(|chain| chain.blocks[1].clone());
+        assert_eq!(block.miner_address, "pool");
+        assert_eq!(solver.blockchain.read(|chain| chain.blocks[1].hash()), block.hash());
+        assert!(originator.challenges.lock().unwrap().is_empty());
+
+        originator_handle.shutdown().await.unwrap();
+        solver_handle.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_solution_to_superseded_challenge_is_ignored() {
+        use crate::core::mining::MinerConfig;
+
+        let chain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let node = NetworkNode::new("127.0.0.1:0".parse().unwrap(), SharedBlockchain::new(chain), NetworkConfig::default());
+        let challenge = node.broadcast_mining_challenge("pool".to_string()).unwrap();
+
+        let miner = GeometricMiner::new(MinerConfig::default());
+        assert!(miner.set_external_challenge(challenge.clone()));
+        let solution = miner.solve_external_challenge().unwrap();
+        assert_eq!(solution.challenge_id, challenge.challenge_id);
+
+        // Another block lands before the solution comes back
+        let tip = node.blockchain.with(|chain| chain.mine_block("other".to_string(), 10)).unwrap();
+        let message = NetworkMessage::ChallengeSolution {
+            challenge_id: solution.challenge_id,
+            nonce: solution.nonce,
+            proof_hash: solution.proof_hash,
+        };
+        let peer = "127.0.0.1:9".parse().unwrap();
+        assert!(NetworkNode::handle_message(&message, &peer, &node.context()).is_none());
+
+        assert_eq!(node.blockchain.read(|chain| chain.blocks.last().unwrap().hash()), tip.hash());
+        assert!(node.challenges.lock().unwrap().is_empty());
+    }
+
+    fn known_peer(address: SocketAddr, last_seen: u64, reputation_score: f64) -> PeerInfo {
+        PeerInfo {
+            peer_id: format!("peer_{}", address),
+            address,
+            version: "0.1.0".to_string(),
+            blockchain_height: 1,
+            total_work: 1,
+            last_seen,
+            reputation_score,
+            connection_state: ConnectionState::Disconnected,
+        }
+    }
+
+    #[test]
+    fn test_peer_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("peers.json");
+        let now = unix_now();
+
+        let mut store = PeerStore::default();
+        store.record(&known_peer("127.0.0.1:9001".parse().unwrap(), now, 0.9));
+        store.record(&known_peer("127.0.0.1:9002".parse().unwrap(), now - 10, 0.2));
+        store.save(&path).unwrap();
+
+        let loaded = PeerStore::load(&path, 3600).unwrap();
+        assert_eq!(loaded, store);
+        assert_eq!(loaded.bootstrap_candidates(8, 0.5), vec!["127.0.0.1:9001".parse().unwrap()]);
+
+        // Both are past a one-second TTL once eleven seconds have gone by
+        let mut aged = loaded.clone();
+        assert_eq!(aged.evict_stale(now + 11, 1), 2);
+        assert!(aged.is_empty());
+
+        assert!(PeerStore::load(&dir.path().join("missing.json"), 3600).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_node_saves_loads_and_discovers_peers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("peers.json");
+        let (first, second): (SocketAddr, SocketAddr) = ("127.0.0.1:9001".parse().unwrap(), "127.0.0.1:9002".parse().unwrap());
+
+        let node = test_node();
+        for addr in [first, second] {
+            let peer = known_peer(addr, unix_now(), 0.8);
+            node.peers.lock().unwrap().insert(peer.peer_id.clone(), peer);
+        }
+        node.save_peers(&path).unwrap();
+
+        let restarted = test_node();
+        assert_eq!(restarted.load_peers(&path).unwrap(), 2);
+        assert_eq!(*restarted.peer_store.lock().unwrap(), *node.peer_store.lock().unwrap());
+        assert_eq!(restarted.load_peers(&path).unwrap(), 0);
+
+        // Gossiped addresses are added once, without touching known peers
+        let discovered: SocketAddr = "127.0.0.1:9003".parse().unwrap();
+        let message = NetworkMessage::PeerDiscovery { known_peers: vec![first, discovered, discovered] };
+        let sender = "127.0.0.1:9".parse().unwrap();
+        assert!(NetworkNode::handle_message(&message, &sender, &restarted.context()).is_none());
+        let store = restarted.peer_store.lock().unwrap();
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get(&first).unwrap().reputation_score, 0.8);
+        assert_eq!(store.get(&discovered).unwrap().reputation_score, NEUTRAL_REPUTATION);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_transaction_reaches_peer_mempool() {
+        let recipient = test_node();
+        let recipient_handle = recipient.start().await.unwrap();
+        let source = test_node();
+        source.connect_to_peer(recipient_handle.local_addr).await.unwrap();
+
+        let transaction = TriangleTransaction::new(
+            None,
+            TriangleAddress::new(vec![3]).unwrap(),
+            TriangleOperation::Create,
+            Some(crate::core::genesis::genesis_triangle().unwrap()),
+            Decimal::new(2, 2),
+        );
+        source.broadcast_transaction(transaction.clone()).unwrap();
+        assert_eq!(source.blockchain.read(|chain| chain.mempool.len()), 1);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !recipient.blockchain.read(|chain| chain.mempool.iter().any(|tx| tx.id == transaction.id)) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("transaction did not reach the peer");
+
+        recipient_handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_malformed_payloads_lower_reputation() {
+        let node = test_node();
+        let sender: SocketAddr = "127.0.0.1:9".parse().unwrap();
+        let mut peer = known_peer(sender, unix_now(), NEUTRAL_REPUTATION);
+        peer.connection_state = ConnectionState::Connected;
+        node.peers.lock().unwrap().insert(peer.peer_id.clone(), peer.clone());
+        let context = node.context();
+        let reputation = || node.peers.lock().unwrap()[&peer.peer_id].reputation_score;
+
+        let garbage = NetworkMessage::TransactionBroadcast {
+            transaction_id: Uuid::new_v4().to_string(),
+            transaction_data: b"not a transaction".to_vec(),
+        };
+        assert!(NetworkNode::handle_message(&garbage, &sender, &context).is_none());
+        assert!(reputation() < NEUTRAL_REPUTATION);
+        assert!(node.blockchain.read(|chain| chain.mempool.is_empty()));
+
+        // Unspecified addresses are dropped, the rest merged
+        let before = reputation();
+        let discovered: SocketAddr = "127.0.0.1:9004".parse().unwrap();
+        let discovery = NetworkMessage::PeerDiscovery { known_peers: vec!["0.0.0.0:0".parse().unwrap(), discovered] };
+        NetworkNode::handle_message(&discovery, &sender, &context);
+        assert!(reputation() < before);
+        assert_eq!(node.peer_store.lock().unwrap().len(), 1);
+
+        // The penalty does not disconnect the peer
+        assert!(matches!(node.peers.lock().unwrap()[&peer.peer_id].connection_state, ConnectionState::Connected));
+    }
+
+    #[tokio::test]
+    async fn test_startup_dials_fresh_remembered_peers_only() {
+        let (fresh_addr, fresh_accepted, _fresh_received) = spawn_fake_peer(false).await;
+        let (expired_addr, expired_accepted, _expired_received) = spawn_fake_peer(false).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("peers.json");
+        let ttl = 3600;
+        let now = unix_now();
+        let mut store = PeerStore::default();
+        store.record(&known_peer(fresh_addr, now, 0.5));
+        store.record(&known_peer(expired_addr, now - 2 * ttl, 0.9));
+        store.save(&path).unwrap();
+
+        let config = NetworkConfig {
+            peers_file: Some(path.clone()),
+            peer_ttl_secs: ttl,
+            ..NetworkConfig::default()
+        };
+        let blockchain = SharedBlockchain::new(TriadChainBlockchain::new().unwrap());
+        let node = NetworkNode::new("127.0.0.1:0".parse().unwrap(), blockchain, config);
+        assert!(node.peer_store.lock().unwrap().get(&expired_addr).is_none());
+
+        let handle = node.start().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while fresh_accepted.load(Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("remembered peer was not dialled");
+        assert_eq!(expired_accepted.load(Ordering::SeqCst), 0);
+
+        // Shutting down writes the peers file back without the expired entry
+        handle.shutdown().await.unwrap();
+        let saved = PeerStore::load(&path, ttl).unwrap();
+        assert!(saved.get(&fresh_addr).is_some());
+        assert!(saved.get(&expired_addr).is_none());
+    }
+}
@@ -0,0 +1,177 @@
+//! Chain state snapshots for fast-syncing new nodes
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+
+use crate::core::{
+    address::TriangleAddress,
+    blockchain::RewardLedger,
+    economics::EconomicsEngine,
+    fractal::FractalStructure,
+    errors::{SierpinskiError, SierpinskiResult},
+    target::Target,
+};
+
+/// Number of recent blocks kept alongside a snapshot by default; enough for
+/// difficulty adjustment, which looks at the last ten blocks
+pub const DEFAULT_SNAPSHOT_RECENT_BLOCKS: usize = 10;
+
+/// Chain state captured at a block, letting a node start from that block
+/// instead of replaying the chain from genesis
+///
+/// The content hash only proves the snapshot was not altered in transit; a
+/// node trusts the peer it fetched the snapshot from to have built the state
+/// correctly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainSnapshot {
+    /// Height of the block the snapshot was taken at
+    pub height: u64,
+    /// Hash of the block the snapshot was taken at
+    pub block_hash: String,
+    /// Target for the next block
+    #[serde(alias = "difficulty")]
+    pub target: Target,
+    /// Total tokens in circulation
+    pub total_supply: Decimal,
+    /// Proof-of-work summed over every block up to `height`
+    pub cumulative_difficulty: u128,
+    /// Balances sorted by address
+    pub balances: Vec<(String, Decimal)>,
+    /// Triangle owners sorted by triangle address
+    pub triangle_owners: Vec<(TriangleAddress, String)>,
+    /// Fractal state at `height`
+    pub fractal_state: FractalStructure,
+    /// Reward ledger at `height`
+    #[serde(default)]
+    pub rewards: RewardLedger,
+    /// Token economics at `height`; only its supply, rates and market prices
+    /// are hashed
+    #[serde(default)]
+    pub economics: EconomicsEngine,
+    /// Hash over everything above, see `compute_hash`
+    pub content_hash: String,
+}
+
+impl ChainSnapshot {
+    /// Hash of the snapshot contents, binding the state to its block
+    pub fn compute_hash(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.height.to_le_bytes());
+        hasher.update(self.block_hash.as_bytes());
+        hasher.update(&self.cumulative_difficulty.to_le_bytes());
+        hasher.update(self.state_hash().as_bytes());
+        hasher.update(rewards_digest(&self.rewards).as_bytes());
+        hasher.update(economics_digest(&self.economics).as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Hash of the chain state alone, comparable with
+    /// `TriadChainBlockchain::state_hash`
+    pub fn state_hash(&self) -> String {
+        state_hash(
+            &self.balances,
+            &self.triangle_owners,
+            &self.fractal_state,
+            self.total_supply,
+            &self.target,
+        )
+    }
+
+    /// Check that the snapshot matches its content hash
+    pub fn verify(&self) -> SierpinskiResult<()> {
+        if self.compute_hash() != self.content_hash {
+            return Err(SierpinskiError::validation("Snapshot content hash mismatch"));
+        }
+        Ok(())
+    }
+}
+
+/// Reward ledger in a stable textual form for hashing
+fn rewards_digest(rewards: &RewardLedger) -> String {
+    let mut digest = format!("{};{};", rewards.pool_funded.normalize(), rewards.pool_paid.normalize());
+    for (label, amounts) in [("accrued", &rewards.accrued), ("claimed", &rewards.claimed)] {
+        digest.push_str(label);
+        for (address, amount) in sorted_balances(amounts) {
+            digest.push_str(&format!(":{}={}", address, amount.normalize()));
+        }
+        digest.push(';');
+    }
+    digest
+}
+
+/// Economics supply, rates and market prices in a stable textual form for
+/// hashing
+fn economics_digest(economics: &EconomicsEngine) -> String {
+    let config = &economics.config;
+    let mut digest = format!(
+        "{};{};{};",
+        config.circulating_supply.normalize(),
+        config.block_inflation_rate.normalize(),
+        config.subdivision_deflation_rate.normalize(),
+    );
+    let mut prices: Vec<_> = economics.market_prices.iter().collect();
+    prices.sort_by(|a, b| a.0.components().cmp(b.0.components()));
+    for (address, price) in prices {
+        digest.push_str(&format!(":{}={}", address, price.normalize()));
+    }
+    digest
+}
+
+/// Balances in a stable order for hashing and serialization
+pub(crate) fn sorted_balances(balances: &HashMap<String, Decimal>) -> Vec<(String, Decimal)> {
+    let mut sorted: Vec<_> = balances
+        .iter()
+        .map(|(address, balance)| (address.clone(), *balance))
+        .collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    sorted
+}
+
+/// Triangle owners in a stable order for hashing and serialization
+pub(crate) fn sorted_owners(owners: &HashMap<TriangleAddress, String>) -> Vec<(TriangleAddress, String)> {
+    let mut sorted: Vec<_> = owners
+        .iter()
+        .map(|(address, owner)| (address.clone(), owner.clone()))
+        .collect();
+    sorted.sort_by(|a, b| a.0.components().cmp(b.0.components()));
+    sorted
+}
+
+/// Hash chain state independently of triangle ids, timestamps and map order,
+/// so two nodes that applied the same blocks agree on it
+pub(crate) fn state_hash(
+    balances: &[(String, Decimal)],
+    triangle_owners: &[(TriangleAddress, String)],
+    fractal_state: &FractalStructure,
+    total_supply: Decimal,
+    target: &Target,
+) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(target.as_bytes());
+    hasher.update(total_supply.normalize().to_string().as_bytes());
+
+    for (address, balance) in balances {
+        hasher.update(format!("{}={};", address, balance.normalize()).as_bytes());
+    }
+    for (address, owner) in triangle_owners {
+        hasher.update(format!("{}={};", address, owner).as_bytes());
+    }
+
+    let mut triangles: Vec<String> = fractal_state
+        .triangles()
+        .map(|triangle| format!(
+            "{}:{}:{:?}:{};",
+            triangle.address,
+            triangle.triangle.canonical_hash(),
+            triangle.state,
+            triangle.depth,
+        ))
+        .collect();
+    triangles.sort();
+    for triangle in triangles {
+        hasher.update(triangle.as_bytes());
+    }
+
+    hasher.finalize().to_hex().to_string()
+}
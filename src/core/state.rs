@@ -115,10 +115,7 @@ impl StateTransition {
         StateTransition {
             from,
             to,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            timestamp: crate::core::unix_now(),
             reason,
         }
     }
@@ -0,0 +1,203 @@
+//! Incrementally updated commitment to the triangles of a fractal structure
+
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::core::fractal::FractalTriangle;
+
+/// Merkle tree over triangle state hashes, shaped like the address tree
+///
+/// Each node commits to the state hashes of the triangles at its address,
+/// in hash order, and to its child nodes in component order, so the root
+/// depends only on the triangles and their addresses. Adding, changing or
+/// removing a triangle rehashes the nodes on its path to the root, one per
+/// depth. Nodes with no triangles at or below them are left out.
+#[derive(Debug, Clone, Default)]
+pub struct StateTree {
+    /// Nodes by address components, the genesis address being empty
+    nodes: HashMap<Vec<u8>, Node>,
+    /// Address each triangle is filed under
+    positions: HashMap<Uuid, Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// State hashes of the triangles at this address, by id
+    triangles: BTreeMap<Uuid, String>,
+    hash: String,
+}
+
+impl StateTree {
+    /// Create an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a tree holding `triangles`, hashing each node once
+    pub fn from_triangles<'a>(triangles: impl IntoIterator<Item = &'a FractalTriangle>) -> Self {
+        let mut tree = Self::new();
+        for triangle in triangles {
+            let address = triangle.address.components();
+            tree.add_path(address);
+            tree.nodes.get_mut(address).unwrap().triangles.insert(triangle.id, triangle.state_hash());
+            tree.positions.insert(triangle.id, address.to_vec());
+        }
+
+        let mut prefixes: Vec<Vec<u8>> = tree.nodes.keys().cloned().collect();
+        prefixes.sort_by_key(|prefix| std::cmp::Reverse(prefix.len()));
+        for prefix in prefixes {
+            let children = tree.children(&prefix);
+            let node = tree.nodes.get_mut(&prefix).unwrap();
+            node.hash = hash_node(node.triangles.values(), &children);
+        }
+        tree
+    }
+
+    /// Add a triangle, or update one already in the tree
+    pub fn insert(&mut self, triangle: &FractalTriangle) {
+        let address = triangle.address.components();
+        if self.positions.get(&triangle.id).is_some_and(|filed| filed != address) {
+            self.remove(&triangle.id);
+        }
+
+        self.add_path(address);
+        self.nodes.get_mut(address).unwrap().triangles.insert(triangle.id, triangle.state_hash());
+        self.positions.insert(triangle.id, address.to_vec());
+        self.rehash_path(address);
+    }
+
+    /// Remove a triangle; unknown ids are ignored
+    pub fn remove(&mut self, id: &Uuid) {
+        let Some(address) = self.positions.remove(id) else { return };
+        if let Some(node) = self.nodes.get_mut(&address) {
+            node.triangles.remove(id);
+        }
+        self.rehash_path(&address);
+    }
+
+    /// Root hash; all zeros for an empty tree
+    pub fn root(&self) -> String {
+        self.nodes
+            .get(&[] as &[u8])
+            .map_or_else(|| "0".repeat(64), |node| node.hash.clone())
+    }
+
+    /// Root the tree would have after `insert(triangle)`, computed along the
+    /// triangle's path without changing the tree
+    pub fn root_with(&self, triangle: &FractalTriangle) -> String {
+        let address = triangle.address.components();
+        if self.positions.get(&triangle.id).is_some_and(|filed| filed != address) {
+            let mut moved = self.clone();
+            moved.insert(triangle);
+            return moved.root();
+        }
+
+        let mut triangles = self.nodes.get(address).map(|node| node.triangles.clone()).unwrap_or_default();
+        triangles.insert(triangle.id, triangle.state_hash());
+        let mut hash = hash_node(triangles.values(), &self.children(address));
+        for depth in (0..address.len()).rev() {
+            let prefix = &address[..depth];
+            let mut children: Vec<(u8, String)> = self
+                .children(prefix)
+                .into_iter()
+                .filter(|(component, _)| *component != address[depth])
+                .collect();
+            children.push((address[depth], hash));
+            children.sort();
+            let own = self.nodes.get(prefix).into_iter().flat_map(|node| node.triangles.values());
+            hash = hash_node(own, &children);
+        }
+        hash
+    }
+
+    /// Make sure a node exists at `address` and at each of its ancestors
+    fn add_path(&mut self, address: &[u8]) {
+        for depth in (0..=address.len()).rev() {
+            if self.nodes.contains_key(&address[..depth]) {
+                break;
+            }
+            self.nodes.insert(address[..depth].to_vec(), Node::default());
+        }
+    }
+
+    /// Rehash the nodes from `address` up to the root, dropping empty ones
+    fn rehash_path(&mut self, address: &[u8]) {
+        for depth in (0..=address.len()).rev() {
+            let prefix = &address[..depth];
+            let children = self.children(prefix);
+            let Some(node) = self.nodes.get_mut(prefix) else { continue };
+            if node.triangles.is_empty() && children.is_empty() {
+                self.nodes.remove(prefix);
+            } else {
+                node.hash = hash_node(node.triangles.values(), &children);
+            }
+        }
+    }
+
+    /// Hashes of the child nodes of `prefix`, in component order
+    fn children(&self, prefix: &[u8]) -> Vec<(u8, String)> {
+        let mut child = prefix.to_vec();
+        child.push(0);
+        (0..=3)
+            .filter_map(|component| {
+                *child.last_mut().unwrap() = component;
+                self.nodes.get(&child).map(|node| (component, node.hash.clone()))
+            })
+            .collect()
+    }
+}
+
+fn hash_node<'a>(triangles: impl Iterator<Item = &'a String>, children: &[(u8, String)]) -> String {
+    let mut hashes: Vec<&String> = triangles.collect();
+    hashes.sort();
+
+    let mut hasher = blake3::Hasher::new();
+    for hash in hashes {
+        hasher.update(b"t");
+        hasher.update(hash.as_bytes());
+    }
+    for (component, hash) in children {
+        hasher.update(&[b'c', *component]);
+        hasher.update(hash.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{genesis::genesis_fractal_triangle, state::TriangleState, subdivision::subdivide_to_depth};
+
+    #[test]
+    fn test_updates_match_rebuilt_tree() {
+        let structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 3).unwrap();
+        let mut triangles: Vec<FractalTriangle> = structure.triangles().cloned().collect();
+        triangles.sort_by(|a, b| b.address.components().cmp(a.address.components()));
+
+        let mut tree = StateTree::new();
+        assert_eq!(tree.root(), "0".repeat(64));
+        for triangle in &triangles {
+            tree.insert(triangle);
+        }
+        assert_eq!(tree.root(), StateTree::from_triangles(&triangles).root());
+
+        // A state change, previewed and then applied
+        let mut changed = triangles.pop().unwrap();
+        changed.state = TriangleState::Inactive;
+        let preview = tree.root_with(&changed);
+        tree.insert(&changed);
+        assert_eq!(tree.root(), preview);
+        triangles.push(changed.clone());
+        assert_eq!(tree.root(), StateTree::from_triangles(&triangles).root());
+
+        // Removing everything leaves no nodes behind
+        tree.remove(&changed.id);
+        triangles.pop();
+        assert_eq!(tree.root(), StateTree::from_triangles(&triangles).root());
+        for triangle in &triangles {
+            tree.remove(&triangle.id);
+        }
+        assert_eq!(tree.root(), "0".repeat(64));
+        assert!(tree.nodes.is_empty());
+    }
+}
@@ -1,13 +1,17 @@
 //! Sierpinski triangle subdivision algorithms
 
+use std::collections::VecDeque;
 use rust_decimal::Decimal;
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 
 use crate::core::{
+    address::TriangleAddress,
+    geometry::GeometricTolerances,
     triangle::Triangle,
     fractal::{FractalTriangle, FractalStructure},
     state::TriangleState,
+    genesis::{genesis_triangle, max_theoretical_depth, MIN_TRIANGLE_AREA},
     errors::{SierpinskiError, SierpinskiResult},
 };
 
@@ -20,11 +24,104 @@ pub struct SubdivisionResult {
     pub void_triangle: FractalTriangle,
     /// Original parent triangle (now in Subdivided state)
     pub parent: FractalTriangle,
+    /// Area of the parent triangle, recorded at subdivision for reporting
+    #[serde(default)]
+    pub parent_area: Decimal,
+    /// Combined area of the three children, recorded for reporting
+    #[serde(default)]
+    pub children_area: Decimal,
+    /// Area of the central void, recorded for reporting
+    #[serde(default)]
+    pub void_area: Decimal,
+    /// Difference between the parent's area and that of the children and
+    /// void together, recorded for reporting
+    #[serde(default)]
+    pub area_error: Decimal,
 }
 
+impl SubdivisionResult {
+    /// Whether the children and void cover the parent's area, to within
+    /// `tolerance` relative to it (see
+    /// `GeometricTolerances::area_conservation_relative`)
+    ///
+    /// Areas are measured from the triangles themselves; the recorded
+    /// fields may come from an untrusted peer and are not consulted.
+    pub fn is_area_conserved(&self, tolerance: Decimal) -> bool {
+        self.measured_area_error()
+            .is_ok_and(|(parent_area, error)| error <= parent_area * tolerance)
+    }
+
+    /// The parent's area and its difference from the area of the children
+    /// and void together, measured from their geometry
+    fn measured_area_error(&self) -> SierpinskiResult<(Decimal, Decimal)> {
+        let parent_area = self.parent.triangle.area()?;
+        let mut covered = self.void_triangle.triangle.area()?;
+        for child in &self.children {
+            covered += child.triangle.area()?;
+        }
+        Ok((parent_area, (parent_area - covered).abs()))
+    }
+}
+
+/// How a triangle's central quarter is treated when it is subdivided
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SubdivisionPattern {
+    /// The classic Sierpinski pattern: three corner children and a void
+    #[default]
+    SierpinskiVoid,
+    /// Four active children with no void, for non-deflationary variants
+    FullFour,
+}
+
+impl SubdivisionPattern {
+    /// State given to the central child (component 3)
+    pub fn center_state(self) -> TriangleState {
+        match self {
+            SubdivisionPattern::SierpinskiVoid => TriangleState::Void,
+            SubdivisionPattern::FullFour => TriangleState::Active,
+        }
+    }
+}
+
+/// Constructor used for the child and void triangles of a subdivision
+type ChildConstructor = fn(Triangle, &FractalTriangle, u8) -> SierpinskiResult<FractalTriangle>;
+
 /// Subdivide a triangle into the Sierpinski pattern
 pub fn subdivide_triangle(
     parent: &FractalTriangle,
+) -> SierpinskiResult<SubdivisionResult> {
+    subdivide_with(parent, FractalTriangle::child)
+}
+
+/// Subdivide a triangle using `pattern`, returning its four children in
+/// component order
+///
+/// The parent is left untouched; use `subdivide_triangle` for the parent in
+/// its Subdivided state.
+pub fn subdivide_triangle_with(
+    parent: &FractalTriangle,
+    pattern: SubdivisionPattern,
+) -> SierpinskiResult<Vec<FractalTriangle>> {
+    let result = subdivide_with(parent, FractalTriangle::child)?;
+    let mut center = result.void_triangle;
+    center.state = pattern.center_state();
+
+    let mut children = result.children.to_vec();
+    children.push(center);
+    Ok(children)
+}
+
+/// Subdivide a triangle, deriving child ids from the parent id and geometry
+/// so repeated subdivisions of the same parent agree on ids
+pub fn subdivide_triangle_deterministic(
+    parent: &FractalTriangle,
+) -> SierpinskiResult<SubdivisionResult> {
+    subdivide_with(parent, FractalTriangle::child_deterministic)
+}
+
+fn subdivide_with(
+    parent: &FractalTriangle,
+    make_child: ChildConstructor,
 ) -> SierpinskiResult<SubdivisionResult> {
     // Check if subdivision is allowed
     if !parent.can_subdivide() {
@@ -34,31 +131,25 @@ pub fn subdivide_triangle(
         )));
     }
 
-    // Get the midpoints of each side
-    let midpoints = parent.triangle.side_midpoints();
-    let [mid_ab, mid_bc, mid_ca] = midpoints;
-    let [a, b, c] = parent.triangle.vertices();
-
-    // Create the three child triangles
-    let child_triangle_1 = Triangle::new(*a, mid_ab, mid_ca)?;
-    let child_triangle_2 = Triangle::new(mid_ab, *b, mid_bc)?;
-    let child_triangle_3 = Triangle::new(mid_ca, mid_bc, *c)?;
-
-    // Create the central void triangle
-    let void_triangle_geom = Triangle::new(mid_ab, mid_bc, mid_ca)?;
+    let [child_triangle_1, child_triangle_2, child_triangle_3, void_triangle_geom] =
+        subdivision_geometry(&parent.triangle)?;
+    let parent_area = parent.triangle.area()?;
+    let children_area = child_triangle_1.area()? + child_triangle_2.area()? + child_triangle_3.area()?;
+    let void_area = void_triangle_geom.area()?;
 
     // Create fractal triangles for children
-    let child_1 = FractalTriangle::child(child_triangle_1, parent, 0)?;
-    let child_2 = FractalTriangle::child(child_triangle_2, parent, 1)?;
-    let child_3 = FractalTriangle::child(child_triangle_3, parent, 2)?;
+    let child_1 = make_child(child_triangle_1, parent, 0)?;
+    let child_2 = make_child(child_triangle_2, parent, 1)?;
+    let child_3 = make_child(child_triangle_3, parent, 2)?;
 
-    // Create void fractal triangle
-    let mut void_triangle = FractalTriangle::child(void_triangle_geom, parent, 3)?;
-    void_triangle.change_state(TriangleState::Void)?;
+    // Create void fractal triangle; it is born empty rather than
+    // transitioned from Active, which no triangle may leave for Void
+    let mut void_triangle = make_child(void_triangle_geom, parent, 3)?;
+    void_triangle.state = TriangleState::Void;
 
     // Create updated parent with new state
     let mut updated_parent = parent.clone();
-    updated_parent.change_state(TriangleState::Subdivided)?;
+    updated_parent.change_state(TriangleState::Subdivided, "subdivided")?;
     updated_parent.add_child(child_1.id);
     updated_parent.add_child(child_2.id);
     updated_parent.add_child(child_3.id);
@@ -67,13 +158,87 @@ pub fn subdivide_triangle(
         children: [child_1, child_2, child_3],
         void_triangle,
         parent: updated_parent,
+        parent_area,
+        children_area,
+        void_area,
+        area_error: (parent_area - (children_area + void_area)).abs(),
     })
 }
 
+/// Geometry of the three children and the central void of a triangle, in
+/// child index order
+fn subdivision_geometry(triangle: &Triangle) -> SierpinskiResult<[Triangle; 4]> {
+    // Get the midpoints of each side
+    let [mid_ab, mid_bc, mid_ca] = triangle.side_midpoints();
+    let [a, b, c] = triangle.vertices();
+
+    Ok([
+        Triangle::new(*a, mid_ab, mid_ca)?,
+        Triangle::new(mid_ab, *b, mid_bc)?,
+        Triangle::new(mid_ca, mid_bc, *c)?,
+        Triangle::new(mid_ab, mid_bc, mid_ca)?,
+    ])
+}
+
+/// Lazily enumerates the Sierpinski triangles under a genesis triangle,
+/// breadth first, without building a `FractalStructure`
+///
+/// Yields the same non-void addresses and geometry as `subdivide_to_depth`.
+/// Only the frontier of the current depth is held in memory. Branches stop
+/// at `MAX_SUBDIVISION_DEPTH` and where children would fall below
+/// `MIN_TRIANGLE_AREA`.
+pub struct FractalGeometryIter {
+    queue: VecDeque<(TriangleAddress, Triangle)>,
+    max_depth: u8,
+}
+
+impl FractalGeometryIter {
+    pub fn new(genesis: Triangle, max_depth: u8) -> Self {
+        FractalGeometryIter {
+            queue: VecDeque::from([(TriangleAddress::genesis(), genesis)]),
+            max_depth: max_depth.min(crate::MAX_SUBDIVISION_DEPTH),
+        }
+    }
+
+    fn enqueue_children(&mut self, address: &TriangleAddress, triangle: &Triangle) {
+        if address.depth() >= self.max_depth {
+            return;
+        }
+        let too_small = triangle
+            .area()
+            .map_or(true, |area| area / Decimal::from(4) < MIN_TRIANGLE_AREA);
+        if too_small {
+            return;
+        }
+
+        if let Ok(geometry) = subdivision_geometry(triangle) {
+            self.queue.extend(address.children_active().into_iter().zip(geometry));
+        }
+    }
+}
+
+impl Iterator for FractalGeometryIter {
+    type Item = (TriangleAddress, Triangle);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (address, triangle) = self.queue.pop_front()?;
+        self.enqueue_children(&address, &triangle);
+        Some((address, triangle))
+    }
+}
+
 /// Subdivide a triangle and add results to a fractal structure
 pub fn subdivide_and_add_to_structure(
     structure: &mut FractalStructure,
     parent_id: &Uuid,
+) -> SierpinskiResult<SubdivisionResult> {
+    subdivide_into_structure(structure, parent_id, FractalTriangle::child)
+}
+
+fn subdivide_into_structure(
+    structure: &mut FractalStructure,
+    parent_id: &Uuid,
+    make_child: ChildConstructor,
 ) -> SierpinskiResult<SubdivisionResult> {
     // Get the parent triangle
     let parent = structure
@@ -81,8 +246,15 @@ pub fn subdivide_and_add_to_structure(
         .ok_or_else(|| SierpinskiError::subdivision("Parent triangle not found".to_string()))?
         .clone();
 
+    // Refuse before touching the parent rather than fail on its children
+    if parent.depth >= structure.max_depth_limit() {
+        return Err(SierpinskiError::MaxDepthExceeded {
+            max_depth: structure.max_depth_limit(),
+        });
+    }
+
     // Perform subdivision
-    let result = subdivide_triangle(&parent)?;
+    let result = subdivide_with(&parent, make_child)?;
 
     // Update the structure with new triangles
     structure.add_triangle(result.parent.clone())?;
@@ -98,6 +270,113 @@ pub fn subdivide_and_add_to_structure(
 pub fn subdivide_to_depth(
     initial_triangle: FractalTriangle,
     target_depth: u8,
+) -> SierpinskiResult<FractalStructure> {
+    subdivide_to_depth_checked(initial_triangle, target_depth, false)
+}
+
+/// Recursively subdivide to a specific depth, stopping each branch once its
+/// children would be too small to represent
+///
+/// Asking for a depth beyond the triangle's `max_theoretical_depth` is an
+/// error unless `allow_precision_loss` is set, in which case generation simply
+/// halts at the precision limit.
+pub fn subdivide_to_depth_checked(
+    initial_triangle: FractalTriangle,
+    target_depth: u8,
+    allow_precision_loss: bool,
+) -> SierpinskiResult<FractalStructure> {
+    build_to_depth(
+        initial_triangle,
+        target_depth,
+        allow_precision_loss,
+        FractalTriangle::child,
+        &mut |_| true,
+    )
+}
+
+/// Recursively subdivide a deterministic genesis built from `genesis` to a
+/// specific depth; the same inputs always produce the same triangle ids
+pub fn subdivide_to_depth_deterministic(
+    genesis: Triangle,
+    target_depth: u8,
+) -> SierpinskiResult<FractalStructure> {
+    build_to_depth(
+        FractalTriangle::genesis_deterministic(genesis),
+        target_depth,
+        false,
+        FractalTriangle::child_deterministic,
+        &mut |_| true,
+    )
+}
+
+/// Grow a sparse fractal from the standard genesis, subdividing each
+/// subdividable triangle above `max_depth` with probability
+/// `branch_probability`
+///
+/// Draws come from a generator seeded with `seed`, taken depth first, and
+/// ids are deterministic, so the same seed always yields the identical
+/// structure. A probability of 1.0 gives `subdivide_to_depth_deterministic`
+/// of the genesis; branches stop early at the precision limit.
+pub fn generate_random_fractal(
+    seed: u64,
+    max_depth: u8,
+    branch_probability: f64,
+) -> SierpinskiResult<FractalStructure> {
+    if !(0.0..=1.0).contains(&branch_probability) {
+        return Err(SierpinskiError::validation(format!(
+            "Branch probability must be between 0 and 1, got {}",
+            branch_probability
+        )));
+    }
+
+    let mut rng = XorShift64::new(seed);
+    build_to_depth(
+        FractalTriangle::genesis_deterministic(genesis_triangle()?),
+        max_depth,
+        true,
+        FractalTriangle::child_deterministic,
+        &mut |_| rng.next_f64() < branch_probability,
+    )
+}
+
+/// xorshift64* generator, kept local so seeded fractals never change with a
+/// dependency upgrade
+struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    fn new(seed: u64) -> Self {
+        // Spread the seed with splitmix64; xorshift never leaves a zero state
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        XorShift64 { state: if z == 0 { 0x9E37_79B9_7F4A_7C15 } else { z } }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Decides whether a subdividable triangle is subdivided
+type BranchPredicate<'a> = dyn FnMut(&FractalTriangle) -> bool + 'a;
+
+fn build_to_depth(
+    initial_triangle: FractalTriangle,
+    target_depth: u8,
+    allow_precision_loss: bool,
+    make_child: ChildConstructor,
+    branch: &mut BranchPredicate<'_>,
 ) -> SierpinskiResult<FractalStructure> {
     if target_depth > crate::MAX_SUBDIVISION_DEPTH {
         return Err(SierpinskiError::MaxDepthExceeded {
@@ -105,11 +384,19 @@ pub fn subdivide_to_depth(
         });
     }
 
+    let precision_limit = initial_triangle.depth + max_theoretical_depth(&initial_triangle.triangle)?;
+    if !allow_precision_loss && target_depth > precision_limit {
+        return Err(SierpinskiError::subdivision(format!(
+            "Requested depth {} exceeds the precision limit of {} for this genesis",
+            target_depth, precision_limit
+        )));
+    }
+
     let mut structure = FractalStructure::new();
     structure.set_genesis(initial_triangle)?;
 
     let genesis_id = structure.genesis().unwrap().id;
-    subdivide_recursive(&mut structure, genesis_id, target_depth)?;
+    subdivide_recursive(&mut structure, genesis_id, target_depth, make_child, branch)?;
 
     Ok(structure)
 }
@@ -119,13 +406,15 @@ fn subdivide_recursive(
     structure: &mut FractalStructure,
     triangle_id: Uuid,
     target_depth: u8,
+    make_child: ChildConstructor,
+    branch: &mut BranchPredicate<'_>,
 ) -> SierpinskiResult<()> {
     let triangle = structure
         .get_triangle(&triangle_id)
         .ok_or_else(|| SierpinskiError::subdivision("Triangle not found".to_string()))?
         .clone();
 
-    if triangle.depth >= target_depth {
+    if triangle.depth >= target_depth.min(structure.max_depth_limit()) {
         return Ok(());
     }
 
@@ -133,12 +422,21 @@ fn subdivide_recursive(
         return Ok(());
     }
 
+    // Children get a quarter of the area; stop before they become unrepresentable
+    if triangle.area()? / Decimal::from(4) < MIN_TRIANGLE_AREA {
+        return Ok(());
+    }
+
+    if !branch(&triangle) {
+        return Ok(());
+    }
+
     // Subdivide the triangle
-    let result = subdivide_and_add_to_structure(structure, &triangle_id)?;
+    let result = subdivide_into_structure(structure, &triangle_id, make_child)?;
 
     // Recursively subdivide children
     for child in &result.children {
-        subdivide_recursive(structure, child.id, target_depth)?;
+        subdivide_recursive(structure, child.id, target_depth, make_child, branch)?;
     }
 
     Ok(())
@@ -174,6 +472,14 @@ pub fn void_area_ratio() -> Decimal {
 
 /// Validate a subdivision result
 pub fn validate_subdivision(result: &SubdivisionResult) -> SierpinskiResult<bool> {
+    validate_subdivision_with_tolerances(result, &GeometricTolerances::DEFAULT)
+}
+
+/// Validate a subdivision result, checking area conservation against `tolerances`
+pub fn validate_subdivision_with_tolerances(
+    result: &SubdivisionResult,
+    tolerances: &GeometricTolerances,
+) -> SierpinskiResult<bool> {
     // Check that parent is in subdivided state
     if result.parent.state != TriangleState::Subdivided {
         return Ok(false);
@@ -194,19 +500,7 @@ pub fn validate_subdivision(result: &SubdivisionResult) -> SierpinskiResult<bool
         return Ok(false);
     }
 
-    // Verify area conservation (approximately)
-    let parent_area = result.parent.area()?;
-    let mut total_child_area = Decimal::ZERO;
-    
-    for child in &result.children {
-        total_child_area += child.area()?;
-    }
-    total_child_area += result.void_triangle.area()?;
-
-    let area_difference = (parent_area - total_child_area).abs();
-    let tolerance = parent_area * Decimal::new(1, 6); // 0.0001% tolerance
-
-    Ok(area_difference <= tolerance)
+    Ok(result.is_area_conserved(tolerances.area_conservation_relative))
 }
 
 /// Get subdivision statistics for a fractal structure
@@ -219,45 +513,159 @@ pub struct SubdivisionStats {
     pub max_depth: u8,
     pub total_area: Decimal,
     pub active_area: Decimal,
+    /// Combined area of all void triangles
+    pub void_area: Decimal,
+    /// Active area as a fraction of the genesis triangle's area
+    pub area_ratio_to_genesis: Decimal,
+}
+
+/// Statistics for a single subdivision depth
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthStats {
+    pub depth: u8,
+    /// Number of triangles at this depth, voids included
+    pub triangle_count: usize,
+    pub active_count: usize,
+    /// Area of active triangles at this depth and every shallower one
+    pub cumulative_active_area: Decimal,
+    pub void_area: Decimal,
+    /// Expected non-void area fraction at this depth, (3/4)^depth
+    pub theoretical_area_ratio: Decimal,
+    /// Measured non-void area at this depth divided by the genesis area
+    pub actual_area_ratio: Decimal,
+}
+
+/// Counts and areas gathered for one depth by `SubdivisionStats::per_depth`
+#[derive(Clone, Default)]
+struct LevelTotals {
+    triangles: usize,
+    active: usize,
+    active_area: Decimal,
+    void_area: Decimal,
+    /// Area of every triangle but the voids
+    covered_area: Decimal,
 }
 
 impl SubdivisionStats {
     /// Calculate statistics for a fractal structure
     pub fn calculate(structure: &FractalStructure) -> SierpinskiResult<Self> {
-        let active_triangles = structure.triangles_by_state(TriangleState::Active);
-        let subdivided_triangles = structure.triangles_by_state(TriangleState::Subdivided);
-        let void_triangles = structure.triangles_by_state(TriangleState::Void);
-        let genesis_triangles = structure.triangles_by_state(TriangleState::Genesis);
-
+        let mut active_triangles = 0;
+        let mut subdivided_triangles = 0;
+        let mut void_triangles = 0;
         let mut total_area = Decimal::ZERO;
         let mut active_area = Decimal::ZERO;
-
-        // Calculate total area from genesis and subdivided triangles
-        for triangle in genesis_triangles.iter().chain(subdivided_triangles.iter()) {
-            total_area += triangle.area()?;
-        }
-
-        // Calculate active area
-        for triangle in &active_triangles {
-            active_area += triangle.area()?;
+        let mut void_area = Decimal::ZERO;
+        let genesis_id = structure.genesis().map(|genesis| genesis.id);
+        let mut genesis_area = None;
+
+        for triangle in structure.triangles() {
+            let area = triangle.area()?;
+            if Some(triangle.id) == genesis_id {
+                genesis_area = Some(area);
+            }
+            match triangle.state {
+                // Total area comes from genesis and subdivided triangles
+                TriangleState::Genesis => total_area += area,
+                TriangleState::Subdivided => {
+                    subdivided_triangles += 1;
+                    total_area += area;
+                }
+                TriangleState::Active => {
+                    active_triangles += 1;
+                    active_area += area;
+                }
+                TriangleState::Void => {
+                    void_triangles += 1;
+                    void_area += area;
+                }
+                TriangleState::Inactive => {}
+            }
         }
 
         Ok(SubdivisionStats {
             total_triangles: structure.total_triangles(),
-            active_triangles: active_triangles.len(),
-            subdivided_triangles: subdivided_triangles.len(),
-            void_triangles: void_triangles.len(),
+            active_triangles,
+            subdivided_triangles,
+            void_triangles,
             max_depth: structure.max_depth(),
             total_area,
             active_area,
+            void_area,
+            area_ratio_to_genesis: ratio_to_genesis(active_area, genesis_area)?,
         })
     }
+
+    /// Break the structure down by depth, one entry per level from 0 to max depth
+    pub fn per_depth(structure: &FractalStructure) -> SierpinskiResult<Vec<DepthStats>> {
+        let mut levels = vec![LevelTotals::default(); structure.max_depth() as usize + 1];
+        let genesis_id = structure.genesis().map(|genesis| genesis.id);
+        let mut genesis_area = None;
+
+        // Single pass: each triangle's area is computed exactly once, the
+        // genesis area included
+        for triangle in structure.triangles() {
+            let area = triangle.area()?;
+            if Some(triangle.id) == genesis_id {
+                genesis_area = Some(area);
+            }
+
+            // Triangles edited through get_triangle_mut may sit deeper than
+            // the recorded max depth
+            let depth = triangle.depth as usize;
+            if levels.len() <= depth {
+                levels.resize(depth + 1, LevelTotals::default());
+            }
+            let level = &mut levels[depth];
+            level.triangles += 1;
+            match triangle.state {
+                TriangleState::Void => level.void_area += area,
+                TriangleState::Active => {
+                    level.active += 1;
+                    level.active_area += area;
+                    level.covered_area += area;
+                }
+                _ => level.covered_area += area,
+            }
+        }
+
+        let three_fourths = Decimal::from(3) / Decimal::from(4);
+        let mut theoretical_area_ratio = Decimal::ONE;
+        let mut cumulative_active_area = Decimal::ZERO;
+        let mut table = Vec::with_capacity(levels.len());
+
+        for (depth, level) in levels.into_iter().enumerate() {
+            cumulative_active_area += level.active_area;
+            table.push(DepthStats {
+                depth: depth as u8,
+                triangle_count: level.triangles,
+                active_count: level.active,
+                cumulative_active_area,
+                void_area: level.void_area,
+                theoretical_area_ratio,
+                actual_area_ratio: ratio_to_genesis(level.covered_area, genesis_area)?,
+            });
+            theoretical_area_ratio *= three_fourths;
+        }
+
+        Ok(table)
+    }
+}
+
+/// Express an area as a fraction of the genesis area, zero for a structure
+/// without a genesis
+fn ratio_to_genesis(area: Decimal, genesis_area: Option<Decimal>) -> SierpinskiResult<Decimal> {
+    match genesis_area {
+        Some(genesis_area) if genesis_area == Decimal::ZERO => Err(SierpinskiError::InvalidArea),
+        Some(genesis_area) => Ok(area / genesis_area),
+        None => Ok(Decimal::ZERO),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::genesis::genesis_fractal_triangle;
+    use crate::core::geometry::Point;
+    use crate::core::genesis::{genesis_fractal_triangle, genesis_triangle, genesis_triangle_with_size};
 
     #[test]
     fn test_subdivision() {
@@ -272,13 +680,73 @@ mod tests {
         assert!(validate_subdivision(&result).unwrap());
     }
 
+    #[test]
+    fn test_subdivision_records_area_conservation() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let mut result = subdivide_triangle(&genesis).unwrap();
+
+        assert_eq!(result.parent_area, genesis.triangle.area().unwrap());
+        assert!(result.area_error < Decimal::new(1, 20));
+        assert!((result.void_area * Decimal::from(3) - result.children_area).abs() < Decimal::new(1, 20));
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json.get("area_error").is_some() && json.get("children_area").is_some());
+
+        // The recorded fields are for reporting; validation measures the
+        // triangles themselves
+        let tolerance = GeometricTolerances::DEFAULT.area_conservation_relative;
+        result.area_error = result.parent_area * tolerance * Decimal::new(11, 1);
+        assert!(result.is_area_conserved(tolerance));
+
+        let mut forged = subdivide_triangle(&genesis).unwrap();
+        forged.children[0].triangle = genesis.triangle.clone();
+        assert!(forged.area_error < Decimal::new(1, 20));
+        assert!(!forged.is_area_conserved(tolerance));
+        assert!(!validate_subdivision(&forged).unwrap());
+
+        // Results recorded before the fields existed still deserialize
+        let mut legacy = serde_json::to_value(&forged).unwrap();
+        for field in ["parent_area", "children_area", "void_area", "area_error"] {
+            legacy.as_object_mut().unwrap().remove(field);
+        }
+        let legacy: SubdivisionResult = serde_json::from_value(legacy).unwrap();
+        assert_eq!(legacy.area_error, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_full_four_pattern_conserves_area() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let parent_area = genesis.triangle.area().unwrap();
+
+        let children = subdivide_triangle_with(&genesis, SubdivisionPattern::FullFour).unwrap();
+        assert_eq!(children.len(), 4);
+        assert!(children.iter().all(|child| child.state == TriangleState::Active));
+        assert_eq!(children[3].address.last_component(), Some(3));
+
+        let total: Decimal = children.iter().map(|child| child.triangle.area().unwrap()).sum();
+        assert!((total - parent_area).abs() < parent_area * Decimal::new(1, 9));
+    }
+
+    #[test]
+    fn test_sierpinski_pattern_keeps_void() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let children = subdivide_triangle_with(&genesis, SubdivisionPattern::default()).unwrap();
+
+        assert_eq!(children.len(), 4);
+        assert!(children[..3].iter().all(|child| child.state == TriangleState::Active));
+        assert_eq!(children[3].state, TriangleState::Void);
+        assert!(children[3].address.is_void());
+    }
+
     #[test]
     fn test_subdivision_to_depth() {
         let genesis = genesis_fractal_triangle().unwrap();
         let structure = subdivide_to_depth(genesis, 2).unwrap();
 
         assert_eq!(structure.max_depth(), 2);
-        assert_eq!(structure.total_triangles(), total_triangles_to_depth(2) as usize);
+        // Void triangles are stored alongside the counted Sierpinski triangles
+        let void_count = structure.triangles_by_state(TriangleState::Void).len();
+        assert_eq!(structure.total_triangles() - void_count, total_triangles_to_depth(2) as usize);
     }
 
     #[test]
@@ -306,5 +774,167 @@ mod tests {
         assert_eq!(stats.active_triangles, 3);
         assert_eq!(stats.subdivided_triangles, 1);
         assert_eq!(stats.void_triangles, 1);
+        assert!((stats.area_ratio_to_genesis - Decimal::new(75, 2)).abs() < Decimal::new(1, 20));
+        assert!((stats.void_area / stats.total_area - Decimal::new(25, 2)).abs() < Decimal::new(1, 20));
+    }
+
+    #[test]
+    fn test_per_depth_stats() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let genesis_area = genesis.area().unwrap();
+        let structure = subdivide_to_depth(genesis, 2).unwrap();
+        let table = SubdivisionStats::per_depth(&structure).unwrap();
+        let tolerance = Decimal::new(1, 20);
+        let quarter = Decimal::new(25, 2);
+        let sixteenth = Decimal::new(625, 4);
+
+        assert_eq!(table.len(), 3);
+
+        // (triangle_count, active_count, void_area / genesis, theoretical, actual)
+        let expected = [
+            (1, 0, Decimal::ZERO, Decimal::ONE, Decimal::ONE),
+            (4, 0, quarter, Decimal::new(75, 2), Decimal::new(75, 2)),
+            (12, 9, sixteenth * Decimal::from(3), Decimal::new(5625, 4), Decimal::new(5625, 4)),
+        ];
+
+        for (row, (count, active, void_ratio, theoretical, actual)) in table.iter().zip(expected) {
+            assert_eq!(row.triangle_count, count, "depth {}", row.depth);
+            assert_eq!(row.active_count, active, "depth {}", row.depth);
+            assert!((row.void_area / genesis_area - void_ratio).abs() < tolerance);
+            assert_eq!(row.theoretical_area_ratio, theoretical);
+            assert!((row.actual_area_ratio - actual).abs() < tolerance);
+        }
+
+        // Only depth 2 holds active triangles: (3/4)^2 of the genesis area
+        assert_eq!(table[1].cumulative_active_area, Decimal::ZERO);
+        assert!((table[2].cumulative_active_area / genesis_area - Decimal::new(5625, 4)).abs() < tolerance);
+    }
+
+    #[test]
+    fn test_deterministic_subdivision_ids() {
+        let genesis = FractalTriangle::genesis_deterministic(genesis_triangle().unwrap());
+        assert_eq!(genesis.id, FractalTriangle::genesis_deterministic(genesis_triangle().unwrap()).id);
+
+        let first = subdivide_triangle_deterministic(&genesis).unwrap();
+        let second = subdivide_triangle_deterministic(&genesis).unwrap();
+        let ids = |result: &SubdivisionResult| {
+            let mut ids: Vec<Uuid> = result.children.iter().map(|c| c.id).collect();
+            ids.push(result.void_triangle.id);
+            ids
+        };
+        assert_eq!(ids(&first), ids(&second));
+        assert_eq!(first.parent.child_ids, second.parent.child_ids);
+
+        // Random ids remain the default
+        assert_ne!(ids(&subdivide_triangle(&genesis).unwrap()), ids(&first));
+
+        let structure_a = subdivide_to_depth_deterministic(genesis_triangle().unwrap(), 3).unwrap();
+        let structure_b = subdivide_to_depth_deterministic(genesis_triangle().unwrap(), 3).unwrap();
+        let mut ids_a: Vec<Uuid> = structure_a.triangles().map(|t| t.id).collect();
+        let mut ids_b: Vec<Uuid> = structure_b.triangles().map(|t| t.id).collect();
+        ids_a.sort();
+        ids_b.sort();
+        assert_eq!(ids_a.len(), structure_a.total_triangles());
+        assert_eq!(ids_a, ids_b);
+    }
+
+    #[test]
+    fn test_per_structure_depth_limit() {
+        let mut structure = subdivide_to_depth(FractalTriangle::genesis(genesis_triangle().unwrap()), 2).unwrap();
+        assert_eq!(structure.max_depth_limit(), crate::MAX_SUBDIVISION_DEPTH);
+        assert!(structure.set_max_depth_limit(1).is_err());
+        assert!(structure.set_max_depth_limit(crate::MAX_SUBDIVISION_DEPTH + 1).is_err());
+        structure.set_max_depth_limit(2).unwrap();
+
+        let leaf = structure.triangles().find(|t| t.depth == 2 && t.state != TriangleState::Void).unwrap().id;
+        let before = layout(&structure);
+        let result = subdivide_and_add_to_structure(&mut structure, &leaf);
+        assert!(matches!(result, Err(SierpinskiError::MaxDepthExceeded { max_depth: 2 })));
+        assert_eq!(layout(&structure), before);
+    }
+
+    #[test]
+    fn test_subdivision_halts_at_precision_limit() {
+        let tiny = genesis_triangle_with_size(Point::new(Decimal::ZERO, Decimal::ZERO), Decimal::new(1, 4)).unwrap();
+        let limit = max_theoretical_depth(&tiny).unwrap();
+        assert!(limit < 10);
+
+        let result = subdivide_to_depth(FractalTriangle::genesis(tiny.clone()), 10);
+        assert!(matches!(result, Err(SierpinskiError::SubdivisionError { .. })));
+
+        let structure = subdivide_to_depth_checked(FractalTriangle::genesis(tiny), 10, true).unwrap();
+        assert_eq!(structure.max_depth(), limit);
+        for triangle in structure.triangles() {
+            assert!(triangle.area().unwrap() >= MIN_TRIANGLE_AREA);
+        }
+    }
+
+    #[test]
+    fn test_geometry_iter_matches_subdivide_to_depth() {
+        let genesis = genesis_triangle().unwrap();
+        let structure = subdivide_to_depth(FractalTriangle::genesis(genesis.clone()), 4).unwrap();
+
+        let mut expected: Vec<(Vec<u8>, String)> = structure
+            .triangles()
+            .filter(|t| t.state != TriangleState::Void)
+            .map(|t| (t.address.components().to_vec(), t.triangle.canonical_hash()))
+            .collect();
+        let mut actual: Vec<(Vec<u8>, String)> = FractalGeometryIter::new(genesis.clone(), 4)
+            .map(|(address, triangle)| (address.components().to_vec(), triangle.canonical_hash()))
+            .collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+
+        // Breadth first: depths never decrease
+        let depths: Vec<u8> = FractalGeometryIter::new(genesis, 4).map(|(address, _)| address.depth()).collect();
+        assert!(depths.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_geometry_iter_counts_deep_fractal() {
+        let genesis = genesis_triangle().unwrap();
+        assert_eq!(FractalGeometryIter::new(genesis, 10).count() as u64, total_triangles_to_depth(10));
+    }
+
+    /// Ids, addresses and states of every triangle, in a stable order
+    fn layout(structure: &FractalStructure) -> Vec<(Uuid, String, TriangleState)> {
+        let mut layout: Vec<_> = structure
+            .triangles()
+            .map(|triangle| (triangle.id, triangle.address.to_string(), triangle.state))
+            .collect();
+        layout.sort_by_key(|(id, _, _)| *id);
+        layout
+    }
+
+    #[test]
+    fn test_random_fractal_is_reproducible_and_valid() {
+        use crate::core::validation::validate_fractal_structure;
+
+        let first = generate_random_fractal(7, 5, 0.6).unwrap();
+        let second = generate_random_fractal(7, 5, 0.6).unwrap();
+        assert_eq!(layout(&first), layout(&second));
+        assert!(validate_fractal_structure(&first).is_valid);
+        assert!(first.max_depth() <= 5);
+
+        let other_seed = generate_random_fractal(8, 5, 0.6).unwrap();
+        assert_ne!(layout(&first), layout(&other_seed));
+
+        // Sparse: fewer triangles than the full fractal, more than genesis alone
+        let full = total_triangles_to_depth(5) as usize;
+        assert!(first.total_triangles() > 1 && first.total_triangles() < full);
+
+        assert!(generate_random_fractal(7, 5, 1.5).is_err());
+        assert!(generate_random_fractal(7, crate::MAX_SUBDIVISION_DEPTH + 1, 0.5).is_err());
+    }
+
+    #[test]
+    fn test_random_fractal_with_certain_branching_is_full() {
+        let random = generate_random_fractal(42, 3, 1.0).unwrap();
+        let full = subdivide_to_depth_deterministic(genesis_triangle().unwrap(), 3).unwrap();
+        assert_eq!(layout(&random), layout(&full));
+
+        let bare = generate_random_fractal(42, 3, 0.0).unwrap();
+        assert_eq!(bare.total_triangles(), 1);
     }
 }
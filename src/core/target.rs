@@ -0,0 +1,300 @@
+//! 256-bit proof-of-work targets and their compact encoding
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+use crate::core::errors::{SierpinskiError, SierpinskiResult};
+
+/// Largest hash, read as a big-endian 256-bit integer, that satisfies a
+/// proof-of-work; smaller targets are harder to meet
+///
+/// Byte arrays compare lexicographically, which is big-endian integer order,
+/// so targets and hashes compare directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Target([u8; 32]);
+
+impl Target {
+    /// Easiest possible target, met by every hash
+    pub const MAX: Target = Target([0xff; 32]);
+
+    /// Target from its big-endian bytes
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Target(bytes)
+    }
+
+    /// Big-endian bytes of the target
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Target equivalent to the legacy difficulty of `difficulty` leading
+    /// zero bits, for chains and parameters from before compact targets
+    pub fn from_legacy_difficulty(difficulty: u32) -> Self {
+        let zero_bits = difficulty.min(256) as usize;
+        let (zero_bytes, extra_bits) = (zero_bits / 8, zero_bits % 8);
+        let mut target = [0xff; 32];
+        target[..zero_bytes].fill(0);
+        if extra_bits > 0 {
+            target[zero_bytes] = 0xff >> extra_bits;
+        }
+        Target(target)
+    }
+
+    /// Decode a compact target: the high byte is the length of the target in
+    /// bytes and the low three bytes its leading bytes, as in Bitcoin's
+    /// `nBits`; the 0x00800000 bit is a sign and must be clear
+    pub fn from_compact(bits: u32) -> SierpinskiResult<Self> {
+        let size = (bits >> 24) as i32;
+        let mantissa = bits & 0x007f_ffff;
+        if bits & 0x0080_0000 != 0 && mantissa != 0 {
+            return Err(SierpinskiError::validation(format!("Compact target {:#010x} is negative", bits)));
+        }
+
+        let mut target = [0u8; 32];
+        for (i, byte) in mantissa.to_be_bytes()[1..].iter().enumerate() {
+            // Byte position counted from the least significant end
+            let position = size - 1 - i as i32;
+            if *byte == 0 || position < 0 {
+                continue;
+            }
+            if position >= 32 {
+                return Err(SierpinskiError::validation(format!(
+                    "Compact target {:#010x} overflows 256 bits",
+                    bits
+                )));
+            }
+            target[31 - position as usize] = *byte;
+        }
+        Ok(Target(target))
+    }
+
+    /// Encode as a compact target, keeping the three leading bytes; the
+    /// round trip through `from_compact` rounds down
+    pub fn to_compact(&self) -> u32 {
+        let Some(first) = self.0.iter().position(|byte| *byte != 0) else {
+            return 0;
+        };
+        let mut size = (32 - first) as u32;
+        let mut leading = [0u8; 4];
+        for (i, byte) in self.0[first..].iter().take(3).enumerate() {
+            leading[i + 1] = *byte;
+        }
+        let mut mantissa = u32::from_be_bytes(leading);
+        // Keep the sign bit clear by moving to a longer size
+        if mantissa & 0x0080_0000 != 0 {
+            mantissa >>= 8;
+            size += 1;
+        }
+        (size << 24) | mantissa
+    }
+
+    /// Check whether a hash meets the target
+    pub fn is_met_by(&self, hash: &[u8; 32]) -> bool {
+        *hash <= self.0
+    }
+
+    /// Number of leading zero bits, the legacy difficulty the target
+    /// rounds to
+    pub fn leading_zero_bits(&self) -> u32 {
+        let mut zeros = 0;
+        for byte in self.0 {
+            zeros += byte.leading_zeros();
+            if byte != 0 {
+                break;
+            }
+        }
+        zeros
+    }
+
+    /// Target multiplied by `numerator / denominator`, saturating at
+    /// `Target::MAX`
+    pub fn scaled(&self, numerator: u64, denominator: u64) -> Self {
+        let denominator = denominator.max(1) as u128;
+        let limbs = self.limbs();
+
+        let mut product = [0u64; 5];
+        let mut carry = 0u128;
+        for (i, limb) in limbs.iter().enumerate() {
+            let value = *limb as u128 * numerator as u128 + carry;
+            product[i] = value as u64;
+            carry = value >> 64;
+        }
+        product[4] = carry as u64;
+
+        let mut quotient = [0u64; 5];
+        let mut remainder = 0u128;
+        for i in (0..5).rev() {
+            let value = (remainder << 64) | product[i] as u128;
+            quotient[i] = (value / denominator) as u64;
+            remainder = value % denominator;
+        }
+
+        if quotient[4] != 0 {
+            return Target::MAX;
+        }
+        Self::from_limbs([quotient[0], quotient[1], quotient[2], quotient[3]])
+    }
+
+    /// Expected number of hashes to meet the target, 2^256 / (target + 1)
+    /// taken to 64 significant bits and saturating at `u128::MAX`
+    pub fn work(&self) -> u128 {
+        let (mantissa, shift) = self.significand();
+        let Some(divisor) = (mantissa as u128).checked_add(1) else {
+            return u128::MAX;
+        };
+        let exponent = 256 - shift;
+        if exponent <= 127 {
+            return (1u128 << exponent) / divisor;
+        }
+
+        let quotient = (1u128 << 127) / divisor;
+        let extra = exponent - 127;
+        if quotient.leading_zeros() < extra {
+            u128::MAX
+        } else {
+            quotient << extra
+        }
+    }
+
+    /// Human-readable difficulty, the number of leading zero bits a target
+    /// is worth as a fraction; `Target::MAX` is 0 and each halving adds 1
+    pub fn difficulty(&self) -> f64 {
+        let (mantissa, shift) = self.significand();
+        256.0 - ((mantissa as f64 + 1.0).log2() + shift as f64)
+    }
+
+    /// Leading 64 bits of the target and the shift that restores it
+    fn significand(&self) -> (u64, u32) {
+        let length = 256 - self.leading_zero_bits();
+        let shift = length.saturating_sub(64);
+        let limbs = self.limbs();
+        let (index, offset) = ((shift / 64) as usize, shift % 64);
+        let mut mantissa = limbs[index] >> offset;
+        if offset > 0 && index + 1 < limbs.len() {
+            mantissa |= limbs[index + 1] << (64 - offset);
+        }
+        (mantissa, shift)
+    }
+
+    /// 64-bit limbs, least significant first
+    fn limbs(&self) -> [u64; 4] {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let start = 32 - (i + 1) * 8;
+            *limb = u64::from_be_bytes(self.0[start..start + 8].try_into().unwrap());
+        }
+        limbs
+    }
+
+    fn from_limbs(limbs: [u64; 4]) -> Self {
+        let mut bytes = [0u8; 32];
+        for (i, limb) in limbs.iter().enumerate() {
+            let start = 32 - (i + 1) * 8;
+            bytes[start..start + 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        Target(bytes)
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+/// Targets serialize as hex; a bare number is a legacy difficulty in leading
+/// zero bits, as written by chains from before compact targets
+impl Serialize for Target {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Target {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Encoded {
+            Hex(String),
+            Legacy(u32),
+        }
+
+        match Encoded::deserialize(deserializer)? {
+            Encoded::Legacy(difficulty) => Ok(Target::from_legacy_difficulty(difficulty)),
+            Encoded::Hex(encoded) => {
+                let bytes = hex::decode(&encoded).map_err(serde::de::Error::custom)?;
+                let bytes: [u8; 32] = bytes
+                    .try_into()
+                    .map_err(|_| serde::de::Error::custom("target must be 32 bytes"))?;
+                Ok(Target(bytes))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_with_prefix(prefix: &[u8]) -> [u8; 32] {
+        let mut hash = [0xff; 32];
+        hash[..prefix.len()].copy_from_slice(prefix);
+        hash
+    }
+
+    #[test]
+    fn test_legacy_difficulty_conversion() {
+        assert_eq!(Target::from_legacy_difficulty(8).as_bytes(), &hash_with_prefix(&[0x00]));
+        assert_eq!(Target::from_legacy_difficulty(12).as_bytes(), &hash_with_prefix(&[0x00, 0x0f]));
+        assert_eq!(Target::from_legacy_difficulty(0), Target::MAX);
+        assert_eq!(Target::from_legacy_difficulty(300).as_bytes(), &[0; 32]);
+
+        // Legacy targets keep their work, difficulty and zero-bit count, and
+        // the compact encoding rounds them down without losing a zero bit
+        for difficulty in [1, 12, 16, 80] {
+            let target = Target::from_legacy_difficulty(difficulty);
+            assert_eq!(target.work(), 1u128 << difficulty);
+            assert_eq!(target.difficulty(), difficulty as f64);
+            let compact = Target::from_compact(target.to_compact()).unwrap();
+            assert!(compact <= target);
+            assert_eq!(compact.leading_zero_bits(), difficulty);
+        }
+        assert_eq!(Target::from_legacy_difficulty(16).to_compact(), 0x1f00_ffff);
+
+        let legacy: Target = serde_json::from_str("16").unwrap();
+        assert_eq!(legacy, Target::from_legacy_difficulty(16));
+        let encoded = serde_json::to_string(&legacy).unwrap();
+        assert_eq!(serde_json::from_str::<Target>(&encoded).unwrap(), legacy);
+    }
+
+    #[test]
+    fn test_hash_at_target_boundary() {
+        let target = Target::from_compact(0x1e12_3456).unwrap();
+        let mut at = [0u8; 32];
+        at[2..5].copy_from_slice(&[0x12, 0x34, 0x56]);
+        assert_eq!(target.as_bytes(), &at);
+        assert!(target.is_met_by(&at));
+
+        let mut above = at;
+        above[31] = 1;
+        assert!(!target.is_met_by(&above));
+        let mut below = at;
+        below[4] = 0x55;
+        below[5..].fill(0xff);
+        assert!(target.is_met_by(&below));
+
+        assert_eq!(target.to_compact(), 0x1e12_3456);
+        assert!(Target::from_compact(0x1e92_3456).is_err());
+        assert!(Target::from_compact(0x2112_3456).is_err());
+    }
+
+    #[test]
+    fn test_scaling_is_multiplicative() {
+        let target = Target::from_legacy_difficulty(16);
+        let halved = target.scaled(1, 2);
+        assert_eq!(halved, Target::from_legacy_difficulty(17));
+        assert!((halved.difficulty() - 17.0).abs() < 1e-9);
+        assert!((target.scaled(3, 2).difficulty() - (16.0 - 1.5f64.log2())).abs() < 1e-9);
+        assert_eq!(Target::MAX.scaled(2, 1), Target::MAX);
+    }
+}
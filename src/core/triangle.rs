@@ -1,10 +1,11 @@
 //! Triangle data structure and fundamental operations
 
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use crate::core::geometry::Point;
+use crate::core::geometry::{BoundingBox, GeometricTolerances, Point};
 use crate::core::errors::{SierpinskiError, SierpinskiResult};
 
 /// A triangle defined by three vertices
@@ -13,6 +14,22 @@ pub struct Triangle {
     pub vertices: [Point; 3],
 }
 
+/// Where a point lies relative to a triangle, see `Triangle::classify_point`
+///
+/// Edge `i` runs from vertex `i` to vertex `(i + 1) % 3`, matching the
+/// order of `Triangle::side_midpoints`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointLocation {
+    /// Strictly inside, off every edge
+    Inside,
+    /// On the given edge, between its endpoints
+    OnEdge(u8),
+    /// At the given vertex
+    OnVertex(u8),
+    /// Outside the triangle
+    Outside,
+}
+
 impl Triangle {
     /// Create a new triangle from three points
     pub fn new(p1: Point, p2: Point, p3: Point) -> SierpinskiResult<Self> {
@@ -58,15 +75,39 @@ impl Triangle {
         Ok(side1 + side2 + side3)
     }
 
+    /// Approximate area in f64, for bulk statistics that do not need the
+    /// decimal precision of `area`
+    pub fn area_f64(&self) -> f64 {
+        let [(x1, y1), (x2, y2), (x3, y3)] = self.vertices_f64();
+        ((x2 - x1) * (y3 - y1) - (y2 - y1) * (x3 - x1)).abs() / 2.0
+    }
+
+    /// Approximate perimeter in f64, see `area_f64`
+    pub fn perimeter_f64(&self) -> f64 {
+        let [a, b, c] = self.vertices_f64();
+        let side = |(x1, y1): (f64, f64), (x2, y2): (f64, f64)| (x2 - x1).hypot(y2 - y1);
+        side(a, b) + side(b, c) + side(c, a)
+    }
+
+    /// Vertices converted to f64 once for the approximate measurements
+    fn vertices_f64(&self) -> [(f64, f64); 3] {
+        self.vertices.map(|point| {
+            (
+                point.x.to_f64().unwrap_or(f64::NAN),
+                point.y.to_f64().unwrap_or(f64::NAN),
+            )
+        })
+    }
+
+    /// Get the axis-aligned box enclosing the triangle
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points(&self.vertices).expect("a triangle has three vertices")
+    }
+
     /// Calculate the centroid (center of mass) of the triangle
     pub fn centroid(&self) -> Point {
         let [p1, p2, p3] = self.vertices;
-        let three = Decimal::from(3);
-        
-        Point::new(
-            (p1.x + p2.x + p3.x) / three,
-            (p1.y + p2.y + p3.y) / three,
-        )
+        (p1 + p2 + p3) / Decimal::from(3)
     }
 
     /// Get the three side lengths of the triangle
@@ -80,10 +121,27 @@ impl Triangle {
         ])
     }
 
-    /// Check if the triangle is equilateral (all sides equal)
+    /// Check if the triangle is equilateral (all sides equal), within the
+    /// default relative side tolerance
     pub fn is_equilateral(&self) -> SierpinskiResult<bool> {
+        self.is_equilateral_within(GeometricTolerances::DEFAULT.side_equality)
+    }
+
+    /// Check if every side deviates from the mean side length by less than
+    /// `rel_tol` times that mean, so the answer does not depend on scale
+    pub fn is_equilateral_within(&self, rel_tol: Decimal) -> SierpinskiResult<bool> {
+        let sides = self.side_lengths()?;
+        let mean = (sides[0] + sides[1] + sides[2]) / Decimal::from(3);
+        if mean.is_zero() {
+            return Ok(false);
+        }
+
+        Ok(sides.iter().all(|side| (*side - mean).abs() / mean < rel_tol))
+    }
+
+    /// Check if all sides differ by less than an absolute `tolerance`
+    pub fn is_equilateral_with_tolerance(&self, tolerance: Decimal) -> SierpinskiResult<bool> {
         let sides = self.side_lengths()?;
-        let tolerance = Decimal::new(1, 10); // 0.1 tolerance for floating point comparison
         
         let diff1 = (sides[0] - sides[1]).abs();
         let diff2 = (sides[1] - sides[2]).abs();
@@ -92,10 +150,16 @@ impl Triangle {
         Ok(diff1 < tolerance && diff2 < tolerance && diff3 < tolerance)
     }
 
-    /// Check if the triangle is isosceles (two sides equal)
+    /// Check if the triangle is isosceles (two sides equal), within the
+    /// default side tolerance scaled to the triangle's size
     pub fn is_isosceles(&self) -> SierpinskiResult<bool> {
+        let tolerance = GeometricTolerances::DEFAULT.side_tolerance(&self.side_lengths()?);
+        self.is_isosceles_with_tolerance(tolerance)
+    }
+
+    /// Check if two sides differ by less than an absolute `tolerance`
+    pub fn is_isosceles_with_tolerance(&self, tolerance: Decimal) -> SierpinskiResult<bool> {
         let sides = self.side_lengths()?;
-        let tolerance = Decimal::new(1, 10);
         
         let eq1 = (sides[0] - sides[1]).abs() < tolerance;
         let eq2 = (sides[1] - sides[2]).abs() < tolerance;
@@ -104,6 +168,148 @@ impl Triangle {
         Ok(eq1 || eq2 || eq3)
     }
 
+    /// Get the side lengths sorted from shortest to longest
+    pub fn sorted_side_lengths(&self) -> SierpinskiResult<[Decimal; 3]> {
+        let mut sides = self.side_lengths()?;
+        sides.sort();
+        Ok(sides)
+    }
+
+    /// Check if two triangles have the same shape (proportional sides),
+    /// regardless of position, size, or vertex order
+    pub fn is_similar_to(&self, other: &Triangle, tol: Decimal) -> SierpinskiResult<bool> {
+        let mine = self.sorted_side_lengths()?;
+        let theirs = other.sorted_side_lengths()?;
+
+        if mine[2] == Decimal::ZERO || theirs[2] == Decimal::ZERO {
+            return Err(SierpinskiError::InvalidArea);
+        }
+
+        // Compare side ratios relative to each triangle's longest side
+        Ok(mine
+            .iter()
+            .zip(theirs.iter())
+            .all(|(a, b)| (*a / mine[2] - *b / theirs[2]).abs() <= tol))
+    }
+
+    /// Check if two triangles have equal side lengths, regardless of
+    /// position or vertex order
+    pub fn is_congruent_to(&self, other: &Triangle, tol: Decimal) -> SierpinskiResult<bool> {
+        let mine = self.sorted_side_lengths()?;
+        let theirs = other.sorted_side_lengths()?;
+
+        Ok(mine.iter().zip(theirs.iter()).all(|(a, b)| (*a - *b).abs() <= tol))
+    }
+
+    /// Copy of the triangle with vertices sorted by (x, then y), so that
+    /// triangles with the same corners compare and hash equal
+    pub fn canonical(&self) -> Triangle {
+        let mut vertices = self.vertices;
+        vertices.sort_by(Point::canonical_cmp);
+        Triangle { vertices }
+    }
+
+    /// Check if two triangles have the same corners, regardless of vertex order
+    pub fn same_shape_and_position(&self, other: &Triangle) -> bool {
+        self.canonical() == other.canonical()
+    }
+
+    /// Check if two triangles overlap with positive area, using the
+    /// separating axis theorem
+    ///
+    /// Boundary rule: triangles that only touch along an edge or at a vertex
+    /// do not intersect, so neighbouring tiles of a subdivision never count
+    /// as overlapping.
+    pub fn intersects(&self, other: &Triangle) -> bool {
+        // Any edge normal of either triangle whose projections at most touch
+        // proves the interiors are disjoint
+        let separated = [self, other].iter().any(|triangle| {
+            (0..3).any(|i| {
+                let start = triangle.vertices[i];
+                let end = triangle.vertices[(i + 1) % 3];
+                let (nx, ny) = (start.y - end.y, end.x - start.x);
+
+                let project = |t: &Triangle| {
+                    let values = t.vertices.map(|v| v.x * nx + v.y * ny);
+                    let min = values.iter().copied().min().unwrap_or_default();
+                    let max = values.iter().copied().max().unwrap_or_default();
+                    (min, max)
+                };
+
+                let (min_a, max_a) = project(self);
+                let (min_b, max_b) = project(other);
+                max_a <= min_b || max_b <= min_a
+            })
+        });
+
+        !separated
+    }
+
+    /// Calculate the area shared by two triangles using Sutherland–Hodgman
+    /// clipping of this triangle against the other
+    pub fn overlap_area(&self, other: &Triangle) -> SierpinskiResult<Decimal> {
+        // Clip edges must run counter-clockwise so "inside" is on the left
+        let mut clip = other.vertices;
+        if clip[0].cross_product(&clip[1], &clip[2]) < Decimal::ZERO {
+            clip.swap(1, 2);
+        }
+
+        let mut polygon = self.vertices.to_vec();
+        for i in 0..3 {
+            let edge_start = clip[i];
+            let edge_end = clip[(i + 1) % 3];
+            let input = std::mem::take(&mut polygon);
+
+            for (j, &current) in input.iter().enumerate() {
+                let previous = input[(j + input.len() - 1) % input.len()];
+                let current_side = edge_start.cross_product(&edge_end, &current);
+                let previous_side = edge_start.cross_product(&edge_end, &previous);
+
+                if current_side >= Decimal::ZERO {
+                    if previous_side < Decimal::ZERO {
+                        polygon.push(Self::edge_crossing(previous, current, previous_side, current_side)?);
+                    }
+                    polygon.push(current);
+                } else if previous_side >= Decimal::ZERO {
+                    polygon.push(Self::edge_crossing(previous, current, previous_side, current_side)?);
+                }
+            }
+
+            if polygon.is_empty() {
+                return Ok(Decimal::ZERO);
+            }
+        }
+
+        // Shoelace formula over the clipped polygon
+        let doubled_area = (0..polygon.len())
+            .map(|k| {
+                let p = polygon[k];
+                let q = polygon[(k + 1) % polygon.len()];
+                p.x * q.y - q.x * p.y
+            })
+            .sum::<Decimal>();
+
+        Ok(doubled_area.abs() / Decimal::from(2))
+    }
+
+    /// Point where segment `from -> to` crosses a clip edge, given each
+    /// endpoint's signed distance (as a cross product) from that edge
+    fn edge_crossing(
+        from: Point,
+        to: Point,
+        from_side: Decimal,
+        to_side: Decimal,
+    ) -> SierpinskiResult<Point> {
+        let t = from_side
+            .checked_div(from_side - to_side)
+            .ok_or(SierpinskiError::ArithmeticOverflow)?;
+
+        Ok(Point::new(
+            from.x + (to.x - from.x) * t,
+            from.y + (to.y - from.y) * t,
+        ))
+    }
+
     /// Get the midpoints of all three sides
     pub fn side_midpoints(&self) -> [Point; 3] {
         let [p1, p2, p3] = self.vertices;
@@ -114,6 +320,34 @@ impl Triangle {
         ]
     }
 
+    /// Corner triangles of the Sierpinski pattern `depth` levels down,
+    /// without the central voids
+    ///
+    /// Works on bare geometry: each level splits every triangle at its side
+    /// midpoints and keeps the three corners, so `3^depth` triangles come
+    /// back, in the same order as subdivision children.
+    pub fn sierpinski_leaves(&self, depth: u8) -> SierpinskiResult<Vec<Triangle>> {
+        if depth > crate::MAX_SUBDIVISION_DEPTH {
+            return Err(SierpinskiError::MaxDepthExceeded {
+                max_depth: crate::MAX_SUBDIVISION_DEPTH,
+            });
+        }
+
+        let mut leaves = vec![self.clone()];
+        for _ in 0..depth {
+            let mut next = Vec::with_capacity(leaves.len() * 3);
+            for triangle in &leaves {
+                let [p1, p2, p3] = triangle.vertices;
+                let [mid_12, mid_23, mid_31] = triangle.side_midpoints();
+                next.push(Triangle::new(p1, mid_12, mid_31)?);
+                next.push(Triangle::new(mid_12, p2, mid_23)?);
+                next.push(Triangle::new(mid_31, mid_23, p3)?);
+            }
+            leaves = next;
+        }
+        Ok(leaves)
+    }
+
     /// Check if a point is inside the triangle using barycentric coordinates
     pub fn contains_point(&self, point: &Point) -> bool {
         let [p1, p2, p3] = self.vertices;
@@ -132,6 +366,35 @@ impl Triangle {
         a >= Decimal::ZERO && b >= Decimal::ZERO && c >= Decimal::ZERO
     }
 
+    /// Locate a point as strictly inside, on an edge or vertex, or outside
+    ///
+    /// Uses the sign of each edge's cross product rather than barycentric
+    /// division, so boundary points are detected exactly.
+    pub fn classify_point(&self, point: &Point) -> PointLocation {
+        if let Some(vertex) = self.vertices.iter().position(|vertex| vertex == point) {
+            return PointLocation::OnVertex(vertex as u8);
+        }
+
+        let orientation = self.vertices[0].cross_product(&self.vertices[1], &self.vertices[2]);
+        if orientation.is_zero() {
+            return PointLocation::Outside; // Degenerate triangle
+        }
+
+        let mut on_edge = None;
+        for edge in 0..3 {
+            let from = self.vertices[edge];
+            let to = self.vertices[(edge + 1) % 3];
+            let side = from.cross_product(&to, point);
+            if side.is_zero() {
+                on_edge = Some(edge as u8);
+            } else if side.is_sign_negative() != orientation.is_sign_negative() {
+                return PointLocation::Outside;
+            }
+        }
+
+        on_edge.map_or(PointLocation::Inside, PointLocation::OnEdge)
+    }
+
     /// Calculate the scale factor relative to another triangle
     pub fn scale_factor(&self, other: &Triangle) -> SierpinskiResult<Decimal> {
         let my_area = self.area()?;
@@ -146,7 +409,57 @@ impl Triangle {
         Point::new(Decimal::ZERO, Decimal::ZERO).decimal_sqrt(area_ratio)
     }
 
-    /// Generate a unique hash for the triangle based on its vertices
+    /// Scale the triangle by `factor` about its centroid, which stays fixed
+    pub fn scale_about_centroid(&self, factor: Decimal) -> SierpinskiResult<Triangle> {
+        self.scale_about_point(&self.centroid(), factor)
+    }
+
+    /// Scale the triangle by `factor` about `center`, which stays fixed
+    pub fn scale_about_point(&self, center: &Point, factor: Decimal) -> SierpinskiResult<Triangle> {
+        if factor <= Decimal::ZERO {
+            return Err(SierpinskiError::validation(format!(
+                "Scale factor must be positive, got {}",
+                factor
+            )));
+        }
+
+        let [p1, p2, p3] = self.vertices.map(|vertex| {
+            Point::new(
+                center.x + (vertex.x - center.x) * factor,
+                center.y + (vertex.y - center.y) * factor,
+            )
+        });
+        Triangle::new(p1, p2, p3)
+    }
+
+    /// Reflect the triangle across the line through `axis_p1` and `axis_p2`
+    ///
+    /// The reflection reverses the vertex winding. Fails with
+    /// `CollinearPoints` when the axis points coincide.
+    pub fn reflect(&self, axis_p1: &Point, axis_p2: &Point) -> SierpinskiResult<Triangle> {
+        let dx = axis_p2.x - axis_p1.x;
+        let dy = axis_p2.y - axis_p1.y;
+        let length_squared = dx * dx + dy * dy;
+        if length_squared == Decimal::ZERO {
+            return Err(SierpinskiError::CollinearPoints);
+        }
+
+        let [p1, p2, p3] = self.vertices.map(|vertex| {
+            // Foot of the perpendicular from the vertex to the axis, which
+            // lies midway between the vertex and its image
+            let t = ((vertex.x - axis_p1.x) * dx + (vertex.y - axis_p1.y) * dy) / length_squared;
+            let foot_x = axis_p1.x + t * dx;
+            let foot_y = axis_p1.y + t * dy;
+            Point::new(foot_x * Decimal::TWO - vertex.x, foot_y * Decimal::TWO - vertex.y)
+        });
+        Triangle::new(p1, p2, p3)
+    }
+
+    /// Generate a hash for the triangle based on its vertices in stored order
+    ///
+    /// Kept for backward compatibility with data hashed before
+    /// `canonical_hash` existed; new code that needs geometric identity should
+    /// use `canonical_hash` instead.
     pub fn hash(&self) -> String {
         let mut hasher = blake3::Hasher::new();
         
@@ -158,6 +471,26 @@ impl Triangle {
         
         hasher.finalize().to_hex().to_string()
     }
+
+    /// Generate a hash that identifies the triangle's geometry regardless of
+    /// vertex order or decimal formatting (`0.50` and `0.5` hash the same)
+    ///
+    /// Migration note: transaction hashes, block triangle hashes, fractal
+    /// triangle hashes and deterministic ids all use this hash. Chains
+    /// serialized before the switch were hashed with `hash` and will fail
+    /// Merkle root validation; regenerate them rather than reloading.
+    pub fn canonical_hash(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+
+        for vertex in &self.canonical().vertices {
+            hasher.update(vertex.x.normalize().to_string().as_bytes());
+            hasher.update(b",");
+            hasher.update(vertex.y.normalize().to_string().as_bytes());
+            hasher.update(b";");
+        }
+
+        hasher.finalize().to_hex().to_string()
+    }
 }
 
 impl fmt::Display for Triangle {
@@ -203,6 +536,36 @@ mod tests {
         assert!((centroid.x - Decimal::new(5, 1)).abs() < Decimal::new(1, 10));
     }
 
+    #[test]
+    fn test_f64_measurements_match_decimal() {
+        let genesis = crate::core::genesis::genesis_triangle().unwrap();
+        let area = genesis.area().unwrap().to_f64().unwrap();
+        let perimeter = genesis.perimeter().unwrap().to_f64().unwrap();
+        assert!((genesis.area_f64() - area).abs() <= 1e-9 * area);
+        assert!((genesis.perimeter_f64() - perimeter).abs() <= 1e-9 * perimeter);
+    }
+
+    #[test]
+    fn test_classify_point() {
+        let triangle = create_test_triangle();
+        let [_, second, third] = triangle.vertices;
+        assert_eq!(triangle.classify_point(&triangle.centroid()), PointLocation::Inside);
+        for (edge, midpoint) in triangle.side_midpoints().iter().enumerate() {
+            assert_eq!(triangle.classify_point(midpoint), PointLocation::OnEdge(edge as u8));
+        }
+        assert_eq!(triangle.classify_point(&second), PointLocation::OnVertex(1));
+        assert_eq!(triangle.classify_point(&third), PointLocation::OnVertex(2));
+        assert_eq!(
+            triangle.classify_point(&Point::from_f64(10.0, 10.0).unwrap()),
+            PointLocation::Outside
+        );
+        // On the line through an edge but past its end
+        assert_eq!(
+            triangle.classify_point(&Point::from_f64(2.0, 0.0).unwrap()),
+            PointLocation::Outside
+        );
+    }
+
     #[test]
     fn test_collinear_triangle_rejection() {
         let result = Triangle::new(
@@ -222,4 +585,337 @@ mod tests {
         let outside_point = Point::from_f64(10.0, 10.0).unwrap();
         assert!(!triangle.contains_point(&outside_point));
     }
+
+    #[test]
+    fn test_similarity_and_congruence() {
+        let triangle = Triangle::new(
+            Point::from_f64(0.0, 0.0).unwrap(),
+            Point::from_f64(3.0, 0.0).unwrap(),
+            Point::from_f64(0.0, 4.0).unwrap(),
+        ).unwrap();
+        let tol = Decimal::new(1, 10);
+
+        // Translated copy with its vertices listed in a different order
+        let translated = Triangle::new(
+            Point::from_f64(5.0, 9.0).unwrap(),
+            Point::from_f64(2.0, 5.0).unwrap(),
+            Point::from_f64(5.0, 5.0).unwrap(),
+        ).unwrap();
+
+        let scaled = Triangle::new(
+            Point::from_f64(0.0, 0.0).unwrap(),
+            Point::from_f64(6.0, 0.0).unwrap(),
+            Point::from_f64(0.0, 8.0).unwrap(),
+        ).unwrap();
+
+        assert!(triangle.is_congruent_to(&triangle, tol).unwrap());
+        assert!(triangle.is_congruent_to(&translated, tol).unwrap());
+        assert!(triangle.is_similar_to(&translated, tol).unwrap());
+
+        assert!(triangle.is_similar_to(&scaled, tol).unwrap());
+        assert!(!triangle.is_congruent_to(&scaled, tol).unwrap());
+
+        assert!(!triangle.is_similar_to(&create_test_triangle(), tol).unwrap());
+    }
+
+    #[test]
+    fn test_canonical_vertex_order() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let a = Point::from_f64(0.0, 0.0).unwrap();
+        let b = Point::from_f64(1.0, 0.0).unwrap();
+        let c = Point::from_f64(0.0, 1.0).unwrap();
+        let triangle = Triangle::new(a, b, c).unwrap();
+        let permuted = Triangle::new(b, c, a).unwrap();
+
+        assert_ne!(triangle, permuted);
+        assert_eq!(triangle.canonical(), permuted.canonical());
+        assert!(triangle.same_shape_and_position(&permuted));
+        assert_eq!(triangle.canonical().vertices, [a, c, b]);
+
+        let std_hash = |t: &Triangle| {
+            let mut hasher = DefaultHasher::new();
+            Hash::hash(t, &mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(std_hash(&triangle.canonical()), std_hash(&permuted.canonical()));
+        assert_eq!(triangle.canonical().hash(), permuted.canonical().hash());
+
+        let moved = Triangle::new(a, b, Point::from_f64(0.0, 2.0).unwrap()).unwrap();
+        assert!(!triangle.same_shape_and_position(&moved));
+    }
+
+    #[test]
+    fn test_intersection_and_overlap_area() {
+        let big = Triangle::new(
+            Point::from_f64(0.0, 0.0).unwrap(),
+            Point::from_f64(4.0, 0.0).unwrap(),
+            Point::from_f64(0.0, 4.0).unwrap(),
+        ).unwrap();
+        // Clockwise vertex order, fully inside `big`
+        let inner = Triangle::new(
+            Point::from_f64(1.0, 1.0).unwrap(),
+            Point::from_f64(1.0, 2.0).unwrap(),
+            Point::from_f64(2.0, 1.0).unwrap(),
+        ).unwrap();
+        let disjoint = Triangle::new(
+            Point::from_f64(10.0, 10.0).unwrap(),
+            Point::from_f64(11.0, 10.0).unwrap(),
+            Point::from_f64(10.0, 11.0).unwrap(),
+        ).unwrap();
+
+        assert!(big.intersects(&inner));
+        assert!(inner.intersects(&big));
+        assert_eq!(inner.overlap_area(&big).unwrap(), inner.area().unwrap());
+        assert_eq!(big.overlap_area(&inner).unwrap(), inner.area().unwrap());
+
+        assert!(!big.intersects(&disjoint));
+        assert_eq!(big.overlap_area(&disjoint).unwrap(), Decimal::ZERO);
+
+        // Mirror image sharing the base: the overlap is the triangle
+        // (0,0), (4,0), (2,2) with area 4
+        let mirrored = Triangle::new(
+            Point::from_f64(0.0, 0.0).unwrap(),
+            Point::from_f64(4.0, 0.0).unwrap(),
+            Point::from_f64(4.0, 4.0).unwrap(),
+        ).unwrap();
+        assert!(big.intersects(&mirrored));
+        assert_eq!(big.overlap_area(&mirrored).unwrap(), Decimal::from(4));
+    }
+
+    #[test]
+    fn test_sierpinski_leaves() {
+        let triangle = Triangle::new(
+            Point::from_f64(0.0, 0.0).unwrap(),
+            Point::from_f64(4.0, 0.0).unwrap(),
+            Point::from_f64(0.0, 4.0).unwrap(),
+        ).unwrap();
+        let leaves = triangle.sierpinski_leaves(2).unwrap();
+        assert_eq!(leaves.len(), 9);
+
+        let total: Decimal = leaves.iter().map(|leaf| leaf.area().unwrap()).sum();
+        assert_eq!(total, triangle.area().unwrap() * Decimal::new(9, 0) / Decimal::new(16, 0));
+
+        // The central void, and the voids of the three corners one level down
+        let mut voids = vec![triangle.side_midpoints()];
+        voids.extend(triangle.sierpinski_leaves(1).unwrap().iter().map(Triangle::side_midpoints));
+        for [a, b, c] in voids {
+            let void = Triangle::new(a, b, c).unwrap();
+            for leaf in &leaves {
+                assert_eq!(leaf.overlap_area(&void).unwrap(), Decimal::ZERO);
+            }
+        }
+
+        assert_eq!(triangle.sierpinski_leaves(0).unwrap(), vec![triangle.clone()]);
+        assert!(matches!(
+            triangle.sierpinski_leaves(crate::MAX_SUBDIVISION_DEPTH + 1),
+            Err(SierpinskiError::MaxDepthExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_canonical_hash() {
+        let half = Point::new(Decimal::new(5, 1), Decimal::ZERO);
+        let half_padded = Point::new(Decimal::new(50, 2), Decimal::ZERO);
+        let a = Point::from_f64(0.0, 0.0).unwrap();
+        let c = Point::from_f64(0.0, 1.0).unwrap();
+
+        let triangle = Triangle::new(a, half, c).unwrap();
+        let permuted = Triangle::new(c, a, half_padded).unwrap();
+        assert_ne!(triangle.hash(), permuted.hash());
+        assert_eq!(triangle.canonical_hash(), permuted.canonical_hash());
+
+        let mut hashes = std::collections::HashSet::new();
+        for i in 1..=50 {
+            for j in 1..=50 {
+                let sample = Triangle::new(
+                    Point::new(Decimal::ZERO, Decimal::ZERO),
+                    Point::new(Decimal::from(i), Decimal::ZERO),
+                    Point::new(Decimal::ZERO, Decimal::from(j)),
+                ).unwrap();
+                hashes.insert(sample.canonical_hash());
+            }
+        }
+        assert_eq!(hashes.len(), 2500);
+    }
+
+    #[test]
+    fn test_intersects_boundary_rule() {
+        let triangle = Triangle::new(
+            Point::from_f64(0.0, 0.0).unwrap(),
+            Point::from_f64(2.0, 0.0).unwrap(),
+            Point::from_f64(0.0, 2.0).unwrap(),
+        ).unwrap();
+
+        let overlapping = Triangle::new(
+            Point::from_f64(0.5, 0.5).unwrap(),
+            Point::from_f64(3.0, 0.5).unwrap(),
+            Point::from_f64(0.5, 3.0).unwrap(),
+        ).unwrap();
+        let disjoint = Triangle::new(
+            Point::from_f64(5.0, 5.0).unwrap(),
+            Point::from_f64(6.0, 5.0).unwrap(),
+            Point::from_f64(5.0, 6.0).unwrap(),
+        ).unwrap();
+        // Shares the hypotenuse from (2, 0) to (0, 2)
+        let edge_touching = Triangle::new(
+            Point::from_f64(2.0, 0.0).unwrap(),
+            Point::from_f64(2.0, 2.0).unwrap(),
+            Point::from_f64(0.0, 2.0).unwrap(),
+        ).unwrap();
+        let vertex_touching = Triangle::new(
+            Point::from_f64(2.0, 0.0).unwrap(),
+            Point::from_f64(4.0, 0.0).unwrap(),
+            Point::from_f64(3.0, -1.0).unwrap(),
+        ).unwrap();
+
+        assert!(triangle.intersects(&overlapping));
+        assert!(overlapping.intersects(&triangle));
+        assert!(triangle.intersects(&triangle));
+        assert!(!triangle.intersects(&disjoint));
+        assert!(!triangle.intersects(&edge_touching));
+        assert!(!edge_touching.intersects(&triangle));
+        assert!(!triangle.intersects(&vertex_touching));
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let triangle = Triangle::new(
+            Point::from_f64(1.0, -1.0).unwrap(),
+            Point::from_f64(4.0, 2.0).unwrap(),
+            Point::from_f64(-2.0, 3.0).unwrap(),
+        ).unwrap();
+
+        let bounds = triangle.bounding_box();
+        assert_eq!(bounds.min, Point::from_f64(-2.0, -1.0).unwrap());
+        assert_eq!(bounds.max, Point::from_f64(4.0, 3.0).unwrap());
+        assert!(triangle.vertices().iter().all(|vertex| bounds.contains(vertex)));
+        assert!(!bounds.contains(&Point::from_f64(4.5, 0.0).unwrap()));
+
+        let far = Triangle::new(
+            Point::from_f64(10.0, 10.0).unwrap(),
+            Point::from_f64(11.0, 10.0).unwrap(),
+            Point::from_f64(10.0, 11.0).unwrap(),
+        ).unwrap().bounding_box();
+        assert!(!bounds.intersects(&far));
+        let union = bounds.union(&far);
+        assert!(union.intersects(&bounds) && union.intersects(&far));
+        assert_eq!(union.max, Point::from_f64(11.0, 11.0).unwrap());
+    }
+
+    #[test]
+    fn test_scale_about_centroid() {
+        let triangle = crate::core::genesis::genesis_triangle().unwrap();
+        let tol = Decimal::new(1, 10);
+
+        for factor in [Decimal::new(5, 1), Decimal::from(3)] {
+            let scaled = triangle.scale_about_centroid(factor).unwrap();
+            let expected_area = triangle.area().unwrap() * factor * factor;
+            assert!((scaled.area().unwrap() - expected_area).abs() < Decimal::new(1, 12));
+            assert!(scaled.centroid().distance_to(&triangle.centroid()).unwrap() < Decimal::new(1, 20));
+            assert!(scaled.is_equilateral().unwrap());
+            assert!(scaled.is_similar_to(&triangle, tol).unwrap());
+        }
+
+        assert!(triangle.scale_about_centroid(Decimal::ZERO).is_err());
+        assert!(triangle.scale_about_centroid(Decimal::NEGATIVE_ONE).is_err());
+    }
+
+    #[test]
+    fn test_scale_about_point() {
+        let triangle = create_test_triangle();
+        let origin = triangle.vertices[0];
+
+        let scaled = triangle.scale_about_point(&origin, Decimal::from(2)).unwrap();
+        assert_eq!(scaled.vertices[0], origin);
+        assert_eq!(scaled.vertices[1], Point::from_f64(2.0, 0.0).unwrap());
+        assert_eq!(scaled.area().unwrap(), triangle.area().unwrap() * Decimal::from(4));
+    }
+
+    #[test]
+    fn test_reflect() {
+        let triangle = crate::core::genesis::genesis_triangle().unwrap();
+        let tol = Decimal::new(1, 10);
+        let [apex, base_a, base_b] = {
+            let mut vertices = triangle.vertices;
+            vertices.sort_by_key(|vertex| std::cmp::Reverse(vertex.y));
+            vertices
+        };
+        let base_mid = Point::new((base_a.x + base_b.x) / Decimal::TWO, (base_a.y + base_b.y) / Decimal::TWO);
+
+        // The axis of symmetry maps the triangle onto itself
+        let mirrored = triangle.reflect(&apex, &base_mid).unwrap();
+        assert!(mirrored.is_congruent_to(&triangle, tol).unwrap());
+        for vertex in mirrored.vertices() {
+            assert!(triangle.vertices().iter().any(|original| original.distance_to(vertex).unwrap() < tol));
+        }
+
+        // Any other axis moves it, and reflecting back restores it
+        let axis = (Point::new(Decimal::from(-3), Decimal::ONE), Point::new(Decimal::from(2), Decimal::from(7)));
+        let reflected = triangle.reflect(&axis.0, &axis.1).unwrap();
+        assert!(reflected.is_congruent_to(&triangle, tol).unwrap());
+        assert!(reflected.centroid().distance_to(&triangle.centroid()).unwrap() > Decimal::ONE);
+        let restored = reflected.reflect(&axis.0, &axis.1).unwrap();
+        for (vertex, original) in restored.vertices().iter().zip(triangle.vertices()) {
+            assert!(vertex.distance_to(original).unwrap() < tol);
+        }
+
+        assert!(matches!(triangle.reflect(&apex, &apex), Err(SierpinskiError::CollinearPoints)));
+    }
+
+    #[test]
+    fn test_deep_child_equilateral_with_scaled_tolerance() {
+        // A wide triangle keeps the depth-15 child large, so rounding sqrt(3)/2
+        // to 16 places shows up as an absolute side difference above 1e-10
+        let side = Decimal::from(1u64 << 40);
+        let height = side * Decimal::new(8660254037844386, 16);
+        let mut triangle = Triangle::new(
+            Point::new(Decimal::ZERO, Decimal::ZERO),
+            Point::new(side, Decimal::ZERO),
+            Point::new(side / Decimal::from(2), height),
+        )
+        .unwrap();
+        for _ in 0..15 {
+            let [m12, _, m31] = triangle.side_midpoints();
+            triangle = Triangle::new(triangle.vertices[0], m12, m31).unwrap();
+        }
+
+        let tolerances = GeometricTolerances::default();
+        let scaled = tolerances.side_tolerance(&triangle.side_lengths().unwrap());
+        assert!(triangle.is_equilateral_with_tolerance(scaled).unwrap());
+        assert!(triangle.is_equilateral().unwrap());
+        assert!(!triangle.is_equilateral_with_tolerance(Decimal::new(1, 10)).unwrap());
+        assert!(triangle.is_isosceles_with_tolerance(Decimal::new(1, 10)).unwrap());
+    }
+
+    #[test]
+    fn test_scaled_up_triangle_equilateral_within_relative_tolerance() {
+        // sqrt(3)/2 to 10 places leaves the sides about 2e-11 apart
+        let unit = Triangle::new(
+            Point::new(Decimal::ZERO, Decimal::ZERO),
+            Point::new(Decimal::ONE, Decimal::ZERO),
+            Point::new(Decimal::new(5, 1), Decimal::new(8660254038, 10)),
+        )
+        .unwrap();
+        let old_absolute = Decimal::new(1, 10);
+        assert!(unit.is_equilateral_with_tolerance(old_absolute).unwrap());
+        assert!(unit.is_equilateral().unwrap());
+
+        let origin = Point::new(Decimal::ZERO, Decimal::ZERO);
+        let large = unit.scale_about_point(&origin, Decimal::from(1_000_000)).unwrap();
+        assert!(!large.is_equilateral_with_tolerance(old_absolute).unwrap());
+        assert!(large.is_equilateral().unwrap());
+        assert!(large.is_equilateral_within(Decimal::new(1, 9)).unwrap());
+        assert!(!large.is_equilateral_within(Decimal::new(1, 12)).unwrap());
+
+        // A visibly stretched triangle fails at any reasonable tolerance
+        let right = Triangle::new(
+            origin,
+            Point::new(Decimal::ONE, Decimal::ZERO),
+            Point::new(Decimal::ZERO, Decimal::ONE),
+        )
+        .unwrap();
+        assert!(!right.is_equilateral_within(Decimal::new(1, 2)).unwrap());
+    }
 }
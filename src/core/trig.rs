@@ -0,0 +1,242 @@
+//! Decimal trigonometry for rotations, angles and other geometry that has
+//! to stay exact
+//!
+//! Series are summed until their terms drop below `SERIES_PRECISION`, well
+//! past what `f64` resolves.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::core::errors::{SierpinskiError, SierpinskiResult};
+use crate::core::geometry::decimal_sqrt;
+
+/// π to the full precision of `Decimal`
+pub const PI: Decimal = Decimal::from_parts(1102470953, 185874565, 1703060790, false, 28);
+
+/// π / 2
+pub const HALF_PI: Decimal = Decimal::from_parts(2698719124, 92937282, 851530395, false, 28);
+
+/// 2π
+pub const TWO_PI: Decimal = Decimal::from_parts(2204941906, 371749130, 3406121580, false, 28);
+
+/// Series terms smaller than this are dropped
+const SERIES_PRECISION: Decimal = Decimal::from_parts(1, 0, 0, false, 27);
+
+/// π / 2 split into parts short enough that multiplying each by a quarter
+/// turn count below 10^15 is exact, so subtracting them in turn reduces an
+/// angle without losing the digits a single `HALF_PI` would drop
+const HALF_PI_PARTS: [Decimal; 3] = [
+    Decimal::from_parts(3133263754, 365, 0, false, 12),
+    Decimal::from_parts(3266033754, 208, 0, false, 24),
+    Decimal::from_parts(3084, 0, 0, true, 28),
+];
+
+/// Halvings of a quarter turn `decimal_acos` makes, enough to pin the angle
+/// to `Decimal`'s last digit
+const ACOS_BISECTION_STEPS: u32 = 96;
+
+/// sin(π/4) = cos(π/4) = 1/√2
+const HALF_SQRT_TWO: Decimal = Decimal::from_parts(2914029285, 733767332, 383323354, false, 28);
+
+/// Decimal sine of an angle in radians
+pub fn decimal_sin(radians: Decimal) -> Decimal {
+    decimal_sin_cos(radians).0
+}
+
+/// Decimal cosine of an angle in radians
+pub fn decimal_cos(radians: Decimal) -> Decimal {
+    decimal_sin_cos(radians).1
+}
+
+/// Decimal sine and cosine of an angle in radians
+///
+/// The angle is reduced to [-π/4, π/4] and a quadrant first, so the Taylor
+/// series only ever sees small arguments.
+pub fn decimal_sin_cos(radians: Decimal) -> (Decimal, Decimal) {
+    let (reduced, quadrant) = reduce_to_quadrant(radians);
+    let (sin, cos) = sin_cos_series(reduced);
+    match quadrant {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+/// Split an angle into a remainder in [-π/4, π/4] and the number of quarter
+/// turns before it, modulo 4
+fn reduce_to_quadrant(radians: Decimal) -> (Decimal, u8) {
+    let turns = (radians / HALF_PI).round();
+    let reduced = HALF_PI_PARTS.iter().fold(radians, |reduced, part| reduced - turns * part);
+    let quadrant = (turns % Decimal::from(4)).to_i32().unwrap_or(0).rem_euclid(4) as u8;
+    (reduced, quadrant)
+}
+
+/// Taylor series for sine and cosine, for small angles
+fn sin_cos_series(angle: Decimal) -> (Decimal, Decimal) {
+    let squared = angle * angle;
+    let mut sin_term = angle;
+    let mut cos_term = Decimal::ONE;
+    let mut sin = Decimal::ZERO;
+    let mut cos = Decimal::ZERO;
+    let mut k = Decimal::ONE;
+    while sin_term.abs() > SERIES_PRECISION || cos_term.abs() > SERIES_PRECISION {
+        sin += sin_term;
+        cos += cos_term;
+        sin_term *= -squared / ((k + Decimal::ONE) * (k + Decimal::TWO));
+        cos_term *= -squared / (k * (k + Decimal::ONE));
+        k += Decimal::TWO;
+    }
+    (sin, cos)
+}
+
+/// Decimal arc cosine in radians, in [0, π]
+///
+/// Found by bisection. Cosine flattens out near 0 and π, where bisecting on
+/// it would only pin down half the digits, so there the angle is bisected
+/// on its sine instead, which is steepest at those ends.
+pub fn decimal_acos(value: Decimal) -> SierpinskiResult<Decimal> {
+    if value.abs() > Decimal::ONE {
+        return Err(SierpinskiError::validation(format!("acos argument {} is outside [-1, 1]", value)));
+    }
+
+    if value.abs() <= HALF_SQRT_TWO {
+        // Cosine falls across [π/4, 3π/4]
+        return Ok(bisect(HALF_PI - HALF_PI / Decimal::TWO, HALF_PI + HALF_PI / Decimal::TWO, |angle| {
+            decimal_cos(angle) > value
+        }));
+    }
+
+    // Sine rises across [0, π/4]
+    let sine = decimal_sqrt(Decimal::ONE - value * value)?;
+    let angle = bisect(Decimal::ZERO, HALF_PI / Decimal::TWO, |angle| decimal_sin(angle) < sine);
+    Ok(if value > Decimal::ZERO { angle } else { PI - angle })
+}
+
+/// Narrow [low, high] onto the point where `below` stops holding
+fn bisect(mut low: Decimal, mut high: Decimal, below: impl Fn(Decimal) -> bool) -> Decimal {
+    for _ in 0..ACOS_BISECTION_STEPS {
+        let middle = (low + high) / Decimal::TWO;
+        if below(middle) {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / Decimal::TWO
+}
+
+/// Decimal angle of the point (x, y) from the positive x axis, in (-π, π];
+/// zero at the origin
+pub fn decimal_atan2(y: Decimal, x: Decimal) -> SierpinskiResult<Decimal> {
+    let quarter_turn = match y.cmp(&Decimal::ZERO) {
+        std::cmp::Ordering::Greater => HALF_PI,
+        std::cmp::Ordering::Less => -HALF_PI,
+        std::cmp::Ordering::Equal if x < Decimal::ZERO => return Ok(PI),
+        std::cmp::Ordering::Equal => return Ok(Decimal::ZERO),
+    };
+    // Too steep to divide is as good as vertical
+    let Some(slope) = y.checked_div(x) else {
+        return Ok(quarter_turn);
+    };
+
+    let angle = decimal_atan(slope)?;
+    Ok(if x > Decimal::ZERO {
+        angle
+    } else if y > Decimal::ZERO {
+        angle + PI
+    } else {
+        angle - PI
+    })
+}
+
+/// Decimal arc tangent in radians, in (-π/2, π/2)
+fn decimal_atan(value: Decimal) -> SierpinskiResult<Decimal> {
+    if value.abs() > Decimal::ONE {
+        let reflected = decimal_atan(Decimal::ONE / value)?;
+        let quarter_turn = if value > Decimal::ZERO { HALF_PI } else { -HALF_PI };
+        return Ok(quarter_turn - reflected);
+    }
+
+    // atan(x) = 2·atan(x / (1 + sqrt(1 + x²))) shrinks the argument so the
+    // Taylor series converges quickly
+    let mut reduced = value;
+    let mut multiplier = Decimal::ONE;
+    for _ in 0..2 {
+        let root = decimal_sqrt(Decimal::ONE + reduced * reduced)?;
+        reduced /= Decimal::ONE + root;
+        multiplier *= Decimal::TWO;
+    }
+
+    let squared = reduced * reduced;
+    let mut power = reduced;
+    let mut sum = Decimal::ZERO;
+    let mut n = Decimal::ONE;
+    while power.abs() / n > SERIES_PRECISION {
+        sum += power / n;
+        power *= -squared;
+        n += Decimal::TWO;
+    }
+    Ok(sum * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const F64_TOLERANCE: f64 = 1e-15;
+
+    /// Evenly spaced angles across [-2π, 2π], ends included
+    fn sample_angles() -> impl Iterator<Item = Decimal> {
+        let steps = 400;
+        (0..=steps).map(move |i| -TWO_PI + TWO_PI * Decimal::from(2 * i) / Decimal::from(steps))
+    }
+
+    #[test]
+    fn test_sin_cos_match_f64() {
+        for angle in sample_angles() {
+            let radians = angle.to_f64().unwrap();
+            let (sin, cos) = decimal_sin_cos(angle);
+            assert!((sin.to_f64().unwrap() - radians.sin()).abs() < F64_TOLERANCE, "sin({})", angle);
+            assert!((cos.to_f64().unwrap() - radians.cos()).abs() < F64_TOLERANCE, "cos({})", angle);
+            assert_eq!((decimal_sin(angle), decimal_cos(angle)), (sin, cos));
+
+            // Far tighter than f64 can check
+            let identity = sin * sin + cos * cos - Decimal::ONE;
+            assert!(identity.abs() < Decimal::new(1, 24), "sin² + cos² - 1 = {} at {}", identity, angle);
+        }
+
+        assert_eq!(decimal_sin(Decimal::ZERO), Decimal::ZERO);
+        assert_eq!(decimal_cos(Decimal::ZERO), Decimal::ONE);
+        assert!((decimal_sin(PI)).abs() < Decimal::new(1, 26));
+        // Reduction keeps large angles accurate
+        let far = Decimal::from(1_000_000);
+        assert!((decimal_sin(far).to_f64().unwrap() - 1_000_000f64.sin()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_acos_and_atan2_match_f64() {
+        for i in -100..=100 {
+            let value = Decimal::new(i, 2);
+            let expected = value.to_f64().unwrap().acos();
+            let actual = decimal_acos(value).unwrap().to_f64().unwrap();
+            assert!((actual - expected).abs() < F64_TOLERANCE, "acos({}) = {}, not {}", value, actual, expected);
+        }
+        assert!(decimal_acos(Decimal::new(101, 2)).is_err());
+
+        for angle in sample_angles() {
+            let (y, x) = decimal_sin_cos(angle);
+            let expected = y.to_f64().unwrap().atan2(x.to_f64().unwrap());
+            let actual = decimal_atan2(y, x).unwrap().to_f64().unwrap();
+            // ±π name the same direction
+            let difference = (actual - expected).abs();
+            assert!(
+                difference < F64_TOLERANCE || (difference - std::f64::consts::TAU).abs() < F64_TOLERANCE,
+                "atan2({}, {}) = {}, not {}", y, x, actual, expected
+            );
+        }
+        assert_eq!(decimal_atan2(Decimal::ZERO, Decimal::ZERO).unwrap(), Decimal::ZERO);
+        assert_eq!(decimal_atan2(Decimal::ONE, Decimal::ZERO).unwrap(), HALF_PI);
+        assert_eq!(decimal_atan2(Decimal::ZERO, -Decimal::ONE).unwrap(), PI);
+    }
+}
@@ -1,17 +1,21 @@
 //! Geometric validation functions for triangles and fractal structures
 
+use std::collections::HashMap;
 use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
 
 use crate::core::{
     triangle::Triangle,
-    fractal::{FractalTriangle, FractalStructure},
-    geometry::Point,
+    address::TriangleAddress,
+    fractal::{ArchivedTriangle, FractalTriangle, FractalStructure},
+    geometry::{GeometricTolerances, Point},
     state::TriangleState,
     errors::SierpinskiResult,
 };
 
 /// Comprehensive validation result
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ValidationResult {
     pub is_valid: bool,
     pub errors: Vec<String>,
@@ -56,13 +60,25 @@ impl ValidationResult {
     }
 }
 
+/// Settings for the `_with_options` validation functions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationOptions {
+    /// Tolerances for collinearity, side equality, area conservation and containment
+    pub tolerances: GeometricTolerances,
+}
+
 /// Validate a basic triangle for geometric correctness
 pub fn validate_triangle(triangle: &Triangle) -> ValidationResult {
+    validate_triangle_with_options(triangle, &ValidationOptions::default())
+}
+
+/// Validate a basic triangle for geometric correctness with custom options
+pub fn validate_triangle_with_options(triangle: &Triangle, options: &ValidationOptions) -> ValidationResult {
     let mut result = ValidationResult::success();
 
     // Check for collinear points
     let vertices = triangle.vertices();
-    if Point::are_collinear(&vertices[0], &vertices[1], &vertices[2]) {
+    if Point::are_collinear_with_tolerance(&vertices[0], &vertices[1], &vertices[2], options.tolerances.collinearity) {
         result.add_error("Triangle vertices are collinear".to_string());
         return result;
     }
@@ -94,7 +110,15 @@ pub fn validate_triangle(triangle: &Triangle) -> ValidationResult {
 
 /// Validate a fractal triangle
 pub fn validate_fractal_triangle(fractal_triangle: &FractalTriangle) -> ValidationResult {
-    let mut result = validate_triangle(&fractal_triangle.triangle);
+    validate_fractal_triangle_with_options(fractal_triangle, &ValidationOptions::default())
+}
+
+/// Validate a fractal triangle with custom options
+pub fn validate_fractal_triangle_with_options(
+    fractal_triangle: &FractalTriangle,
+    options: &ValidationOptions,
+) -> ValidationResult {
+    let mut result = validate_triangle_with_options(&fractal_triangle.triangle, options);
 
     // Validate state consistency
     match fractal_triangle.state {
@@ -163,7 +187,10 @@ pub fn validate_parent_child_relationship(
 
     // Check that parent contains child ID
     if !parent.child_ids.contains(&child.id) {
-        result.add_error("Parent does not contain child ID in its child list".to_string());
+        result.add_error(format!(
+            "Triangle {} names parent {} which does not list it as a child",
+            child.id, parent.id
+        ));
     }
 
     // Check state compatibility
@@ -192,28 +219,64 @@ pub fn validate_parent_child_relationship(
     result
 }
 
+/// Check a child's links against a parent that has been pruned to the archive
+fn validate_archived_parent_relationship(
+    parent: &ArchivedTriangle,
+    child: &FractalTriangle,
+) -> ValidationResult {
+    let mut result = ValidationResult::success();
+
+    if child.depth != parent.depth + 1 {
+        result.add_error(format!(
+            "Child depth {} should be parent depth {} + 1",
+            child.depth, parent.depth
+        ));
+    }
+    if !parent.child_ids.contains(&child.id) {
+        result.add_error(format!(
+            "Triangle {} names parent {} which does not list it as a child",
+            child.id, parent.id
+        ));
+    }
+    if parent.state != TriangleState::Subdivided {
+        result.add_error("Parent of a child triangle must be in Subdivided state".to_string());
+    }
+
+    result
+}
+
 /// Validate an entire fractal structure
+///
+/// Pruned structures are supported: links to archived triangles are checked
+/// against the archive, and archived parents count towards subdivision
+/// consistency. Archived triangles themselves have no geometry to validate.
 pub fn validate_fractal_structure(structure: &FractalStructure) -> ValidationResult {
+    validate_fractal_structure_with_options(structure, &ValidationOptions::default())
+}
+
+/// Validate an entire fractal structure with custom options
+pub fn validate_fractal_structure_with_options(
+    structure: &FractalStructure,
+    options: &ValidationOptions,
+) -> ValidationResult {
     let mut result = ValidationResult::success();
 
     // Check for genesis triangle
-    if structure.genesis().is_none() {
-        result.add_error("Fractal structure must have a genesis triangle".to_string());
-        return result;
+    match structure.genesis() {
+        Some(genesis) => result.combine(validate_fractal_triangle_with_options(genesis, options)),
+        None if structure.is_genesis_archived() => {}
+        None => {
+            result.add_error("Fractal structure must have a genesis triangle".to_string());
+            return result;
+        }
     }
 
-    let genesis = structure.genesis().unwrap();
-
-    // Validate genesis triangle
-    let genesis_validation = validate_fractal_triangle(genesis);
-    result.combine(genesis_validation);
-
     // Validate all triangles
     for depth in 0..=structure.max_depth() {
         let triangles_at_depth = structure.triangles_at_depth(depth);
         
         for triangle in triangles_at_depth {
-            let triangle_validation = validate_fractal_triangle(triangle);
+            let triangle_validation = validate_fractal_triangle_with_options(triangle, options);
             if !triangle_validation.is_valid {
                 result.add_error(format!(
                     "Triangle {} at depth {} failed validation: {:?}",
@@ -226,6 +289,8 @@ pub fn validate_fractal_structure(structure: &FractalStructure) -> ValidationRes
                 if let Some(parent) = structure.get_triangle(&parent_id) {
                     let relationship_validation = validate_parent_child_relationship(parent, triangle);
                     result.combine(relationship_validation);
+                } else if let Some(parent) = structure.archived_triangle(&parent_id) {
+                    result.combine(validate_archived_parent_relationship(parent, triangle));
                 } else {
                     result.add_error(format!(
                         "Triangle {} references non-existent parent {}",
@@ -236,46 +301,160 @@ pub fn validate_fractal_structure(structure: &FractalStructure) -> ValidationRes
         }
     }
 
-    // Validate subdivision consistency
-    let subdivided_triangles = structure.triangles_by_state(TriangleState::Subdivided);
-    for parent in subdivided_triangles {
-        let children = structure.children(&parent.id);
-        
-        if children.len() != parent.child_ids.len() {
-            result.add_error(format!(
-                "Triangle {} has {} child IDs but {} actual children found",
-                parent.id,
-                parent.child_ids.len(),
-                children.len()
-            ));
-        }
+    // Validate subdivision consistency, over resident and archived parents
+    let subdivided_parents = structure
+        .triangles_by_state(TriangleState::Subdivided)
+        .into_iter()
+        .map(|parent| (parent.id, &parent.address, &parent.child_ids));
+    let archived_parents = structure
+        .archived_triangles()
+        .filter(|parent| parent.state == TriangleState::Subdivided)
+        .map(|parent| (parent.id, &parent.address, &parent.child_ids));
+
+    for (parent_id, parent_address, child_ids) in subdivided_parents.chain(archived_parents) {
+        let children: Vec<(&TriangleAddress, TriangleState)> = child_ids
+            .iter()
+            .filter_map(|id| {
+                structure
+                    .get_triangle(id)
+                    .map(|child| (&child.address, child.state))
+                    .or_else(|| structure.archived_triangle(id).map(|child| (&child.address, child.state)))
+            })
+            .collect();
+        let child_states: Vec<TriangleState> = children.iter().map(|(_, state)| *state).collect();
 
         // For Sierpinski triangles, we expect 3 active children + 1 void
-        if children.len() == 4 {
-            let active_children = children.iter()
-                .filter(|c| c.state == TriangleState::Active)
+        if child_states.len() == 4 {
+            let active_children = child_states.iter()
+                .filter(|state| **state == TriangleState::Active)
                 .count();
-            let void_children = children.iter()
-                .filter(|c| c.state == TriangleState::Void)
+            let void_children = child_states.iter()
+                .filter(|state| **state == TriangleState::Void)
                 .count();
 
             if active_children != 3 || void_children != 1 {
                 result.add_warning(format!(
                     "Triangle {} subdivision should have 3 active + 1 void children, found {} active + {} void",
-                    parent.id, active_children, void_children
+                    parent_id, active_children, void_children
+                ));
+            }
+        }
+
+        // The void belongs at the central child address and nowhere else
+        let void_address = parent_address.child_void();
+        for (address, state) in &children {
+            if (*state == TriangleState::Void) != (**address == void_address) {
+                result.add_warning(format!(
+                    "Child {} of triangle {} is in state {} but the void child is {}",
+                    address, parent_id, state, void_address
+                ));
+            }
+        }
+    }
+
+    result.combine(validate_structure_links(structure));
+
+    result
+}
+
+/// Check ids, links and addresses across resident and archived triangles:
+/// every listed child exists and points back, each address agrees with its
+/// depth and with the parent's address, and no address is claimed twice
+fn validate_structure_links(structure: &FractalStructure) -> ValidationResult {
+    let mut result = ValidationResult::success();
+
+    struct Node<'a> {
+        id: Uuid,
+        address: &'a TriangleAddress,
+        depth: u8,
+        parent_id: Option<Uuid>,
+        child_ids: &'a [Uuid],
+    }
+    let resident = structure.triangles().map(|t| Node {
+        id: t.id,
+        address: &t.address,
+        depth: t.depth,
+        parent_id: t.parent_id,
+        child_ids: &t.child_ids,
+    });
+    let archived = structure.archived_triangles().map(|t| Node {
+        id: t.id,
+        address: &t.address,
+        depth: t.depth,
+        parent_id: t.parent_id,
+        child_ids: &t.child_ids,
+    });
+    let nodes: HashMap<Uuid, Node> = resident.chain(archived).map(|node| (node.id, node)).collect();
+
+    let mut claims: HashMap<&TriangleAddress, Vec<Uuid>> = HashMap::new();
+    for node in nodes.values() {
+        claims.entry(node.address).or_default().push(node.id);
+
+        if node.address.depth() != node.depth {
+            result.add_error(format!(
+                "Triangle {} has depth {} but its address {} is at depth {}",
+                node.id, node.depth, node.address, node.address.depth()
+            ));
+        }
+
+        for child_id in node.child_ids {
+            match nodes.get(child_id) {
+                None => result.add_error(format!(
+                    "Triangle {} lists child {} which does not exist",
+                    node.id, child_id
+                )),
+                Some(child) if child.parent_id != Some(node.id) => result.add_error(format!(
+                    "Triangle {} lists child {} whose parent is {}",
+                    node.id,
+                    child_id,
+                    child.parent_id.map_or("none".to_string(), |id| id.to_string())
+                )),
+                Some(_) => {}
+            }
+        }
+
+        if let Some(parent) = node.parent_id.and_then(|id| nodes.get(&id)) {
+            if node.address.parent().as_ref() != Some(parent.address) {
+                result.add_error(format!(
+                    "Triangle {} at address {} is not a child address of its parent {} at {}",
+                    node.id, node.address, parent.id, parent.address
                 ));
             }
         }
     }
 
+    let mut duplicates: Vec<_> = claims.into_iter().filter(|(_, ids)| ids.len() > 1).collect();
+    duplicates.sort_by(|a, b| a.0.components().cmp(b.0.components()));
+    for (address, ids) in duplicates {
+        result.add_error(format!("Address {} is claimed by {} triangles", address, ids.len()));
+    }
+
     result
 }
 
 /// Validate equilateral properties of a triangle
 pub fn validate_equilateral_triangle(triangle: &Triangle) -> ValidationResult {
+    validate_equilateral_triangle_with_options(triangle, &ValidationOptions::default())
+}
+
+/// Validate equilateral properties of a triangle with custom options; the
+/// side tolerance is scaled by the triangle's longest side
+pub fn validate_equilateral_triangle_with_options(
+    triangle: &Triangle,
+    options: &ValidationOptions,
+) -> ValidationResult {
     let mut result = ValidationResult::success();
 
-    match triangle.is_equilateral() {
+    let side_lengths = match triangle.side_lengths() {
+        Ok(side_lengths) => side_lengths,
+        Err(e) => {
+            result.add_error(format!("Failed to check equilateral property: {}", e));
+            return result;
+        }
+    };
+    let tolerance = options.tolerances.side_tolerance(&side_lengths);
+
+    match triangle.is_equilateral_with_tolerance(tolerance) {
         Ok(is_equilateral) => {
             if !is_equilateral {
                 result.add_error("Triangle is not equilateral".to_string());
@@ -287,18 +466,15 @@ pub fn validate_equilateral_triangle(triangle: &Triangle) -> ValidationResult {
     }
 
     // Additional checks for equilateral triangles
-    if let Ok(side_lengths) = triangle.side_lengths() {
-        let tolerance = Decimal::new(1, 6); // 0.000001 tolerance
-        let avg_length = (side_lengths[0] + side_lengths[1] + side_lengths[2]) / Decimal::from(3);
-        
-        for (i, &length) in side_lengths.iter().enumerate() {
-            let diff = (length - avg_length).abs();
-            if diff > tolerance {
-                result.add_error(format!(
-                    "Side {} length {} differs from average {} by {}",
-                    i, length, avg_length, diff
-                ));
-            }
+    let avg_length = (side_lengths[0] + side_lengths[1] + side_lengths[2]) / Decimal::from(3);
+    
+    for (i, &length) in side_lengths.iter().enumerate() {
+        let diff = (length - avg_length).abs();
+        if diff > tolerance {
+            result.add_error(format!(
+                "Side {} length {} differs from average {} by {}",
+                i, length, avg_length, diff
+            ));
         }
     }
 
@@ -307,11 +483,19 @@ pub fn validate_equilateral_triangle(triangle: &Triangle) -> ValidationResult {
 
 /// Validate Sierpinski fractal properties
 pub fn validate_sierpinski_properties(structure: &FractalStructure) -> ValidationResult {
+    validate_sierpinski_properties_with_options(structure, &ValidationOptions::default())
+}
+
+/// Validate Sierpinski fractal properties with custom options
+pub fn validate_sierpinski_properties_with_options(
+    structure: &FractalStructure,
+    options: &ValidationOptions,
+) -> ValidationResult {
     let mut result = ValidationResult::success();
 
     // Check that genesis is equilateral
     if let Some(genesis) = structure.genesis() {
-        let equilateral_validation = validate_equilateral_triangle(&genesis.triangle);
+        let equilateral_validation = validate_equilateral_triangle_with_options(&genesis.triangle, options);
         if !equilateral_validation.is_valid {
             result.add_error("Genesis triangle is not equilateral".to_string());
         }
@@ -328,7 +512,7 @@ pub fn validate_sierpinski_properties(structure: &FractalStructure) -> Validatio
             let children = structure.children(&parent.id);
             
             if !children.is_empty() {
-                match validate_area_conservation(parent, &children) {
+                match validate_area_conservation(parent, &children, &options.tolerances) {
                     Ok(is_conserved) => {
                         if !is_conserved {
                             result.add_warning(format!(
@@ -345,6 +529,20 @@ pub fn validate_sierpinski_properties(structure: &FractalStructure) -> Validatio
                     }
                 }
             }
+
+            match find_child_overlap_problems(parent, &children, &options.tolerances) {
+                Ok(problems) => {
+                    for problem in problems {
+                        result.add_error(problem);
+                    }
+                }
+                Err(e) => {
+                    result.add_error(format!(
+                        "Failed to check child overlap for triangle {}: {}",
+                        parent.id, e
+                    ));
+                }
+            }
         }
     }
 
@@ -355,6 +553,7 @@ pub fn validate_sierpinski_properties(structure: &FractalStructure) -> Validatio
 fn validate_area_conservation(
     parent: &FractalTriangle,
     children: &[&FractalTriangle],
+    tolerances: &GeometricTolerances,
 ) -> SierpinskiResult<bool> {
     let parent_area = parent.triangle.area()?;
     let mut total_child_area = Decimal::ZERO;
@@ -364,11 +563,48 @@ fn validate_area_conservation(
     }
     
     let difference = (parent_area - total_child_area).abs();
-    let tolerance = parent_area * Decimal::new(1, 6); // 0.0001% tolerance
+    let tolerance = parent_area * tolerances.area_conservation_relative;
     
     Ok(difference <= tolerance)
 }
 
+/// Find siblings that overlap each other and children that poke outside
+/// their parent
+fn find_child_overlap_problems(
+    parent: &FractalTriangle,
+    children: &[&FractalTriangle],
+    tolerances: &GeometricTolerances,
+) -> SierpinskiResult<Vec<String>> {
+    let mut problems = Vec::new();
+    let tolerance = parent.triangle.area()? * tolerances.containment;
+
+    for (i, child) in children.iter().enumerate() {
+        let contained_area = child.triangle.overlap_area(&parent.triangle)?;
+        if (child.triangle.area()? - contained_area).abs() > tolerance {
+            problems.push(format!(
+                "Child triangle {} is not contained in parent {}",
+                child.id, parent.id
+            ));
+        }
+
+        for sibling in &children[i + 1..] {
+            if !child.triangle.intersects(&sibling.triangle) {
+                continue;
+            }
+
+            let overlap = child.triangle.overlap_area(&sibling.triangle)?;
+            if overlap > tolerance {
+                problems.push(format!(
+                    "Sibling triangles {} and {} overlap by area {}",
+                    child.id, sibling.id, overlap
+                ));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
 /// Quick validation function for simple use cases
 pub fn is_valid_triangle(triangle: &Triangle) -> bool {
     validate_triangle(triangle).is_valid
@@ -385,7 +621,7 @@ mod tests {
     use crate::core::{
         geometry::Point,
         genesis::genesis_fractal_triangle,
-        subdivision::subdivide_triangle,
+        subdivision::{subdivide_to_depth, subdivide_triangle},
     };
 
     fn create_test_triangle() -> Triangle {
@@ -444,4 +680,162 @@ mod tests {
             println!("Equilateral validation errors: {:?}", result.errors);
         }
     }
+
+    #[test]
+    fn test_subdivided_children_do_not_overlap() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let subdivision = subdivide_triangle(&genesis).unwrap();
+        let children = &subdivision.children;
+
+        for (i, child) in children.iter().enumerate() {
+            let inside = child.triangle.overlap_area(&subdivision.parent.triangle).unwrap();
+            assert!((inside - child.triangle.area().unwrap()).abs() < Decimal::new(1, 20));
+
+            for sibling in &children[i + 1..] {
+                let overlap = child.triangle.overlap_area(&sibling.triangle).unwrap();
+                assert!(overlap < Decimal::new(1, 20));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sierpinski_properties_flag_overlapping_children() {
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 1).unwrap();
+        let result = validate_sierpinski_properties(&structure);
+        assert!(result.is_valid, "Validation failed: {:?}", result.errors);
+
+        // Replace one child with a shifted copy of the parent, which covers
+        // its siblings and pokes out of the parent's right edge
+        let genesis = structure.genesis().unwrap().clone();
+        let child_id = genesis.child_ids[0];
+        let [a, b, c] = genesis.triangle.vertices;
+        let shifted = |p: Point| Point::new(p.x + Decimal::new(25, 2), p.y);
+        structure.get_triangle_mut(&child_id).unwrap().triangle =
+            Triangle::new(shifted(a), shifted(b), shifted(c)).unwrap();
+
+        let result = validate_sierpinski_properties(&structure);
+        assert!(!result.is_valid);
+        assert!(result.errors.iter().any(|e| e.contains("not contained")));
+        assert!(result.errors.iter().any(|e| e.contains("overlap")));
+    }
+
+    #[test]
+    fn test_void_must_sit_at_void_address() {
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 1).unwrap();
+        let genesis_address = TriangleAddress::genesis();
+        let corner = structure.find_by_address(&genesis_address.children_active()[0]).unwrap().id;
+        let center = structure.find_by_address(&genesis_address.child_void()).unwrap().id;
+
+        // Same counts of active and void children, in the wrong places
+        structure.get_triangle_mut(&corner).unwrap().state = TriangleState::Void;
+        structure.get_triangle_mut(&center).unwrap().state = TriangleState::Active;
+
+        let result = validate_fractal_structure(&structure);
+        assert_eq!(result.warnings.iter().filter(|w| w.contains("the void child is 3")).count(), 2);
+    }
+
+    /// A valid depth-1 structure and the ids of genesis and its corner children
+    fn linked_fixture() -> (FractalStructure, Uuid, [Uuid; 3]) {
+        let structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 1).unwrap();
+        let genesis = structure.genesis().unwrap().id;
+        let corners = TriangleAddress::genesis()
+            .children_active()
+            .iter()
+            .map(|address| structure.find_by_address(address).unwrap().id)
+            .collect::<Vec<_>>();
+        (structure, genesis, [corners[0], corners[1], corners[2]])
+    }
+
+    fn has_error(structure: &FractalStructure, needle: &str) -> bool {
+        let result = validate_fractal_structure(structure);
+        !result.is_valid && result.errors.iter().any(|e| e.contains(needle))
+    }
+
+    #[test]
+    fn test_dangling_child_id_is_reported() {
+        let (mut structure, genesis, _) = linked_fixture();
+        assert!(validate_fractal_structure(&structure).is_valid);
+
+        let missing = Uuid::new_v4();
+        structure.get_triangle_mut(&genesis).unwrap().child_ids.push(missing);
+        assert!(has_error(&structure, &format!("lists child {} which does not exist", missing)));
+    }
+
+    #[test]
+    fn test_asymmetric_links_are_reported() {
+        // Parent lists a child that points elsewhere
+        let (mut structure, _, [first, second, _]) = linked_fixture();
+        structure.get_triangle_mut(&first).unwrap().parent_id = Some(second);
+        assert!(has_error(&structure, &format!("lists child {} whose parent is {}", first, second)));
+
+        // Child points at a parent that does not list it
+        let (mut structure, genesis, [first, ..]) = linked_fixture();
+        structure.get_triangle_mut(&genesis).unwrap().child_ids.retain(|id| *id != first);
+        assert!(has_error(&structure, &format!("{} names parent {} which does not list it", first, genesis)));
+    }
+
+    #[test]
+    fn test_address_depth_mismatch_is_reported() {
+        let (mut structure, _, [first, ..]) = linked_fixture();
+        structure.get_triangle_mut(&first).unwrap().address =
+            TriangleAddress::from_string_representation("0.0").unwrap();
+        assert!(has_error(&structure, "but its address 0.0 is at depth 2"));
+    }
+
+    #[test]
+    fn test_address_outside_parent_is_reported() {
+        // Right depth, but not under the parent's address
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 2).unwrap();
+        let id = structure
+            .find_by_address(&TriangleAddress::from_string_representation("1.2").unwrap())
+            .unwrap()
+            .id;
+        structure.get_triangle_mut(&id).unwrap().address =
+            TriangleAddress::from_string_representation("2.2").unwrap();
+        assert!(has_error(&structure, "at address 2.2 is not a child address of its parent"));
+    }
+
+    #[test]
+    fn test_duplicate_address_is_reported() {
+        let (mut structure, _, [first, second, _]) = linked_fixture();
+        let taken = structure.get_triangle(&second).unwrap().address.clone();
+        structure.get_triangle_mut(&first).unwrap().address = taken.clone();
+        assert!(has_error(&structure, &format!("Address {} is claimed by 2 triangles", taken)));
+    }
+
+    #[test]
+    fn test_pruned_structure_validates() {
+        use crate::core::fractal::PrunePolicy;
+
+        let mut structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 8).unwrap();
+        let before = validate_fractal_structure(&structure);
+        assert!(before.is_valid);
+
+        structure.prune(PrunePolicy::DropVoids);
+        structure.prune(PrunePolicy::ArchiveSubdividedAbove(6));
+
+        let after = validate_fractal_structure(&structure);
+        assert!(after.is_valid, "{:?}", after.errors);
+        assert_eq!(after.warnings.len(), before.warnings.len());
+    }
+
+    #[test]
+    fn test_validation_options_override_tolerances() {
+        // Sides of the test triangle differ by about 1e-5 of their length
+        let triangle = create_test_triangle();
+        assert!(!validate_equilateral_triangle(&triangle).is_valid);
+
+        let options = ValidationOptions {
+            tolerances: GeometricTolerances {
+                side_equality: Decimal::new(1, 4),
+                ..GeometricTolerances::default()
+            },
+        };
+        let result = validate_equilateral_triangle_with_options(&triangle, &options);
+        assert!(result.is_valid, "{:?}", result.errors);
+
+        let structure = subdivide_to_depth(genesis_fractal_triangle().unwrap(), 2).unwrap();
+        assert!(validate_sierpinski_properties_with_options(&structure, &options).is_valid);
+        assert!(validate_fractal_structure_with_options(&structure, &options).is_valid);
+    }
 }
@@ -1,17 +1,166 @@
 //! Wallet system for managing triangle ownership and transactions
 
 use std::collections::HashMap;
+use std::mem::Discriminant;
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
 use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, aead::{Aead, Payload}};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
 
 use crate::core::{
     address::TriangleAddress,
     block::{TriangleTransaction, TriangleOperation},
     triangle::Triangle,
-    blockchain::TriadChainBlockchain,
+    blockchain::{GasFloorParams, TriadChainBlockchain},
     errors::{SierpinskiError, SierpinskiResult},
 };
+use uuid::Uuid;
+
+/// Lowest gas fee ever suggested, used when there is no fee history
+pub const MIN_GAS_FEE: Decimal = Decimal::from_parts(1, 0, 0, false, 2); // 0.01
+
+/// Number of most recent blocks considered when estimating fees
+pub(crate) const FEE_HISTORY_BLOCKS: usize = 20;
+
+/// Backlog surcharge is capped at this many blocks' worth of pending transactions
+const MAX_BACKLOG_BLOCKS: usize = 10;
+
+/// Length of the hex part of a wallet address after the `ST` prefix
+const WALLET_ADDRESS_HEX_LEN: usize = 32;
+
+/// Length of the checksum ending a wallet address
+const WALLET_ADDRESS_CHECKSUM_LEN: usize = 4;
+
+/// Offset marking a hardened derivation index, as in BIP32
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// Domain key for deriving a wallet's chain code from its signing key
+const CHAIN_CODE_DOMAIN: &[u8] = b"TriadChain HD seed";
+
+/// Prefix of every signed off-chain message; transaction signing messages
+/// begin with a transaction id, so neither kind of signature passes for the
+/// other
+const MESSAGE_SIGNING_DOMAIN: &[u8] = b"TriadChain Signed Message:\n";
+
+/// Consecutive unused child addresses scanned before a sync stops looking
+pub const HD_GAP_LIMIT: u32 = 20;
+
+/// How urgently a transaction should be included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeePriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl FeePriority {
+    /// Percentile of recent fees paid that this priority targets
+    pub fn percentile(&self) -> u32 {
+        match self {
+            FeePriority::Low => 25,
+            FeePriority::Normal => 50,
+            FeePriority::High => 90,
+        }
+    }
+
+    /// Nearest-rank percentile of ascending `fees` for this priority
+    pub(crate) fn pick(&self, fees: &[Decimal]) -> Option<Decimal> {
+        let rank = (fees.len() * self.percentile() as usize).div_ceil(100).max(1);
+        fees.get(rank - 1).copied()
+    }
+}
+
+/// Gas fee to attach to a new transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeChoice {
+    /// Use exactly this fee
+    Fixed(Decimal),
+    /// Estimate from the fee market seen at the last blockchain sync
+    Auto(FeePriority),
+}
+
+impl From<Decimal> for FeeChoice {
+    fn from(fee: Decimal) -> Self {
+        FeeChoice::Fixed(fee)
+    }
+}
+
+/// Snapshot of recently paid fees and mempool backlog
+#[derive(Debug, Clone, Default)]
+pub struct FeeMarket {
+    /// Fees included in recent blocks, tagged with their operation type
+    recent_fees: Vec<(Discriminant<TriangleOperation>, Decimal)>,
+    /// Number of recent blocks the fees were taken from
+    block_count: usize,
+    /// Transactions waiting in the mempool
+    pending_transactions: usize,
+    /// Floor the mempool enforces on fees
+    gas_floor: GasFloorParams,
+}
+
+impl FeeMarket {
+    /// Capture the fee market from the most recent blocks and the mempool
+    pub fn from_blockchain(blockchain: &TriadChainBlockchain) -> Self {
+        // The genesis block carries no real fees, nor do coinbases
+        let recent_blocks: Vec<_> = blockchain.blocks
+            .iter()
+            .skip(1)
+            .rev()
+            .take(FEE_HISTORY_BLOCKS)
+            .collect();
+
+        let recent_fees = recent_blocks
+            .iter()
+            .flat_map(|block| block.triangle_transactions.iter().skip(1))
+            .map(|tx| (std::mem::discriminant(&tx.operation), tx.gas_fee))
+            .collect();
+
+        FeeMarket {
+            recent_fees,
+            block_count: recent_blocks.len(),
+            pending_transactions: blockchain.mempool.len(),
+            gas_floor: blockchain.gas_floor.clone(),
+        }
+    }
+
+    /// Suggest a gas fee for an operation on `address` at the given priority
+    ///
+    /// Takes the priority's percentile of fees recently paid for the same
+    /// operation type (or for any operation if that type has no history),
+    /// then adds 10% for every full block's worth of pending transactions.
+    /// Never less than the mempool accepts for `address`.
+    pub fn estimate(&self, operation: &TriangleOperation, address: &TriangleAddress, priority: FeePriority) -> Decimal {
+        let floor = self.gas_floor.minimum_for(address);
+        let kind = std::mem::discriminant(operation);
+        let mut fees: Vec<Decimal> = self.recent_fees
+            .iter()
+            .filter(|(op, _)| *op == kind)
+            .map(|(_, fee)| *fee)
+            .collect();
+        if fees.is_empty() {
+            fees = self.recent_fees.iter().map(|(_, fee)| *fee).collect();
+        }
+        if fees.is_empty() {
+            return floor;
+        }
+
+        fees.sort();
+        let Some(base) = priority.pick(&fees) else {
+            return floor;
+        };
+
+        let average_block_size = self.recent_fees.len() / self.block_count.max(1);
+        let backlog_blocks = self.pending_transactions
+            .checked_div(average_block_size)
+            .unwrap_or(0)
+            .min(MAX_BACKLOG_BLOCKS);
+        let surcharge = Decimal::ONE + Decimal::new(backlog_blocks as i64, 1);
+
+        (base * surcharge).max(floor)
+    }
+}
 
 /// Wallet for managing cryptocurrency and triangle ownership
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,14 +175,39 @@ pub struct TriadChainWallet {
     signing_key: Option<SigningKey>,
     /// Owned triangle addresses
     pub owned_triangles: HashMap<TriangleAddress, TriangleOwnership>,
-    /// Transaction history
-    pub transaction_history: Vec<String>, // Transaction IDs
+    /// Transactions this wallet signed or received, oldest first
+    #[serde(default)]
+    pub transaction_history: Vec<WalletTxRecord>,
     /// Cached balance
     pub balance: Decimal,
-    /// Staked amounts
+    /// Amounts staked on chain, which have left `balance`
     pub staked_balance: Decimal,
+    /// Fees and stakes of this wallet's transactions still in the mempool,
+    /// as of the last blockchain sync
+    #[serde(default)]
+    pub pending_outgoing: Decimal,
     /// Wallet creation time
     pub created_at: u64,
+    /// Fee market seen at the last blockchain sync
+    #[serde(skip)]
+    fee_market: FeeMarket,
+    /// Child indexes leading from the root wallet to this one
+    #[serde(default)]
+    derivation_path: Vec<u32>,
+    /// Derived child addresses found in use at the last blockchain sync
+    #[serde(default)]
+    pub derived_accounts: Vec<DerivedAccount>,
+    /// Incoming transactions in blocks below this height may be missing
+    /// from `transaction_history`, the node having pruned their bodies
+    #[serde(default)]
+    pub history_pruned_below: u64,
+}
+
+/// A derived child address that holds funds or triangles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedAccount {
+    pub index: u32,
+    pub stats: WalletStats,
 }
 
 /// Information about owned triangle
@@ -47,6 +221,69 @@ pub struct TriangleOwnership {
     pub estimated_value: Decimal,
 }
 
+/// Whether a transaction moved value out of or into the wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxDirection {
+    Outgoing,
+    Incoming,
+}
+
+/// What a transaction moved: tokens or a triangle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TxSubject {
+    Amount(Decimal),
+    Triangle(TriangleAddress),
+}
+
+/// Where a wallet transaction stands, as of the last blockchain sync
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxStatus {
+    /// Signed, and waiting in the mempool if it was submitted
+    Pending,
+    /// Included in the block at this height
+    Confirmed(u64),
+    /// Neither on the chain nor in the mempool
+    Failed(String),
+}
+
+/// One entry in a wallet's transaction history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletTxRecord {
+    pub tx_id: Uuid,
+    pub direction: TxDirection,
+    pub operation: TriangleOperation,
+    /// The other wallet involved, when there is one
+    pub counterparty: Option<String>,
+    pub amount_or_triangle: TxSubject,
+    pub status: TxStatus,
+    pub timestamp: u64,
+}
+
+impl WalletTxRecord {
+    /// Pending record of a transaction, seen from `direction`
+    fn pending(transaction: &TriangleTransaction, direction: TxDirection) -> Self {
+        let amount_or_triangle = match &transaction.operation {
+            TriangleOperation::Stake { amount }
+            | TriangleOperation::ClaimReward { amount }
+            | TriangleOperation::Coinbase { amount } => TxSubject::Amount(*amount),
+            _ => TxSubject::Triangle(transaction.to_address.clone()),
+        };
+        let counterparty = match direction {
+            TxDirection::Outgoing => transaction.recipient.clone(),
+            TxDirection::Incoming => transaction.sender.clone(),
+        };
+        WalletTxRecord {
+            tx_id: transaction.id,
+            direction,
+            operation: transaction.operation.clone(),
+            counterparty,
+            amount_or_triangle,
+            status: TxStatus::Pending,
+            timestamp: transaction.timestamp,
+        }
+    }
+}
+
 /// Transaction builder for creating signed transactions
 #[allow(dead_code)]
 pub struct TransactionBuilder {
@@ -56,25 +293,14 @@ pub struct TransactionBuilder {
 
 impl TriadChainWallet {
     /// Create a new wallet with generated keypair
+    #[cfg(feature = "node")]
     pub fn new() -> SierpinskiResult<Self> {
-        let signing_key = SigningKey::from_bytes(&rand::random::<[u8; 32]>());
-        let public_key = signing_key.verifying_key();
-        
-        let wallet_id = Self::derive_wallet_address(&public_key);
-        
-        Ok(TriadChainWallet {
-            wallet_id,
-            public_key,
-            signing_key: Some(signing_key),
-            owned_triangles: HashMap::new(),
-            transaction_history: Vec::new(),
-            balance: Decimal::ZERO,
-            staked_balance: Decimal::ZERO,
-            created_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        })
+        Ok(Self::from_seed(&rand::random::<[u8; 32]>()))
+    }
+
+    /// Create a wallet whose keypair, and so `wallet_id`, is fixed by `seed`
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self::from_signing_key(SigningKey::from_bytes(seed))
     }
 
     /// Create wallet from existing signing key (for recovery)
@@ -90,54 +316,175 @@ impl TriadChainWallet {
             transaction_history: Vec::new(),
             balance: Decimal::ZERO,
             staked_balance: Decimal::ZERO,
+            pending_outgoing: Decimal::ZERO,
             created_at: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            fee_market: FeeMarket::default(),
+            derivation_path: Vec::new(),
+            derived_accounts: Vec::new(),
+            history_pruned_below: 0,
         }
     }
 
-    /// Derive wallet address from public key
-    fn derive_wallet_address(public_key: &VerifyingKey) -> String {
+    /// Derive the hardened child wallet at `index`
+    ///
+    /// BIP32-style: HMAC-SHA512 keyed with this wallet's chain code over
+    /// `0x00 || signing key || index` gives the child's ed25519 seed. The
+    /// chain code is itself an HMAC of the signing key, so every child can be
+    /// re-derived from the root key alone. Only hardened derivation exists
+    /// for ed25519, so `index` must be below 2^31.
+    pub fn derive_child_wallet(&self, index: u32) -> SierpinskiResult<TriadChainWallet> {
+        let signing_key = self.signing_key.as_ref()
+            .ok_or_else(|| SierpinskiError::validation("Wallet signing key not available"))?;
+        if index >= HARDENED_OFFSET {
+            return Err(SierpinskiError::validation(format!("Derivation index {} out of range", index)));
+        }
+
+        let mut chain = Self::hmac_sha512(CHAIN_CODE_DOMAIN, signing_key.as_bytes());
+        let mut data = Vec::with_capacity(37);
+        data.push(0);
+        data.extend_from_slice(signing_key.as_bytes());
+        data.extend_from_slice(&(index | HARDENED_OFFSET).to_be_bytes());
+        let derived = Self::hmac_sha512(&chain[32..], &data);
+        chain.fill(0);
+
+        let child_seed: [u8; 32] = derived[..32].try_into().expect("HMAC-SHA512 output is 64 bytes");
+        let mut child = TriadChainWallet::from_signing_key(SigningKey::from_bytes(&child_seed));
+        child.derivation_path = self.derivation_path.clone();
+        child.derivation_path.push(index);
+        Ok(child)
+    }
+
+    /// Path from the root wallet, e.g. `m/0'/3'`
+    pub fn derivation_path(&self) -> String {
+        std::iter::once("m".to_string())
+            .chain(self.derivation_path.iter().map(|index| format!("{}'", index)))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+        let mut mac = <Hmac<Sha512> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(data);
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Derive wallet address from public key: `ST`, then hex from the key's
+    /// hash, then a checksum, see `validate_address`
+    pub(crate) fn derive_wallet_address(public_key: &VerifyingKey) -> String {
         let mut hasher = blake3::Hasher::new();
         hasher.update(public_key.as_bytes());
         let hash = hasher.finalize();
-        format!("ST{}", &hash.to_hex()[..32]) // ST prefix for Sierpinski Triangle
+        let body = format!("ST{}", &hash.to_hex()[..WALLET_ADDRESS_HEX_LEN]); // ST prefix for Sierpinski Triangle
+        let checksum = Self::address_checksum(&body);
+        body + &checksum
     }
 
-    /// Sign a transaction
-    pub fn sign_transaction(&self, transaction: &mut TriangleTransaction) -> SierpinskiResult<()> {
-        let signing_key = self.signing_key.as_ref()
-            .ok_or_else(|| SierpinskiError::validation("Wallet signing key not available"))?;
+    /// Checksum ending an address, the leading hex of the blake3 hash of
+    /// everything before it
+    fn address_checksum(body: &str) -> String {
+        blake3::hash(body.as_bytes()).to_hex()[..WALLET_ADDRESS_CHECKSUM_LEN].to_string()
+    }
 
-        // Create message to sign
-        let message = format!(
+    /// Check the `ST` prefix, hex body and checksum of a wallet address, so
+    /// a mistyped address is caught
+    pub fn validate_address(address: &str) -> bool {
+        if address.len() != 2 + WALLET_ADDRESS_HEX_LEN + WALLET_ADDRESS_CHECKSUM_LEN || !address.is_ascii() {
+            return false;
+        }
+        let (body, checksum) = address.split_at(2 + WALLET_ADDRESS_HEX_LEN);
+        body.strip_prefix("ST")
+            .is_some_and(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            && checksum == Self::address_checksum(body)
+    }
+
+    /// Check that a string is a valid wallet address, see `validate_address`
+    pub fn validate_wallet_address(address: &str) -> SierpinskiResult<()> {
+        if !Self::validate_address(address) {
+            return Err(SierpinskiError::InvalidAddress { address: address.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Message covered by a transaction signature
+    fn signing_message(transaction: &TriangleTransaction) -> String {
+        let mut message = format!(
             "{}:{}:{}:{}",
             transaction.id,
             transaction.to_address,
             serde_json::to_string(&transaction.operation).unwrap(),
             transaction.timestamp
         );
+        // Appended only when present so older signatures stay valid
+        if let Some(recipient) = &transaction.recipient {
+            message.push(':');
+            message.push_str(recipient);
+        }
+        // Transactions carrying their signer's key also cover who pays what,
+        // the triangle carried and the price traded at
+        if transaction.sender_public_key.is_some() {
+            let from = transaction.from_address.as_ref().map(|address| address.to_string());
+            let triangle = transaction.triangle_data.as_ref().map(Triangle::canonical_hash);
+            let price = transaction.price.map(|price| price.normalize().to_string());
+            message.push_str(&format!(
+                ":{}:{}:{}:{}:{}",
+                transaction.sender.as_deref().unwrap_or(""),
+                from.as_deref().unwrap_or(""),
+                transaction.gas_fee.normalize(),
+                triangle.as_deref().unwrap_or(""),
+                price.as_deref().unwrap_or(""),
+            ));
+        }
+        message
+    }
+
+    /// Sign a transaction, recording it as pending in the history
+    pub fn sign_transaction(&mut self, transaction: &mut TriangleTransaction) -> SierpinskiResult<()> {
+        let signing_key = self.signing_key.as_ref()
+            .ok_or_else(|| SierpinskiError::validation("Wallet signing key not available"))?;
+
+        // The key travels with the transaction so nodes can check the signature
+        transaction.sender_public_key = Some(self.public_key);
+
+        // Create message to sign
+        let message = Self::signing_message(transaction);
 
         // Sign the message
         let signature = signing_key.sign(message.as_bytes());
         transaction.signature = signature.to_bytes().to_vec();
 
+        // Signing again after an edit replaces the earlier record
+        let record = WalletTxRecord::pending(transaction, TxDirection::Outgoing);
+        match self.transaction_history.iter_mut().find(|r| r.tx_id == transaction.id) {
+            Some(existing) => *existing = record,
+            None => self.transaction_history.push(record),
+        }
+
         Ok(())
     }
 
+    /// Sign an off-chain message, e.g. to prove ownership of the wallet to a
+    /// marketplace without sending a transaction
+    pub fn sign_message(&self, message: &[u8]) -> SierpinskiResult<SignedMessage> {
+        let signing_key = self.signing_key.as_ref()
+            .ok_or_else(|| SierpinskiError::validation("Wallet signing key not available"))?;
+
+        let signature = signing_key.sign(&SignedMessage::signed_bytes(message));
+        Ok(SignedMessage {
+            message: message.to_vec(),
+            signature: signature.to_bytes().to_vec(),
+            public_key: self.public_key,
+        })
+    }
+
     /// Verify a transaction signature
     pub fn verify_transaction_signature(
         transaction: &TriangleTransaction,
         public_key: &VerifyingKey,
     ) -> bool {
-        let message = format!(
-            "{}:{}:{}:{}",
-            transaction.id,
-            transaction.to_address,
-            serde_json::to_string(&transaction.operation).unwrap(),
-            transaction.timestamp
-        );
+        let message = Self::signing_message(transaction);
 
         if transaction.signature.len() == 64 {
             if let Ok(signature_bytes) = transaction.signature.as_slice().try_into() {
@@ -149,9 +496,51 @@ impl TriadChainWallet {
     }
 
     /// Update wallet state from blockchain
+    ///
+    /// Wallets holding a signing key also scan their derived children,
+    /// stopping after `HD_GAP_LIMIT` unused addresses in a row.
     pub fn sync_with_blockchain(&mut self, blockchain: &TriadChainBlockchain) -> SierpinskiResult<()> {
+        self.sync_own_state(blockchain);
+        self.sync_transaction_history(blockchain);
+
+        self.derived_accounts.clear();
+        if self.signing_key.is_none() {
+            return Ok(());
+        }
+
+        let mut unused = 0;
+        let mut index = 0;
+        while unused < HD_GAP_LIMIT {
+            let mut child = self.derive_child_wallet(index)?;
+            child.sync_own_state(blockchain);
+
+            if child.balance.is_zero() && child.owned_triangles.is_empty() {
+                unused += 1;
+            } else {
+                unused = 0;
+                self.derived_accounts.push(DerivedAccount { index, stats: child.get_stats() });
+            }
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Update this address's balance, stakes, pending spending and
+    /// triangles, ignoring derived children
+    fn sync_own_state(&mut self, blockchain: &TriadChainBlockchain) {
         // Update balance
         self.balance = blockchain.get_balance(&self.wallet_id);
+        self.fee_market = FeeMarket::from_blockchain(blockchain);
+
+        let stakes: HashMap<TriangleAddress, Decimal> = blockchain.economics
+            .stakes_of(&self.wallet_id)
+            .map(|(address, amount)| (address.clone(), amount))
+            .collect();
+        self.staked_balance = stakes.values().sum();
+        // Only what will leave this wallet's balance: the gas it pays and
+        // the amount of its own stakes
+        self.pending_outgoing = blockchain.pending_outgoing(&self.wallet_id);
 
         // Update owned triangles
         let owned_addresses = blockchain.get_owned_triangles(&self.wallet_id);
@@ -181,7 +570,41 @@ impl TriadChainWallet {
             }
         }
 
-        Ok(())
+        for (address, ownership) in &mut self.owned_triangles {
+            ownership.staked_amount = stakes.get(address).copied().unwrap_or(Decimal::ZERO);
+            ownership.is_staked = ownership.staked_amount > Decimal::ZERO;
+        }
+    }
+
+    /// Add transfers received on chain and bring every status up to date
+    ///
+    /// A transaction found in a block is confirmed at its height, one still
+    /// in the mempool stays pending, and one in neither has failed, whether
+    /// it was rejected, evicted or never submitted.
+    fn sync_transaction_history(&mut self, blockchain: &TriadChainBlockchain) {
+        self.history_pruned_below = blockchain.pruned_below;
+        for block in &blockchain.blocks {
+            // Pruned bodies are empty rather than transaction-free
+            let Ok(transactions) = blockchain.block_transactions(block.height) else { continue };
+            for transaction in transactions {
+                let received = transaction.recipient.as_deref() == Some(self.wallet_id.as_str());
+                if received && !self.transaction_history.iter().any(|r| r.tx_id == transaction.id) {
+                    self.transaction_history.push(WalletTxRecord::pending(transaction, TxDirection::Incoming));
+                }
+            }
+        }
+
+        for record in &mut self.transaction_history {
+            record.status = if let Some(height) = blockchain.transaction_height(&record.tx_id) {
+                TxStatus::Confirmed(height)
+            } else if blockchain.mempool.iter().any(|tx| tx.id == record.tx_id) {
+                TxStatus::Pending
+            } else if let TxStatus::Failed(reason) = &record.status {
+                TxStatus::Failed(reason.clone())
+            } else {
+                TxStatus::Failed("Not in the mempool or on the chain".to_string())
+            };
+        }
     }
 
     /// Estimate the value of a triangle based on its properties
@@ -212,18 +635,44 @@ impl TriadChainWallet {
         base_value * depth_multiplier * area_multiplier
     }
 
-    /// Create a transaction to transfer triangle ownership
-    pub fn create_transfer_transaction(
+    /// Suggest a gas fee for an operation on `address` based on recent
+    /// blocks and the mempool
+    pub fn estimate_gas(
         &self,
+        blockchain: &TriadChainBlockchain,
+        operation: &TriangleOperation,
+        address: &TriangleAddress,
+        priority: FeePriority,
+    ) -> Decimal {
+        FeeMarket::from_blockchain(blockchain).estimate(operation, address, priority)
+    }
+
+    /// Turn a fee choice into a concrete fee using the last synced fee market
+    fn resolve_fee(&self, fee: FeeChoice, operation: &TriangleOperation, address: &TriangleAddress) -> Decimal {
+        match fee {
+            FeeChoice::Fixed(fee) => fee,
+            FeeChoice::Auto(priority) => self.fee_market.estimate(operation, address, priority),
+        }
+    }
+
+    /// Create a transaction transferring an owned triangle to another wallet
+    ///
+    /// The gas fee is paid from this wallet's balance.
+    pub fn create_transfer_transaction(
+        &mut self,
         to_address: &str,
         triangle_address: TriangleAddress,
-        gas_fee: Decimal,
+        gas_fee: impl Into<FeeChoice>,
     ) -> SierpinskiResult<TriangleTransaction> {
+        Self::validate_wallet_address(to_address)?;
+
         // Check if we own this triangle
         if !self.owned_triangles.contains_key(&triangle_address) {
             return Err(SierpinskiError::validation("Triangle not owned by this wallet"));
         }
 
+        let gas_fee = self.resolve_fee(gas_fee.into(), &TriangleOperation::Transfer, &triangle_address);
+
         // Check sufficient balance for gas
         if self.balance < gas_fee {
             return Err(SierpinskiError::validation("Insufficient balance for gas fee"));
@@ -231,12 +680,14 @@ impl TriadChainWallet {
 
         let mut transaction = TriangleTransaction::new(
             Some(triangle_address.clone()),
-            TriangleAddress::from_string_representation(to_address)?,
+            triangle_address.clone(),
             TriangleOperation::Transfer,
             self.owned_triangles.get(&triangle_address)
                 .and_then(|ownership| ownership.triangle_data.clone()),
             gas_fee,
         );
+        transaction.sender = Some(self.wallet_id.clone());
+        transaction.recipient = Some(to_address.to_string());
 
         // Sign the transaction
         self.sign_transaction(&mut transaction)?;
@@ -246,16 +697,19 @@ impl TriadChainWallet {
 
     /// Create a staking transaction
     pub fn create_stake_transaction(
-        &self,
+        &mut self,
         triangle_address: TriangleAddress,
         stake_amount: Decimal,
-        gas_fee: Decimal,
+        gas_fee: impl Into<FeeChoice>,
     ) -> SierpinskiResult<TriangleTransaction> {
         // Check ownership and sufficient balance
         if !self.owned_triangles.contains_key(&triangle_address) {
             return Err(SierpinskiError::validation("Triangle not owned by this wallet"));
         }
 
+        let operation = TriangleOperation::Stake { amount: stake_amount };
+        let gas_fee = self.resolve_fee(gas_fee.into(), &operation, &triangle_address);
+
         if self.balance < stake_amount + gas_fee {
             return Err(SierpinskiError::validation("Insufficient balance for stake and gas"));
         }
@@ -263,26 +717,52 @@ impl TriadChainWallet {
         let mut transaction = TriangleTransaction::new(
             Some(triangle_address.clone()),
             triangle_address,
-            TriangleOperation::Stake { amount: stake_amount },
+            operation,
             None,
             gas_fee,
         );
+        transaction.sender = Some(self.wallet_id.clone());
 
         self.sign_transaction(&mut transaction)?;
         Ok(transaction)
     }
 
+    /// Create a transaction claiming accrued staking or mining rewards
+    pub fn create_claim_transaction(
+        &mut self,
+        amount: Decimal,
+        gas_fee: impl Into<FeeChoice>,
+    ) -> SierpinskiResult<TriangleTransaction> {
+        let operation = TriangleOperation::ClaimReward { amount };
+        let gas_fee = self.resolve_fee(gas_fee.into(), &operation, &TriangleAddress::genesis());
+
+        let mut transaction = TriangleTransaction::new(
+            None,
+            TriangleAddress::genesis(),
+            operation,
+            None,
+            gas_fee,
+        );
+        transaction.sender = Some(self.wallet_id.clone());
+
+        transaction.validate()?;
+        self.sign_transaction(&mut transaction)?;
+        Ok(transaction)
+    }
+
     /// Create a subdivision transaction (mining)
     pub fn create_subdivision_transaction(
-        &self,
+        &mut self,
         triangle_address: TriangleAddress,
-        gas_fee: Decimal,
+        gas_fee: impl Into<FeeChoice>,
     ) -> SierpinskiResult<TriangleTransaction> {
         // Check ownership
         let triangle_data = self.owned_triangles.get(&triangle_address)
             .ok_or_else(|| SierpinskiError::validation("Triangle not owned by this wallet"))?
             .triangle_data.clone();
 
+        let gas_fee = self.resolve_fee(gas_fee.into(), &TriangleOperation::Subdivide, &triangle_address);
+
         if self.balance < gas_fee {
             return Err(SierpinskiError::validation("Insufficient balance for gas fee"));
         }
@@ -314,14 +794,40 @@ impl TriadChainWallet {
             wallet_id: self.wallet_id.clone(),
             total_balance: self.balance,
             staked_balance: self.staked_balance,
-            available_balance: self.balance - self.staked_balance,
+            available_balance: (self.balance - self.pending_outgoing).max(Decimal::ZERO),
+            pending_outgoing: self.pending_outgoing,
             total_triangles,
             staked_triangles,
             estimated_portfolio_value,
-            transaction_count: self.transaction_history.len(),
+            transaction_count: self.transaction_history
+                .iter()
+                .filter(|record| matches!(record.status, TxStatus::Confirmed(_)))
+                .count(),
+            pending_transaction_count: self.transaction_history
+                .iter()
+                .filter(|record| record.status == TxStatus::Pending)
+                .count(),
         }
     }
 
+    /// Statistics summed over this wallet and the derived accounts found at
+    /// the last sync
+    pub fn get_stats_aggregate(&self) -> WalletStats {
+        self.derived_accounts.iter().fold(self.get_stats(), |mut total, account| {
+            let stats = &account.stats;
+            total.total_balance += stats.total_balance;
+            total.staked_balance += stats.staked_balance;
+            total.available_balance += stats.available_balance;
+            total.pending_outgoing += stats.pending_outgoing;
+            total.total_triangles += stats.total_triangles;
+            total.staked_triangles += stats.staked_triangles;
+            total.estimated_portfolio_value += stats.estimated_portfolio_value;
+            total.transaction_count += stats.transaction_count;
+            total.pending_transaction_count += stats.pending_transaction_count;
+            total
+        })
+    }
+
     /// Export wallet (without private key)
     pub fn export_public(&self) -> PublicWalletData {
         PublicWalletData {
@@ -334,6 +840,119 @@ impl TriadChainWallet {
     }
 }
 
+/// Wallet key file protected by a password
+///
+/// The signing key is sealed with ChaCha20-Poly1305 under a key derived from
+/// the password with Argon2; everything else about the wallet is rebuilt by
+/// syncing with the blockchain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedWallet {
+    pub wallet_id: String,
+    pub created_at: u64,
+    /// Argon2 salt, hex encoded
+    pub salt: String,
+    /// ChaCha20-Poly1305 nonce, hex encoded
+    pub nonce: String,
+    /// Sealed signing key, hex encoded
+    pub ciphertext: String,
+    /// Child indexes from the root wallet, empty for a root wallet
+    #[serde(default)]
+    pub derivation_path: Vec<u32>,
+}
+
+impl EncryptedWallet {
+    /// Seal a wallet's signing key under a password
+    #[cfg(feature = "node")]
+    pub fn encrypt(wallet: &TriadChainWallet, password: &str) -> SierpinskiResult<Self> {
+        let signing_key = wallet.signing_key.as_ref()
+            .ok_or_else(|| SierpinskiError::validation("Wallet signing key not available"))?;
+
+        let salt: [u8; 16] = rand::random();
+        let nonce: [u8; 12] = rand::random();
+        let cipher = Self::cipher(password, &salt)?;
+        let ciphertext = cipher
+            .encrypt(&nonce.into(), Payload { msg: signing_key.as_bytes(), aad: wallet.wallet_id.as_bytes() })
+            .map_err(|_| SierpinskiError::validation("Failed to encrypt wallet key"))?;
+
+        Ok(EncryptedWallet {
+            wallet_id: wallet.wallet_id.clone(),
+            created_at: wallet.created_at,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+            derivation_path: wallet.derivation_path.clone(),
+        })
+    }
+
+    /// Unseal the signing key and rebuild the wallet
+    pub fn decrypt(&self, password: &str) -> SierpinskiResult<TriadChainWallet> {
+        let salt = Self::decode_hex(&self.salt)?;
+        let nonce: [u8; 12] = Self::decode_hex(&self.nonce)?
+            .try_into()
+            .map_err(|_| SierpinskiError::validation("Corrupted wallet file"))?;
+        let ciphertext = Self::decode_hex(&self.ciphertext)?;
+
+        let key_bytes: [u8; 32] = Self::cipher(password, &salt)?
+            .decrypt(&nonce.into(), Payload { msg: &ciphertext, aad: self.wallet_id.as_bytes() })
+            .map_err(|_| SierpinskiError::validation("Wrong password or corrupted wallet file"))?
+            .try_into()
+            .map_err(|_| SierpinskiError::validation("Corrupted wallet file"))?;
+
+        let mut wallet = TriadChainWallet::from_signing_key(SigningKey::from_bytes(&key_bytes));
+        if wallet.wallet_id != self.wallet_id {
+            return Err(SierpinskiError::validation("Wallet key does not match wallet address"));
+        }
+        wallet.created_at = self.created_at;
+        wallet.derivation_path = self.derivation_path.clone();
+        Ok(wallet)
+    }
+
+    /// Derive the sealing cipher from a password and salt
+    fn cipher(password: &str, salt: &[u8]) -> SierpinskiResult<ChaCha20Poly1305> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(password.as_bytes(), salt, &mut key)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to derive wallet key: {}", e)))?;
+        Ok(ChaCha20Poly1305::new(&key.into()))
+    }
+
+    fn decode_hex(value: &str) -> SierpinskiResult<Vec<u8>> {
+        hex::decode(value).map_err(|_| SierpinskiError::validation("Corrupted wallet file"))
+    }
+}
+
+/// Message signed by a wallet with `TriadChainWallet::sign_message`,
+/// carrying the key that signed it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedMessage {
+    pub message: Vec<u8>,
+    pub signature: Vec<u8>,
+    #[serde(with = "verifying_key_serde")]
+    pub public_key: VerifyingKey,
+}
+
+impl SignedMessage {
+    /// Check that the embedded key signed the message and belongs to
+    /// `expected_wallet_id`; the wallet id follows from the key, so no key
+    /// registry is needed
+    pub fn verify(&self, expected_wallet_id: &str) -> bool {
+        if TriadChainWallet::derive_wallet_address(&self.public_key) != expected_wallet_id {
+            return false;
+        }
+        let Ok(signature) = Signature::from_slice(&self.signature) else {
+            return false;
+        };
+        self.public_key
+            .verify(&Self::signed_bytes(&self.message), &signature)
+            .is_ok()
+    }
+
+    /// Bytes covered by the signature: the domain prefix, then the message
+    fn signed_bytes(message: &[u8]) -> Vec<u8> {
+        [MESSAGE_SIGNING_DOMAIN, message].concat()
+    }
+}
+
 /// Public wallet data for sharing/display
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicWalletData {
@@ -351,15 +970,22 @@ pub struct WalletStats {
     pub wallet_id: String,
     pub total_balance: Decimal,
     pub staked_balance: Decimal,
+    /// Balance less pending spending, never negative
     pub available_balance: Decimal,
+    /// Fees and stakes of transactions still in the mempool
+    #[serde(default)]
+    pub pending_outgoing: Decimal,
     pub total_triangles: usize,
     pub staked_triangles: usize,
     pub estimated_portfolio_value: Decimal,
+    /// Confirmed transactions only
     pub transaction_count: usize,
+    #[serde(default)]
+    pub pending_transaction_count: usize,
 }
 
 /// Serde helper for VerifyingKey
-mod verifying_key_serde {
+pub(crate) mod verifying_key_serde {
     use super::*;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -377,8 +1003,31 @@ mod verifying_key_serde {
         let bytes: [u8; 32] = Deserialize::deserialize(deserializer)?;
         VerifyingKey::from_bytes(&bytes).map_err(serde::de::Error::custom)
     }
+
+    /// Same encoding for an optional key
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S>(key: &Option<VerifyingKey>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            key.as_ref().map(|key| *key.as_bytes()).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<VerifyingKey>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let bytes: Option<[u8; 32]> = Deserialize::deserialize(deserializer)?;
+            bytes
+                .map(|bytes| VerifyingKey::from_bytes(&bytes).map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
 }
 
+#[cfg(feature = "node")]
 impl Default for TriadChainWallet {
     fn default() -> Self {
         Self::new().unwrap()
@@ -388,10 +1037,29 @@ impl Default for TriadChainWallet {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::block::Block;
+    use crate::core::target::Target;
+
+    /// Append a block containing one transaction per fee, bypassing mining
+    fn push_block_with_fees(blockchain: &mut TriadChainBlockchain, operation: TriangleOperation, fees: &[i64]) {
+        let transactions = fees
+            .iter()
+            .map(|fee| TriangleTransaction::new(
+                None,
+                TriangleAddress::genesis(),
+                operation.clone(),
+                None,
+                Decimal::new(*fee, 2),
+            ))
+            .collect();
+        let previous_hash = blockchain.blocks.last().unwrap().hash();
+        let block = Block::new(previous_hash, transactions, &blockchain.fractal_state, "miner".to_string(), blockchain.target);
+        blockchain.blocks.push(block);
+    }
 
     #[test]
     fn test_wallet_creation() {
-        let wallet = TriadChainWallet::new().unwrap();
+        let wallet = TriadChainWallet::from_seed(&[21; 32]);
         assert!(!wallet.wallet_id.is_empty());
         assert!(wallet.wallet_id.starts_with("ST"));
         assert_eq!(wallet.balance, Decimal::ZERO);
@@ -399,7 +1067,7 @@ mod tests {
 
     #[test]
     fn test_transaction_signing() {
-        let wallet = TriadChainWallet::new().unwrap();
+        let mut wallet = TriadChainWallet::from_seed(&[22; 32]);
         
         let mut transaction = TriangleTransaction::new(
             None,
@@ -416,12 +1084,412 @@ mod tests {
         assert!(TriadChainWallet::verify_transaction_signature(&transaction, &wallet.public_key));
     }
 
+    #[test]
+    fn test_signature_covers_triangle_data_and_price() {
+        let mut wallet = TriadChainWallet::from_seed(&[23; 32]);
+        let mut transaction = TriangleTransaction::new(
+            Some(TriangleAddress::genesis()),
+            TriangleAddress::genesis(),
+            TriangleOperation::Transfer,
+            Some(crate::core::genesis::genesis_triangle().unwrap()),
+            Decimal::new(1, 2),
+        );
+        transaction.price = Some(Decimal::from(250));
+        wallet.sign_transaction(&mut transaction).unwrap();
+        assert!(TriadChainWallet::verify_transaction_signature(&transaction, &wallet.public_key));
+
+        let mut reshaped = transaction.clone();
+        reshaped.triangle_data.as_mut().unwrap().vertices[0].x += Decimal::ONE;
+        assert!(!TriadChainWallet::verify_transaction_signature(&reshaped, &wallet.public_key));
+
+        let mut repriced = transaction.clone();
+        repriced.price = Some(Decimal::from(1));
+        assert!(!TriadChainWallet::verify_transaction_signature(&repriced, &wallet.public_key));
+
+        let mut unpriced = transaction;
+        unpriced.price = None;
+        assert!(!TriadChainWallet::verify_transaction_signature(&unpriced, &wallet.public_key));
+    }
+
     #[test]
     fn test_wallet_stats() {
-        let wallet = TriadChainWallet::new().unwrap();
+        let wallet = TriadChainWallet::from_seed(&[24; 32]);
         let stats = wallet.get_stats();
         
         assert_eq!(stats.total_triangles, 0);
         assert_eq!(stats.total_balance, Decimal::ZERO);
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_gas_estimation_percentiles() {
+        let wallet = TriadChainWallet::from_seed(&[25; 32]);
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let genesis = TriangleAddress::genesis();
+
+        // No history yet: fall back to the floor
+        let floor = wallet.estimate_gas(&blockchain, &TriangleOperation::Transfer, &genesis, FeePriority::High);
+        assert_eq!(floor, MIN_GAS_FEE);
+
+        // Transfers paid 0.10 ..= 1.00, subdivisions paid far more
+        push_block_with_fees(&mut blockchain, TriangleOperation::Transfer, &[10, 20, 30, 40, 50]);
+        push_block_with_fees(&mut blockchain, TriangleOperation::Transfer, &[100, 90, 80, 70, 60]);
+        push_block_with_fees(&mut blockchain, TriangleOperation::Subdivide, &[500, 500, 500, 500, 500]);
+
+        let transfer = TriangleOperation::Transfer;
+        assert_eq!(wallet.estimate_gas(&blockchain, &transfer, &genesis, FeePriority::Low), Decimal::new(30, 2));
+        assert_eq!(wallet.estimate_gas(&blockchain, &transfer, &genesis, FeePriority::Normal), Decimal::new(50, 2));
+        assert_eq!(wallet.estimate_gas(&blockchain, &transfer, &genesis, FeePriority::High), Decimal::new(90, 2));
+        assert_eq!(
+            wallet.estimate_gas(&blockchain, &TriangleOperation::Subdivide, &genesis, FeePriority::Low),
+            Decimal::new(500, 2)
+        );
+
+        // Two blocks' worth of pending transactions adds a 20% surcharge
+        for _ in 0..10 {
+            blockchain.mempool.push(TriangleTransaction::new(
+                None,
+                TriangleAddress::genesis(),
+                TriangleOperation::Transfer,
+                None,
+                Decimal::ONE,
+            ));
+        }
+        assert_eq!(wallet.estimate_gas(&blockchain, &transfer, &genesis, FeePriority::Normal), Decimal::new(60, 2));
+    }
+
+    #[test]
+    fn test_auto_fee_uses_synced_market() {
+        let mut wallet = TriadChainWallet::from_seed(&[26; 32]);
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        push_block_with_fees(&mut blockchain, TriangleOperation::Subdivide, &[25, 75]);
+
+        let address = TriangleAddress::genesis();
+        wallet.owned_triangles.insert(address.clone(), TriangleOwnership {
+            address: address.clone(),
+            triangle_data: None,
+            acquisition_time: 0,
+            is_staked: false,
+            staked_amount: Decimal::ZERO,
+            estimated_value: Decimal::ZERO,
+        });
+
+        wallet.sync_with_blockchain(&blockchain).unwrap();
+        wallet.balance = Decimal::from(10);
+
+        let auto = wallet
+            .create_subdivision_transaction(address.clone(), FeeChoice::Auto(FeePriority::High))
+            .unwrap();
+        assert_eq!(auto.gas_fee, Decimal::new(75, 2));
+
+        let fixed = wallet.create_subdivision_transaction(address, Decimal::ONE).unwrap();
+        assert_eq!(fixed.gas_fee, Decimal::ONE);
+
+        // Deeper targets never get less than the mempool accepts for them
+        blockchain.gas_floor = GasFloorParams { base: Decimal::ONE, depth_factor: Decimal::ONE };
+        wallet.sync_with_blockchain(&blockchain).unwrap();
+        wallet.balance = Decimal::from(10);
+        let deep = TriangleAddress::new(vec![0, 1, 2]).unwrap();
+        wallet.owned_triangles.insert(deep.clone(), TriangleOwnership {
+            address: deep.clone(),
+            triangle_data: None,
+            acquisition_time: 0,
+            is_staked: false,
+            staked_amount: Decimal::ZERO,
+            estimated_value: Decimal::ZERO,
+        });
+        let auto = wallet
+            .create_subdivision_transaction(deep.clone(), FeeChoice::Auto(FeePriority::High))
+            .unwrap();
+        assert_eq!(auto.gas_fee, blockchain.minimum_gas_for(&deep));
+    }
+
+    #[test]
+    #[cfg(feature = "node")]
+    fn test_encrypted_wallet_roundtrip() {
+        let wallet = TriadChainWallet::from_seed(&[27; 32]);
+        let sealed = EncryptedWallet::encrypt(&wallet, "correct horse").unwrap();
+
+        let json = serde_json::to_string(&sealed).unwrap();
+        let sealed: EncryptedWallet = serde_json::from_str(&json).unwrap();
+
+        let restored = sealed.decrypt("correct horse").unwrap();
+        assert_eq!(restored.wallet_id, wallet.wallet_id);
+        assert_eq!(restored.public_key, wallet.public_key);
+        assert!(sealed.decrypt("battery staple").is_err());
+    }
+
+    #[test]
+    fn test_transfer_to_wallet() {
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        let mut sender = TriadChainWallet::from_seed(&[28; 32]);
+        let recipient = TriadChainWallet::from_seed(&[29; 32]);
+        let triangle = TriangleAddress::from_string_representation("0.1.2").unwrap();
+        blockchain.triangle_owners.insert(triangle.clone(), sender.wallet_id.clone());
+        blockchain.balances.insert(sender.wallet_id.clone(), Decimal::ONE);
+        sender.sync_with_blockchain(&blockchain).unwrap();
+
+        assert!(matches!(
+            sender.create_transfer_transaction("0.1", triangle.clone(), Decimal::new(5, 2)),
+            Err(SierpinskiError::InvalidAddress { .. })
+        ));
+
+        let transaction = sender
+            .create_transfer_transaction(&recipient.wallet_id, triangle.clone(), Decimal::new(5, 2))
+            .unwrap();
+        assert!(TriadChainWallet::verify_transaction_signature(&transaction, &sender.public_key));
+
+        // The recipient is covered by the signature
+        let mut redirected = transaction.clone();
+        redirected.recipient = Some(sender.wallet_id.clone());
+        assert!(!TriadChainWallet::verify_transaction_signature(&redirected, &sender.public_key));
+
+        // Only the owner may submit a transfer
+        let mut stolen = transaction.clone();
+        stolen.sender = Some(recipient.wallet_id.clone());
+        blockchain.balances.insert(recipient.wallet_id.clone(), Decimal::ONE);
+        assert!(blockchain.add_transaction(stolen).is_err());
+
+        blockchain.target = Target::from_legacy_difficulty(1);
+        blockchain.add_transaction(transaction).unwrap();
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        assert_eq!(blockchain.triangle_owners.get(&triangle), Some(&recipient.wallet_id));
+        assert_eq!(blockchain.get_balance(&sender.wallet_id), Decimal::new(95, 2));
+    }
+
+    #[test]
+    fn test_sync_tracks_stakes_and_pending_spending() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut sender = TriadChainWallet::from_seed(&[30; 32]);
+        let recipient = TriadChainWallet::from_seed(&[31; 32]);
+        let triangle = TriangleAddress::from_string_representation("0.1").unwrap();
+        let staked = TriangleAddress::from_string_representation("0.2").unwrap();
+        blockchain.triangle_owners.insert(triangle.clone(), sender.wallet_id.clone());
+        blockchain.triangle_owners.insert(staked.clone(), sender.wallet_id.clone());
+        blockchain.balances.insert(sender.wallet_id.clone(), Decimal::ONE);
+        sender.sync_with_blockchain(&blockchain).unwrap();
+
+        // A pending stake counts against the balance with its gas fee
+        let stake = sender
+            .create_stake_transaction(staked.clone(), Decimal::new(3, 1), Decimal::new(5, 2))
+            .unwrap();
+        blockchain.add_transaction(stake).unwrap();
+        sender.sync_with_blockchain(&blockchain).unwrap();
+        let stats = sender.get_stats();
+        assert_eq!(stats.pending_outgoing, Decimal::new(35, 2));
+        assert_eq!(stats.staked_balance, Decimal::ZERO);
+        assert_eq!(stats.available_balance, Decimal::new(65, 2));
+
+        // Once mined it has left the balance for the staking ledger
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        let stakes: Vec<_> = blockchain.economics.stakes_of(&sender.wallet_id).collect();
+        assert_eq!(stakes, vec![(&staked, Decimal::new(3, 1))]);
+        sender.sync_with_blockchain(&blockchain).unwrap();
+        let stats = sender.get_stats();
+        assert_eq!(stats.staked_balance, Decimal::new(3, 1));
+        assert_eq!(stats.staked_triangles, 1);
+        assert_eq!(sender.owned_triangles[&staked].staked_amount, Decimal::new(3, 1));
+        assert!(!sender.owned_triangles[&triangle].is_staked);
+        assert_eq!(stats.pending_outgoing, Decimal::ZERO);
+        assert_eq!(stats.total_balance, Decimal::new(65, 2));
+        assert_eq!(stats.available_balance, Decimal::new(65, 2));
+
+        let transaction = sender
+            .create_transfer_transaction(&recipient.wallet_id, triangle.clone(), Decimal::new(5, 2))
+            .unwrap();
+        blockchain.add_transaction(transaction).unwrap();
+        sender.sync_with_blockchain(&blockchain).unwrap();
+        let stats = sender.get_stats();
+        assert_eq!(stats.pending_outgoing, Decimal::new(5, 2));
+        assert_eq!(stats.pending_transaction_count, 1);
+        assert_eq!(stats.available_balance, Decimal::new(6, 1));
+
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        sender.sync_with_blockchain(&blockchain).unwrap();
+        let stats = sender.get_stats();
+        assert_eq!(stats.pending_outgoing, Decimal::ZERO);
+        assert_eq!(stats.total_balance, Decimal::new(6, 1));
+        assert_eq!(stats.available_balance, Decimal::new(6, 1));
+        assert_eq!(stats.staked_balance, Decimal::new(3, 1));
+    }
+
+    #[test]
+    fn test_history_confirms_transfer_after_mining() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut sender = TriadChainWallet::from_seed(&[32; 32]);
+        let mut recipient = TriadChainWallet::from_seed(&[33; 32]);
+        let triangle = TriangleAddress::from_string_representation("0.1").unwrap();
+        blockchain.triangle_owners.insert(triangle.clone(), sender.wallet_id.clone());
+        blockchain.balances.insert(sender.wallet_id.clone(), Decimal::ONE);
+        sender.sync_with_blockchain(&blockchain).unwrap();
+
+        let transaction = sender
+            .create_transfer_transaction(&recipient.wallet_id, triangle.clone(), Decimal::new(5, 2))
+            .unwrap();
+        let record = &sender.transaction_history[0];
+        assert_eq!(record.tx_id, transaction.id);
+        assert_eq!(record.direction, TxDirection::Outgoing);
+        assert_eq!(record.counterparty.as_deref(), Some(recipient.wallet_id.as_str()));
+        assert_eq!(record.amount_or_triangle, TxSubject::Triangle(triangle));
+        assert_eq!(record.status, TxStatus::Pending);
+
+        blockchain.add_transaction(transaction).unwrap();
+        sender.sync_with_blockchain(&blockchain).unwrap();
+        assert_eq!(sender.transaction_history[0].status, TxStatus::Pending);
+        assert_eq!(sender.get_stats().transaction_count, 0);
+        assert_eq!(sender.get_stats().pending_transaction_count, 1);
+
+        let block = blockchain.mine_block("miner".to_string(), 10).unwrap();
+        sender.sync_with_blockchain(&blockchain).unwrap();
+        assert_eq!(sender.transaction_history[0].status, TxStatus::Confirmed(block.height));
+        assert_eq!(sender.get_stats().transaction_count, 1);
+        assert_eq!(sender.get_stats().pending_transaction_count, 0);
+
+        // The recipient learns of the transfer from the chain
+        recipient.sync_with_blockchain(&blockchain).unwrap();
+        assert_eq!(recipient.transaction_history.len(), 1);
+        assert_eq!(recipient.transaction_history[0].direction, TxDirection::Incoming);
+        assert_eq!(recipient.transaction_history[0].counterparty.as_deref(), Some(sender.wallet_id.as_str()));
+        assert_eq!(recipient.transaction_history[0].status, TxStatus::Confirmed(block.height));
+
+        // A wallet syncing only after the body was pruned is told its
+        // history may be missing it
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        blockchain.set_mode(crate::core::blockchain::ChainMode::HeaderOnly { keep_recent: 1 });
+        let mut late = TriadChainWallet::from_seed(&[33; 32]);
+        late.sync_with_blockchain(&blockchain).unwrap();
+        assert!(late.transaction_history.is_empty());
+        assert!(late.history_pruned_below > block.height);
+    }
+
+    #[test]
+    fn test_history_marks_rejected_transaction_failed() {
+        let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+        let mut wallet = TriadChainWallet::from_seed(&[34; 32]);
+
+        // Nothing has accrued, so the node turns the claim away
+        let claim = wallet.create_claim_transaction(Decimal::ONE, Decimal::new(1, 2)).unwrap();
+        assert!(blockchain.add_transaction(claim).is_err());
+        assert_eq!(wallet.get_stats().pending_transaction_count, 1);
+
+        wallet.sync_with_blockchain(&blockchain).unwrap();
+        blockchain.mine_block("miner".to_string(), 10).unwrap();
+        wallet.sync_with_blockchain(&blockchain).unwrap();
+
+        assert!(matches!(wallet.transaction_history[0].status, TxStatus::Failed(_)));
+        assert_eq!(wallet.transaction_history[0].amount_or_triangle, TxSubject::Amount(Decimal::ONE));
+        let stats = wallet.get_stats();
+        assert_eq!((stats.transaction_count, stats.pending_transaction_count), (0, 0));
+    }
+
+    #[test]
+    fn test_child_derivation_is_deterministic() {
+        // Two independent restores of the same root key
+        let first = TriadChainWallet::from_seed(&[7; 32]);
+        let second = TriadChainWallet::from_seed(&[7; 32]);
+
+        let child = first.derive_child_wallet(3).unwrap();
+        assert_eq!(child.wallet_id, second.derive_child_wallet(3).unwrap().wallet_id);
+        assert_eq!(child.derivation_path(), "m/3'");
+        assert_eq!(child.derive_child_wallet(1).unwrap().derivation_path(), "m/3'/1'");
+
+        assert_ne!(child.wallet_id, first.derive_child_wallet(4).unwrap().wallet_id);
+        assert_ne!(child.wallet_id, first.wallet_id);
+        assert!(first.derive_child_wallet(HARDENED_OFFSET).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_balance_sums_children() {
+        let mut wallet = TriadChainWallet::from_seed(&[9; 32]);
+        let mut blockchain = TriadChainBlockchain::new().unwrap();
+        blockchain.balances.insert(wallet.wallet_id.clone(), Decimal::ONE);
+        blockchain.balances.insert(wallet.derive_child_wallet(0).unwrap().wallet_id, Decimal::new(2, 0));
+        blockchain.balances.insert(wallet.derive_child_wallet(5).unwrap().wallet_id, Decimal::new(3, 0));
+        // Past the gap limit after index 5, so never found
+        let hidden = wallet.derive_child_wallet(6 + HD_GAP_LIMIT).unwrap();
+        blockchain.balances.insert(hidden.wallet_id, Decimal::new(100, 0));
+
+        wallet.sync_with_blockchain(&blockchain).unwrap();
+
+        let indexes: Vec<u32> = wallet.derived_accounts.iter().map(|account| account.index).collect();
+        assert_eq!(indexes, vec![0, 5]);
+        assert_eq!(wallet.get_stats().total_balance, Decimal::ONE);
+        assert_eq!(wallet.get_stats_aggregate().total_balance, Decimal::new(6, 0));
+    }
+
+    #[test]
+    fn test_wallet_from_seed_is_reproducible() {
+        let first = TriadChainWallet::from_seed(&[42; 32]);
+        let second = TriadChainWallet::from_seed(&[42; 32]);
+        assert_eq!(first.wallet_id, second.wallet_id);
+        assert_eq!(first.public_key, second.public_key);
+        assert!(TriadChainWallet::validate_wallet_address(&first.wallet_id).is_ok());
+
+        let other = TriadChainWallet::from_seed(&[43; 32]);
+        assert_ne!(first.wallet_id, other.wallet_id);
+    }
+
+    #[test]
+    fn test_signed_message_verification() {
+        let wallet = TriadChainWallet::from_seed(&[9; 32]);
+        let signed = wallet.sign_message(b"login nonce 1234").unwrap();
+        assert!(signed.verify(&wallet.wallet_id));
+
+        let mut tampered = signed.clone();
+        tampered.message = b"login nonce 1235".to_vec();
+        assert!(!tampered.verify(&wallet.wallet_id));
+
+        let other = TriadChainWallet::from_seed(&[10; 32]);
+        assert!(!signed.verify(&other.wallet_id));
+
+        // Swapping in another key changes the wallet id it proves
+        let mut rekeyed = signed.clone();
+        rekeyed.public_key = other.public_key;
+        assert!(!rekeyed.verify(&wallet.wallet_id));
+        assert!(!rekeyed.verify(&other.wallet_id));
+    }
+
+    #[test]
+    fn test_signed_message_cannot_replay_as_transaction() {
+        let mut wallet = TriadChainWallet::from_seed(&[11; 32]);
+        let mut transaction = TriangleTransaction::new(
+            None,
+            TriangleAddress::genesis(),
+            TriangleOperation::Create,
+            None,
+            Decimal::new(1, 2),
+        );
+        transaction.sender_public_key = Some(wallet.public_key);
+
+        // A message crafted to equal the transaction's signing text still
+        // signs different bytes
+        let message = TriadChainWallet::signing_message(&transaction);
+        let signed = wallet.sign_message(message.as_bytes()).unwrap();
+        transaction.signature = signed.signature.clone();
+        assert!(!TriadChainWallet::verify_transaction_signature(&transaction, &wallet.public_key));
+
+        // Nor does a transaction signature pass as a signed message
+        wallet.sign_transaction(&mut transaction).unwrap();
+        let forged = SignedMessage {
+            message: message.into_bytes(),
+            signature: transaction.signature.clone(),
+            public_key: wallet.public_key,
+        };
+        assert!(!forged.verify(&wallet.wallet_id));
+    }
+
+    #[test]
+    fn test_address_checksum_catches_typos() {
+        let address = TriadChainWallet::from_seed(&[7; 32]).wallet_id;
+        assert!(TriadChainWallet::validate_address(&address));
+
+        for position in 2..address.len() {
+            let mut typo: Vec<char> = address.chars().collect();
+            typo[position] = if typo[position] == '0' { '1' } else { '0' };
+            let typo: String = typo.into_iter().collect();
+            assert!(!TriadChainWallet::validate_address(&typo), "{} passed", typo);
+        }
+
+        assert!(!TriadChainWallet::validate_address(&address[..address.len() - 1]));
+        assert!(!TriadChainWallet::validate_address(&address.replacen("ST", "SX", 1)));
+        assert!(TriadChainWallet::validate_wallet_address("STnot-an-address").is_err());
+    }
+}
@@ -7,6 +7,12 @@
 pub mod core;
 pub mod visualization;
 
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 // Re-export commonly used types
 pub use core::{
     errors::SierpinskiError,
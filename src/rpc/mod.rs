@@ -0,0 +1,6 @@
+//! HTTP API for querying the chain and submitting transactions
+
+pub mod server;
+
+// Re-export commonly used items
+pub use server::{RpcError, RpcHandle, RpcServer};
@@ -0,0 +1,407 @@
+//! REST server exposing blockchain queries over HTTP with JSON bodies
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::{
+    extract::{rejection::JsonRejection, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::core::{
+    address::TriangleAddress,
+    block::{Block, TriangleTransaction},
+    blockchain::{BlockchainStats, SharedBlockchain},
+    fractal::FractalTriangle,
+    errors::{SierpinskiError, SierpinskiResult},
+};
+
+/// How long shutdown waits for in-flight requests to finish
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Errors returned to API clients, each mapped to an HTTP status
+#[derive(Error, Debug)]
+pub enum RpcError {
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl RpcError {
+    fn status(&self) -> StatusCode {
+        match self {
+            RpcError::NotFound(_) => StatusCode::NOT_FOUND,
+            RpcError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            RpcError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<SierpinskiError> for RpcError {
+    fn from(error: SierpinskiError) -> Self {
+        match error {
+            SierpinskiError::ValidationError { .. }
+            | SierpinskiError::InvalidAddress { .. }
+            | SierpinskiError::AddressComponentOutOfRange { .. }
+            | SierpinskiError::ChecksumMismatch { .. }
+            | SierpinskiError::InvalidTriangle { .. }
+            | SierpinskiError::CollinearPoints
+            | SierpinskiError::InvalidArea
+            | SierpinskiError::StateTransitionError { .. }
+            | SierpinskiError::SizeLimitExceeded { .. } => RpcError::BadRequest(error.to_string()),
+            SierpinskiError::PrunedDataUnavailable { .. } => RpcError::NotFound(error.to_string()),
+            _ => RpcError::Internal(error.to_string()),
+        }
+    }
+}
+
+impl From<JsonRejection> for RpcError {
+    fn from(rejection: JsonRejection) -> Self {
+        RpcError::BadRequest(rejection.body_text())
+    }
+}
+
+impl IntoResponse for RpcError {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse { error: self.to_string() };
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+type RpcResult<T> = Result<Json<T>, RpcError>;
+
+/// Error body returned with every non-2xx response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// Balance of a wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceResponse {
+    pub wallet: String,
+    pub balance: Decimal,
+}
+
+/// A triangle together with its current owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriangleResponse {
+    pub triangle: FractalTriangle,
+    pub owner: Option<String>,
+}
+
+/// Triangles owned by a wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnedTrianglesResponse {
+    pub wallet: String,
+    pub triangles: Vec<TriangleAddress>,
+}
+
+/// Outcome of submitting a transaction to the mempool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitTransactionResponse {
+    pub transaction_id: Uuid,
+    pub mempool_size: usize,
+}
+
+/// HTTP API over a shared blockchain
+pub struct RpcServer {
+    blockchain: SharedBlockchain,
+}
+
+/// Handle to a running RPC server, used to stop it
+pub struct RpcHandle {
+    /// Address the listener actually bound to
+    pub local_addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<std::io::Result<()>>,
+}
+
+impl RpcHandle {
+    /// Stop accepting requests and wait for in-flight ones to finish
+    pub async fn shutdown(self) -> SierpinskiResult<()> {
+        // The server may already have exited on its own
+        let _ = self.shutdown.send(());
+
+        match tokio::time::timeout(SHUTDOWN_TIMEOUT, self.task).await {
+            Ok(Ok(Ok(()))) => Ok(()),
+            Ok(Ok(Err(e))) => Err(SierpinskiError::validation(format!("RPC server failed: {}", e))),
+            Ok(Err(e)) => Err(SierpinskiError::validation(format!("RPC server task panicked: {}", e))),
+            Err(_) => Err(SierpinskiError::validation("Timed out waiting for RPC server to stop")),
+        }
+    }
+}
+
+impl RpcServer {
+    /// Create a server over the chain the rest of the node shares
+    pub fn new(blockchain: SharedBlockchain) -> Self {
+        RpcServer { blockchain }
+    }
+
+    /// Build the router with every endpoint
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/blocks/{height}", get(get_block_by_height))
+            .route("/blocks/hash/{hash}", get(get_block_by_hash))
+            .route("/balances/{wallet}", get(get_balance))
+            .route("/triangles/{address}", get(get_triangle))
+            .route("/wallets/{wallet}/triangles", get(get_owned_triangles))
+            .route("/transactions", post(submit_transaction))
+            .route("/stats", get(get_stats))
+            .with_state(self.blockchain.clone())
+    }
+
+    /// Start serving on the given address
+    pub async fn start(&self, address: SocketAddr) -> SierpinskiResult<RpcHandle> {
+        let listener = TcpListener::bind(address).await
+            .map_err(|e| SierpinskiError::validation(format!("Failed to bind to address: {}", e)))?;
+        let local_addr = listener.local_addr()
+            .map_err(|e| SierpinskiError::validation(format!("Failed to read bound address: {}", e)))?;
+
+        tracing::info!(%local_addr, "RPC server listening");
+
+        let (shutdown, stop) = oneshot::channel();
+        let server = axum::serve(listener, self.router())
+            .with_graceful_shutdown(async move {
+                let _ = stop.await;
+            });
+        let task = tokio::spawn(async move { server.await });
+
+        Ok(RpcHandle { local_addr, shutdown, task })
+    }
+}
+
+async fn get_block_by_height(
+    State(blockchain): State<SharedBlockchain>,
+    Path(height): Path<u64>,
+) -> RpcResult<Block> {
+    blockchain.read(|chain| {
+        if chain.is_pruned(height) {
+            return Err(SierpinskiError::PrunedDataUnavailable { height }.into());
+        }
+        chain.get_block_by_height(height)
+            .cloned()
+            .map(Json)
+            .ok_or_else(|| RpcError::NotFound(format!("No block at height {}", height)))
+    })
+}
+
+async fn get_block_by_hash(
+    State(blockchain): State<SharedBlockchain>,
+    Path(hash): Path<String>,
+) -> RpcResult<Block> {
+    blockchain.read(|chain| {
+        let block = chain.get_block_by_hash(&hash)
+            .ok_or_else(|| RpcError::NotFound(format!("No block with hash {}", hash)))?;
+        if chain.is_pruned(block.height) {
+            return Err(SierpinskiError::PrunedDataUnavailable { height: block.height }.into());
+        }
+        Ok(Json(block.clone()))
+    })
+}
+
+async fn get_balance(
+    State(blockchain): State<SharedBlockchain>,
+    Path(wallet): Path<String>,
+) -> RpcResult<BalanceResponse> {
+    let balance = blockchain.read(|chain| chain.get_balance(&wallet));
+    Ok(Json(BalanceResponse { wallet, balance }))
+}
+
+async fn get_triangle(
+    State(blockchain): State<SharedBlockchain>,
+    Path(address): Path<String>,
+) -> RpcResult<TriangleResponse> {
+    let address = TriangleAddress::from_string_representation(&address)?;
+    blockchain.read(|chain| {
+        let triangle = chain.get_triangle(&address)
+            .cloned()
+            .ok_or_else(|| RpcError::NotFound(format!("No triangle at address {}", address)))?;
+        let owner = chain.triangle_owners.get(&address).cloned();
+
+        Ok(Json(TriangleResponse { triangle, owner }))
+    })
+}
+
+async fn get_owned_triangles(
+    State(blockchain): State<SharedBlockchain>,
+    Path(wallet): Path<String>,
+) -> RpcResult<OwnedTrianglesResponse> {
+    let triangles = blockchain.read(|chain| chain.get_owned_triangles(&wallet));
+    Ok(Json(OwnedTrianglesResponse { wallet, triangles }))
+}
+
+async fn submit_transaction(
+    State(blockchain): State<SharedBlockchain>,
+    payload: Result<Json<TriangleTransaction>, JsonRejection>,
+) -> RpcResult<SubmitTransactionResponse> {
+    let Json(transaction) = payload?;
+    let transaction_id = transaction.id;
+
+    blockchain.with(|chain| {
+        chain.add_transaction(transaction)?;
+
+        Ok(Json(SubmitTransactionResponse {
+            transaction_id,
+            mempool_size: chain.mempool.len(),
+        }))
+    })
+}
+
+async fn get_stats(State(blockchain): State<SharedBlockchain>) -> RpcResult<BlockchainStats> {
+    Ok(Json(blockchain.read(|chain| chain.stats())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use crate::core::block::TriangleOperation;
+    use crate::core::blockchain::TriadChainBlockchain;
+
+    /// Send one HTTP/1.1 request and return the status code and JSON body
+    async fn request(addr: SocketAddr, method: &str, path: &str, body: Option<String>) -> (u16, serde_json::Value) {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let body = body.unwrap_or_default();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method, path, addr, body.len(), body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    async fn start_server() -> (SharedBlockchain, RpcHandle) {
+        let blockchain = SharedBlockchain::new(TriadChainBlockchain::new().unwrap());
+        let handle = RpcServer::new(blockchain.clone())
+            .start("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        (blockchain, handle)
+    }
+
+    #[tokio::test]
+    async fn test_query_endpoints() {
+        let (blockchain, handle) = start_server().await;
+        let addr = handle.local_addr;
+        let genesis_hash = blockchain.read(|chain| chain.blocks[0].hash());
+
+        let (status, block) = request(addr, "GET", "/blocks/0", None).await;
+        assert_eq!(status, 200);
+        assert_eq!(block["height"], 0);
+
+        let (status, block) = request(addr, "GET", &format!("/blocks/hash/{}", genesis_hash), None).await;
+        assert_eq!(status, 200);
+        assert_eq!(block["height"], 0);
+
+        let (status, error) = request(addr, "GET", "/blocks/99", None).await;
+        assert_eq!(status, 404);
+        assert!(error["error"].as_str().unwrap().contains("99"));
+
+        let (status, balance) = request(addr, "GET", "/balances/genesis_miner", None).await;
+        assert_eq!(status, 200);
+        assert_eq!(balance["balance"], "1000000");
+
+        let (status, triangle) = request(addr, "GET", "/triangles/genesis", None).await;
+        assert_eq!(status, 200);
+        assert_eq!(triangle["owner"], "genesis_miner");
+
+        let (status, _) = request(addr, "GET", "/triangles/9.9", None).await;
+        assert_eq!(status, 400);
+
+        // A mistyped checked address fails its checksum
+        let checked = TriangleAddress::new(vec![0, 1]).unwrap().to_checked_string();
+        let typo = checked.replacen("01", "02", 1);
+        let (status, error) = request(addr, "GET", &format!("/triangles/{}", typo), None).await;
+        assert_eq!(status, 400);
+        assert!(error["error"].as_str().unwrap().contains(&typo), "{}", error);
+
+        let (status, owned) = request(addr, "GET", "/wallets/genesis_miner/triangles", None).await;
+        assert_eq!(status, 200);
+        assert_eq!(owned["triangles"].as_array().unwrap().len(), 1);
+
+        let (status, stats) = request(addr, "GET", "/stats", None).await;
+        assert_eq!(status, 200);
+        assert_eq!(stats["total_blocks"], 1);
+
+        // A pruned body is reported missing however the block is looked up
+        {
+            let mut chain = blockchain.lock();
+            chain.target = crate::core::target::Target::from_legacy_difficulty(1);
+            chain.mine_block("miner".to_string(), 10).unwrap();
+            chain.set_mode(crate::core::blockchain::ChainMode::HeaderOnly { keep_recent: 1 });
+        }
+        let (status, error) = request(addr, "GET", &format!("/blocks/hash/{}", genesis_hash), None).await;
+        assert_eq!(status, 404);
+        assert!(error["error"].as_str().unwrap().contains("pruned"), "{}", error);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_submit_transaction() {
+        let (blockchain, handle) = start_server().await;
+        let addr = handle.local_addr;
+
+        let transaction = TriangleTransaction::new(
+            None,
+            TriangleAddress::new(vec![3]).unwrap(),
+            TriangleOperation::Create,
+            Some(crate::core::genesis::genesis_triangle().unwrap()),
+            Decimal::new(2, 2),
+        );
+        let body = serde_json::to_string(&transaction).unwrap();
+
+        let (status, submitted) = request(addr, "POST", "/transactions", Some(body)).await;
+        assert_eq!(status, 200);
+        assert_eq!(submitted["transaction_id"], transaction.id.to_string());
+        assert_eq!(blockchain.read(|chain| chain.mempool.len()), 1);
+
+        // Create without triangle data fails validation
+        let invalid = TriangleTransaction::new(
+            None,
+            TriangleAddress::genesis(),
+            TriangleOperation::Create,
+            None,
+            Decimal::new(1, 2),
+        );
+        let body = serde_json::to_string(&invalid).unwrap();
+        let (status, error) = request(addr, "POST", "/transactions", Some(body)).await;
+        assert_eq!(status, 400);
+        assert!(error["error"].as_str().unwrap().contains("triangle data"));
+
+        let mut oversized = transaction.clone();
+        oversized.signature = vec![0; crate::core::block::MAX_TRANSACTION_BYTES];
+        let body = serde_json::to_string(&oversized).unwrap();
+        let (status, error) = request(addr, "POST", "/transactions", Some(body)).await;
+        assert_eq!(status, 400);
+        assert!(error["error"].as_str().unwrap().contains("byte limit"), "{}", error);
+
+        let (status, _) = request(addr, "POST", "/transactions", Some("not json".to_string())).await;
+        assert_eq!(status, 400);
+        assert_eq!(blockchain.read(|chain| chain.mempool.len()), 1);
+
+        handle.shutdown().await.unwrap();
+    }
+}
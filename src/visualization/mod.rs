@@ -1,6 +1,9 @@
 //! Visualization utilities for Sierpinski triangles
 
 pub mod renderer;
+pub mod terminal;
+mod transform;
 
 // Re-export commonly used items
 pub use renderer::{render_fractal_svg, RenderOptions};
+pub use terminal::{render_ascii, TerminalOptions};
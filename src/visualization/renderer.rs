@@ -1,17 +1,21 @@
 //! SVG rendering for Sierpinski triangle fractals
 
-use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use std::fmt::Write;
 
 use crate::core::{
+    address::TriangleAddress,
     fractal::FractalStructure,
-    geometry::Point,
     state::TriangleState,
-    errors::SierpinskiResult,
+    errors::{SierpinskiError, SierpinskiResult},
 };
+use super::transform::{calculate_bounds, calculate_scale, to_render_f64, transform_point, Scale};
 
 /// Rendering options for SVG output
-#[derive(Debug, Clone)]
+///
+/// Stored presets may leave out any field to keep its default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RenderOptions {
     pub width: u32,
     pub height: u32,
@@ -19,6 +23,16 @@ pub struct RenderOptions {
     pub show_void_triangles: bool,
     pub stroke_width: f64,
     pub colors: ColorScheme,
+    /// Triangles at or above this depth are always labelled
+    pub label_max_depth: Option<u8>,
+    /// Deeper triangles are labelled only when at least this many pixels wide
+    pub label_min_pixel_size: f64,
+    /// Labels keep at most this many trailing address components
+    pub label_max_components: usize,
+    /// Draw a legend of the state colors in a column on the right
+    pub show_legend: bool,
+    /// Title drawn in a band across the top
+    pub title: Option<String>,
 }
 
 impl Default for RenderOptions {
@@ -30,12 +44,32 @@ impl Default for RenderOptions {
             show_void_triangles: true,
             stroke_width: 1.0,
             colors: ColorScheme::default(),
+            label_max_depth: None,
+            label_min_pixel_size: 40.0,
+            label_max_components: 3,
+            show_legend: false,
+            title: None,
         }
     }
 }
 
+impl RenderOptions {
+    /// Encode as a pretty-printed JSON preset
+    pub fn to_json(&self) -> SierpinskiResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to encode render options: {}", e)))
+    }
+
+    /// Decode a preset written by `to_json`
+    pub fn from_json(json: &str) -> SierpinskiResult<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| SierpinskiError::validation(format!("Failed to decode render options: {}", e)))
+    }
+}
+
 /// Color scheme for rendering
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ColorScheme {
     pub genesis: String,
     pub active: String,
@@ -84,9 +118,14 @@ pub fn render_fractal_svg_with_options(
 ) -> SierpinskiResult<String> {
     let mut svg = String::new();
     
-    // Calculate bounds
+    // Calculate bounds, keeping the fractal clear of the title and legend
+    let (title_height, legend_width) = reserved_margins(options);
+    if title_height >= options.height || legend_width >= options.width {
+        return Err(SierpinskiError::validation("Canvas too small for the title and legend"));
+    }
     let bounds = calculate_bounds(structure)?;
-    let scale = calculate_scale(&bounds, options.width, options.height);
+    let mut scale = calculate_scale(&bounds, options.width - legend_width, options.height - title_height)?;
+    scale.canvas_height = options.height as f64;
     
     // SVG header
     writeln!(
@@ -106,17 +145,23 @@ pub fn render_fractal_svg_with_options(
     write_styles(&mut svg, options)?;
     
     // Render triangles by depth (background to foreground)
-    for depth in (0..=structure.max_depth()).rev() {
-        let triangles = structure.triangles_at_depth(depth);
-        
-        for triangle in triangles {
-            render_triangle(&mut svg, triangle, &bounds, scale, options)?;
-        }
-    }
+    render_triangle_paths(&mut svg, structure, scale, options)?;
     
     // Render addresses if requested
     if options.show_addresses {
-        render_addresses(&mut svg, structure, &bounds, scale, options)?;
+        render_addresses(&mut svg, structure, scale, options)?;
+    }
+
+    if let Some(title) = &options.title {
+        writeln!(
+            &mut svg,
+            r#"<text class="title" x="{:.2}" y="{:.2}" font-family="monospace" font-size="18" fill="{}" text-anchor="middle" dominant-baseline="middle">{}</text>"#,
+            (options.width - legend_width) as f64 / 2.0, title_height as f64 / 2.0,
+            options.colors.text, escape_xml(title)
+        ).unwrap();
+    }
+    if options.show_legend {
+        render_legend(&mut svg, structure, options, options.width - legend_width, title_height);
     }
     
     // SVG footer
@@ -125,108 +170,145 @@ pub fn render_fractal_svg_with_options(
     Ok(svg)
 }
 
-/// Calculate the bounding box of all triangles
-fn calculate_bounds(structure: &FractalStructure) -> SierpinskiResult<Bounds> {
-    let mut min_x = Decimal::MAX;
-    let mut max_x = Decimal::MIN;
-    let mut min_y = Decimal::MAX;
-    let mut max_y = Decimal::MIN;
-    
-    for depth in 0..=structure.max_depth() {
-        let triangles = structure.triangles_at_depth(depth);
-        
-        for triangle in triangles {
-            for vertex in triangle.triangle.vertices() {
-                if vertex.x < min_x { min_x = vertex.x; }
-                if vertex.x > max_x { max_x = vertex.x; }
-                if vertex.y < min_y { min_y = vertex.y; }
-                if vertex.y > max_y { max_y = vertex.y; }
-            }
-        }
+/// CSS classes for each rendered triangle state, in drawing order within a depth
+const STYLE_CLASSES: [&str; 4] = [
+    "triangle-genesis",
+    "triangle-subdivided",
+    "triangle-active",
+    "triangle-void",
+];
+
+/// Index into `STYLE_CLASSES` for a triangle state, or None if it is hidden
+fn style_slot(state: TriangleState, options: &RenderOptions) -> Option<usize> {
+    match state {
+        TriangleState::Genesis => Some(0),
+        TriangleState::Subdivided => Some(1),
+        TriangleState::Active | TriangleState::Inactive => Some(2),
+        TriangleState::Void => options.show_void_triangles.then_some(3),
     }
-    
-    // Add padding
-    let padding = (max_x - min_x) * Decimal::new(1, 1); // 10% padding
-    
-    Ok(Bounds {
-        min_x: min_x - padding,
-        max_x: max_x + padding,
-        min_y: min_y - padding,
-        max_y: max_y + padding,
-    })
 }
 
-/// Calculate scale factor for coordinate transformation
-fn calculate_scale(bounds: &Bounds, width: u32, height: u32) -> Scale {
-    let bounds_width = bounds.max_x - bounds.min_x;
-    let bounds_height = bounds.max_y - bounds.min_y;
-    
-    let scale_x = Decimal::try_from(width as f64).unwrap() / bounds_width;
-    let scale_y = Decimal::try_from(height as f64).unwrap() / bounds_height;
-    
-    // Use the smaller scale to maintain aspect ratio
-    let scale = if scale_x < scale_y { scale_x } else { scale_y };
-    
-    Scale {
-        factor: scale,
-        offset_x: bounds.min_x,
-        offset_y: bounds.min_y,
-        canvas_width: width,
-        canvas_height: height,
+/// Legend labels for `STYLE_CLASSES`
+const LEGEND_LABELS: [&str; 4] = ["Genesis", "Subdivided", "Active", "Void"];
+
+/// Height of the title band
+const TITLE_HEIGHT: u32 = 40;
+
+/// Width of the legend column
+const LEGEND_WIDTH: u32 = 140;
+
+/// Height of one legend row
+const LEGEND_ROW_HEIGHT: u32 = 22;
+
+/// Canvas kept free of the fractal for the title along the top and the
+/// legend along the right, as (height, width)
+fn reserved_margins(options: &RenderOptions) -> (u32, u32) {
+    let title_height = if options.title.is_some() { TITLE_HEIGHT } else { 0 };
+    let legend_width = if options.show_legend { LEGEND_WIDTH } else { 0 };
+    (title_height, legend_width)
+}
+
+/// Render a legend group with a swatch and label for each style drawn,
+/// starting at canvas column `x` below the title band at `top`
+fn render_legend(svg: &mut String, structure: &FractalStructure, options: &RenderOptions, x: u32, top: u32) {
+    let mut drawn = [false; 4];
+    for triangle in structure.triangles() {
+        if let Some(slot) = style_slot(triangle.state, options) {
+            drawn[slot] = true;
+        }
+    }
+
+    let entries: Vec<usize> = (0..STYLE_CLASSES.len()).filter(|slot| drawn[*slot]).collect();
+    let padding = 10;
+    writeln!(
+        svg,
+        r#"<g class="legend" transform="translate({},{})">"#,
+        x + padding, top + padding
+    ).unwrap();
+    writeln!(
+        svg,
+        r#"<rect width="{}" height="{}" fill="{}" stroke="{}"/>"#,
+        LEGEND_WIDTH - 2 * padding,
+        entries.len() as u32 * LEGEND_ROW_HEIGHT + padding,
+        options.colors.background, options.colors.stroke
+    ).unwrap();
+    for (row, slot) in entries.into_iter().enumerate() {
+        let y = padding + row as u32 * LEGEND_ROW_HEIGHT;
+        writeln!(
+            svg,
+            r#"<rect class="{} triangle-stroke" x="{}" y="{}" width="14" height="14"/>"#,
+            STYLE_CLASSES[slot], padding, y
+        ).unwrap();
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="monospace" font-size="12" fill="{}" dominant-baseline="middle">{}</text>"#,
+            padding + 22, y + 7, options.colors.text, LEGEND_LABELS[slot]
+        ).unwrap();
     }
+    writeln!(svg, "</g>").unwrap();
 }
 
-/// Transform a point from world coordinates to SVG coordinates
-fn transform_point(point: &Point, _bounds: &Bounds, scale: &Scale) -> (f64, f64) {
-    let x = ((point.x - scale.offset_x) * scale.factor).to_string().parse::<f64>().unwrap_or(0.0);
-    let y = (scale.canvas_height as f64) - ((point.y - scale.offset_y) * scale.factor).to_string().parse::<f64>().unwrap_or(0.0);
-    (x, y)
+/// Escape text for use in SVG markup
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
-/// Render a single triangle
-fn render_triangle(
+/// Render all triangles as one `<path>` per (depth, state) group
+///
+/// Each triangle becomes an `M ... Z` subpath; fill, stroke and opacity come
+/// from the CSS classes in `<defs>`, which keeps large fractals compact.
+fn render_triangle_paths(
     svg: &mut String,
-    triangle: &crate::core::fractal::FractalTriangle,
-    bounds: &Bounds,
+    structure: &FractalStructure,
     scale: Scale,
     options: &RenderOptions,
 ) -> SierpinskiResult<()> {
-    let vertices = triangle.triangle.vertices();
-    let (x1, y1) = transform_point(&vertices[0], bounds, &scale);
-    let (x2, y2) = transform_point(&vertices[1], bounds, &scale);
-    let (x3, y3) = transform_point(&vertices[2], bounds, &scale);
-    
-    let fill_color = match triangle.state {
-        TriangleState::Genesis => &options.colors.genesis,
-        TriangleState::Active => &options.colors.active,
-        TriangleState::Subdivided => &options.colors.subdivided,
-        TriangleState::Void => {
-            if !options.show_void_triangles {
-                return Ok(());
+    let mut groups: Vec<[String; 4]> = vec![Default::default(); structure.max_depth() as usize + 1];
+
+    for triangle in structure.triangles() {
+        let Some(slot) = style_slot(triangle.state, options) else {
+            continue;
+        };
+
+        let vertices = triangle.triangle.vertices();
+        let (x1, y1) = transform_point(&vertices[0], &scale)?;
+        let (x2, y2) = transform_point(&vertices[1], &scale)?;
+        let (x3, y3) = transform_point(&vertices[2], &scale)?;
+
+        write!(
+            &mut groups[triangle.depth as usize][slot],
+            "M{:.2},{:.2}L{:.2},{:.2} {:.2},{:.2}Z",
+            x1, y1, x2, y2, x3, y3
+        ).unwrap();
+    }
+
+    for (depth, paths) in groups.iter().enumerate().rev() {
+        for (class, path) in STYLE_CLASSES.iter().zip(paths) {
+            if path.is_empty() {
+                continue;
             }
-            &options.colors.void_triangle
+
+            writeln!(
+                svg,
+                r#"<path class="{} triangle-stroke" data-depth="{}" d="{}"/>"#,
+                class, depth, path
+            ).unwrap();
         }
-        TriangleState::Inactive => &options.colors.active,
-    };
-    
-    writeln!(
-        svg,
-        r#"<polygon points="{:.2},{:.2} {:.2},{:.2} {:.2},{:.2}" fill="{}" stroke="{}" stroke-width="{}" opacity="{}"/>"#,
-        x1, y1, x2, y2, x3, y3,
-        fill_color,
-        options.colors.stroke,
-        options.stroke_width,
-        if triangle.state == TriangleState::Void { 0.3 } else { 0.8 }
-    ).unwrap();
-    
+    }
+
     Ok(())
 }
 
 /// Render triangle addresses
+///
+/// Only triangles within `label_max_depth` or at least
+/// `label_min_pixel_size` wide get a label, so deep renders stay legible.
 fn render_addresses(
     svg: &mut String,
     structure: &FractalStructure,
-    bounds: &Bounds,
     scale: Scale,
     options: &RenderOptions,
 ) -> SierpinskiResult<()> {
@@ -238,16 +320,23 @@ fn render_addresses(
             if triangle.state == TriangleState::Void {
                 continue;
             }
+
+            let within_depth = options.label_max_depth.is_some_and(|max| depth <= max);
+            let pixel_size = to_render_f64(triangle.triangle.bounding_box().width())? * scale.factor;
+            if !within_depth && pixel_size < options.label_min_pixel_size {
+                continue;
+            }
             
             let centroid = triangle.triangle.centroid();
-            let (x, y) = transform_point(&centroid, bounds, &scale);
+            let (x, y) = transform_point(&centroid, &scale)?;
             
             let font_size = (12.0 - (depth as f64 * 1.5)).max(6.0);
             
             writeln!(
                 svg,
                 r#"<text x="{:.2}" y="{:.2}" font-family="monospace" font-size="{}" fill="{}" text-anchor="middle" dominant-baseline="middle">{}</text>"#,
-                x, y, font_size, options.colors.text, triangle.address
+                x, y, font_size, options.colors.text,
+                short_label(&triangle.address, options.label_max_components)
             ).unwrap();
         }
     }
@@ -255,13 +344,28 @@ fn render_addresses(
     Ok(())
 }
 
+/// An address label cut to its last `max_components` components, with an
+/// ellipsis marking the cut, e.g. `…2.0.1`
+fn short_label(address: &TriangleAddress, max_components: usize) -> String {
+    let components = address.components();
+    if components.len() <= max_components {
+        return address.to_string();
+    }
+
+    let tail: Vec<String> = components[components.len() - max_components..]
+        .iter()
+        .map(|component| component.to_string())
+        .collect();
+    format!("…{}", tail.join("."))
+}
+
 /// Write CSS styles to SVG
 fn write_styles(svg: &mut String, options: &RenderOptions) -> SierpinskiResult<()> {
     writeln!(svg, "<defs>").unwrap();
     writeln!(svg, "<style>").unwrap();
-    writeln!(svg, ".triangle-genesis {{ fill: {}; }}", options.colors.genesis).unwrap();
-    writeln!(svg, ".triangle-active {{ fill: {}; }}", options.colors.active).unwrap();
-    writeln!(svg, ".triangle-subdivided {{ fill: {}; }}", options.colors.subdivided).unwrap();
+    writeln!(svg, ".triangle-genesis {{ fill: {}; opacity: 0.8; }}", options.colors.genesis).unwrap();
+    writeln!(svg, ".triangle-active {{ fill: {}; opacity: 0.8; }}", options.colors.active).unwrap();
+    writeln!(svg, ".triangle-subdivided {{ fill: {}; opacity: 0.8; }}", options.colors.subdivided).unwrap();
     writeln!(svg, ".triangle-void {{ fill: {}; opacity: 0.3; }}", options.colors.void_triangle).unwrap();
     writeln!(svg, ".triangle-stroke {{ stroke: {}; stroke-width: {}; }}", options.colors.stroke, options.stroke_width).unwrap();
     writeln!(svg, "</style>").unwrap();
@@ -269,26 +373,6 @@ fn write_styles(svg: &mut String, options: &RenderOptions) -> SierpinskiResult<(
     Ok(())
 }
 
-/// Coordinate bounds
-#[derive(Debug, Clone)]
-struct Bounds {
-    min_x: Decimal,
-    max_x: Decimal,
-    min_y: Decimal,
-    max_y: Decimal,
-}
-
-/// Scaling information
-#[derive(Debug, Clone, Copy)]
-struct Scale {
-    factor: Decimal,
-    offset_x: Decimal,
-    offset_y: Decimal,
-    #[allow(dead_code)]
-    canvas_width: u32,
-    canvas_height: u32,
-}
-
 /// Generate a simple fractal visualization for testing
 pub fn generate_test_svg() -> String {
     use crate::core::{
@@ -319,7 +403,8 @@ mod tests {
         
         assert!(svg.contains("<svg"));
         assert!(svg.contains("</svg>"));
-        assert!(svg.contains("polygon"));
+        assert!(svg.contains("<path"));
+        assert!(!svg.contains("<polygon"));
     }
 
     #[test]
@@ -329,8 +414,8 @@ mod tests {
         
         let bounds = calculate_bounds(&structure).unwrap();
         
-        assert!(bounds.max_x > bounds.min_x);
-        assert!(bounds.max_y > bounds.min_y);
+        assert!(bounds.max.x > bounds.min.x);
+        assert!(bounds.max.y > bounds.min.y);
     }
 
     #[test]
@@ -343,4 +428,156 @@ mod tests {
         assert!(svg.contains("<text"));
         assert!(svg.contains("genesis"));
     }
+
+    #[test]
+    fn test_deep_labels_are_thinned_and_shortened() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let structure = subdivide_to_depth(genesis, 6).unwrap();
+
+        let svg = render_fractal_svg(&structure, 400, 400, true).unwrap();
+        let labels: Vec<&str> = svg
+            .lines()
+            .filter_map(|line| line.strip_suffix("</text>"))
+            .map(|line| line.rsplit('>').next().unwrap())
+            .collect();
+
+        // Only the first few depths are wide enough at 400px
+        assert!(!labels.is_empty());
+        assert!(labels.len() <= 40);
+        // Single-digit components joined by dots, or an ellipsis and the tail
+        let max_chars = RenderOptions::default().label_max_components * 2;
+        assert!(labels.iter().all(|label| *label == "genesis" || label.chars().count() <= max_chars));
+
+        // A depth cutoff labels everything above it regardless of size
+        let options = RenderOptions {
+            show_addresses: true,
+            label_max_depth: Some(5),
+            ..Default::default()
+        };
+        let svg = render_fractal_svg_with_options(&structure, &options).unwrap();
+        assert!(svg.contains(">…"));
+        assert!(svg.matches("<text").count() > labels.len());
+    }
+
+    #[test]
+    fn test_legend_and_title() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let structure = subdivide_to_depth(genesis, 2).unwrap();
+
+        let options = RenderOptions {
+            width: 600,
+            height: 400,
+            show_legend: true,
+            title: Some("Depth 2 <demo>".to_string()),
+            show_void_triangles: false,
+            ..Default::default()
+        };
+        let svg = render_fractal_svg_with_options(&structure, &options).unwrap();
+        assert!(svg.contains(r#"<g class="legend""#));
+        assert!(svg.contains(">Depth 2 &lt;demo&gt;</text>"));
+        assert!(svg.contains(">Subdivided</text>"));
+        assert!(svg.contains(">Active</text>"));
+        // Hidden voids stay out of the legend
+        assert!(!svg.contains(">Void</text>"));
+
+        // The fractal stays clear of the title band and the legend column
+        let coordinates: Vec<(f64, f64)> = svg
+            .lines()
+            .filter_map(|line| line.split(" d=\"").nth(1))
+            .flat_map(|d| d.trim_end_matches("\"/>").split(['M', 'L', ' ', 'Z']))
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (x, y) = pair.split_once(',').unwrap();
+                (x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect();
+        assert!(!coordinates.is_empty());
+        assert!(coordinates.iter().all(|&(x, y)| x <= (600 - LEGEND_WIDTH) as f64 && y >= TITLE_HEIGHT as f64));
+
+        let plain = render_fractal_svg(&structure, 400, 400, false).unwrap();
+        assert!(!plain.contains("legend"));
+    }
+
+    #[test]
+    fn test_short_label_keeps_trailing_components() {
+        let address = TriangleAddress::from_string_representation("0.1.2.0.1").unwrap();
+        assert_eq!(short_label(&address, 3), "…2.0.1");
+        assert_eq!(short_label(&address, 5), "0.1.2.0.1");
+        assert_eq!(short_label(&TriangleAddress::genesis(), 3), "genesis");
+    }
+
+    #[test]
+    fn test_render_options_json_roundtrip() {
+        let options = RenderOptions {
+            width: 1024,
+            show_addresses: true,
+            label_max_depth: Some(2),
+            colors: ColorScheme {
+                active: "#112233".to_string(),
+                background: "#000000".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let restored = RenderOptions::from_json(&options.to_json().unwrap()).unwrap();
+        assert_eq!(restored, options);
+
+        // Missing fields fall back to the defaults
+        let partial = RenderOptions::from_json(r##"{"colors": {"text": "#FF0000"}}"##).unwrap();
+        assert_eq!(partial.width, 800);
+        assert_eq!(partial.colors.text, "#FF0000");
+        assert_eq!(partial.colors.genesis, ColorScheme::default().genesis);
+    }
+
+    #[test]
+    fn test_large_fractal_is_batched_into_paths() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let structure = subdivide_to_depth(genesis, 8).unwrap();
+
+        let svg = render_fractal_svg(&structure, 800, 800, false).unwrap();
+
+        // One path per (depth, state) group, not one element per triangle
+        let path_count = svg.matches("<path").count();
+        assert!(path_count <= (structure.max_depth() as usize + 1) * STYLE_CLASSES.len());
+        assert_eq!(svg.matches('Z').count(), structure.total_triangles());
+
+        // Around 45 bytes per triangle instead of ~125 for styled polygons
+        assert!(svg.len() < structure.total_triangles() * 60);
+    }
+
+    #[test]
+    fn test_tiny_triangles_are_not_collapsed_to_origin() {
+        use crate::core::subdivision::subdivide_triangle;
+
+        // Follow the first child down to depth 15 instead of the full tree
+        let mut current = genesis_fractal_triangle().unwrap();
+        let mut structure = FractalStructure::new();
+        structure.set_genesis(current.clone()).unwrap();
+        for _ in 0..15 {
+            let result = subdivide_triangle(&current).unwrap();
+            for child in result.children.iter().chain(std::iter::once(&result.void_triangle)) {
+                structure.add_triangle(child.clone()).unwrap();
+            }
+            current = result.children[0].clone();
+        }
+        assert_eq!(structure.max_depth(), 15);
+
+        let svg = render_fractal_svg(&structure, 800, 800, false).unwrap();
+        let coordinates: Vec<(f64, f64)> = svg
+            .lines()
+            .filter_map(|line| line.split(" d=\"").nth(1))
+            .flat_map(|d| d.trim_end_matches("\"/>").split(['M', 'L', ' ', 'Z']))
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (x, y) = pair.split_once(',').unwrap();
+                (x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect();
+
+        assert!(!coordinates.is_empty());
+        // With padding around the genesis triangle nothing maps to the corner
+        assert!(coordinates.iter().all(|&(x, y)| (x, y) != (0.0, 0.0)));
+        assert!(coordinates.iter().all(|&(x, y)| x > 0.0 && y > 0.0));
+    }
 }
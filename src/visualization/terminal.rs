@@ -0,0 +1,231 @@
+//! Character-grid rendering for inspecting fractals in a terminal
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use crate::core::{
+    fractal::FractalStructure,
+    state::TriangleState,
+    errors::{SierpinskiError, SierpinskiResult},
+};
+use super::transform::{calculate_bounds, calculate_scale, transform_point};
+
+/// Rendering options for terminal output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalOptions {
+    /// Color cells with ANSI escapes instead of picking glyphs per state
+    pub color: bool,
+    pub show_void_triangles: bool,
+}
+
+impl Default for TerminalOptions {
+    fn default() -> Self {
+        TerminalOptions {
+            color: false,
+            show_void_triangles: true,
+        }
+    }
+}
+
+/// Render a fractal structure into `rows` lines of `cols` characters
+///
+/// Every character cell holds two vertically stacked pixels drawn with
+/// half-block glyphs, so the pixels are roughly square on a terminal and the
+/// triangle keeps its shape. Subdivided triangles are left to their
+/// children, so hidden voids show up as gaps.
+pub fn render_ascii(
+    structure: &FractalStructure,
+    cols: u32,
+    rows: u32,
+    options: &TerminalOptions,
+) -> SierpinskiResult<String> {
+    if cols == 0 || rows == 0 {
+        return Err(SierpinskiError::validation("Terminal grid must be at least 1x1"));
+    }
+
+    let width = cols as usize;
+    let height = rows as usize * 2;
+    let bounds = calculate_bounds(structure)?;
+    let scale = calculate_scale(&bounds, cols, rows * 2)?;
+
+    let mut triangles: Vec<_> = structure
+        .triangles()
+        .filter(|triangle| match triangle.state {
+            TriangleState::Subdivided => false,
+            TriangleState::Void => options.show_void_triangles,
+            _ => true,
+        })
+        .collect();
+    triangles.sort_by_key(|triangle| triangle.depth);
+
+    let mut pixels: Vec<Option<TriangleState>> = vec![None; width * height];
+    for triangle in triangles {
+        let vertices = triangle.triangle.vertices();
+        let corners = [
+            transform_point(&vertices[0], &scale)?,
+            transform_point(&vertices[1], &scale)?,
+            transform_point(&vertices[2], &scale)?,
+        ];
+
+        let min_x = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min).floor().max(0.0) as usize;
+        let max_x = corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max).ceil().min(width as f64) as usize;
+        let min_y = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min).floor().max(0.0) as usize;
+        let max_y = corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max).ceil().min(height as f64) as usize;
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                if covers(&corners, (x as f64 + 0.5, y as f64 + 0.5)) {
+                    pixels[y * width + x] = Some(triangle.state);
+                }
+            }
+        }
+    }
+
+    let mut output = String::with_capacity(height * (width + 1));
+    for row in 0..rows as usize {
+        for x in 0..width {
+            let top = pixels[row * 2 * width + x];
+            let bottom = pixels[(row * 2 + 1) * width + x];
+            if options.color {
+                write_color_cell(&mut output, top, bottom);
+            } else {
+                output.push(glyph(top, bottom));
+            }
+        }
+        if options.color {
+            output.push_str("\x1b[0m");
+        }
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// Whether a pixel center lies inside or on a triangle given in canvas space
+fn covers(corners: &[(f64, f64); 3], point: (f64, f64)) -> bool {
+    let side = |a: (f64, f64), b: (f64, f64)| (b.0 - a.0) * (point.1 - a.1) - (b.1 - a.1) * (point.0 - a.0);
+    let d1 = side(corners[0], corners[1]);
+    let d2 = side(corners[1], corners[2]);
+    let d3 = side(corners[2], corners[0]);
+
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
+
+/// Full-cell glyph for a triangle state
+fn state_glyph(state: TriangleState) -> char {
+    match state {
+        TriangleState::Active | TriangleState::Inactive => '█',
+        TriangleState::Genesis | TriangleState::Subdivided => '▓',
+        TriangleState::Void => '░',
+    }
+}
+
+/// Glyph for a cell from its two pixels; half blocks mark edges, and where
+/// two states share a cell the solid one wins
+fn glyph(top: Option<TriangleState>, bottom: Option<TriangleState>) -> char {
+    match (top, bottom) {
+        (None, None) => ' ',
+        (Some(_), None) => '▀',
+        (None, Some(_)) => '▄',
+        (Some(top), Some(bottom)) => {
+            if top == TriangleState::Void {
+                state_glyph(bottom)
+            } else {
+                state_glyph(top)
+            }
+        }
+    }
+}
+
+/// ANSI foreground color code for a triangle state
+fn state_color(state: TriangleState) -> u8 {
+    match state {
+        TriangleState::Genesis => 31,                            // Red
+        TriangleState::Active | TriangleState::Inactive => 36,   // Cyan
+        TriangleState::Subdivided => 34,                         // Blue
+        TriangleState::Void => 90,                               // Gray
+    }
+}
+
+/// Write an upper half block colored by the top pixel over a background
+/// colored by the bottom pixel
+fn write_color_cell(output: &mut String, top: Option<TriangleState>, bottom: Option<TriangleState>) {
+    let foreground = top.map_or(39, state_color);
+    let background = bottom.map_or(49, |state| state_color(state) + 10);
+    write!(output, "\x1b[{};{}m▀", foreground, background).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        genesis::genesis_fractal_triangle,
+        subdivision::subdivide_to_depth,
+    };
+
+    /// Character at the cell containing the canvas point (x, y)
+    fn cell_at(grid: &str, x: f64, y: f64) -> char {
+        let line = grid.lines().nth((y / 2.0) as usize).unwrap();
+        line.chars().nth(x as usize).unwrap()
+    }
+
+    #[test]
+    fn test_render_depth_two_grid() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let structure = subdivide_to_depth(genesis, 2).unwrap();
+
+        let grid = render_ascii(&structure, 40, 20, &TerminalOptions::default()).unwrap();
+        assert_eq!(grid.lines().count(), 20);
+        assert!(grid.lines().all(|line| line.chars().count() == 40));
+
+        // Locate the genesis corners and the central void on the grid
+        let bounds = calculate_bounds(&structure).unwrap();
+        let scale = calculate_scale(&bounds, 40, 40).unwrap();
+        let root = structure.genesis().unwrap();
+        let corners: Vec<(f64, f64)> = root
+            .triangle
+            .vertices()
+            .iter()
+            .map(|vertex| transform_point(vertex, &scale).unwrap())
+            .collect();
+        let void = structure
+            .triangles_at_depth(1)
+            .into_iter()
+            .find(|triangle| triangle.state == TriangleState::Void)
+            .unwrap();
+        let (void_x, void_y) = transform_point(&void.triangle.centroid(), &scale).unwrap();
+
+        // Cells just inside each genesis corner are drawn
+        let (cx, cy) = transform_point(&root.triangle.centroid(), &scale).unwrap();
+        for (x, y) in corners {
+            let inside = (x + (cx - x) * 0.1, y + (cy - y) * 0.1);
+            assert_ne!(cell_at(&grid, inside.0, inside.1), ' ');
+        }
+        assert_eq!(cell_at(&grid, void_x, void_y), '░');
+
+        let hidden = TerminalOptions {
+            show_void_triangles: false,
+            ..Default::default()
+        };
+        let grid = render_ascii(&structure, 40, 20, &hidden).unwrap();
+        assert_eq!(cell_at(&grid, void_x, void_y), ' ');
+    }
+
+    #[test]
+    fn test_color_output_resets_each_line() {
+        let genesis = genesis_fractal_triangle().unwrap();
+        let structure = subdivide_to_depth(genesis, 1).unwrap();
+
+        let options = TerminalOptions {
+            color: true,
+            ..Default::default()
+        };
+        let grid = render_ascii(&structure, 20, 10, &options).unwrap();
+        assert!(grid.lines().all(|line| line.ends_with("\x1b[0m")));
+        assert!(grid.contains("\x1b[36;"));
+        assert!(render_ascii(&structure, 0, 10, &options).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+//! World-to-canvas coordinate transform shared by the renderers
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::core::{
+    fractal::FractalStructure,
+    geometry::{BoundingBox, Point},
+    errors::{SierpinskiError, SierpinskiResult},
+};
+
+/// Calculate the bounding box of all triangles
+pub(crate) fn calculate_bounds(structure: &FractalStructure) -> SierpinskiResult<BoundingBox> {
+    let bounds = structure
+        .bounding_box()
+        .ok_or_else(|| SierpinskiError::validation("Cannot render an empty fractal structure"))?;
+    
+    // Add padding
+    let padding = bounds.width() * Decimal::new(1, 1); // 10% padding
+    
+    Ok(BoundingBox {
+        min: Point::new(bounds.min.x - padding, bounds.min.y - padding),
+        max: Point::new(bounds.max.x + padding, bounds.max.y + padding),
+    })
+}
+
+/// Calculate scale factor for coordinate transformation
+pub(crate) fn calculate_scale(bounds: &BoundingBox, width: u32, height: u32) -> SierpinskiResult<Scale> {
+    let bounds_width = bounds.width();
+    let bounds_height = bounds.height();
+    
+    let scale_x = Decimal::from(width) / bounds_width;
+    let scale_y = Decimal::from(height) / bounds_height;
+    
+    // Use the smaller scale to maintain aspect ratio
+    let scale = if scale_x < scale_y { scale_x } else { scale_y };
+    
+    Ok(Scale {
+        factor: to_render_f64(scale)?,
+        offset_x: bounds.min.x,
+        offset_y: bounds.min.y,
+        canvas_height: height as f64,
+    })
+}
+
+/// Convert a Decimal to f64 for output, failing rather than plotting garbage
+pub(crate) fn to_render_f64(value: Decimal) -> SierpinskiResult<f64> {
+    value.to_f64().ok_or_else(|| SierpinskiError::PrecisionError {
+        details: format!("Cannot convert {} to a render coordinate", value),
+    })
+}
+
+/// Transform a point from world coordinates to canvas coordinates
+///
+/// The offset is subtracted exactly in Decimal so tiny triangles far from
+/// the origin keep their shape; only the result is converted to f64.
+pub(crate) fn transform_point(point: &Point, scale: &Scale) -> SierpinskiResult<(f64, f64)> {
+    let x = to_render_f64(point.x - scale.offset_x)? * scale.factor;
+    let y = scale.canvas_height - to_render_f64(point.y - scale.offset_y)? * scale.factor;
+    Ok((x, y))
+}
+
+/// Scaling information, with the per-render factors already in f64
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Scale {
+    pub(crate) factor: f64,
+    pub(crate) offset_x: Decimal,
+    pub(crate) offset_y: Decimal,
+    pub(crate) canvas_height: f64,
+}
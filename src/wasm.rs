@@ -0,0 +1,80 @@
+//! JavaScript bindings for generating, validating and rendering fractals
+//! client-side
+//!
+//! Structures cross the boundary as the JSON the CLI reads and writes, so
+//! `JSON.stringify` of a generated structure can be passed back in. Errors
+//! are thrown as JavaScript `Error`s.
+//!
+//! The crate builds as an rlib by default; build the loadable module with
+//! `cargo rustc --lib --release --target wasm32-unknown-unknown
+//! --no-default-features --features wasm --crate-type cdylib`.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::core::{
+    address::TriangleAddress,
+    fractal::FractalStructure,
+    genesis::genesis_fractal_triangle,
+    subdivision::subdivide_to_depth,
+    validation::{validate_fractal_structure, ValidationResult},
+};
+use crate::visualization::render_fractal_svg;
+
+/// Parsed form of an address, as returned by `parse_address`
+#[derive(Debug, Serialize)]
+struct AddressInfo {
+    address: String,
+    checked: String,
+    depth: u8,
+    is_genesis: bool,
+    is_void: bool,
+    components: Vec<u8>,
+}
+
+/// Convert to a plain object; maps become objects rather than `Map`s, so
+/// the result survives `JSON.stringify`
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Subdivide the genesis triangle to `depth`, returning the structure
+#[wasm_bindgen]
+pub fn generate_fractal(depth: u8) -> Result<JsValue, JsError> {
+    let structure = subdivide_to_depth(genesis_fractal_triangle()?, depth)?;
+    to_js(&structure)
+}
+
+/// Render a structure, given as JSON, to an SVG document
+#[wasm_bindgen]
+pub fn render_svg(structure_json: &str, width: u32, height: u32, show_addresses: bool) -> Result<String, JsError> {
+    let structure = FractalStructure::read_json(structure_json.as_bytes())?;
+    Ok(render_fractal_svg(&structure, width, height, show_addresses)?)
+}
+
+/// Parse an address in plain or checked form
+#[wasm_bindgen]
+pub fn parse_address(s: &str) -> Result<JsValue, JsError> {
+    let address = TriangleAddress::from_string_representation(s)?;
+    to_js(&AddressInfo {
+        address: address.to_string(),
+        checked: address.to_checked_string(),
+        depth: address.depth(),
+        is_genesis: address.is_genesis(),
+        is_void: address.is_void(),
+        components: address.components().to_vec(),
+    })
+}
+
+/// Validate a structure given as JSON, returning `{ is_valid, errors,
+/// warnings }`; JSON that does not decode is reported as an error
+#[wasm_bindgen]
+pub fn validate_structure(json: &str) -> Result<JsValue, JsError> {
+    let result = match FractalStructure::read_json(json.as_bytes()) {
+        Ok(structure) => validate_fractal_structure(&structure),
+        Err(e) => ValidationResult::failure(vec![e.to_string()]),
+    };
+    to_js(&result)
+}
@@ -0,0 +1,40 @@
+//! End-to-end tests for the `mine` CLI command
+
+use std::process::Command;
+
+use rust_decimal::Decimal;
+use triadchain::core::{blockchain::TriadChainBlockchain, target::Target, wallet::TriadChainWallet};
+
+#[test]
+fn test_mine_extends_chain_and_pays_miner() {
+    let dir = tempfile::tempdir().unwrap();
+    let chain_file = dir.path().join("chain.bin");
+    let miner = TriadChainWallet::new().unwrap();
+
+    let mut blockchain = TriadChainBlockchain::with_target(Target::from_legacy_difficulty(1)).unwrap();
+    blockchain.difficulty_params.max_difficulty = 2;
+    std::fs::write(&chain_file, blockchain.to_bytes().unwrap()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_triadchain_cli"))
+        .arg("mine")
+        .arg("--chain-file").arg(&chain_file)
+        .args(["--reward-address", &miner.wallet_id, "--blocks", "2", "--max-tx", "50"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("nonce attempts"));
+
+    let mined = TriadChainBlockchain::from_bytes(&std::fs::read(&chain_file).unwrap()).unwrap();
+    assert_eq!(mined.tip_height(), blockchain.tip_height() + 2);
+    assert!(mined.get_balance(&miner.wallet_id) > Decimal::ZERO);
+    assert!(mined.blocks[1..].iter().all(|block| block.miner_address == miner.wallet_id));
+
+    // Rewards must go to a well-formed address
+    let output = Command::new(env!("CARGO_BIN_EXE_triadchain_cli"))
+        .arg("mine")
+        .arg("--chain-file").arg(&chain_file)
+        .args(["--reward-address", "not-an-address"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
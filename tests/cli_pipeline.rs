@@ -0,0 +1,49 @@
+//! End-to-end tests for piping fractal JSON between CLI commands
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use triadchain::core::fractal::FractalStructure;
+
+fn run_with_stdin(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_triadchain_cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_generated_json_pipes_into_render_and_validate() {
+    let dir = tempfile::tempdir().unwrap();
+    let svg_path = dir.path().join("fractal.svg");
+
+    let generated = Command::new(env!("CARGO_BIN_EXE_triadchain_cli"))
+        .args(["generatetriangle", "--depth", "2", "--output", "-"])
+        .output()
+        .unwrap();
+    assert!(generated.status.success(), "{}", String::from_utf8_lossy(&generated.stderr));
+    // Nothing but the JSON on stdout
+    let structure = FractalStructure::read_json(generated.stdout.as_slice()).unwrap();
+    // The void child of the genesis is not subdivided further
+    assert_eq!(structure.total_triangles(), 1 + 4 + 3 * 4);
+
+    let rendered = run_with_stdin(
+        &["render", "--input", "-", "--output", svg_path.to_str().unwrap()],
+        &generated.stdout,
+    );
+    assert!(rendered.status.success(), "{}", String::from_utf8_lossy(&rendered.stderr));
+    assert!(std::fs::read_to_string(&svg_path).unwrap().starts_with("<svg"));
+
+    let validated = run_with_stdin(&["validate", "--input", "-", "--invariants"], &generated.stdout);
+    assert!(validated.status.success());
+    assert!(String::from_utf8_lossy(&validated.stdout).contains("is valid"));
+    assert!(String::from_utf8_lossy(&validated.stdout).contains("Structural invariants hold"));
+
+    let info = run_with_stdin(&["info", "--input", "-"], b"not json");
+    assert!(!info.status.success());
+}
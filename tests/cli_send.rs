@@ -0,0 +1,71 @@
+//! End-to-end tests for the `send` CLI command
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use rust_decimal::Decimal;
+use triadchain::core::{
+    address::TriangleAddress,
+    blockchain::TriadChainBlockchain,
+    wallet::{EncryptedWallet, TriadChainWallet},
+};
+
+const PASSWORD: &str = "hunter2";
+
+/// Write a new wallet to `path` and return it
+fn write_wallet(path: &Path) -> TriadChainWallet {
+    let wallet = TriadChainWallet::new().unwrap();
+    let sealed = EncryptedWallet::encrypt(&wallet, PASSWORD).unwrap();
+    std::fs::write(path, serde_json::to_string(&sealed).unwrap()).unwrap();
+    wallet
+}
+
+fn send(wallet: &Path, to: &str, triangle: &str, chain_file: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_triadchain_cli"))
+        .arg("send")
+        .arg("--wallet").arg(wallet)
+        .args(["--password", PASSWORD, "--to", to, "--triangle", triangle, "--fee", "0.05"])
+        .arg("--chain-file").arg(chain_file)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_send_submits_transfer_and_rejects_non_owner() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner_file = dir.path().join("owner.json");
+    let other_file = dir.path().join("other.json");
+    let chain_file = dir.path().join("chain.bin");
+
+    let owner = write_wallet(&owner_file);
+    let other = write_wallet(&other_file);
+
+    let triangle = TriangleAddress::from_string_representation("0.1.2").unwrap();
+    let mut blockchain = TriadChainBlockchain::new().unwrap();
+    blockchain.triangle_owners.insert(triangle.clone(), owner.wallet_id.clone());
+    blockchain.balances.insert(owner.wallet_id.clone(), Decimal::ONE);
+    blockchain.balances.insert(other.wallet_id.clone(), Decimal::ONE);
+    std::fs::write(&chain_file, blockchain.to_bytes().unwrap()).unwrap();
+
+    let output = send(&owner_file, &other.wallet_id, "0.1.2", &chain_file);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let blockchain = TriadChainBlockchain::from_bytes(&std::fs::read(&chain_file).unwrap()).unwrap();
+    assert_eq!(blockchain.mempool.len(), 1);
+    let transaction = &blockchain.mempool[0];
+    assert_eq!(transaction.recipient.as_deref(), Some(other.wallet_id.as_str()));
+    assert_eq!(transaction.gas_fee, Decimal::new(5, 2));
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&transaction.id.to_string()));
+
+    // The other wallet does not own the triangle
+    let output = send(&other_file, &owner.wallet_id, "0.1.2", &chain_file);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Triangle not owned by this wallet"));
+
+    let output = send(&owner_file, "not-an-address", "0.1.2", &chain_file);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid address format"));
+
+    let blockchain = TriadChainBlockchain::from_bytes(&std::fs::read(&chain_file).unwrap()).unwrap();
+    assert_eq!(blockchain.mempool.len(), 1);
+}
@@ -0,0 +1,36 @@
+//! Browser bindings, run under a JavaScript engine with
+//! `cargo test --target wasm32-unknown-unknown --no-default-features --features wasm --test wasm`
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use triadchain::core::fractal::FractalStructure;
+use triadchain::wasm::{generate_fractal, parse_address, render_svg, validate_structure};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn stringify(value: &JsValue) -> String {
+    js_sys::JSON::stringify(value).unwrap().into()
+}
+
+#[wasm_bindgen_test]
+fn test_generate_fractal_to_depth_three() {
+    let json = stringify(&generate_fractal(3).unwrap());
+    let structure = FractalStructure::read_json(json.as_bytes()).unwrap();
+    // 27 corners and the 9 voids beside them
+    assert_eq!(structure.triangles().filter(|triangle| triangle.depth == 3).count(), 36);
+
+    let validation = stringify(&validate_structure(&json).unwrap());
+    assert!(validation.contains("\"is_valid\":true"), "{}", validation);
+    let garbage = stringify(&validate_structure("{").unwrap());
+    assert!(garbage.contains("\"is_valid\":false"));
+}
+
+#[wasm_bindgen_test]
+fn test_render_svg_and_parse_address() {
+    let json = stringify(&generate_fractal(2).unwrap());
+    let svg = render_svg(&json, 200, 200, true).unwrap();
+    assert!(svg.contains("<svg"));
+
+    let address = stringify(&parse_address("0.1").unwrap());
+    assert!(address.contains("\"depth\":2"), "{}", address);
+    assert!(parse_address("9").is_err());
+}